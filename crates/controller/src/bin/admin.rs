@@ -0,0 +1,306 @@
+use controller::config::{self, ApiContext, NetworkContext};
+use controller::indexer::function::FunctionIndexer;
+use controller::service::domain::{block_stack, module, workspace};
+use controller::service::Error;
+
+use database::models::block_stack::compile_job::COMPILE_JOB_STATUS_FAILED;
+use database::repository::block_stack::BlockStackRepository;
+use database::repository::module::ModuleDetailRepository;
+use database::schema;
+
+use clap::{Parser, Subcommand};
+use env_logger::Env;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Maintenance of the databases and script workspaces the server is configured with
+#[derive(Parser)]
+#[command(name = "admin")]
+struct Cli {
+    /// Network to work on, the default network when absent
+    #[arg(long, global = true)]
+    network: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Applies pending migrations to the app database
+    Migrate,
+    /// Recompiles block stacks one at a time, waiting for each compile
+    Recompile {
+        /// Every stack of the network
+        #[arg(long, conflicts_with = "ids", required_unless_present = "ids")]
+        all: bool,
+        ids: Vec<i32>,
+    },
+    /// Checks the registered source of every module still resolves
+    Reverify,
+    /// Removes script workspaces left behind by compiles that never finished
+    PurgeWorkspaces {
+        #[arg(long, default_value_t = 3600)]
+        older_than_secs: u64,
+    },
+    /// Indexes the functions of modules published since the last checkpoint
+    BackfillFunctions {
+        /// Drops indexed functions and starts over from the first module
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Writes every detail table to a json file
+    ExportDetails { path: PathBuf },
+    /// Loads a file written by `export-details`, rows whose id exists are skipped
+    ImportDetails { path: PathBuf },
+    /// Lists the latest failed compile jobs
+    CompileFailures {
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+}
+
+fn select_network<'a>(
+    context: &'a ApiContext,
+    name: Option<&str>,
+) -> Result<&'a NetworkContext, Error> {
+    match name {
+        Some(name) => context.network(name).ok_or_else(|| Error::InvalidParams {
+            msg: format!("unknown network {}", name),
+        }),
+        None => Ok(context.default_network()),
+    }
+}
+
+async fn recompile(
+    context: &ApiContext,
+    network: &NetworkContext,
+    ids: Option<&[i32]>,
+) -> Result<(), Error> {
+    let block_stacks = context
+        .app_db
+        .get_block_stacks(&network.name, ids)
+        .await
+        .map_err(Error::DbError)?;
+
+    let mut failed = 0;
+    for stack in &block_stacks {
+        let res = block_stack::create_bytecode(
+            context.app_db.clone(),
+            context.settings.compile.clone(),
+            network.name.clone(),
+            stack.stack.clone(),
+            stack.id,
+        )
+        .await;
+
+        match res {
+            Ok(()) => println!("{} {}: ok", stack.id, stack.name),
+            Err(e) => {
+                failed += 1;
+                println!("{} {}: {}", stack.id, stack.name, e);
+            }
+        }
+    }
+
+    println!("recompiled {}, {} failed", block_stacks.len(), failed);
+    Ok(())
+}
+
+async fn reverify(context: &ApiContext, network: &NetworkContext) -> Result<(), Error> {
+    let module_details = context
+        .app_db
+        .get_module_details_with_source(&network.name)
+        .await
+        .map_err(Error::DbError)?;
+
+    let mut failed = 0;
+    for module_detail in &module_details {
+        let res = module::verify_module_source(
+            &network.aptos_full_index_db,
+            &context.settings.compile,
+            module_detail,
+        )
+        .await;
+
+        let name = format!("{}::{}", module_detail.address, module_detail.module_name);
+        match res {
+            Ok(package) => println!("{}: package {}", name, package),
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", name, e);
+            }
+        }
+    }
+
+    println!("verified {}, {} failed", module_details.len(), failed);
+    Ok(())
+}
+
+/// Functions are only indexed in-process for the default network, other networks and an external
+/// function indexer database are filled by their own indexer
+async fn backfill_functions(
+    context: &ApiContext,
+    network: &NetworkContext,
+    reset: bool,
+) -> Result<(), Error> {
+    if network.name != context.default_network().name
+        || context.settings.database.function_indexer.is_some()
+    {
+        return Err(Error::InvalidParams {
+            msg: format!("functions of network {} are not indexed here", network.name),
+        });
+    }
+
+    let indexer = FunctionIndexer::new(
+        network.aptos_full_index_db.clone(),
+        network.function_index_db.clone(),
+        context.settings.indexers.function.indexer_config(),
+        context.cache.clone(),
+        network.name.clone(),
+    );
+
+    if reset {
+        indexer.reset().await?;
+    }
+
+    let mut indexed = 0;
+    loop {
+        let modules = indexer.process_next_batch().await?;
+        if modules.is_empty() {
+            break;
+        }
+
+        indexed += modules.len();
+        println!(
+            "indexed {} modules, checkpoint {}",
+            indexed,
+            indexer.checkpoint().await?
+        );
+    }
+
+    println!("caught up after {} modules", indexed);
+    Ok(())
+}
+
+async fn export_details(context: &ApiContext, path: &Path) -> Result<(), Error> {
+    let mut tables = Map::new();
+    for table in schema::DETAIL_TABLES {
+        let rows = schema::export_detail_table(&context.app_db, table)
+            .await
+            .map_err(Error::DbError)?;
+        println!(
+            "{}: {} rows",
+            table,
+            rows.as_array().map(Vec::len).unwrap_or_default()
+        );
+        tables.insert(table.to_string(), rows);
+    }
+
+    let body =
+        serde_json::to_vec_pretty(&Value::Object(tables)).map_err(|e| Error::AnyError(e.into()))?;
+    tokio::fs::write(path, body)
+        .await
+        .map_err(|e| Error::AnyError(e.into()))
+}
+
+async fn import_details(context: &ApiContext, path: &Path) -> Result<(), Error> {
+    let body = tokio::fs::read(path)
+        .await
+        .map_err(|e| Error::AnyError(e.into()))?;
+    let tables: Map<String, Value> =
+        serde_json::from_slice(&body).map_err(|e| Error::AnyError(e.into()))?;
+
+    if let Some(table) = tables
+        .keys()
+        .find(|t| !schema::DETAIL_TABLES.contains(&t.as_str()))
+    {
+        return Err(Error::InvalidParams {
+            msg: format!("{} is not a detail table", table),
+        });
+    }
+
+    for table in schema::DETAIL_TABLES {
+        if let Some(rows) = tables.get(table) {
+            let inserted = schema::import_detail_table(&context.app_db, table, rows)
+                .await
+                .map_err(Error::DbError)?;
+            println!("{}: {} rows inserted", table, inserted);
+        }
+    }
+
+    context.cache.invalidate_all().await;
+    Ok(())
+}
+
+async fn compile_failures(
+    context: &ApiContext,
+    network: &NetworkContext,
+    limit: i64,
+) -> Result<(), Error> {
+    let compile_jobs = context
+        .app_db
+        .get_compile_jobs(&network.name, Some(COMPILE_JOB_STATUS_FAILED), limit)
+        .await
+        .map_err(Error::DbError)?;
+
+    for job in compile_jobs {
+        println!(
+            "job {} stack {} at {}: {}",
+            job.id,
+            job.block_stack_id,
+            job.started_at,
+            job.error.unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
+
+async fn run(cli: Cli, context: ApiContext) -> Result<(), Error> {
+    let network = select_network(&context, cli.network.as_deref())?;
+
+    match cli.command {
+        Command::Migrate => schema::run_migrations(&context.app_db)
+            .await
+            .map_err(Error::DbError),
+        Command::Recompile { all, ids } => {
+            recompile(&context, network, (!all).then_some(ids.as_slice())).await
+        }
+        Command::Reverify => reverify(&context, network).await,
+        Command::PurgeWorkspaces { older_than_secs } => {
+            let removed = workspace::purge_script_workspaces(
+                &context.settings.compile.script_dir,
+                Duration::from_secs(older_than_secs),
+            )
+            .await?;
+            for dir in &removed {
+                println!("removed {}", dir.display());
+            }
+            println!("removed {} workspaces", removed.len());
+            Ok(())
+        }
+        Command::BackfillFunctions { reset } => backfill_functions(&context, network, reset).await,
+        Command::ExportDetails { path } => export_details(&context, &path).await,
+        Command::ImportDetails { path } => import_details(&context, &path).await,
+        Command::CompileFailures { limit } => compile_failures(&context, network, limit).await,
+    }
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    dotenv::dotenv().ok();
+    env_logger::init_from_env(Env::default().default_filter_or("warn"));
+
+    let cli = Cli::parse();
+
+    let settings = config::Settings::load()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+    let context = config::config_api_context(settings)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+
+    run(cli, context)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))
+}
@@ -1,24 +1,119 @@
-use controller::config;
+use controller::service::domain::{block_stack, workspace};
+use controller::service::rate_limit::{RateLimit, RateLimiter};
+use controller::{config, metrics};
 
+use actix_web::dev::Service;
 use actix_web::middleware::{Compress, Logger, NormalizePath};
+use actix_web::web::Data;
 use actix_web::{App, HttpServer};
 use env_logger::Env;
+use std::time::{Duration, Instant};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv::dotenv().ok();
     env_logger::init_from_env(Env::default().default_filter_or("info"));
 
-    HttpServer::new(|| {
+    let settings = config::Settings::load()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+    let (host, port) = (settings.server.host.clone(), settings.server.port);
+    let context = Data::new(
+        config::config_api_context(settings)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?,
+    );
+
+    config::config_databases(&context)
+        .await
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+
+    // Left behind by a previous run that stopped mid compile, recent ones may still be in use
+    let stale_workspace = Duration::from_secs(context.settings.compile.stale_workspace_secs);
+    match workspace::purge_script_workspaces(&context.settings.compile.script_dir, stale_workspace)
+        .await
+    {
+        Ok(removed) if !removed.is_empty() => {
+            log::info!("removed {} orphaned script workspaces", removed.len())
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("cannot sweep script workspaces: {}", e),
+    }
+
+    for network in &context.networks {
+        match block_stack::resume_compile_jobs(
+            &context.app_db,
+            &context.settings.compile,
+            &context.compile_jobs,
+            &network.name,
+        )
+        .await
+        {
+            Ok(0) => {}
+            Ok(resumed) => log::info!("resumed {} compiles on {}", resumed, network.name),
+            Err(e) => log::warn!("cannot resume compiles on {}: {}", network.name, e),
+        }
+    }
+
+    for network in &context.networks {
+        if let Some(function_indexer) = config::config_function_indexer(&context, network) {
+            actix_rt::spawn(function_indexer.run());
+        }
+
+        if let Some(dependency_indexer) = config::config_dependency_indexer(&context, network) {
+            actix_rt::spawn(dependency_indexer.run());
+        }
+
+        if let Some(resource_account_indexer) =
+            config::config_resource_account_indexer(&context, network)
+        {
+            actix_rt::spawn(resource_account_indexer.run());
+        }
+
+        if let Some(function_usage_indexer) =
+            config::config_function_usage_indexer(&context, network)
+        {
+            actix_rt::spawn(function_usage_indexer.run());
+        }
+
+        if let Some(block_stack_checker) = config::config_block_stack_checker(&context, network) {
+            actix_rt::spawn(block_stack_checker.run());
+        }
+    }
+
+    // Buckets are shared by every worker
+    let rate_limiter = RateLimiter::new(context.settings.rate_limit.clone());
+    let compile_jobs = context.compile_jobs.clone();
+    let shutdown_timeout = Duration::from_secs(context.settings.compile.shutdown_timeout_secs);
+    let session_resolver = config::config_session_resolver(&context);
+
+    HttpServer::new(move || {
         App::new()
+            .wrap(RateLimit::new(rate_limiter.clone()))
+            .wrap_fn(|req, srv| {
+                let started = Instant::now();
+                let res = srv.call(req);
+                async move {
+                    let res = res.await?;
+                    metrics::observe_http_request(&res, started);
+                    Ok(res)
+                }
+            })
             .wrap(Logger::default())
             .wrap(NormalizePath::trim())
             .wrap(Compress::default())
-            .wrap(config::config_cors())
-            .configure(config::config_service)
-            .configure(config::config_app_data)
+            .configure(|cfg| config::config_service(cfg, &context.settings.cors))
+            .app_data(context.clone())
+            .app_data(session_resolver.clone())
     })
-    .bind(("0.0.0.0", config::config_port()))?
+    .bind((host, port))?
     .run()
-    .await
+    .await?;
+
+    let unfinished = compile_jobs.drain(shutdown_timeout).await;
+    if unfinished > 0 {
+        // Blocking compile threads would otherwise hold the runtime open past the deadline
+        log::error!("{} compiles unfinished, resumed on next start", unfinished);
+        std::process::exit(1);
+    }
+
+    Ok(())
 }
@@ -0,0 +1,486 @@
+use crate::config::settings::{CacheSettings, NETWORKS};
+use crate::metrics;
+use crate::service::Error;
+
+use futures::lock::Mutex as AsyncMutex;
+use redis::aio::ConnectionManager;
+use redis::RedisResult;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Prefixes every redis key so the server can be shared with other data
+const REDIS_KEY_PREFIX: &str = "moveblock";
+
+#[derive(Default)]
+struct MemoryStore {
+    /// Keyed by `network:generation:key`, or `network:generation:scope@generation:key` for scoped
+    /// entries, with the instant the entry expires
+    entries: HashMap<String, (Instant, String)>,
+    /// Keyed by network
+    generations: HashMap<String, u64>,
+    /// Keyed by `network/scope`, cleared once it holds as many scopes as the store holds entries
+    scope_generations: HashMap<String, u64>,
+    /// Generation of the scopes missing from `scope_generations`, above any they were cleared at
+    scope_floor: u64,
+}
+
+impl MemoryStore {
+    fn generation(&self, network: &str, scope: Option<&str>) -> u64 {
+        match scope {
+            Some(scope) => self
+                .scope_generations
+                .get(&scope_name(network, scope))
+                .copied()
+                .unwrap_or(self.scope_floor),
+            None => self.generations.get(network).copied().unwrap_or_default(),
+        }
+    }
+
+    /// Raising the floor gives every forgotten scope a generation none of its entries has
+    fn bump_generation(&mut self, network: &str, scope: Option<&str>, capacity: usize) {
+        let scope = match scope {
+            Some(scope) => scope,
+            None => {
+                *self.generations.entry(network.to_string()).or_default() += 1;
+
+                let prefix = format!("{}:", network);
+                self.entries.retain(|key, _| !key.starts_with(&prefix));
+                return;
+            }
+        };
+
+        let prefix = format!("{}:{}:{}@", network, self.generation(network, None), scope);
+        self.entries.retain(|key, _| !key.starts_with(&prefix));
+
+        if self.scope_generations.len() >= capacity {
+            self.scope_floor = self
+                .scope_generations
+                .values()
+                .copied()
+                .max()
+                .unwrap_or_default()
+                .max(self.scope_floor)
+                + 1;
+            self.scope_generations.clear();
+        }
+
+        let floor = self.scope_floor;
+        *self
+            .scope_generations
+            .entry(scope_name(network, scope))
+            .or_insert(floor) += 1;
+    }
+}
+
+fn scope_name(network: &str, scope: &str) -> String {
+    format!("{}/{}", network, scope)
+}
+
+fn redis_generation_key(network: &str, scope: Option<&str>) -> String {
+    match scope {
+        Some(scope) => format!(
+            "{}:{}:generation",
+            REDIS_KEY_PREFIX,
+            scope_name(network, scope)
+        ),
+        None => format!("{}:{}:generation", REDIS_KEY_PREFIX, network),
+    }
+}
+
+#[derive(Clone)]
+enum Backend {
+    None,
+    Memory {
+        store: Arc<Mutex<MemoryStore>>,
+        capacity: usize,
+    },
+    /// Connects on first use, so an unreachable server only slows down uncached reads
+    Redis {
+        client: redis::Client,
+        connection: Arc<AsyncMutex<Option<ConnectionManager>>>,
+    },
+}
+
+/// Serialized reads keyed by network. Every key embeds the generation of its network, and of its
+/// scope when it has one, so invalidating bumps the generation and stale entries are never read
+/// again, only expire. Backend failures are logged and fall through to the database
+#[derive(Clone)]
+pub struct Cache {
+    backend: Backend,
+    ttl: Duration,
+}
+
+impl Cache {
+    pub fn new(settings: &CacheSettings) -> RedisResult<Self> {
+        let backend = match settings.backend.as_str() {
+            "memory" => Backend::Memory {
+                store: Arc::new(Mutex::new(MemoryStore::default())),
+                capacity: settings.capacity,
+            },
+            "redis" => Backend::Redis {
+                client: redis::Client::open(settings.redis_url.as_str())?,
+                connection: Arc::new(AsyncMutex::new(None)),
+            },
+            _ => Backend::None,
+        };
+
+        Ok(Cache {
+            backend,
+            ttl: Duration::from_secs(settings.ttl_secs),
+        })
+    }
+
+    pub fn disabled() -> Self {
+        Cache {
+            backend: Backend::None,
+            ttl: Duration::ZERO,
+        }
+    }
+
+    /// The cached value of `key` on `network`, `load` fills it on a miss
+    pub async fn get_or_load<T, F, Fut>(
+        &self,
+        network: &str,
+        key: &str,
+        load: F,
+    ) -> Result<T, Error>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.load_cached(network, None, key, load).await
+    }
+
+    /// Like `get_or_load` for a value about one account, `scope` is the address or alias the
+    /// request named, so `invalidate_scope` drops it without the rest of the network
+    pub async fn get_or_load_scoped<T, F, Fut>(
+        &self,
+        network: &str,
+        scope: &str,
+        key: &str,
+        load: F,
+    ) -> Result<T, Error>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.load_cached(network, Some(scope), key, load).await
+    }
+
+    async fn load_cached<T, F, Fut>(
+        &self,
+        network: &str,
+        scope: Option<&str>,
+        key: &str,
+        load: F,
+    ) -> Result<T, Error>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        if matches!(self.backend, Backend::None) {
+            return load().await;
+        }
+
+        let key = match self.versioned_key(network, scope, key).await {
+            Ok(key) => key,
+            Err(e) => {
+                log::warn!("cache unavailable: {}", e);
+                return load().await;
+            }
+        };
+
+        match self.get(&key).await {
+            Ok(Some(value)) => {
+                if let Ok(value) = serde_json::from_str(&value) {
+                    metrics::observe_cache(true);
+                    return Ok(value);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("cache read of {} failed: {}", key, e),
+        }
+
+        metrics::observe_cache(false);
+        let value = load().await?;
+
+        if let Ok(serialized) = serde_json::to_string(&value) {
+            if let Err(e) = self.set(&key, serialized).await {
+                log::warn!("cache write of {} failed: {}", key, e);
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Drops every entry of the network
+    pub async fn invalidate(&self, network: &str) {
+        if let Err(e) = self.bump_generation(network, None).await {
+            log::warn!("cache invalidation of {} failed: {}", network, e);
+        }
+    }
+
+    /// Drops the entries loaded with `get_or_load_scoped` under any of `scopes`
+    pub async fn invalidate_scopes(&self, network: &str, scopes: &[String]) {
+        for scope in scopes {
+            if let Err(e) = self.bump_generation(network, Some(scope)).await {
+                log::warn!(
+                    "cache invalidation of {} failed: {}",
+                    scope_name(network, scope),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Drops every entry of every network
+    pub async fn invalidate_all(&self) {
+        for network in NETWORKS {
+            self.invalidate(network).await;
+        }
+    }
+
+    async fn redis(
+        client: &redis::Client,
+        connection: &AsyncMutex<Option<ConnectionManager>>,
+    ) -> RedisResult<ConnectionManager> {
+        let mut connection = connection.lock().await;
+        if let Some(connection) = connection.as_ref() {
+            return Ok(connection.clone());
+        }
+
+        let manager = ConnectionManager::new(client.clone()).await?;
+        *connection = Some(manager.clone());
+
+        Ok(manager)
+    }
+
+    async fn versioned_key(
+        &self,
+        network: &str,
+        scope: Option<&str>,
+        key: &str,
+    ) -> RedisResult<String> {
+        let generation = self.generation(network, None).await?;
+
+        Ok(match scope {
+            Some(scope) => {
+                let scope_generation = self.generation(network, Some(scope)).await?;
+                format!(
+                    "{}:{}:{}@{}:{}",
+                    network, generation, scope, scope_generation, key
+                )
+            }
+            None => format!("{}:{}:{}", network, generation, key),
+        })
+    }
+
+    async fn generation(&self, network: &str, scope: Option<&str>) -> RedisResult<u64> {
+        match &self.backend {
+            Backend::None => Ok(0),
+            Backend::Memory { store, .. } => {
+                let store = store.lock().unwrap();
+                Ok(store.generation(network, scope))
+            }
+            Backend::Redis { client, connection } => {
+                let mut connection = Self::redis(client, connection).await?;
+                let generation: Option<u64> = redis::cmd("GET")
+                    .arg(redis_generation_key(network, scope))
+                    .query_async(&mut connection)
+                    .await?;
+
+                Ok(generation.unwrap_or_default())
+            }
+        }
+    }
+
+    /// Entries are dropped from memory right away, redis lets them expire
+    async fn bump_generation(&self, network: &str, scope: Option<&str>) -> RedisResult<()> {
+        match &self.backend {
+            Backend::None => Ok(()),
+            Backend::Memory { store, capacity } => {
+                let mut store = store.lock().unwrap();
+                store.bump_generation(network, scope, *capacity);
+
+                Ok(())
+            }
+            Backend::Redis { client, connection } => {
+                let mut connection = Self::redis(client, connection).await?;
+                redis::cmd("INCR")
+                    .arg(redis_generation_key(network, scope))
+                    .query_async(&mut connection)
+                    .await
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> RedisResult<Option<String>> {
+        match &self.backend {
+            Backend::None => Ok(None),
+            Backend::Memory { store, .. } => {
+                let store = store.lock().unwrap();
+                Ok(store
+                    .entries
+                    .get(key)
+                    .filter(|(expires, _)| *expires > Instant::now())
+                    .map(|(_, value)| value.clone()))
+            }
+            Backend::Redis { client, connection } => {
+                let mut connection = Self::redis(client, connection).await?;
+                redis::cmd("GET")
+                    .arg(format!("{}:{}", REDIS_KEY_PREFIX, key))
+                    .query_async(&mut connection)
+                    .await
+            }
+        }
+    }
+
+    /// A full memory store first drops expired entries, then everything
+    async fn set(&self, key: &str, value: String) -> RedisResult<()> {
+        match &self.backend {
+            Backend::None => Ok(()),
+            Backend::Memory { store, capacity } => {
+                let mut store = store.lock().unwrap();
+                if store.entries.len() >= *capacity {
+                    let now = Instant::now();
+                    store.entries.retain(|_, (expires, _)| *expires > now);
+                }
+                if store.entries.len() >= *capacity {
+                    store.entries.clear();
+                }
+
+                store
+                    .entries
+                    .insert(key.to_string(), (Instant::now() + self.ttl, value));
+
+                Ok(())
+            }
+            Backend::Redis { client, connection } => {
+                let mut connection = Self::redis(client, connection).await?;
+                redis::cmd("SET")
+                    .arg(format!("{}:{}", REDIS_KEY_PREFIX, key))
+                    .arg(value)
+                    .arg("EX")
+                    .arg(self.ttl.as_secs())
+                    .query_async(&mut connection)
+                    .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod memory {
+    use crate::cache::{Backend, Cache};
+    use crate::config::settings::CacheSettings;
+    use crate::service::Error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn load(cache: &Cache, network: &str, loads: &AtomicUsize) -> i64 {
+        cache
+            .get_or_load(network, "count:functions", || async {
+                Ok::<_, Error>(loads.fetch_add(1, Ordering::SeqCst) as i64)
+            })
+            .await
+            .unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_invalidate_per_network() {
+        let cache = Cache::new(&CacheSettings::default()).unwrap();
+        let loads = AtomicUsize::new(0);
+
+        assert_eq!(load(&cache, "devnet", &loads).await, 0);
+        assert_eq!(load(&cache, "devnet", &loads).await, 0);
+        assert_eq!(load(&cache, "mainnet", &loads).await, 1);
+
+        cache.invalidate("devnet").await;
+        assert_eq!(load(&cache, "devnet", &loads).await, 2);
+        assert_eq!(load(&cache, "mainnet", &loads).await, 1);
+
+        cache.invalidate_all().await;
+        assert_eq!(load(&cache, "mainnet", &loads).await, 3);
+    }
+
+    #[actix_rt::test]
+    async fn test_invalidate_scope() {
+        let cache = Cache::new(&CacheSettings::default()).unwrap();
+        let loads = AtomicUsize::new(0);
+        let load_scoped = |scope: &'static str| {
+            cache.get_or_load_scoped("devnet", scope, "module:coin", || async {
+                Ok::<_, Error>(loads.fetch_add(1, Ordering::SeqCst) as i64)
+            })
+        };
+
+        assert_eq!(load_scoped("0xa").await.unwrap(), 0);
+        assert_eq!(load_scoped("0xb").await.unwrap(), 1);
+        assert_eq!(load(&cache, "devnet", &loads).await, 2);
+
+        cache
+            .invalidate_scopes("devnet", &["0xa".to_string()])
+            .await;
+        assert_eq!(load_scoped("0xa").await.unwrap(), 3);
+        assert_eq!(load_scoped("0xb").await.unwrap(), 1);
+        assert_eq!(load(&cache, "devnet", &loads).await, 2);
+
+        cache.invalidate("devnet").await;
+        assert_eq!(load_scoped("0xb").await.unwrap(), 4);
+    }
+
+    #[actix_rt::test]
+    async fn test_errors_are_not_cached() {
+        let cache = Cache::new(&CacheSettings::default()).unwrap();
+
+        let res: Result<i64, Error> = cache
+            .get_or_load("devnet", "count:functions", || async {
+                Err(Error::NotFound {
+                    msg: "gone".to_string(),
+                })
+            })
+            .await;
+        assert!(res.is_err());
+
+        let loads = AtomicUsize::new(7);
+        assert_eq!(load(&cache, "devnet", &loads).await, 7);
+    }
+
+    #[actix_rt::test]
+    async fn test_scopes_are_dropped_and_bounded() {
+        let cache = Cache::new(&CacheSettings {
+            capacity: 2,
+            ..CacheSettings::default()
+        })
+        .unwrap();
+        let store = match &cache.backend {
+            Backend::Memory { store, .. } => store.clone(),
+            _ => unreachable!(),
+        };
+        let loads = AtomicUsize::new(0);
+        let load_scoped = |scope: &'static str| {
+            cache.get_or_load_scoped("devnet", scope, "module:coin", || async {
+                Ok::<_, Error>(loads.fetch_add(1, Ordering::SeqCst) as i64)
+            })
+        };
+
+        assert_eq!(load_scoped("0xa").await.unwrap(), 0);
+        cache
+            .invalidate_scopes("devnet", &["0xa".to_string()])
+            .await;
+        assert!(store.lock().unwrap().entries.is_empty());
+
+        // Forgetting scope generations moves every scope past its old entries
+        assert_eq!(load_scoped("0xb").await.unwrap(), 1);
+        cache
+            .invalidate_scopes("devnet", &["0xc".to_string(), "0xd".to_string()])
+            .await;
+        assert!(store.lock().unwrap().scope_generations.len() <= 2);
+        assert_eq!(load_scoped("0xa").await.unwrap(), 2);
+        assert_eq!(load_scoped("0xb").await.unwrap(), 3);
+    }
+}
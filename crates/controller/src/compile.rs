@@ -0,0 +1,107 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often `drain` checks whether the last compile finished
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct State {
+    accepting: bool,
+    in_flight: usize,
+}
+
+/// Counts compiles in flight so shutdown can wait for them. Once closed no compile starts,
+/// jobs of compiles cut off by shutdown stay unfinished and are resumed by the next start
+#[derive(Clone, Debug)]
+pub struct CompileJobs {
+    state: Arc<Mutex<State>>,
+}
+
+/// Held for the duration of one compile
+pub struct CompileGuard {
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for CompileJobs {
+    fn default() -> Self {
+        CompileJobs {
+            state: Arc::new(Mutex::new(State {
+                accepting: true,
+                in_flight: 0,
+            })),
+        }
+    }
+}
+
+impl CompileJobs {
+    /// None once closed
+    pub fn start(&self) -> Option<CompileGuard> {
+        let mut state = self.state.lock().unwrap();
+        if !state.accepting {
+            return None;
+        }
+
+        state.in_flight += 1;
+        Some(CompileGuard {
+            state: self.state.clone(),
+        })
+    }
+
+    pub fn close(&self) {
+        self.state.lock().unwrap().accepting = false;
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// Closes and waits up to `timeout` for compiles in flight, returns how many are left
+    pub async fn drain(&self, timeout: Duration) -> usize {
+        self.close();
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let in_flight = self.in_flight();
+            if in_flight == 0 || Instant::now() >= deadline {
+                return in_flight;
+            }
+
+            actix_rt::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for CompileGuard {
+    fn drop(&mut self) {
+        self.state.lock().unwrap().in_flight -= 1;
+    }
+}
+
+#[cfg(test)]
+mod drain {
+    use crate::compile::CompileJobs;
+    use std::time::Duration;
+
+    #[actix_rt::test]
+    async fn test_drain_waits_for_compiles_in_flight() {
+        let compile_jobs = CompileJobs::default();
+        let guard = compile_jobs.start().unwrap();
+
+        let waiting = compile_jobs.clone();
+        let drained = actix_rt::spawn(async move { waiting.drain(Duration::from_secs(5)).await });
+
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        assert!(compile_jobs.start().is_none());
+
+        drop(guard);
+        assert_eq!(drained.await.unwrap(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_drain_gives_up_at_deadline() {
+        let compile_jobs = CompileJobs::default();
+        let _guard = compile_jobs.start().unwrap();
+
+        assert_eq!(compile_jobs.drain(Duration::from_millis(10)).await, 1);
+    }
+}
@@ -1,63 +1,450 @@
+pub mod settings;
+
+use crate::cache::Cache;
+use crate::compile::CompileJobs;
+use crate::indexer::{
+    block_stack::BlockStackChecker, dependency::DependencyIndexer, function::FunctionIndexer,
+    resource_account::ResourceAccountIndexer, usage::FunctionUsageIndexer,
+};
 use crate::service;
+use crate::service::domain::{
+    auth_key::AuthKeySource, profile::DomainVerifier, session::SessionLookup,
+};
 
-use database::db::{new_postgres_pool, PostgresPool};
-use middleware::AptosAuth;
+use database::db::PostgresPool;
+use database::schema;
+use middleware::{AptosAuth, SessionResolver};
 
-use actix_web::web::{self, Data};
+use actix_web::web;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub struct ApiContext {
-    pub app_db: PostgresPool,
+pub use settings::{ConfigError, CorsPolicySettings, CorsSettings, ExecutorSettings, Settings};
+
+/// Pools and node of one network, requests select it with the network header
+#[derive(Clone)]
+pub struct NetworkContext {
+    pub name: String,
     pub aptos_full_index_db: PostgresPool,
     pub function_index_db: PostgresPool,
+    pub node_url: String,
+    pub chain_id: u8,
+    /// Signers are checked against the authentication keys this network's node reports
+    pub auth_keys: AuthKeySource,
+    /// Block stacks are executed from this account
+    pub executor: ExecutorSettings,
+}
+
+pub struct ApiContext {
+    pub app_db: PostgresPool,
+    /// The default network comes first
+    pub networks: Vec<NetworkContext>,
+    pub domain_verifier: DomainVerifier,
+    pub cache: Cache,
+    /// Closed on shutdown, compiles requested afterwards resume on the next start
+    pub compile_jobs: CompileJobs,
+    pub settings: Settings,
 }
 
 impl ApiContext {
     pub fn new(
         app_db: PostgresPool,
-        aptos_full_index_db: PostgresPool,
-        function_index_db: PostgresPool,
+        default_network: NetworkContext,
+        other_networks: Vec<NetworkContext>,
+        domain_verifier: DomainVerifier,
+        cache: Cache,
+        settings: Settings,
     ) -> Self {
+        let mut networks = vec![default_network];
+        networks.extend(other_networks);
+
         ApiContext {
             app_db,
-            aptos_full_index_db,
-            function_index_db,
+            networks,
+            domain_verifier,
+            cache,
+            compile_jobs: CompileJobs::default(),
+            settings,
         }
     }
-}
 
-pub fn config_port() -> u16 {
-    std::env::var("PORT")
-        .expect("env PORT not found")
-        .parse()
-        .unwrap()
+    pub fn default_network(&self) -> &NetworkContext {
+        &self.networks[0]
+    }
+
+    pub fn network(&self, name: &str) -> Option<&NetworkContext> {
+        self.networks.iter().find(|network| network.name == name)
+    }
 }
 
-pub fn config_cors() -> actix_cors::Cors {
-    actix_cors::Cors::default()
-        .allow_any_origin()
-        .allow_any_method()
-        .allow_any_header()
+/// Only what the policy lists is allowed, `*` allows any origin
+pub fn config_cors(policy: &CorsPolicySettings) -> actix_cors::Cors {
+    let mut cors = actix_cors::Cors::default()
+        .allowed_methods(policy.allowed_methods.iter().map(String::as_str))
+        .allowed_headers(policy.allowed_headers.iter().map(String::as_str))
+        .expose_headers(policy.exposed_headers.iter().map(String::as_str))
+        .max_age(policy.max_age_secs);
+
+    if policy.supports_credentials {
+        cors = cors.supports_credentials();
+    }
+
+    if policy.allowed_origins.iter().any(|o| o == "*") {
+        return cors.allow_any_origin();
+    }
+
+    policy
+        .allowed_origins
+        .iter()
+        .fold(cors, |cors, origin| cors.allowed_origin(origin))
 }
 
-pub fn config_service(cfg: &mut web::ServiceConfig) {
+/// Health checks and metrics are unversioned, orchestrators probe `/health/live` and `/health/ready`.
+/// Block stack and session routes have their own CORS policy, outside of `AptosAuth` so preflights
+/// carry no signature
+pub fn config_service(cfg: &mut web::ServiceConfig, cors: &CorsSettings) {
+    cfg.app_data(web::JsonConfig::default().error_handler(service::extractor_error_handler));
+    cfg.app_data(web::QueryConfig::default().error_handler(service::extractor_error_handler));
+    cfg.app_data(web::PathConfig::default().error_handler(service::extractor_error_handler));
+    cfg.default_service(web::to(service::not_found));
+    cfg.service(service::health::routers(web::scope("/health")));
+    cfg.service(service::metrics::routers(web::scope("/metrics")));
     cfg.service(
         web::scope("/api/v1")
             .service(
-                service::block_stack::routers(web::scope("/block-stacks")).wrap(AptosAuth::build()),
+                service::block_stack::routers(web::scope("/block-stacks"))
+                    .wrap_fn(service::rate_limit::charge_session)
+                    .wrap(AptosAuth::build())
+                    .wrap(config_cors(&cors.block_stacks)),
+            )
+            .service(
+                service::session::routers(web::scope("/sessions"))
+                    .wrap(config_cors(&cors.block_stacks)),
             )
-            .service(service::account::routers(web::scope("/accounts")))
-            .service(service::function::routers(web::scope("/functions"))),
+            .service(
+                service::account::routers(web::scope("/accounts")).wrap(config_cors(&cors.public)),
+            )
+            .service(
+                service::function::routers(web::scope("/functions"))
+                    .wrap(config_cors(&cors.public)),
+            )
+            .service(
+                service::openapi::routers(web::scope("/openapi.json"))
+                    .wrap(config_cors(&cors.public)),
+            ),
     );
 }
 
-pub fn config_app_data(cfg: &mut web::ServiceConfig) {
-    let app_db = new_postgres_pool("DATABASE_URL");
-    let function_indexer_db = new_postgres_pool("FUNCTION_INDEXER_URL");
-    let aptos_full_indexer_db = new_postgres_pool("FULL_INDEXER_URL");
+/// App data `AptosAuth` resolves session tokens with
+pub fn config_session_resolver(context: &ApiContext) -> web::Data<dyn SessionResolver> {
+    let resolver: Arc<dyn SessionResolver> = Arc::new(SessionLookup::new(
+        context.app_db.clone(),
+        &context.default_network().name,
+    ));
+    web::Data::from(resolver)
+}
+
+fn config_auth_keys(node_url: &str) -> Result<AuthKeySource, ConfigError> {
+    AuthKeySource::node(node_url).map_err(|e| ConfigError::Invalid {
+        problems: vec![format!("cannot build a client for {}: {}", node_url, e)],
+    })
+}
+
+/// Without a function indexer database functions are indexed in-process into the app database,
+/// only the default network may omit it
+pub fn config_api_context(settings: Settings) -> Result<ApiContext, ConfigError> {
+    let app_db = settings.database.app.connect()?;
+    let default_network = NetworkContext {
+        name: settings.network.name.clone(),
+        aptos_full_index_db: settings.database.full_indexer.connect()?,
+        function_index_db: match &settings.database.function_indexer {
+            Some(function_indexer) => function_indexer.connect()?,
+            None => app_db.clone(),
+        },
+        node_url: settings.network.node_url.clone(),
+        chain_id: settings.network.chain_id,
+        auth_keys: config_auth_keys(&settings.network.node_url)?,
+        executor: settings.network.executor.clone(),
+    };
+
+    let mut other_networks = vec![];
+    for (name, chain) in &settings.networks {
+        other_networks.push(NetworkContext {
+            name: name.clone(),
+            aptos_full_index_db: chain.full_indexer.connect()?,
+            function_index_db: chain.function_indexer.connect()?,
+            node_url: chain.node_url.clone(),
+            chain_id: chain.chain_id,
+            auth_keys: config_auth_keys(&chain.node_url)?,
+            executor: chain.executor.clone(),
+        });
+    }
+
+    let cache = Cache::new(&settings.cache).map_err(|e| ConfigError::Invalid {
+        problems: vec![format!("cannot use cache: {}", e)],
+    })?;
 
-    cfg.app_data(Data::new(ApiContext::new(
+    Ok(ApiContext::new(
         app_db,
-        aptos_full_indexer_db,
-        function_indexer_db,
-    )));
+        default_network,
+        other_networks,
+        config_domain_verifier(&settings)?,
+        cache,
+        settings,
+    ))
+}
+
+/// Migrates the app database when enabled, then refuses to start against indexer databases
+/// lacking the tables the api reads
+pub async fn config_databases(context: &ApiContext) -> Result<(), ConfigError> {
+    if context.settings.database.run_migrations {
+        schema::run_migrations(&context.app_db)
+            .await
+            .map_err(|e| ConfigError::Migrate { msg: e.to_string() })?;
+    }
+
+    let mut checks = vec![];
+    for network in &context.networks {
+        let prefix = match network.name == context.settings.network.name {
+            true => "database".to_string(),
+            false => format!("networks.{}", network.name),
+        };
+        checks.push((
+            format!("{}.full_indexer", prefix),
+            &network.aptos_full_index_db,
+            &schema::MOVE_MODULES,
+        ));
+        checks.push((
+            format!("{}.function_indexer", prefix),
+            &network.function_index_db,
+            &schema::MODULE_FUNCTION,
+        ));
+    }
+
+    let mut problems = vec![];
+    for (name, pool, table) in checks {
+        match schema::check_tables(pool, &[table]).await {
+            Ok(missing) => problems.extend(
+                missing
+                    .into_iter()
+                    .map(|problem| format!("{}: {}", name, problem)),
+            ),
+            Err(e) => problems.push(format!("{}: cannot read schema: {}", name, e)),
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(ConfigError::Schema { problems });
+    }
+
+    Ok(())
+}
+
+/// Point both urls at a local stand-in to verify domains without touching the internet
+pub fn config_domain_verifier(settings: &Settings) -> Result<DomainVerifier, ConfigError> {
+    DomainVerifier::new(
+        &settings.network.domain_verify_well_known_url,
+        &settings.network.domain_verify_dns_over_https_url,
+    )
+    .map_err(|e| ConfigError::Invalid {
+        problems: vec![format!(
+            "cannot build a client for domain verification: {}",
+            e
+        )],
+    })
+}
+
+/// Functions are only indexed in-process for the default network, other networks are read from
+/// their own function indexer, which `Settings::validate` requires. Nothing invalidates the cache for a network whose functions are
+/// indexed elsewhere, its function pages are served until `cache.ttl_secs` expires
+pub fn config_function_indexer(
+    context: &ApiContext,
+    network: &NetworkContext,
+) -> Option<FunctionIndexer> {
+    let settings = &context.settings;
+    let indexer = &settings.indexers.function;
+
+    if network.name != context.default_network().name
+        || !indexer.is_enabled(settings.database.function_indexer.is_none())
+    {
+        return None;
+    }
+
+    Some(FunctionIndexer::new(
+        network.aptos_full_index_db.clone(),
+        network.function_index_db.clone(),
+        indexer.indexer_config(),
+        context.cache.clone(),
+        network.name.clone(),
+    ))
+}
+
+pub fn config_dependency_indexer(
+    context: &ApiContext,
+    network: &NetworkContext,
+) -> Option<DependencyIndexer> {
+    let indexer = &context.settings.indexers.dependency;
+
+    if !indexer.is_enabled(true) {
+        return None;
+    }
+
+    Some(DependencyIndexer::new(
+        network.name.clone(),
+        network.aptos_full_index_db.clone(),
+        context.app_db.clone(),
+        indexer.indexer_config(),
+    ))
+}
+
+pub fn config_resource_account_indexer(
+    context: &ApiContext,
+    network: &NetworkContext,
+) -> Option<ResourceAccountIndexer> {
+    let indexer = &context.settings.indexers.resource_account;
+
+    if !indexer.is_enabled(true) {
+        return None;
+    }
+
+    Some(ResourceAccountIndexer::new(
+        network.name.clone(),
+        network.aptos_full_index_db.clone(),
+        context.app_db.clone(),
+        indexer.indexer_config(),
+    ))
+}
+
+pub fn config_function_usage_indexer(
+    context: &ApiContext,
+    network: &NetworkContext,
+) -> Option<FunctionUsageIndexer> {
+    let settings = &context.settings;
+    let indexer = &settings.indexers.function_usage;
+
+    if !indexer.is_enabled(true) {
+        return None;
+    }
+
+    Some(FunctionUsageIndexer::new(
+        network.name.clone(),
+        network.aptos_full_index_db.clone(),
+        context.app_db.clone(),
+        indexer.indexer_config(),
+        settings.function_usage.windows.clone(),
+        Duration::from_secs(settings.function_usage.refresh_interval_secs),
+    ))
+}
+
+/// Backfilling re-checks every past upgrade instead of starting at the chain head
+pub fn config_block_stack_checker(
+    context: &ApiContext,
+    network: &NetworkContext,
+) -> Option<BlockStackChecker> {
+    let settings = &context.settings;
+    let indexer = &settings.indexers.block_stack_checker;
+
+    if !indexer.is_enabled(true) {
+        return None;
+    }
+
+    Some(BlockStackChecker::new(
+        network.name.clone(),
+        network.aptos_full_index_db.clone(),
+        context.app_db.clone(),
+        indexer.indexer_config(),
+        settings.indexers.recompile_stale_block_stacks,
+        settings.compile.clone(),
+        context.compile_jobs.clone(),
+    ))
+}
+
+#[cfg(test)]
+mod cors {
+    use crate::config::{config_service, CorsSettings};
+    use actix_web::http::{header, StatusCode};
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::App;
+
+    fn preflight(uri: &str, origin: &str, method: &str, headers: &str) -> TestRequest {
+        TestRequest::default()
+            .method(actix_web::http::Method::OPTIONS)
+            .uri(uri)
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, method))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, headers))
+    }
+
+    #[actix_rt::test]
+    async fn test_preflights() {
+        let mut cors = CorsSettings::default();
+        cors.block_stacks.allowed_origins = vec!["https://moveblock.xyz".to_string()];
+        cors.block_stacks.supports_credentials = true;
+        let app = init_service(App::new().configure(|cfg| config_service(cfg, &cors))).await;
+
+        // Public reads echo any origin, never with credentials
+        let req = preflight(
+            "/api/v1/functions",
+            "https://a.example",
+            "GET",
+            "x-aptos-network",
+        );
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://a.example"
+        );
+        assert!(res
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+            .is_none());
+
+        // Signed writes are not sent from any origin unless listed
+        let req = preflight(
+            "/api/v1/accounts/0x1",
+            "https://a.example",
+            "POST",
+            "content-type",
+        );
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        // Block stacks only answer listed origins, and the preflight is not asked for a session
+        let req = preflight(
+            "/api/v1/block-stacks/0x1",
+            "https://moveblock.xyz",
+            "POST",
+            "authorization,content-type",
+        );
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://moveblock.xyz"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .unwrap(),
+            "true"
+        );
+
+        let req = preflight(
+            "/api/v1/block-stacks/0x1",
+            "https://a.example",
+            "POST",
+            "authorization",
+        );
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(res
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
 }
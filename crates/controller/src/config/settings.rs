@@ -0,0 +1,1161 @@
+use crate::indexer::IndexerConfig;
+
+use database::db::{new_postgres_pool, PostgresPool};
+
+use aptos_sdk::crypto::ed25519::Ed25519PrivateKey;
+use aptos_sdk::crypto::ValidCryptoMaterialStringExt;
+use aptos_sdk::move_types::account_address::AccountAddress;
+
+use actix_web::http::header::HeaderName;
+use actix_web::http::Method;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use url::Url;
+
+/// Read when `CONFIG_FILE` is not set, skipped if missing
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Backends `cache.backend` accepts, `none` disables caching
+pub const CACHE_BACKENDS: [&str; 3] = ["memory", "redis", "none"];
+
+/// Names a network can be configured and requested under
+pub const NETWORKS: [&str; 4] = ["mainnet", "testnet", "devnet", "local"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {path}: {msg}")]
+    Read { path: String, msg: String },
+
+    #[error("cannot parse config file {path}: {msg}")]
+    Parse { path: String, msg: String },
+
+    #[error("invalid configuration:\n  {}", .problems.join("\n  "))]
+    Invalid { problems: Vec<String> },
+
+    #[error("cannot migrate the app database: {msg}")]
+    Migrate { msg: String },
+
+    #[error("unexpected database schema:\n  {}", .problems.join("\n  "))]
+    Schema { problems: Vec<String> },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        ServerSettings {
+            host: "0.0.0.0".to_string(),
+            port: 8000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PoolSettings {
+    pub url: String,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        PoolSettings {
+            url: String::new(),
+            max_connections: 10,
+            acquire_timeout_secs: 2,
+        }
+    }
+}
+
+impl PoolSettings {
+    pub fn connect(&self) -> Result<PostgresPool, ConfigError> {
+        new_postgres_pool(
+            &self.url,
+            self.max_connections,
+            Duration::from_secs(self.acquire_timeout_secs),
+        )
+        .map_err(|e| ConfigError::Invalid {
+            problems: vec![format!("cannot use database url: {}", e)],
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DatabaseSettings {
+    /// Apply the embedded migrations to the app database on startup
+    pub run_migrations: bool,
+    pub app: PoolSettings,
+    pub full_indexer: PoolSettings,
+    /// Functions are indexed in-process into the app database when unset
+    pub function_indexer: Option<PoolSettings>,
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CorsPolicySettings {
+    /// `*` allows any origin, but never together with credentials or methods other than reads
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    /// Response headers scripts on another origin may read
+    pub exposed_headers: Vec<String>,
+    pub supports_credentials: bool,
+    /// How long browsers may cache a preflight
+    pub max_age_secs: Option<usize>,
+}
+
+impl Default for CorsPolicySettings {
+    fn default() -> Self {
+        CorsPolicySettings {
+            allowed_origins: vec![],
+            allowed_methods: strings(&["GET"]),
+            allowed_headers: strings(&["content-type", "x-aptos-network"]),
+            exposed_headers: strings(&["etag", "retry-after"]),
+            supports_credentials: false,
+            max_age_secs: Some(3600),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CorsSettings {
+    /// Account, function and openapi routes. Reads only by default, account writes carry their
+    /// signature in the body and need `POST` together with a list of origins
+    pub public: CorsPolicySettings,
+    /// Block stack and session routes, block stacks are authenticated by a session token. Its
+    /// origins have no default and must be listed
+    pub block_stacks: CorsPolicySettings,
+}
+
+impl Default for CorsSettings {
+    fn default() -> Self {
+        CorsSettings {
+            public: CorsPolicySettings {
+                allowed_origins: strings(&["*"]),
+                allowed_headers: strings(&["content-type", "if-none-match", "x-aptos-network"]),
+                ..CorsPolicySettings::default()
+            },
+            block_stacks: CorsPolicySettings {
+                allowed_methods: strings(&["GET", "POST", "PATCH", "DELETE"]),
+                allowed_headers: strings(&["authorization", "content-type", "x-aptos-network"]),
+                ..CorsPolicySettings::default()
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CompileSettings {
+    /// Block stack scripts are generated and compiled in a fresh directory under it
+    pub script_dir: PathBuf,
+    /// Raw file host the `Move.toml` of dependencies is fetched from
+    pub github_raw_base: String,
+    /// How long shutdown waits for compiles in flight, unfinished ones resume on the next start
+    pub shutdown_timeout_secs: u64,
+    /// Workspaces untouched this long are swept at startup, younger ones may belong to a
+    /// compile still running elsewhere, like `admin recompile`
+    pub stale_workspace_secs: u64,
+}
+
+impl Default for CompileSettings {
+    fn default() -> Self {
+        CompileSettings {
+            script_dir: std::env::temp_dir().join("moveblock-scripts"),
+            github_raw_base: "https://raw.githubusercontent.com".to_string(),
+            shutdown_timeout_secs: 30,
+            stale_workspace_secs: 3600,
+        }
+    }
+}
+
+/// Account block stacks are submitted from, execution is refused while either value is unset
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExecutorSettings {
+    /// Ed25519 private key, hex encoded
+    pub private_key: String,
+    pub address: String,
+}
+
+impl ExecutorSettings {
+    pub fn is_configured(&self) -> bool {
+        !self.private_key.is_empty() && !self.address.is_empty()
+    }
+
+    /// `env` names the variables of the key and the address, empty for other networks
+    fn problems(&self, name: &str, env: (&str, &str)) -> Vec<String> {
+        let mut problems = vec![];
+        if self.private_key.is_empty() != self.address.is_empty() {
+            problems.push(format!(
+                "{}.private_key{} and address{} must be set together",
+                name, env.0, env.1
+            ));
+        }
+        if !self.private_key.is_empty()
+            && Ed25519PrivateKey::from_encoded_string(&self.private_key).is_err()
+        {
+            problems.push(format!(
+                "{}.private_key{} is not an ed25519 private key",
+                name, env.0
+            ));
+        }
+        if !self.address.is_empty() && AccountAddress::from_hex_literal(&self.address).is_err() {
+            problems.push(format!(
+                "{}.address{} is not an account address",
+                name, env.1
+            ));
+        }
+        problems
+    }
+}
+
+/// The key never ends up in logs
+impl std::fmt::Debug for ExecutorSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecutorSettings")
+            .field("private_key", &"<redacted>")
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+/// The default network, `database.full_indexer` and `database.function_indexer` index it
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// Served when a request names no network
+    pub name: String,
+    /// REST endpoint block stacks are submitted to
+    pub node_url: String,
+    pub chain_id: u8,
+    /// `{domain}` is replaced by the domain being verified
+    pub domain_verify_well_known_url: String,
+    pub domain_verify_dns_over_https_url: String,
+    pub executor: ExecutorSettings,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        NetworkSettings {
+            name: "devnet".to_string(),
+            node_url: "https://fullnode.devnet.aptoslabs.com/v1".to_string(),
+            chain_id: 43,
+            domain_verify_well_known_url: "https://{domain}/.well-known/moveblock.txt".to_string(),
+            domain_verify_dns_over_https_url: "https://dns.google/resolve".to_string(),
+            executor: ExecutorSettings::default(),
+        }
+    }
+}
+
+/// A network served next to the default one, requests select it by name
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ChainSettings {
+    pub node_url: String,
+    pub chain_id: u8,
+    pub full_indexer: PoolSettings,
+    /// Functions are only indexed in-process for the default network, so it is required and
+    /// cannot be the app database
+    pub function_indexer: PoolSettings,
+    pub executor: ExecutorSettings,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct FunctionUsageSettings {
+    /// Days usage is rolled up over, the first one is the default
+    pub windows: Vec<i32>,
+    pub refresh_interval_secs: u64,
+}
+
+impl Default for FunctionUsageSettings {
+    fn default() -> Self {
+        FunctionUsageSettings {
+            windows: vec![7, 1, 30],
+            refresh_interval_secs: 60,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct HealthSettings {
+    /// Versions the full indexer may lag behind the node before readiness is degraded
+    pub max_indexer_lag: i64,
+    /// Per dependency, a probe taking longer counts as down
+    pub timeout_ms: u64,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        HealthSettings {
+            max_indexer_lag: 10_000,
+            timeout_ms: 2000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    /// One of `CACHE_BACKENDS`
+    pub backend: String,
+    /// Any server speaking the redis protocol, only read by the redis backend
+    pub redis_url: String,
+    /// Bounds staleness of data no in-process indexer invalidates, like function pages read from
+    /// an external function indexer
+    pub ttl_secs: u64,
+    /// Entries the memory backend holds before evicting
+    pub capacity: usize,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            backend: "memory".to_string(),
+            redis_url: String::new(),
+            ttl_secs: 60,
+            capacity: 10_000,
+        }
+    }
+}
+
+/// A token bucket, `burst` requests at once refilled at `per_minute`
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct BudgetSettings {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl BudgetSettings {
+    pub fn new(burst: u32, per_minute: u32) -> Self {
+        BudgetSettings { burst, per_minute }
+    }
+}
+
+/// Every budget applies per client ip and, once a signature or session is verified, per signer too
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RateLimitSettings {
+    pub enabled: bool,
+    /// Key clients by `Forwarded` or `X-Forwarded-For`, only behind a proxy that sets them
+    pub trust_forwarded_for: bool,
+    pub reads: BudgetSettings,
+    pub signed_writes: BudgetSettings,
+    /// Creating or editing a block stack compiles it
+    pub compiles: BudgetSettings,
+    pub executions: BudgetSettings,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        RateLimitSettings {
+            enabled: true,
+            trust_forwarded_for: false,
+            reads: BudgetSettings::new(120, 600),
+            signed_writes: BudgetSettings::new(20, 60),
+            compiles: BudgetSettings::new(5, 10),
+            executions: BudgetSettings::new(10, 30),
+        }
+    }
+}
+
+/// Lifetimes of the tokens handed out after a signed login
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SessionSettings {
+    /// How long a challenge may be signed for
+    pub challenge_ttl_secs: u64,
+    /// Session tokens are short lived and sent with every block stack request
+    pub token_ttl_secs: u64,
+    /// A refresh token is exchanged once for a new pair, until it expires too
+    pub refresh_ttl_secs: u64,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        SessionSettings {
+            challenge_ttl_secs: 300,
+            token_ttl_secs: 900,
+            refresh_ttl_secs: 7 * 24 * 3600,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct IndexerSettings {
+    /// Each indexer decides its own default when unset
+    pub enabled: Option<bool>,
+    pub batch_size: i64,
+    pub poll_interval_ms: u64,
+    pub backfill: bool,
+}
+
+impl Default for IndexerSettings {
+    fn default() -> Self {
+        IndexerSettings {
+            enabled: None,
+            batch_size: 500,
+            poll_interval_ms: 1000,
+            backfill: false,
+        }
+    }
+}
+
+impl IndexerSettings {
+    pub fn is_enabled(&self, default: bool) -> bool {
+        self.enabled.unwrap_or(default)
+    }
+
+    pub fn indexer_config(&self) -> IndexerConfig {
+        IndexerConfig {
+            batch_size: self.batch_size,
+            poll_interval: Duration::from_millis(self.poll_interval_ms),
+            backfill: self.backfill,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct IndexersSettings {
+    pub function: IndexerSettings,
+    pub dependency: IndexerSettings,
+    pub resource_account: IndexerSettings,
+    pub function_usage: IndexerSettings,
+    pub block_stack_checker: IndexerSettings,
+    /// Recompile stale stacks right away instead of waiting for their next edit
+    pub recompile_stale_block_stacks: bool,
+}
+
+/// Loaded once at startup from an optional toml file, then overridden by env
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub server: ServerSettings,
+    pub database: DatabaseSettings,
+    pub cors: CorsSettings,
+    pub compile: CompileSettings,
+    pub network: NetworkSettings,
+    /// Keyed by network name, the default network is configured by `network`
+    pub networks: BTreeMap<String, ChainSettings>,
+    pub function_usage: FunctionUsageSettings,
+    pub health: HealthSettings,
+    pub cache: CacheSettings,
+    pub rate_limit: RateLimitSettings,
+    pub session: SessionSettings,
+    pub indexers: IndexersSettings,
+}
+
+/// Collects every malformed variable instead of stopping at the first one
+struct EnvOverrides<'a> {
+    lookup: &'a dyn Fn(&str) -> Option<String>,
+    problems: Vec<String>,
+}
+
+impl<'a> EnvOverrides<'a> {
+    fn parse<T: FromStr>(&mut self, key: &str) -> Option<T> {
+        let value = (self.lookup)(key)?;
+        match value.trim().parse() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                self.problems
+                    .push(format!("env {} has an invalid value {:?}", key, value));
+                None
+            }
+        }
+    }
+
+    fn set<T: FromStr>(&mut self, key: &str, target: &mut T) {
+        if let Some(value) = self.parse(key) {
+            *target = value;
+        }
+    }
+
+    fn set_option<T: FromStr>(&mut self, key: &str, target: &mut Option<T>) {
+        if let Some(value) = self.parse(key) {
+            *target = Some(value);
+        }
+    }
+
+    fn set_list<T: FromStr>(&mut self, key: &str, target: &mut Vec<T>) {
+        let value = match (self.lookup)(key) {
+            Some(value) => value,
+            None => return,
+        };
+
+        let parsed: Result<Vec<T>, _> = value
+            .split(',')
+            .filter(|item| !item.trim().is_empty())
+            .map(|item| item.trim().parse())
+            .collect();
+
+        match parsed {
+            Ok(parsed) => *target = parsed,
+            Err(_) => self
+                .problems
+                .push(format!("env {} has an invalid value {:?}", key, value)),
+        }
+    }
+
+    fn set_indexer(&mut self, prefix: &str, target: &mut IndexerSettings) {
+        self.set_option(&format!("{}_ENABLED", prefix), &mut target.enabled);
+        self.set(&format!("{}_BATCH_SIZE", prefix), &mut target.batch_size);
+        self.set(
+            &format!("{}_POLL_INTERVAL_MS", prefix),
+            &mut target.poll_interval_ms,
+        );
+        self.set(&format!("{}_BACKFILL", prefix), &mut target.backfill);
+    }
+}
+
+impl Settings {
+    /// `CONFIG_FILE` or `config.toml`, then env, then validation
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut settings = match std::env::var("CONFIG_FILE") {
+            Ok(path) => Self::from_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            Err(_) => Self::default(),
+        };
+
+        settings.apply_env(&|key| std::env::var(key).ok())?;
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|e| ConfigError::Read {
+            path: path.display().to_string(),
+            msg: e.to_string(),
+        })?;
+
+        toml::from_str(&content).map_err(|e| ConfigError::Parse {
+            path: path.display().to_string(),
+            msg: e.to_string(),
+        })
+    }
+
+    /// Env names predate the config file and are kept as they were
+    pub fn apply_env(
+        &mut self,
+        lookup: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<(), ConfigError> {
+        let mut env = EnvOverrides {
+            lookup,
+            problems: vec![],
+        };
+
+        env.set("HOST", &mut self.server.host);
+        env.set("PORT", &mut self.server.port);
+
+        env.set("RUN_MIGRATIONS", &mut self.database.run_migrations);
+        env.set("DATABASE_URL", &mut self.database.app.url);
+        env.set(
+            "DATABASE_MAX_CONNECTIONS",
+            &mut self.database.app.max_connections,
+        );
+        env.set("FULL_INDEXER_URL", &mut self.database.full_indexer.url);
+        env.set(
+            "FULL_INDEXER_MAX_CONNECTIONS",
+            &mut self.database.full_indexer.max_connections,
+        );
+        if let Some(url) = env.parse::<String>("FUNCTION_INDEXER_URL") {
+            self.database
+                .function_indexer
+                .get_or_insert_with(PoolSettings::default)
+                .url = url;
+        }
+        if let Some(function_indexer) = self.database.function_indexer.as_mut() {
+            env.set(
+                "FUNCTION_INDEXER_MAX_CONNECTIONS",
+                &mut function_indexer.max_connections,
+            );
+        }
+
+        env.set_list(
+            "CORS_ALLOWED_ORIGINS",
+            &mut self.cors.public.allowed_origins,
+        );
+        env.set_list(
+            "CORS_ALLOWED_METHODS",
+            &mut self.cors.public.allowed_methods,
+        );
+        env.set_list(
+            "CORS_BLOCK_STACKS_ALLOWED_ORIGINS",
+            &mut self.cors.block_stacks.allowed_origins,
+        );
+        env.set_list(
+            "CORS_BLOCK_STACKS_ALLOWED_METHODS",
+            &mut self.cors.block_stacks.allowed_methods,
+        );
+        env.set(
+            "CORS_BLOCK_STACKS_SUPPORTS_CREDENTIALS",
+            &mut self.cors.block_stacks.supports_credentials,
+        );
+
+        env.set("MOVE_SCRIPT_DEFAULT_PATH", &mut self.compile.script_dir);
+        env.set("GITHUB_RAW_BASE", &mut self.compile.github_raw_base);
+        env.set(
+            "COMPILE_SHUTDOWN_TIMEOUT_SECS",
+            &mut self.compile.shutdown_timeout_secs,
+        );
+        env.set(
+            "COMPILE_STALE_WORKSPACE_SECS",
+            &mut self.compile.stale_workspace_secs,
+        );
+
+        env.set("APTOS_NETWORK", &mut self.network.name);
+        env.set("APTOS_NODE_URL", &mut self.network.node_url);
+        env.set("APTOS_CHAIN_ID", &mut self.network.chain_id);
+        env.set(
+            "DOMAIN_VERIFY_WELL_KNOWN_URL",
+            &mut self.network.domain_verify_well_known_url,
+        );
+        env.set(
+            "DOMAIN_VERIFY_DNS_OVER_HTTPS_URL",
+            &mut self.network.domain_verify_dns_over_https_url,
+        );
+        env.set(
+            "EXECUTOR_PRIVATE_KEY",
+            &mut self.network.executor.private_key,
+        );
+        env.set("EXECUTOR_ADDRESS", &mut self.network.executor.address);
+
+        env.set_list("FUNCTION_USAGE_WINDOWS", &mut self.function_usage.windows);
+        env.set(
+            "FUNCTION_USAGE_REFRESH_INTERVAL_SECS",
+            &mut self.function_usage.refresh_interval_secs,
+        );
+
+        env.set("HEALTH_MAX_INDEXER_LAG", &mut self.health.max_indexer_lag);
+        env.set("HEALTH_TIMEOUT_MS", &mut self.health.timeout_ms);
+
+        env.set("CACHE_BACKEND", &mut self.cache.backend);
+        env.set("CACHE_REDIS_URL", &mut self.cache.redis_url);
+        env.set("CACHE_TTL_SECS", &mut self.cache.ttl_secs);
+
+        env.set("RATE_LIMIT_ENABLED", &mut self.rate_limit.enabled);
+        env.set(
+            "RATE_LIMIT_TRUST_FORWARDED_FOR",
+            &mut self.rate_limit.trust_forwarded_for,
+        );
+
+        env.set("SESSION_TOKEN_TTL_SECS", &mut self.session.token_ttl_secs);
+        env.set(
+            "SESSION_REFRESH_TTL_SECS",
+            &mut self.session.refresh_ttl_secs,
+        );
+
+        env.set_indexer("FUNCTION_INDEXER", &mut self.indexers.function);
+        env.set_indexer("DEPENDENCY_INDEXER", &mut self.indexers.dependency);
+        env.set_indexer(
+            "RESOURCE_ACCOUNT_INDEXER",
+            &mut self.indexers.resource_account,
+        );
+        env.set_indexer("FUNCTION_USAGE_INDEXER", &mut self.indexers.function_usage);
+        env.set_indexer(
+            "BLOCK_STACK_CHECKER",
+            &mut self.indexers.block_stack_checker,
+        );
+        env.set(
+            "BLOCK_STACK_CHECKER_RECOMPILE",
+            &mut self.indexers.recompile_stale_block_stacks,
+        );
+
+        if !env.problems.is_empty() {
+            return Err(ConfigError::Invalid {
+                problems: env.problems,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reports every problem at once, naming the setting and its env variable
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = vec![];
+
+        if self.server.port == 0 {
+            problems.push("server.port (env PORT) must not be 0".to_string());
+        }
+
+        let mut pools = vec![
+            (
+                "database.app".to_string(),
+                " (env DATABASE_URL)",
+                &self.database.app,
+            ),
+            (
+                "database.full_indexer".to_string(),
+                " (env FULL_INDEXER_URL)",
+                &self.database.full_indexer,
+            ),
+        ];
+        if let Some(function_indexer) = &self.database.function_indexer {
+            pools.push((
+                "database.function_indexer".to_string(),
+                " (env FUNCTION_INDEXER_URL)",
+                function_indexer,
+            ));
+        }
+        for (name, chain) in &self.networks {
+            pools.push((
+                format!("networks.{}.full_indexer", name),
+                "",
+                &chain.full_indexer,
+            ));
+            // The in-process function indexer writes rows without a network, so it only runs for
+            // the default network and the app database holds its functions alone
+            if chain.function_indexer.url.is_empty() {
+                problems.push(format!(
+                    "networks.{}.function_indexer.url is required, functions are only indexed in-process for the default network",
+                    name
+                ));
+            } else if chain.function_indexer.url == self.database.app.url {
+                problems.push(format!(
+                    "networks.{}.function_indexer.url cannot be the app database, which holds the functions of the default network",
+                    name
+                ));
+            } else {
+                pools.push((
+                    format!("networks.{}.function_indexer", name),
+                    "",
+                    &chain.function_indexer,
+                ));
+            }
+        }
+
+        for (name, env, pool) in pools {
+            if pool.url.is_empty() {
+                problems.push(format!("{}.url{} is required", name, env));
+            } else if !pool.url.starts_with("postgres://") && !pool.url.starts_with("postgresql://")
+            {
+                problems.push(format!("{}.url{} must be a postgres url", name, env));
+            }
+
+            if pool.max_connections == 0 {
+                problems.push(format!("{}.max_connections must be at least 1", name));
+            }
+        }
+
+        // Without it the frontend cannot reach block stacks and sessions at all
+        if self.cors.block_stacks.allowed_origins.is_empty() {
+            problems.push(
+                "cors.block_stacks.allowed_origins (env CORS_BLOCK_STACKS_ALLOWED_ORIGINS) is required"
+                    .to_string(),
+            );
+        }
+
+        let policies = [
+            ("public", &self.cors.public),
+            ("block_stacks", &self.cors.block_stacks),
+        ];
+        for (name, policy) in policies {
+            for origin in &policy.allowed_origins {
+                if origin != "*" && !is_http_url(origin) {
+                    problems.push(format!(
+                        "cors.{}.allowed_origins has an invalid origin {:?}",
+                        name, origin
+                    ));
+                }
+            }
+
+            if policy.supports_credentials && policy.allowed_origins.iter().any(|o| o == "*") {
+                problems.push(format!(
+                    "cors.{} must list its origins to support credentials",
+                    name
+                ));
+            }
+
+            let allows_writes = policy
+                .allowed_methods
+                .iter()
+                .any(|m| !m.eq_ignore_ascii_case("GET") && !m.eq_ignore_ascii_case("HEAD"));
+            if allows_writes && policy.allowed_origins.iter().any(|o| o == "*") {
+                problems.push(format!(
+                    "cors.{} must list its origins to allow methods other than GET",
+                    name
+                ));
+            }
+
+            for method in &policy.allowed_methods {
+                if Method::from_str(method).is_err() {
+                    problems.push(format!(
+                        "cors.{}.allowed_methods has an invalid method {:?}",
+                        name, method
+                    ));
+                }
+            }
+
+            let headers = policy.allowed_headers.iter().chain(&policy.exposed_headers);
+            for header in headers {
+                if HeaderName::from_str(header).is_err() {
+                    problems.push(format!("cors.{} has an invalid header {:?}", name, header));
+                }
+            }
+        }
+
+        if self.compile.script_dir.as_os_str().is_empty() {
+            problems
+                .push("compile.script_dir (env MOVE_SCRIPT_DEFAULT_PATH) is required".to_string());
+        }
+
+        if !is_http_url(&self.compile.github_raw_base) {
+            problems.push(
+                "compile.github_raw_base (env GITHUB_RAW_BASE) must be an http(s) url".to_string(),
+            );
+        }
+
+        if !is_http_url(&self.network.node_url) {
+            problems
+                .push("network.node_url (env APTOS_NODE_URL) must be an http(s) url".to_string());
+        }
+
+        if !NETWORKS.contains(&self.network.name.as_str()) {
+            problems.push(format!(
+                "network.name (env APTOS_NETWORK) must be one of {}",
+                NETWORKS.join(", ")
+            ));
+        }
+
+        problems.extend(self.network.executor.problems(
+            "network.executor",
+            (" (env EXECUTOR_PRIVATE_KEY)", " (env EXECUTOR_ADDRESS)"),
+        ));
+
+        for (name, chain) in &self.networks {
+            problems.extend(
+                chain
+                    .executor
+                    .problems(&format!("networks.{}.executor", name), ("", "")),
+            );
+
+            if !NETWORKS.contains(&name.as_str()) {
+                problems.push(format!(
+                    "networks.{} is not one of {}",
+                    name,
+                    NETWORKS.join(", ")
+                ));
+            } else if name == &self.network.name {
+                problems.push(format!(
+                    "networks.{} is the default network, configure it under network",
+                    name
+                ));
+            }
+
+            if !is_http_url(&chain.node_url) {
+                problems.push(format!("networks.{}.node_url must be an http(s) url", name));
+            }
+        }
+
+        if !self
+            .network
+            .domain_verify_well_known_url
+            .contains("{domain}")
+        {
+            problems.push("network.domain_verify_well_known_url must contain {domain}".to_string());
+        }
+
+        if !is_http_url(&self.network.domain_verify_dns_over_https_url) {
+            problems.push(
+                "network.domain_verify_dns_over_https_url must be an http(s) url".to_string(),
+            );
+        }
+
+        if self.function_usage.windows.is_empty()
+            || self.function_usage.windows.iter().any(|w| *w <= 0)
+        {
+            problems.push(
+                "function_usage.windows (env FUNCTION_USAGE_WINDOWS) must be positive days"
+                    .to_string(),
+            );
+        }
+
+        if self.health.max_indexer_lag < 0 || self.health.timeout_ms == 0 {
+            problems.push(
+                "health.max_indexer_lag must not be negative and health.timeout_ms must be at least 1"
+                    .to_string(),
+            );
+        }
+
+        if !CACHE_BACKENDS.contains(&self.cache.backend.as_str()) {
+            problems.push(format!(
+                "cache.backend (env CACHE_BACKEND) must be one of {}",
+                CACHE_BACKENDS.join(", ")
+            ));
+        } else if self.cache.backend == "redis"
+            && !self.cache.redis_url.starts_with("redis://")
+            && !self.cache.redis_url.starts_with("rediss://")
+        {
+            problems.push("cache.redis_url (env CACHE_REDIS_URL) must be a redis url".to_string());
+        }
+
+        if self.cache.ttl_secs == 0 || self.cache.capacity == 0 {
+            problems.push("cache.ttl_secs and cache.capacity must be at least 1".to_string());
+        }
+
+        let budgets = [
+            ("reads", &self.rate_limit.reads),
+            ("signed_writes", &self.rate_limit.signed_writes),
+            ("compiles", &self.rate_limit.compiles),
+            ("executions", &self.rate_limit.executions),
+        ];
+        for (name, budget) in budgets {
+            if budget.burst == 0 || budget.per_minute == 0 {
+                problems.push(format!(
+                    "rate_limit.{}.burst and per_minute must be at least 1",
+                    name
+                ));
+            }
+        }
+
+        let session = &self.session;
+        if session.challenge_ttl_secs == 0 || session.token_ttl_secs == 0 {
+            problems.push(
+                "session.challenge_ttl_secs and token_ttl_secs must be at least 1".to_string(),
+            );
+        }
+
+        if session.refresh_ttl_secs < session.token_ttl_secs {
+            problems.push(
+                "session.refresh_ttl_secs must be at least session.token_ttl_secs".to_string(),
+            );
+        }
+
+        let indexers = [
+            ("function", &self.indexers.function),
+            ("dependency", &self.indexers.dependency),
+            ("resource_account", &self.indexers.resource_account),
+            ("function_usage", &self.indexers.function_usage),
+            ("block_stack_checker", &self.indexers.block_stack_checker),
+        ];
+        for (name, indexer) in indexers {
+            if indexer.batch_size <= 0 {
+                problems.push(format!("indexers.{}.batch_size must be at least 1", name));
+            }
+        }
+
+        // Migrations only run against the app database, an external one is filled by its own
+        // indexer
+        if self.database.function_indexer.is_some() && self.indexers.function.is_enabled(false) {
+            problems.push(
+                "indexers.function.enabled (env FUNCTION_INDEXER_ENABLED) cannot be set together with database.function_indexer (env FUNCTION_INDEXER_URL)"
+                    .to_string(),
+            );
+        }
+
+        if !problems.is_empty() {
+            return Err(ConfigError::Invalid { problems });
+        }
+
+        Ok(())
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    matches!(Url::parse(value), Ok(url) if url.scheme() == "https" || url.scheme() == "http")
+}
+
+#[cfg(test)]
+mod load {
+    use crate::config::settings::{ConfigError, Settings};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[test]
+    fn test_file_env_and_validation() {
+        let mut settings: Settings = toml::from_str(
+            r#"
+                [server]
+                port = 9000
+
+                [database.app]
+                url = "postgres://localhost/moveblock"
+                max_connections = 4
+
+                [indexers.dependency]
+                enabled = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(settings.server.host, "0.0.0.0");
+        assert_eq!(settings.database.app.max_connections, 4);
+        assert!(!settings.indexers.dependency.is_enabled(true));
+        assert!(settings.indexers.function.is_enabled(true));
+
+        // Full indexer url and block stack origins are missing
+        match settings.validate() {
+            Err(ConfigError::Invalid { problems }) => {
+                assert_eq!(problems.len(), 2);
+                assert!(problems[0].contains("FULL_INDEXER_URL"));
+                assert!(problems[1].contains("CORS_BLOCK_STACKS_ALLOWED_ORIGINS"));
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+
+        let env = HashMap::from([
+            ("PORT", "8080"),
+            ("FULL_INDEXER_URL", "postgresql://localhost/indexer"),
+            ("FUNCTION_USAGE_WINDOWS", "30, 7"),
+            ("RUN_MIGRATIONS", "true"),
+            (
+                "CORS_ALLOWED_ORIGINS",
+                "https://moveblock.xyz,http://localhost:3000",
+            ),
+            ("CORS_ALLOWED_METHODS", "GET,POST"),
+            ("CORS_BLOCK_STACKS_ALLOWED_ORIGINS", "https://moveblock.xyz"),
+            ("CORS_BLOCK_STACKS_ALLOWED_METHODS", "GET,POST,PATCH,DELETE"),
+        ]);
+        settings
+            .apply_env(&|key| env.get(key).map(|v| v.to_string()))
+            .unwrap();
+        assert_eq!(settings.server.port, 8080);
+        assert_eq!(settings.function_usage.windows, vec![30, 7]);
+        assert!(settings.database.run_migrations);
+        assert_eq!(settings.cors.public.allowed_methods, vec!["GET", "POST"]);
+        assert!(settings.validate().is_ok());
+
+        let env = HashMap::from([
+            ("FUNCTION_INDEXER_URL", "postgres://localhost/functions"),
+            ("FUNCTION_INDEXER_ENABLED", "true"),
+        ]);
+        let mut external = settings.clone();
+        external
+            .apply_env(&|key| env.get(key).map(|v| v.to_string()))
+            .unwrap();
+        match external.validate() {
+            Err(ConfigError::Invalid { problems }) => assert_eq!(problems.len(), 1),
+            res => panic!("unexpected {:?}", res),
+        }
+
+        let env = HashMap::from([("PORT", "http"), ("FUNCTION_INDEXER_BATCH_SIZE", "many")]);
+        match settings.apply_env(&|key| env.get(key).map(|v| v.to_string())) {
+            Err(ConfigError::Invalid { problems }) => assert_eq!(problems.len(), 2),
+            res => panic!("unexpected {:?}", res),
+        }
+
+        let example = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/config.example.toml"));
+        assert!(Settings::from_file(example).unwrap().validate().is_ok());
+
+        settings.cors.public.allowed_origins = vec!["moveblock.xyz".to_string()];
+        settings.function_usage.windows = vec![];
+        settings.cors.block_stacks.allowed_origins = vec!["*".to_string()];
+        match settings.validate() {
+            Err(ConfigError::Invalid { problems }) => assert_eq!(problems.len(), 3),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_networks() {
+        let mut settings: Settings = toml::from_str(
+            r#"
+                [database.app]
+                url = "postgres://localhost/moveblock"
+
+                [database.full_indexer]
+                url = "postgres://localhost/indexer"
+
+                [cors.block_stacks]
+                allowed_origins = ["https://moveblock.xyz"]
+
+                [networks.mainnet]
+                node_url = "https://fullnode.mainnet.aptoslabs.com/v1"
+                chain_id = 1
+                full_indexer = { url = "postgres://localhost/mainnet" }
+                function_indexer = { url = "postgres://localhost/mainnet" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(settings.network.name, "devnet");
+        assert!(settings.validate().is_ok());
+
+        // Other networks need their own function indexer, and cannot redefine the default one
+        settings
+            .networks
+            .get_mut("mainnet")
+            .unwrap()
+            .function_indexer
+            .url = String::new();
+        settings
+            .networks
+            .insert("devnet".to_string(), settings.networks["mainnet"].clone());
+        match settings.validate() {
+            Err(ConfigError::Invalid { problems }) => {
+                assert_eq!(problems.len(), 3, "{:?}", problems)
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+
+        // Nor share the functions the app database holds for the default network
+        settings.networks.remove("devnet");
+        settings
+            .networks
+            .get_mut("mainnet")
+            .unwrap()
+            .function_indexer
+            .url = settings.database.app.url.clone();
+        match settings.validate() {
+            Err(ConfigError::Invalid { problems }) => {
+                assert_eq!(problems.len(), 1, "{:?}", problems)
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_executor() {
+        let mut settings: Settings = toml::from_str(
+            r#"
+                [database.app]
+                url = "postgres://localhost/moveblock"
+
+                [database.full_indexer]
+                url = "postgres://localhost/indexer"
+
+                [cors.block_stacks]
+                allowed_origins = ["https://moveblock.xyz"]
+            "#,
+        )
+        .unwrap();
+        assert!(!settings.network.executor.is_configured());
+        assert!(settings.validate().is_ok());
+
+        // The key is missing and the address is malformed
+        let env = HashMap::from([("EXECUTOR_ADDRESS", "0xzz")]);
+        settings
+            .apply_env(&|key| env.get(key).map(|v| v.to_string()))
+            .unwrap();
+        match settings.validate() {
+            Err(ConfigError::Invalid { problems }) => {
+                assert_eq!(problems.len(), 2, "{:?}", problems)
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+
+        settings.network.executor.private_key = format!("0x{}", "1".repeat(64));
+        settings.network.executor.address = "0x1".to_string();
+        assert!(settings.validate().is_ok());
+        assert!(!format!("{:?}", settings).contains(&"1".repeat(64)));
+    }
+}
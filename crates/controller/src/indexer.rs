@@ -0,0 +1,134 @@
+pub mod block_stack;
+pub mod dependency;
+pub mod function;
+pub mod resource_account;
+pub mod usage;
+
+use crate::service::Error;
+
+use database::db::PostgresPool;
+use database::models::indexer::status::IndexerStatus;
+use database::models::module_hub::core::module::MoveModule;
+
+use sqlx::{query, query_as, Postgres, Transaction};
+use std::future::Future;
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+pub struct IndexerConfig {
+    /// Max count of distinct transaction versions fetched at once
+    pub batch_size: i64,
+    pub poll_interval: Duration,
+    /// Drop every derived row and start over from the first module
+    pub backfill: bool,
+}
+
+/// Keeps processing batches, sleeping only once caught up or after a failure
+pub(crate) async fn poll<F, Fut>(indexer_name: &str, poll_interval: Duration, mut next_batch: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<usize, Error>>,
+{
+    loop {
+        match next_batch().await {
+            Ok(processed) if processed > 0 => continue,
+            Ok(_) => {}
+            Err(e) => log::error!("{} indexer batch failed: {}", indexer_name, e),
+        }
+
+        actix_rt::time::sleep(poll_interval).await;
+    }
+}
+
+/// Indexers writing to the app database share its `indexer_status` between networks
+pub(crate) fn checkpoint_name(network: &str, indexer_name: &str) -> String {
+    format!("{}.{}", network, indexer_name)
+}
+
+/// Last fully indexed transaction version, -1 before the first batch
+pub(crate) async fn read_checkpoint(db: &PostgresPool, indexer_name: &str) -> Result<i64, Error> {
+    let status: Option<IndexerStatus> = query_as(
+        "
+            SELECT *
+            FROM indexer_status
+            WHERE indexer_name = $1
+        ",
+    )
+    .bind(indexer_name)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| Error::DbError(e.into()))?;
+
+    Ok(status.map(|s| s.last_success_version).unwrap_or(-1))
+}
+
+pub(crate) async fn write_checkpoint(
+    tx: &mut Transaction<'_, Postgres>,
+    indexer_name: &str,
+    version: i64,
+) -> Result<(), Error> {
+    query(
+        "
+            INSERT INTO indexer_status
+                (indexer_name, last_success_version, last_updated)
+                VALUES
+                ($1, $2, now())
+            ON CONFLICT (indexer_name) DO UPDATE SET
+                last_success_version = EXCLUDED.last_success_version,
+                last_updated = EXCLUDED.last_updated
+        ",
+    )
+    .bind(indexer_name)
+    .bind(version)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| Error::DbError(e.into()))?;
+
+    Ok(())
+}
+
+pub(crate) async fn delete_checkpoint(
+    tx: &mut Transaction<'_, Postgres>,
+    indexer_name: &str,
+) -> Result<(), Error> {
+    query("DELETE FROM indexer_status WHERE indexer_name = $1")
+        .bind(indexer_name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::DbError(e.into()))?;
+
+    Ok(())
+}
+
+/// Whole transaction versions only, so a write set is never split between batches
+pub(crate) async fn fetch_next_modules(
+    aptos_full_index_db: &PostgresPool,
+    checkpoint: i64,
+    batch_size: i64,
+) -> Result<Vec<MoveModule>, Error> {
+    query_as(
+        "
+            SELECT *
+            FROM move_modules
+            WHERE
+                transaction_version > $1
+                AND
+                transaction_version <= (
+                    SELECT MAX(transaction_version)
+                    FROM (
+                        SELECT DISTINCT transaction_version
+                        FROM move_modules
+                        WHERE transaction_version > $1
+                        ORDER BY transaction_version
+                        LIMIT $2
+                    ) batch
+                )
+            ORDER BY transaction_version, write_set_change_index
+        ",
+    )
+    .bind(checkpoint)
+    .bind(batch_size)
+    .fetch_all(aptos_full_index_db)
+    .await
+    .map_err(|e| Error::DbError(e.into()))
+}
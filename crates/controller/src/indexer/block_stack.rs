@@ -0,0 +1,256 @@
+use crate::compile::CompileJobs;
+use crate::config::settings::CompileSettings;
+use crate::indexer::{self, IndexerConfig};
+use crate::service::domain::{abi, block_stack, bytecode::standardize_address, module};
+use crate::service::Error;
+
+use database::db::PostgresPool;
+use database::models::block_stack::stack::{
+    BlockStack, BLOCK_STACK_STATUS_BROKEN, BLOCK_STACK_STATUS_STALE,
+};
+use database::models::module_hub::core::module::MoveModule;
+
+use sqlx::query_as;
+use std::collections::HashSet;
+
+pub const BLOCK_STACK_CHECKER_NAME: &str = "block_stack_checker";
+
+#[derive(Debug, Default, sqlx::FromRow)]
+struct MaxVersion {
+    version: Option<i64>,
+}
+
+/// Re-validates saved block stacks of one network whenever a module they call is upgraded
+#[derive(Clone)]
+pub struct BlockStackChecker {
+    network: String,
+    indexer_name: String,
+    aptos_full_index_db: PostgresPool,
+    app_db: PostgresPool,
+    config: IndexerConfig,
+    /// Recompile stale stacks right away instead of waiting for their next edit
+    recompile: bool,
+    compile: CompileSettings,
+    compile_jobs: CompileJobs,
+}
+
+impl BlockStackChecker {
+    pub fn new(
+        network: String,
+        aptos_full_index_db: PostgresPool,
+        app_db: PostgresPool,
+        config: IndexerConfig,
+        recompile: bool,
+        compile: CompileSettings,
+        compile_jobs: CompileJobs,
+    ) -> Self {
+        BlockStackChecker {
+            indexer_name: indexer::checkpoint_name(&network, BLOCK_STACK_CHECKER_NAME),
+            network,
+            aptos_full_index_db,
+            app_db,
+            config,
+            recompile,
+            compile,
+            compile_jobs,
+        }
+    }
+
+    pub async fn run(self) {
+        indexer::poll(&self.indexer_name, self.config.poll_interval, || async {
+            self.process_next_batch().await.map(|modules| modules.len())
+        })
+        .await
+    }
+
+    /// Without a checkpoint only upgrades from now on are checked, unless backfilling
+    async fn checkpoint(&self) -> Result<i64, Error> {
+        let checkpoint = indexer::read_checkpoint(&self.app_db, &self.indexer_name).await?;
+        if checkpoint >= 0 || self.config.backfill {
+            return Ok(checkpoint);
+        }
+
+        let head: MaxVersion = query_as(
+            "
+                SELECT MAX(transaction_version) as version
+                FROM move_modules
+            ",
+        )
+        .fetch_one(&self.aptos_full_index_db)
+        .await
+        .map_err(|e| Error::DbError(e.into()))?;
+
+        let head = head.version.unwrap_or(-1);
+
+        let mut tx = self
+            .app_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+        indexer::write_checkpoint(&mut tx, &self.indexer_name, head).await?;
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))?;
+
+        Ok(head)
+    }
+
+    /// Returns the modules checked by this batch, empty when caught up
+    pub async fn process_next_batch(&self) -> Result<Vec<MoveModule>, Error> {
+        let checkpoint = self.checkpoint().await?;
+
+        let modules = indexer::fetch_next_modules(
+            &self.aptos_full_index_db,
+            checkpoint,
+            self.config.batch_size,
+        )
+        .await?;
+
+        let last_version = match modules.last() {
+            Some(module) => module.transaction_version,
+            None => return Ok(modules),
+        };
+
+        for module in &modules {
+            self.check_module(module).await?;
+        }
+
+        let mut tx = self
+            .app_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+        indexer::write_checkpoint(&mut tx, &self.indexer_name, last_version).await?;
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))?;
+
+        Ok(modules)
+    }
+
+    async fn check_module(&self, module: &MoveModule) -> Result<(), Error> {
+        let previous = match module::get_previous_module_version(
+            &self.aptos_full_index_db,
+            &module.address,
+            &module.name,
+            module.transaction_version,
+        )
+        .await?
+        {
+            Some(previous) => previous,
+            None => return Ok(()),
+        };
+
+        let current = if module.is_deleted {
+            MoveModule {
+                exposed_functions: None,
+                structs: None,
+                ..module.clone()
+            }
+        } else {
+            module.clone()
+        };
+
+        let diff = abi::diff_modules(&previous, &current);
+        let affected = diff
+            .removed_functions
+            .iter()
+            .map(|f| f.name.clone())
+            .chain(diff.changed_functions.iter().map(|c| c.name.clone()))
+            .collect::<HashSet<_>>();
+
+        if affected.is_empty() {
+            return Ok(());
+        }
+
+        let block_stacks = block_stack::get_block_stacks_using_module(
+            &self.app_db,
+            &self.network,
+            &module.address,
+            &module.name,
+        )
+        .await?;
+
+        for stack in block_stacks {
+            self.check_block_stack(&current, &affected, stack).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn check_block_stack(
+        &self,
+        module: &MoveModule,
+        affected: &HashSet<String>,
+        stack: BlockStack,
+    ) -> Result<(), Error> {
+        let address = standardize_address(&module.address);
+        let functions = module.parse_exposed_functions();
+
+        let mut is_stale = false;
+        let mut broken_reason = None;
+
+        for block in block_stack::parse_blocks(&stack.stack) {
+            let function_name = match block_stack::split_function(&block.function) {
+                Some((block_address, module_name, function_name))
+                    if standardize_address(block_address) == address
+                        && module_name == module.name
+                        && affected.contains(function_name) =>
+                {
+                    function_name.to_string()
+                }
+                _ => continue,
+            };
+
+            let function = functions.iter().find(|f| f.name == function_name);
+            match block_stack::check_block(&block, function) {
+                Ok(()) => is_stale = true,
+                Err(reason) => {
+                    broken_reason = Some(reason);
+                    break;
+                }
+            }
+        }
+
+        if let Some(reason) = broken_reason {
+            block_stack::update_block_stack_status(
+                &self.app_db,
+                stack.id,
+                BLOCK_STACK_STATUS_BROKEN,
+                Some(format!(
+                    "upgrade at version {}: {}",
+                    module.transaction_version, reason
+                )),
+            )
+            .await?;
+        } else if is_stale && stack.status != BLOCK_STACK_STATUS_BROKEN {
+            block_stack::update_block_stack_status(
+                &self.app_db,
+                stack.id,
+                BLOCK_STACK_STATUS_STALE,
+                Some(format!(
+                    "{}::{} upgraded at version {}",
+                    module.address, module.name, module.transaction_version
+                )),
+            )
+            .await?;
+
+            // While draining the stack stays stale until its next edit, failing the batch would
+            // only mark it again on every retry
+            let guard = if self.recompile {
+                block_stack::start_compile(&self.compile_jobs).ok()
+            } else {
+                None
+            };
+            if let Some(guard) = guard {
+                block_stack::spawn_create_bytecode(
+                    self.app_db.clone(),
+                    self.compile.clone(),
+                    guard,
+                    self.network.clone(),
+                    stack.stack,
+                    stack.id,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use crate::indexer::{self, IndexerConfig};
+use crate::service::domain::bytecode::{self, standardize_address, BytecodeHandles};
+use crate::service::Error;
+
+use database::db::PostgresPool;
+use database::models::module_hub::core::dependency::{
+    DEPENDENCY_KIND_FRIEND, DEPENDENCY_KIND_USES,
+};
+use database::models::module_hub::core::module::MoveModule;
+
+use sqlx::{query, Postgres, Transaction};
+use std::collections::HashSet;
+
+pub const DEPENDENCY_INDEXER_NAME: &str = "module_dependency";
+
+/// Derives the module graph of one network from friends and bytecode handles of `move_modules`,
+/// only the latest version of a module keeps edges
+#[derive(Clone)]
+pub struct DependencyIndexer {
+    network: String,
+    indexer_name: String,
+    aptos_full_index_db: PostgresPool,
+    app_db: PostgresPool,
+    config: IndexerConfig,
+}
+
+impl DependencyIndexer {
+    pub fn new(
+        network: String,
+        aptos_full_index_db: PostgresPool,
+        app_db: PostgresPool,
+        config: IndexerConfig,
+    ) -> Self {
+        DependencyIndexer {
+            indexer_name: indexer::checkpoint_name(&network, DEPENDENCY_INDEXER_NAME),
+            network,
+            aptos_full_index_db,
+            app_db,
+            config,
+        }
+    }
+
+    pub async fn run(self) {
+        if self.config.backfill {
+            if let Err(e) = self.reset().await {
+                log::error!("dependency indexer reset failed: {}", e);
+                return;
+            }
+        }
+
+        indexer::poll(&self.indexer_name, self.config.poll_interval, || async {
+            self.process_next_batch().await.map(|modules| modules.len())
+        })
+        .await
+    }
+
+    pub async fn reset(&self) -> Result<(), Error> {
+        let mut tx = self
+            .app_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        for table in ["module_dependency", "module_function_usage"] {
+            query(&format!("DELETE FROM {} WHERE network = $1", table))
+                .bind(&self.network)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| Error::DbError(e.into()))?;
+        }
+
+        indexer::delete_checkpoint(&mut tx, &self.indexer_name).await?;
+
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))
+    }
+
+    pub async fn checkpoint(&self) -> Result<i64, Error> {
+        indexer::read_checkpoint(&self.app_db, &self.indexer_name).await
+    }
+
+    /// Returns the modules indexed by this batch, empty when caught up
+    pub async fn process_next_batch(&self) -> Result<Vec<MoveModule>, Error> {
+        let checkpoint = self.checkpoint().await?;
+
+        let modules = indexer::fetch_next_modules(
+            &self.aptos_full_index_db,
+            checkpoint,
+            self.config.batch_size,
+        )
+        .await?;
+
+        let last_version = match modules.last() {
+            Some(module) => module.transaction_version,
+            None => return Ok(modules),
+        };
+
+        let mut tx = self
+            .app_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        for module in &modules {
+            index_module(&mut tx, &self.network, module).await?;
+        }
+
+        indexer::write_checkpoint(&mut tx, &self.indexer_name, last_version).await?;
+
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))?;
+
+        Ok(modules)
+    }
+}
+
+/// `0x1::genesis` entries of `MoveModule.friends`
+fn parse_friends(module: &MoveModule) -> Vec<(String, String)> {
+    serde_json::from_value::<Vec<String>>(module.friends.clone().unwrap_or_default())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|friend| {
+            friend
+                .rsplit_once("::")
+                .map(|(address, name)| (standardize_address(address), name.to_string()))
+        })
+        .collect()
+}
+
+async fn index_module(
+    tx: &mut Transaction<'_, Postgres>,
+    network: &str,
+    module: &MoveModule,
+) -> Result<(), Error> {
+    let address = standardize_address(&module.address);
+
+    for table in ["module_dependency", "module_function_usage"] {
+        query(&format!(
+            "DELETE FROM {} WHERE network = $1 AND address = $2 AND module_name = $3",
+            table
+        ))
+        .bind(network)
+        .bind(&address)
+        .bind(&module.name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::DbError(e.into()))?;
+    }
+
+    if module.is_deleted {
+        return Ok(());
+    }
+
+    let handles = match bytecode::parse_handles(&module.bytecode.clone().unwrap_or_default()) {
+        Ok(handles) => handles,
+        Err(e) => {
+            log::error!(
+                "cannot read bytecode of {}::{}: {}",
+                module.address,
+                module.name,
+                e
+            );
+            BytecodeHandles::default()
+        }
+    };
+
+    let is_self = |handle_address: &str, handle_name: &str| {
+        handle_address == address && handle_name == module.name
+    };
+
+    let dependencies = handles
+        .module_handles
+        .iter()
+        .filter(|h| !is_self(&h.address, &h.name))
+        .map(|h| (h.address.clone(), h.name.clone(), DEPENDENCY_KIND_USES))
+        .chain(
+            parse_friends(module)
+                .into_iter()
+                .map(|(address, name)| (address, name, DEPENDENCY_KIND_FRIEND)),
+        )
+        .collect::<HashSet<_>>();
+
+    for (dependency_address, dependency_module_name, kind) in dependencies {
+        query(
+            "
+                INSERT INTO module_dependency
+                    (network, address, module_name, dependency_address, dependency_module_name, kind, transaction_version)
+                    VALUES
+                    ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT DO NOTHING
+            ",
+        )
+        .bind(network)
+        .bind(&address)
+        .bind(&module.name)
+        .bind(dependency_address)
+        .bind(dependency_module_name)
+        .bind(kind)
+        .bind(module.transaction_version)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::DbError(e.into()))?;
+    }
+
+    let function_usages = handles
+        .function_handles
+        .into_iter()
+        .filter(|h| !is_self(&h.module.address, &h.module.name))
+        .collect::<HashSet<_>>();
+
+    for function in function_usages {
+        query(
+            "
+                INSERT INTO module_function_usage
+                    (network, address, module_name, function_address, function_module_name, function_name, transaction_version)
+                    VALUES
+                    ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT DO NOTHING
+            ",
+        )
+        .bind(network)
+        .bind(&address)
+        .bind(&module.name)
+        .bind(function.module.address)
+        .bind(function.module.name)
+        .bind(function.name)
+        .bind(module.transaction_version)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::DbError(e.into()))?;
+    }
+
+    Ok(())
+}
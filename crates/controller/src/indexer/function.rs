@@ -0,0 +1,200 @@
+use crate::cache::Cache;
+use crate::indexer::{self, IndexerConfig};
+use crate::service::Error;
+
+use database::db::PostgresPool;
+use database::models::module_hub::core::module::MoveModule;
+
+use sqlx::{query, Postgres, Transaction};
+
+pub const FUNCTION_INDEXER_NAME: &str = "module_function";
+
+/// Derives `module_function` rows from `move_modules` of the full indexer
+#[derive(Clone)]
+pub struct FunctionIndexer {
+    aptos_full_index_db: PostgresPool,
+    function_index_db: PostgresPool,
+    config: IndexerConfig,
+    /// A batch means the full indexer advanced, so cached module and function reads are stale
+    cache: Cache,
+    network: String,
+    indexer_name: String,
+}
+
+impl FunctionIndexer {
+    pub fn new(
+        aptos_full_index_db: PostgresPool,
+        function_index_db: PostgresPool,
+        config: IndexerConfig,
+        cache: Cache,
+        network: String,
+    ) -> Self {
+        FunctionIndexer {
+            indexer_name: indexer::checkpoint_name(&network, FUNCTION_INDEXER_NAME),
+            aptos_full_index_db,
+            function_index_db,
+            config,
+            cache,
+            network,
+        }
+    }
+
+    pub async fn run(self) {
+        if self.config.backfill {
+            if let Err(e) = self.reset().await {
+                log::error!("function indexer reset failed: {}", e);
+                return;
+            }
+        }
+
+        indexer::poll(&self.indexer_name, self.config.poll_interval, || async {
+            self.process_next_batch().await.map(|modules| modules.len())
+        })
+        .await
+    }
+
+    pub async fn reset(&self) -> Result<(), Error> {
+        let mut tx = self
+            .function_index_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        query("TRUNCATE module_function")
+            .execute(&mut tx)
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        indexer::delete_checkpoint(&mut tx, &self.indexer_name).await?;
+
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))
+    }
+
+    pub async fn checkpoint(&self) -> Result<i64, Error> {
+        indexer::read_checkpoint(&self.function_index_db, &self.indexer_name).await
+    }
+
+    /// Returns the modules indexed by this batch, empty when caught up
+    pub async fn process_next_batch(&self) -> Result<Vec<MoveModule>, Error> {
+        let checkpoint = self.checkpoint().await?;
+
+        let modules = indexer::fetch_next_modules(
+            &self.aptos_full_index_db,
+            checkpoint,
+            self.config.batch_size,
+        )
+        .await?;
+
+        let last_version = match modules.last() {
+            Some(module) => module.transaction_version,
+            None => return Ok(modules),
+        };
+
+        let mut tx = self
+            .function_index_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        for module in &modules {
+            index_module(&mut tx, module).await?;
+        }
+
+        indexer::write_checkpoint(&mut tx, &self.indexer_name, last_version).await?;
+
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))?;
+        self.cache.invalidate(&self.network).await;
+
+        Ok(modules)
+    }
+}
+
+/// Deletion is stored as a new row at the version of the module, so earlier versions keep their
+/// rows as they were and a function added back later is live again
+async fn index_module(
+    tx: &mut Transaction<'_, Postgres>,
+    module: &MoveModule,
+) -> Result<(), Error> {
+    let functions = if module.is_deleted {
+        vec![]
+    } else {
+        module.parse_exposed_functions()
+    };
+
+    for function in &functions {
+        query(
+            "
+                INSERT INTO module_function
+                    (
+                        module_address, module_name,
+                        move_modules_transaction_version, move_modules_write_set_change_index,
+                        name, visibility, is_entry, generic_type_params, params, return_types
+                    )
+                    VALUES
+                    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT (module_address, module_name, name, move_modules_transaction_version)
+                    DO NOTHING
+            ",
+        )
+        .bind(&module.address)
+        .bind(&module.name)
+        .bind(module.transaction_version)
+        .bind(module.write_set_change_index)
+        .bind(&function.name)
+        .bind(&function.visibility)
+        .bind(function.is_entry)
+        .bind(&function.generic_type_params)
+        .bind(&function.params)
+        .bind(&function.return_types)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::DbError(e.into()))?;
+    }
+
+    let function_names = functions
+        .into_iter()
+        .map(|f| f.name)
+        .collect::<Vec<String>>();
+
+    query(
+        "
+            INSERT INTO module_function
+                (
+                    module_address, module_name,
+                    move_modules_transaction_version, move_modules_write_set_change_index,
+                    name, visibility, is_entry, generic_type_params, params, return_types,
+                    is_deleted
+                )
+                SELECT
+                    module_address, module_name,
+                    $3, $4,
+                    name, visibility, is_entry, generic_type_params, params, return_types,
+                    TRUE
+                FROM (
+                    SELECT DISTINCT ON (name) *
+                        FROM module_function
+                        WHERE
+                            module_address = $1
+                            AND
+                            module_name = $2
+                        ORDER BY name, id DESC
+                ) latest
+                WHERE
+                    NOT is_deleted
+                    AND
+                    NOT (name = ANY($5))
+            ON CONFLICT (module_address, module_name, name, move_modules_transaction_version)
+                DO NOTHING
+        ",
+    )
+    .bind(&module.address)
+    .bind(&module.name)
+    .bind(module.transaction_version)
+    .bind(module.write_set_change_index)
+    .bind(function_names)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| Error::DbError(e.into()))?;
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use crate::indexer::{self, IndexerConfig};
+use crate::service::domain::resource_account;
+use crate::service::Error;
+
+use database::db::PostgresPool;
+use database::models::module_hub::detail::resource_account::RESOURCE_ACCOUNT_SOURCE_DISCOVERED;
+
+use sqlx::types::JsonValue;
+use sqlx::{query, query_as, FromRow};
+
+pub const RESOURCE_ACCOUNT_INDEXER_NAME: &str = "resource_account";
+
+pub const CONTAINER_TYPE: &str = "0x1::resource_account::Container";
+
+#[derive(Debug, FromRow)]
+struct Container {
+    transaction_version: i64,
+    address: String,
+    data: Option<JsonValue>,
+}
+
+/// Discovers resource accounts of one network from `Container` resources of their owners,
+/// accounts created through `0x1::account::create_resource_account` keep no container
+#[derive(Clone)]
+pub struct ResourceAccountIndexer {
+    network: String,
+    indexer_name: String,
+    aptos_full_index_db: PostgresPool,
+    app_db: PostgresPool,
+    config: IndexerConfig,
+}
+
+impl ResourceAccountIndexer {
+    pub fn new(
+        network: String,
+        aptos_full_index_db: PostgresPool,
+        app_db: PostgresPool,
+        config: IndexerConfig,
+    ) -> Self {
+        ResourceAccountIndexer {
+            indexer_name: indexer::checkpoint_name(&network, RESOURCE_ACCOUNT_INDEXER_NAME),
+            network,
+            aptos_full_index_db,
+            app_db,
+            config,
+        }
+    }
+
+    pub async fn run(self) {
+        if self.config.backfill {
+            if let Err(e) = self.reset().await {
+                log::error!("resource account indexer reset failed: {}", e);
+                return;
+            }
+        }
+
+        indexer::poll(&self.indexer_name, self.config.poll_interval, || async {
+            self.process_next_batch().await
+        })
+        .await
+    }
+
+    /// Registered accounts are kept
+    pub async fn reset(&self) -> Result<(), Error> {
+        let mut tx = self
+            .app_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        query("DELETE FROM resource_account WHERE network = $1 AND source = $2")
+            .bind(&self.network)
+            .bind(RESOURCE_ACCOUNT_SOURCE_DISCOVERED)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        indexer::delete_checkpoint(&mut tx, &self.indexer_name).await?;
+
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))
+    }
+
+    /// Returns the count of containers read by this batch, 0 when caught up
+    pub async fn process_next_batch(&self) -> Result<usize, Error> {
+        let checkpoint = indexer::read_checkpoint(&self.app_db, &self.indexer_name).await?;
+
+        let containers: Vec<Container> = query_as(
+            "
+                SELECT transaction_version, address, data
+                FROM move_resources
+                WHERE
+                    type = $1
+                    AND
+                    transaction_version > $2
+                    AND
+                    transaction_version <= (
+                        SELECT MAX(transaction_version)
+                        FROM (
+                            SELECT DISTINCT transaction_version
+                            FROM move_resources
+                            WHERE
+                                type = $1
+                                AND
+                                transaction_version > $2
+                            ORDER BY transaction_version
+                            LIMIT $3
+                        ) batch
+                    )
+                ORDER BY transaction_version, write_set_change_index
+            ",
+        )
+        .bind(CONTAINER_TYPE)
+        .bind(checkpoint)
+        .bind(self.config.batch_size)
+        .fetch_all(&self.aptos_full_index_db)
+        .await
+        .map_err(|e| Error::DbError(e.into()))?;
+
+        let last_version = match containers.last() {
+            Some(container) => container.transaction_version,
+            None => return Ok(0),
+        };
+
+        let mut tx = self
+            .app_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        for container in &containers {
+            let resource_addresses = resource_account::parse_container_addresses(
+                &container.data.clone().unwrap_or_default(),
+            );
+
+            for resource_address in resource_addresses {
+                resource_account::save_discovered_resource_account(
+                    &mut tx,
+                    &self.network,
+                    &container.address,
+                    &resource_address,
+                )
+                .await?;
+            }
+        }
+
+        indexer::write_checkpoint(&mut tx, &self.indexer_name, last_version).await?;
+
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))?;
+
+        Ok(containers.len())
+    }
+}
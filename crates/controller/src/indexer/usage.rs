@@ -0,0 +1,239 @@
+use crate::indexer::{self, IndexerConfig};
+use crate::service::domain::{bytecode::standardize_address, usage};
+use crate::service::Error;
+
+use database::db::PostgresPool;
+
+use sqlx::types::chrono::{NaiveDate, NaiveDateTime};
+use sqlx::{query, query_as, FromRow};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const FUNCTION_USAGE_INDEXER_NAME: &str = "function_usage";
+
+#[derive(Debug, FromRow)]
+struct UserTransaction {
+    version: i64,
+    sender: String,
+    timestamp: NaiveDateTime,
+    entry_function_id_str: Option<String>,
+    script_bytecode: Option<String>,
+}
+
+#[derive(Debug, Default, FromRow)]
+struct MinVersion {
+    version: Option<i64>,
+}
+
+type DailyKey = (String, String, String, NaiveDate, String, &'static str);
+
+/// Counts calls of successful user transactions of one network per day and caller,
+/// rolled up into `function_usage` for each window every refresh interval
+#[derive(Clone)]
+pub struct FunctionUsageIndexer {
+    network: String,
+    indexer_name: String,
+    aptos_full_index_db: PostgresPool,
+    app_db: PostgresPool,
+    config: IndexerConfig,
+    /// Days each rollup covers, the largest one bounds what is kept
+    windows: Vec<i32>,
+    refresh_interval: Duration,
+    last_refresh: Arc<Mutex<Option<Instant>>>,
+}
+
+impl FunctionUsageIndexer {
+    pub fn new(
+        network: String,
+        aptos_full_index_db: PostgresPool,
+        app_db: PostgresPool,
+        config: IndexerConfig,
+        windows: Vec<i32>,
+        refresh_interval: Duration,
+    ) -> Self {
+        FunctionUsageIndexer {
+            indexer_name: indexer::checkpoint_name(&network, FUNCTION_USAGE_INDEXER_NAME),
+            network,
+            aptos_full_index_db,
+            app_db,
+            config,
+            windows,
+            refresh_interval,
+            last_refresh: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub async fn run(self) {
+        if self.config.backfill {
+            if let Err(e) = self.reset().await {
+                log::error!("function usage indexer reset failed: {}", e);
+                return;
+            }
+        }
+
+        indexer::poll(&self.indexer_name, self.config.poll_interval, || async {
+            let processed = self.process_next_batch().await?;
+            self.refresh_if_due().await?;
+            Ok(processed)
+        })
+        .await
+    }
+
+    pub async fn reset(&self) -> Result<(), Error> {
+        let mut tx = self
+            .app_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        for table in ["function_call_daily", "function_usage"] {
+            query(&format!("DELETE FROM {} WHERE network = $1", table))
+                .bind(&self.network)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| Error::DbError(e.into()))?;
+        }
+
+        indexer::delete_checkpoint(&mut tx, &self.indexer_name).await?;
+
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))
+    }
+
+    /// Without a checkpoint only transactions inside the largest window are read
+    async fn checkpoint(&self) -> Result<i64, Error> {
+        let checkpoint = indexer::read_checkpoint(&self.app_db, &self.indexer_name).await?;
+        if checkpoint >= 0 {
+            return Ok(checkpoint);
+        }
+
+        let max_window = self.windows.iter().max().copied().unwrap_or_default();
+        let first: MinVersion = query_as(
+            "
+                SELECT MIN(version) as version
+                FROM user_transactions
+                WHERE timestamp > now() - make_interval(days => $1)
+            ",
+        )
+        .bind(max_window)
+        .fetch_one(&self.aptos_full_index_db)
+        .await
+        .map_err(|e| Error::DbError(e.into()))?;
+
+        Ok(first.version.map(|v| v - 1).unwrap_or(checkpoint))
+    }
+
+    /// Returns the count of transactions read by this batch, 0 when caught up
+    pub async fn process_next_batch(&self) -> Result<usize, Error> {
+        let checkpoint = self.checkpoint().await?;
+
+        let transactions: Vec<UserTransaction> = query_as(
+            "
+                SELECT
+                    ut.version,
+                    ut.sender,
+                    ut.timestamp,
+                    ut.entry_function_id_str,
+                    CASE WHEN t.payload->>'type' = 'script_payload'
+                        THEN t.payload->'code'->>'bytecode'
+                    END as script_bytecode
+                FROM user_transactions ut
+                JOIN transactions t ON t.version = ut.version
+                WHERE
+                    ut.version > $1
+                    AND
+                    t.success
+                ORDER BY ut.version
+                LIMIT $2
+            ",
+        )
+        .bind(checkpoint)
+        .bind(self.config.batch_size)
+        .fetch_all(&self.aptos_full_index_db)
+        .await
+        .map_err(|e| Error::DbError(e.into()))?;
+
+        let last_version = match transactions.last() {
+            Some(transaction) => transaction.version,
+            None => return Ok(0),
+        };
+
+        let mut daily: HashMap<DailyKey, (i64, NaiveDateTime)> = HashMap::new();
+        for transaction in &transactions {
+            let calls = usage::calls_of_transaction(
+                transaction.entry_function_id_str.as_deref(),
+                transaction.script_bytecode.as_deref(),
+            );
+
+            for call in calls {
+                let key = (
+                    call.module_address,
+                    call.module_name,
+                    call.function_name,
+                    transaction.timestamp.date(),
+                    standardize_address(&transaction.sender),
+                    call.source,
+                );
+                let entry = daily.entry(key).or_insert((0, transaction.timestamp));
+                entry.0 += 1;
+                entry.1 = entry.1.max(transaction.timestamp);
+            }
+        }
+
+        let mut tx = self
+            .app_db
+            .begin()
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+
+        for ((module_address, module_name, function_name, day, sender, source), (count, last)) in
+            daily
+        {
+            query(
+                "
+                    INSERT INTO function_call_daily
+                        (network, module_address, module_name, function_name, day, sender, source, call_count, last_called_at)
+                        VALUES
+                        ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                    ON CONFLICT (network, module_address, module_name, function_name, day, sender, source) DO UPDATE SET
+                        call_count = function_call_daily.call_count + EXCLUDED.call_count,
+                        last_called_at = GREATEST(function_call_daily.last_called_at, EXCLUDED.last_called_at)
+                ",
+            )
+            .bind(&self.network)
+            .bind(module_address)
+            .bind(module_name)
+            .bind(function_name)
+            .bind(day)
+            .bind(sender)
+            .bind(source)
+            .bind(count)
+            .bind(last)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| Error::DbError(e.into()))?;
+        }
+
+        indexer::write_checkpoint(&mut tx, &self.indexer_name, last_version).await?;
+
+        tx.commit().await.map_err(|e| Error::DbError(e.into()))?;
+
+        Ok(transactions.len())
+    }
+
+    async fn refresh_if_due(&self) -> Result<(), Error> {
+        let is_due = match *self.last_refresh.lock().unwrap() {
+            Some(last_refresh) => last_refresh.elapsed() >= self.refresh_interval,
+            None => true,
+        };
+        if !is_due {
+            return Ok(());
+        }
+
+        usage::refresh_function_usage(&self.app_db, &self.network, &self.windows).await?;
+
+        *self.last_refresh.lock().unwrap() = Some(Instant::now());
+
+        Ok(())
+    }
+}
@@ -1,2 +1,6 @@
+pub mod cache;
+pub mod compile;
 pub mod config;
+pub mod indexer;
+pub mod metrics;
 pub mod service;
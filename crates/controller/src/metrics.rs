@@ -0,0 +1,167 @@
+use database::db::PostgresPool;
+
+use actix_web::dev::ServiceResponse;
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge_vec, Encoder,
+    HistogramTimer, HistogramVec, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+use std::time::Instant;
+
+pub const RESULT_SUCCESS: &str = "success";
+pub const RESULT_FAILURE: &str = "failure";
+pub const RESULT_HIT: &str = "hit";
+pub const RESULT_MISS: &str = "miss";
+
+/// Compiling fetches every dependency, so buckets reach well past the http ones
+const COMPILE_BUCKETS: [f64; 9] = [0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+lazy_static! {
+    pub static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "moveblock_http_requests_total",
+        "Handled requests by route pattern",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "moveblock_http_request_duration_seconds",
+        "Request latency by route pattern",
+        &["method", "route"]
+    )
+    .unwrap();
+    pub static ref DB_POOL_CONNECTIONS: IntGaugeVec = register_int_gauge_vec!(
+        "moveblock_db_pool_connections",
+        "Connections of each pool by state, sampled when scraped",
+        &["pool", "state"]
+    )
+    .unwrap();
+    pub static ref DB_QUERY_DURATION: HistogramVec = register_histogram_vec!(
+        "moveblock_db_query_duration_seconds",
+        "Latency of domain functions querying the databases",
+        &["function"]
+    )
+    .unwrap();
+    pub static ref COMPILE_JOBS: IntCounterVec = register_int_counter_vec!(
+        "moveblock_compile_jobs_total",
+        "Block stack compilations by result",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref COMPILE_DURATION: HistogramVec = register_histogram_vec!(
+        "moveblock_compile_duration_seconds",
+        "Block stack compilation time by result",
+        &["result"],
+        COMPILE_BUCKETS.to_vec()
+    )
+    .unwrap();
+    pub static ref TRANSACTION_SUBMISSIONS: IntCounterVec = register_int_counter_vec!(
+        "moveblock_transaction_submissions_total",
+        "Block stack script submissions to the node by result",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref CACHE_LOOKUPS: IntCounterVec = register_int_counter_vec!(
+        "moveblock_cache_lookups_total",
+        "Response cache lookups by result",
+        &["result"]
+    )
+    .unwrap();
+}
+
+/// Unmatched requests share one label so unknown paths cannot blow up cardinality
+pub fn observe_http_request<B>(res: &ServiceResponse<B>, started: Instant) {
+    let request = res.request();
+    let method = request.method().as_str();
+    let route = request
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    HTTP_REQUESTS
+        .with_label_values(&[method, &route, res.status().as_str()])
+        .inc();
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[method, &route])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+/// Observes the elapsed time once dropped, bind it for the whole function body
+pub fn query_timer(function: &str) -> HistogramTimer {
+    DB_QUERY_DURATION
+        .with_label_values(&[function])
+        .start_timer()
+}
+
+pub fn observe_compile(started: Instant, succeeded: bool) {
+    let result = if succeeded {
+        RESULT_SUCCESS
+    } else {
+        RESULT_FAILURE
+    };
+
+    COMPILE_JOBS.with_label_values(&[result]).inc();
+    COMPILE_DURATION
+        .with_label_values(&[result])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+pub fn observe_transaction_submission(succeeded: bool) {
+    let result = if succeeded {
+        RESULT_SUCCESS
+    } else {
+        RESULT_FAILURE
+    };
+
+    TRANSACTION_SUBMISSIONS.with_label_values(&[result]).inc();
+}
+
+pub fn observe_cache(hit: bool) {
+    let result = if hit { RESULT_HIT } else { RESULT_MISS };
+
+    CACHE_LOOKUPS.with_label_values(&[result]).inc();
+}
+
+/// sqlx does not expose the configured maximum, so it is passed along from the settings
+pub fn observe_pool(name: &str, pool: &PostgresPool, max_connections: u32) {
+    let idle = pool.num_idle() as i64;
+    let size = pool.size() as i64;
+
+    DB_POOL_CONNECTIONS
+        .with_label_values(&[name, "idle"])
+        .set(idle);
+    DB_POOL_CONNECTIONS
+        .with_label_values(&[name, "in_use"])
+        .set(size - idle);
+    DB_POOL_CONNECTIONS
+        .with_label_values(&[name, "max"])
+        .set(max_connections as i64);
+}
+
+/// Prometheus text format of every registered metric
+pub fn encode() -> String {
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .unwrap_or_default();
+
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod exposition {
+    use crate::metrics::{encode, observe_compile, observe_transaction_submission, query_timer};
+    use std::time::Instant;
+
+    #[test]
+    fn test_encode() {
+        drop(query_timer("function::get_functions_count"));
+        observe_compile(Instant::now(), false);
+        observe_transaction_submission(true);
+
+        let text = encode();
+        assert!(text.contains(
+            r#"moveblock_db_query_duration_seconds_count{function="function::get_functions_count"} 1"#
+        ));
+        assert!(text.contains(r#"moveblock_compile_jobs_total{result="failure"} 1"#));
+        assert!(text.contains(r#"moveblock_transaction_submissions_total{result="success"} 1"#));
+    }
+}
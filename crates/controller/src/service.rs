@@ -1,11 +1,33 @@
 pub mod account;
 pub mod block_stack;
 pub mod domain;
+pub mod etag;
 pub mod function;
+pub mod health;
+pub mod metrics;
+pub mod network;
+pub mod openapi;
+pub mod rate_limit;
+pub mod session;
 
 use actix_http::StatusCode;
-use actix_web::ResponseError;
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 use thiserror::Error;
+use utoipa::ToSchema;
+
+pub const ERROR_CODE_BAD_REQUEST: &str = "bad_request";
+pub const ERROR_CODE_DATABASE: &str = "database_error";
+pub const ERROR_CODE_INTERNAL: &str = "internal_error";
+pub const ERROR_CODE_UNAUTHORIZED: &str = "unauthorized";
+pub const ERROR_CODE_NOT_FOUND: &str = "not_found";
+pub const ERROR_CODE_INVALID_PARAMS: &str = "invalid_params";
+pub const ERROR_CODE_COMPILE: &str = "compile_error";
+pub const ERROR_CODE_CONFLICT: &str = "conflict";
+pub const ERROR_CODE_RATE_LIMITED: &str = "rate_limited";
+pub const ERROR_CODE_UNAVAILABLE: &str = "unavailable";
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -29,6 +51,97 @@ pub enum Error {
 
     #[error("Compile failed: {msg}")]
     CompileError { msg: String },
+
+    #[error("Conflict: {msg}")]
+    Conflict { msg: String },
+
+    #[error("TooManyRequests: retry after {retry_after_secs}s")]
+    TooManyRequests { retry_after_secs: u64 },
+
+    #[error("Unavailable: {msg}")]
+    Unavailable { msg: String },
+}
+
+/// Body of every error response, clients match on `code` rather than on `message`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorEnvelope {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    #[schema(value_type = Option<Value>)]
+    pub details: Option<JsonValue>,
+}
+
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::ActixWeb(e) if e.error_response().status().is_client_error() => {
+                ERROR_CODE_BAD_REQUEST
+            }
+            Error::ActixWeb(_) => ERROR_CODE_INTERNAL,
+            Error::AnyError(_) => ERROR_CODE_INTERNAL,
+            Error::DbError(_) => ERROR_CODE_DATABASE,
+            Error::UnAuthorized {} => ERROR_CODE_UNAUTHORIZED,
+            Error::NotFound { .. } => ERROR_CODE_NOT_FOUND,
+            Error::InvalidParams { .. } => ERROR_CODE_INVALID_PARAMS,
+            Error::CompileError { .. } => ERROR_CODE_COMPILE,
+            Error::Conflict { .. } => ERROR_CODE_CONFLICT,
+            Error::TooManyRequests { .. } => ERROR_CODE_RATE_LIMITED,
+            Error::Unavailable { .. } => ERROR_CODE_UNAVAILABLE,
+        }
+    }
+
+    /// Database and internal failures are logged instead of leaking queries or stack details
+    pub fn envelope(&self) -> ErrorEnvelope {
+        let (message, details) = match self {
+            Error::ActixWeb(e) => (e.to_string(), None),
+            Error::AnyError(_) => ("internal server error".to_string(), None),
+            Error::DbError(_) => ("database query failed".to_string(), None),
+            Error::UnAuthorized {} => ("unauthorized".to_string(), None),
+            Error::NotFound { msg } => (msg.clone(), None),
+            Error::InvalidParams { msg } => (msg.clone(), None),
+            Error::CompileError { msg } => (
+                "block stack failed to compile".to_string(),
+                Some(JsonValue::String(msg.clone())),
+            ),
+            Error::Conflict { msg } => (msg.clone(), None),
+            Error::TooManyRequests { retry_after_secs } => (
+                "too many requests".to_string(),
+                Some(serde_json::json!({ "retry_after_secs": retry_after_secs })),
+            ),
+            Error::Unavailable { msg } => (msg.clone(), None),
+        };
+
+        ErrorEnvelope {
+            error: ErrorBody {
+                code: self.code().to_string(),
+                message,
+                details,
+            },
+        }
+    }
+}
+
+/// Extractor rejections, e.g. a malformed body or query, answer with the same envelope
+pub fn extractor_error_handler<E>(err: E, _: &HttpRequest) -> actix_web::Error
+where
+    E: std::fmt::Display,
+{
+    Error::InvalidParams {
+        msg: err.to_string(),
+    }
+    .into()
+}
+
+/// Routes that do not exist answer with the same envelope
+pub async fn not_found() -> Result<HttpResponse, Error> {
+    Err(Error::NotFound {
+        msg: "route not found".to_string(),
+    })
 }
 
 impl ResponseError for Error {
@@ -41,7 +154,23 @@ impl ResponseError for Error {
             Error::NotFound { .. } => StatusCode::NOT_FOUND,
             Error::InvalidParams { .. } => StatusCode::BAD_REQUEST,
             Error::CompileError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Conflict { .. } => StatusCode::CONFLICT,
+            Error::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Error::Unavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if matches!(self, Error::DbError(_) | Error::AnyError(_)) {
+            log::error!("{}", self);
+        }
+
+        let mut res = HttpResponse::build(self.status_code());
+        if let Error::TooManyRequests { retry_after_secs } = self {
+            res.insert_header((RETRY_AFTER, retry_after_secs.to_string()));
         }
+
+        res.json(self.envelope())
     }
 }
 
@@ -56,3 +185,35 @@ impl From<anyhow::Error> for Error {
         Self::AnyError(e)
     }
 }
+
+#[cfg(test)]
+mod envelope {
+    use crate::service::{Error, ErrorEnvelope, ERROR_CODE_DATABASE, ERROR_CODE_NOT_FOUND};
+    use actix_http::StatusCode;
+    use actix_web::body::to_bytes;
+    use actix_web::ResponseError;
+
+    async fn render(error: Error) -> (StatusCode, ErrorEnvelope) {
+        let res = error.error_response();
+        let status = res.status();
+        let body = to_bytes(res.into_body()).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[actix_rt::test]
+    async fn test_error_response() {
+        let (status, envelope) = render(Error::NotFound {
+            msg: "block stack not found".to_string(),
+        })
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(envelope.error.code, ERROR_CODE_NOT_FOUND);
+        assert_eq!(envelope.error.message, "block stack not found");
+
+        let (status, envelope) = render(Error::DbError(sqlx::Error::PoolTimedOut.into())).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(envelope.error.code, ERROR_CODE_DATABASE);
+        assert!(!envelope.error.message.contains("PoolTimedOut"));
+    }
+}
@@ -1,17 +1,27 @@
 use crate::config::ApiContext;
-use crate::service::domain::{self, Response, SignedBody};
-use crate::service::Error;
+use crate::service::domain::{
+    self,
+    cursor::{self, ModuleCursor},
+    Response, SignedBody,
+};
+use crate::service::session::ChallengeAnswer;
+use crate::service::{etag, network::Network, rate_limit, Error};
 
+use database::models::module_hub::detail::account::NewAccountProfile;
 use database::models::module_hub::detail::function::NewModuleFunctionDetail;
 use database::models::module_hub::detail::module::NewModuleDetail;
+use database::models::module_hub::detail::move_struct::NewModuleStructDetail;
+use database::models::session::CHALLENGE_PURPOSE_DOMAIN_VERIFICATION;
 
 use actix_web::{
     get, post,
-    web::{self, Data},
-    HttpResponse, Responder,
+    web::{self, Data, Query},
+    HttpRequest, HttpResponse, Responder,
 };
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::{IntoParams, ToSchema};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct PathParams {
@@ -19,13 +29,56 @@ struct PathParams {
     pub module_name: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct AccountAliasBody {
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ModuleQueryParams {
+    pub limit: Option<i64>,
+    /// Opaque `next_cursor` of a previous page
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct GraphQueryParams {
+    /// 1 for direct neighbours only
+    pub depth: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AbiDiffQueryParams {
+    /// Version preceding `to` when omitted
+    pub from: Option<i64>,
+    /// Latest version when omitted
+    pub to: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct AccountAliasBody {
     pub alias: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ModuleDetailBody {
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct AccountProfileBody {
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub website: Option<String>,
+    /// Platform name to profile url
+    pub social_links: Option<HashMap<String, String>>,
+    pub description: Option<String>,
+    /// Bare host name, verified separately
+    pub domain: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ResourceAccountBody {
+    /// Utf8 seed as passed to `create_resource_account`
+    pub seed: Option<String>,
+    pub seed_hex: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ModuleDetailBody {
     pub description: Option<String>,
     pub github_url: Option<String>,
     pub rev: Option<String>,
@@ -47,95 +100,764 @@ struct FunctionPostParams {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct FunctionDetailBody {
+struct StructPathParams {
+    pub address_or_alias: String,
+    pub module_name: String,
+    pub struct_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StructPostParams {
+    pub address: String,
+    pub module_name: String,
+    pub struct_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct StructDetailBody {
+    pub description: Option<String>,
+    /// Field name to description
+    pub field_descriptions: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct FunctionDetailBody {
     pub description: Option<String>,
     pub param_names: Option<Vec<String>>,
     pub generic_type_params: Option<Vec<String>>,
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("module_address" = String, Path, description = "Address the module is published at"),
+    ),
+    responses(
+        (status = 200, description = "Whether the module address belongs to the account", body = bool),
+    )
+)]
 #[get("/{address}/contains/{module_address}")]
-async fn check_module_auth(path: web::Path<(String, String)>) -> Result<impl Responder, Error> {
+async fn check_module_auth(
+    path: web::Path<(String, String)>,
+    context: Data<ApiContext>,
+    network: Network,
+) -> Result<impl Responder, Error> {
     let mut result = false;
 
     let (address, module_address) = path.into_inner();
     let resource_account = domain::get_resource_account(&address)?;
 
-    if address == module_address || resource_account == module_address {
+    if address == module_address
+        || resource_account == module_address
+        || domain::resource_account::is_resource_account_of(
+            &context.app_db,
+            &network.name,
+            &address,
+            &module_address,
+        )
+        .await?
+    {
         result = true;
     }
 
     Ok(web::Json(result))
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address_or_alias" = String, Path, description = "Account address or alias"),
+        ModuleQueryParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = ModulesResponse),
+        (status = 304, description = "Unchanged since the ETag in If-None-Match"),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+    )
+)]
 #[get("/{address_or_alias}/modules")]
 async fn modules_by_address_or_alias(
+    req: HttpRequest,
     path: web::Path<PathParams>,
+    params: Query<ModuleQueryParams>,
     context: Data<ApiContext>,
+    network: Network,
 ) -> Result<impl Responder, Error> {
+    let cursor: Option<ModuleCursor> = cursor::decode_optional(&params.cursor)?;
+
     let modules = domain::module::get_modules_by_account(
-        &context.aptos_full_index_db,
+        &network.aptos_full_index_db,
+        &context.app_db,
+        &network.name,
+        &path.address_or_alias,
+        &cursor,
+        params.limit,
+    )
+    .await?;
+
+    let total_len = match params.limit {
+        Some(_) => {
+            context
+                .cache
+                .get_or_load_scoped(
+                    &network.name,
+                    &domain::account::cache_scope(&path.address_or_alias),
+                    "count:modules",
+                    || {
+                        domain::module::get_modules_count_by_account(
+                            &network.aptos_full_index_db,
+                            &context.app_db,
+                            &network.name,
+                            &path.address_or_alias,
+                        )
+                    },
+                )
+                .await?
+                .count
+        }
+        None => modules.len() as i64,
+    };
+
+    let next_cursor = params
+        .limit
+        .and_then(|limit| cursor::next_cursor(&modules, limit, |m| ModuleCursor::from(m)));
+
+    etag::json_with_etag(
+        &req,
+        &Response::new(total_len, modules, None, params.limit).with_next_cursor(next_cursor),
+    )
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address_or_alias" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ModuleResponse),
+        (status = 304, description = "Unchanged since the ETag in If-None-Match"),
+        (status = 404, description = "Module not found", body = ErrorEnvelope),
+    )
+)]
+#[get("/{address_or_alias}/modules/{module_name}")]
+async fn functions_by_account_and_module_name(
+    req: HttpRequest,
+    path: web::Path<PathParams>,
+    context: Data<ApiContext>,
+    network: Network,
+) -> Result<impl Responder, Error> {
+    let module_name = path.module_name.clone().unwrap_or_default();
+    let scope = domain::account::cache_scope(&path.address_or_alias);
+    let key = format!("module:{}", module_name);
+
+    let modules = context
+        .cache
+        .get_or_load_scoped(&network.name, &scope, &key, || {
+            domain::module::get_module_with_functions_and_details(
+                &network.aptos_full_index_db,
+                &network.function_index_db,
+                &context.app_db,
+                &network.name,
+                &path.address_or_alias,
+                &module_name,
+            )
+        })
+        .await?
+        .ok_or_else(|| Error::NotFound {
+            msg: "module not found".to_string(),
+        })?;
+
+    let total_len = modules.functions_with_detail.len() as i64;
+
+    etag::json_with_etag(&req, &Response::new(total_len, modules, None, None))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address_or_alias" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = StructsResponse),
+        (status = 404, description = "Module not found", body = ErrorEnvelope),
+    )
+)]
+#[get("/{address_or_alias}/modules/{module_name}/structs")]
+async fn structs_by_account_and_module_name(
+    path: web::Path<PathParams>,
+    context: Data<ApiContext>,
+    network: Network,
+) -> Result<impl Responder, Error> {
+    let structs = domain::move_struct::get_structs_with_detail(
+        &network.aptos_full_index_db,
         &context.app_db,
+        &network.name,
         &path.address_or_alias,
+        &path.module_name.clone().unwrap_or_default(),
     )
-    .await
-    .unwrap_or_default();
+    .await?
+    .ok_or_else(|| Error::NotFound {
+        msg: "module not found".to_string(),
+    })?;
 
     Ok(web::Json(Response::new(
-        modules.len() as i64,
-        modules,
+        structs.len() as i64,
+        structs,
         None,
         None,
     )))
 }
 
-#[get("/{address_or_alias}/modules/{module_name}")]
-async fn functions_by_account_and_module_name(
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address_or_alias" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+        ("struct_name" = String, Path, description = "Struct name"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = MoveStructWithDetail),
+        (status = 404, description = "Struct not found", body = ErrorEnvelope),
+    )
+)]
+#[get("/{address_or_alias}/modules/{module_name}/structs/{struct_name}")]
+async fn struct_by_account_and_module_name(
+    path: web::Path<StructPathParams>,
+    context: Data<ApiContext>,
+    network: Network,
+) -> Result<impl Responder, Error> {
+    let move_struct = domain::move_struct::get_struct_with_detail(
+        &network.aptos_full_index_db,
+        &context.app_db,
+        &network.name,
+        &path.address_or_alias,
+        &path.module_name,
+        &path.struct_name,
+    )
+    .await?
+    .ok_or_else(|| Error::NotFound {
+        msg: "struct not found".to_string(),
+    })?;
+
+    Ok(web::Json(move_struct))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address_or_alias" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ModuleVersionsResponse),
+        (status = 404, description = "Module not found", body = ErrorEnvelope),
+    )
+)]
+#[get("/{address_or_alias}/modules/{module_name}/versions")]
+async fn module_versions(
     path: web::Path<PathParams>,
     context: Data<ApiContext>,
+    network: Network,
 ) -> Result<impl Responder, Error> {
-    let modules = domain::module::get_module_with_functions_and_details(
-        &context.aptos_full_index_db,
-        &context.function_index_db,
+    let versions = domain::module::get_module_versions(
+        &network.aptos_full_index_db,
         &context.app_db,
+        &network.name,
         &path.address_or_alias,
         &path.module_name.clone().unwrap_or_default(),
     )
-    .await;
+    .await?;
+
+    if versions.is_empty() {
+        return Err(Error::NotFound {
+            msg: "module not found".to_string(),
+        });
+    }
+
+    Ok(web::Json(Response::new(
+        versions.len() as i64,
+        versions,
+        None,
+        None,
+    )))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address_or_alias" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+        AbiDiffQueryParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = AbiDiff),
+        (status = 404, description = "Module version not found", body = ErrorEnvelope),
+    )
+)]
+#[get("/{address_or_alias}/modules/{module_name}/diff")]
+async fn module_abi_diff(
+    path: web::Path<PathParams>,
+    params: Query<AbiDiffQueryParams>,
+    context: Data<ApiContext>,
+    network: Network,
+) -> Result<impl Responder, Error> {
+    let module_name = path.module_name.clone().unwrap_or_default();
+
+    let to = domain::module::get_module_at_version(
+        &network.aptos_full_index_db,
+        &context.app_db,
+        &network.name,
+        &path.address_or_alias,
+        &module_name,
+        params.to,
+    )
+    .await?
+    .ok_or_else(|| Error::NotFound {
+        msg: "module version not found".to_string(),
+    })?;
+
+    let from_version = match params.from {
+        Some(from) => Some(from),
+        None => domain::module::get_module_versions(
+            &network.aptos_full_index_db,
+            &context.app_db,
+            &network.name,
+            &path.address_or_alias,
+            &module_name,
+        )
+        .await?
+        .into_iter()
+        .map(|v| v.transaction_version)
+        .rev()
+        .find(|version| *version < to.transaction_version),
+    };
 
-    let total_len = match modules.clone() {
-        Some(modules) => modules.functions_with_detail.len() as i64,
-        None => 0,
+    // A module published once is diffed against itself
+    let from = match from_version {
+        Some(version) => domain::module::get_module_at_version(
+            &network.aptos_full_index_db,
+            &context.app_db,
+            &network.name,
+            &path.address_or_alias,
+            &module_name,
+            Some(version),
+        )
+        .await?
+        .ok_or_else(|| Error::NotFound {
+            msg: "module version not found".to_string(),
+        })?,
+        None => to.clone(),
     };
 
-    Ok(web::Json(Response::new(total_len, modules, None, None)))
+    Ok(web::Json(domain::abi::diff_modules(&from, &to)))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address_or_alias" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+        GraphQueryParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = ModuleNodesResponse),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+    )
+)]
+#[get("/{address_or_alias}/modules/{module_name}/dependencies")]
+async fn module_dependencies(
+    path: web::Path<PathParams>,
+    params: Query<GraphQueryParams>,
+    context: Data<ApiContext>,
+    network: Network,
+) -> Result<impl Responder, Error> {
+    let depth = domain::dependency::validate_depth(params.depth)?;
+    let address =
+        domain::dependency::resolve_address(&context.app_db, &network.name, &path.address_or_alias)
+            .await?;
+
+    let modules = domain::dependency::get_dependencies(
+        &context.app_db,
+        &network.name,
+        &address,
+        &path.module_name.clone().unwrap_or_default(),
+        depth,
+    )
+    .await?;
+
+    Ok(web::Json(Response::new(
+        modules.len() as i64,
+        modules,
+        None,
+        None,
+    )))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address_or_alias" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+        GraphQueryParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = ModuleNodesResponse),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+    )
+)]
+#[get("/{address_or_alias}/modules/{module_name}/dependents")]
+async fn module_dependents(
+    path: web::Path<PathParams>,
+    params: Query<GraphQueryParams>,
+    context: Data<ApiContext>,
+    network: Network,
+) -> Result<impl Responder, Error> {
+    let depth = domain::dependency::validate_depth(params.depth)?;
+    let address =
+        domain::dependency::resolve_address(&context.app_db, &network.name, &path.address_or_alias)
+            .await?;
+
+    let modules = domain::dependency::get_dependents(
+        &context.app_db,
+        &network.name,
+        &address,
+        &path.module_name.clone().unwrap_or_default(),
+        depth,
+    )
+    .await?;
+
+    Ok(web::Json(Response::new(
+        modules.len() as i64,
+        modules,
+        None,
+        None,
+    )))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address_or_alias" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ModuleNodesResponse),
+    )
+)]
+#[get("/{address_or_alias}/modules/{module_name}/friends")]
+async fn module_friends(
+    path: web::Path<PathParams>,
+    context: Data<ApiContext>,
+    network: Network,
+) -> Result<impl Responder, Error> {
+    let address =
+        domain::dependency::resolve_address(&context.app_db, &network.name, &path.address_or_alias)
+            .await?;
+
+    let modules = domain::dependency::get_friends(
+        &context.app_db,
+        &network.name,
+        &address,
+        &path.module_name.clone().unwrap_or_default(),
+    )
+    .await?;
+
+    Ok(web::Json(Response::new(
+        modules.len() as i64,
+        modules,
+        None,
+        None,
+    )))
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+    ),
+    request_body(content = SignedBody, description = "Signed `AccountAliasBody`"),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+        (status = 401, description = "Not signed by the account", body = ErrorEnvelope),
+        (status = 404, description = "On-chain account not found", body = ErrorEnvelope),
+        (status = 409, description = "Alias not available", body = ErrorEnvelope),
+    )
+)]
 #[post("/{address}")]
 async fn account_alias(
+    req: HttpRequest,
     context: Data<ApiContext>,
+    network: Network,
     path: web::Path<String>,
     body: web::Json<SignedBody>,
 ) -> Result<impl Responder, Error> {
-    let verified_body: AccountAliasBody = domain::verify(&body.into_inner(), path.as_str()).await?;
+    let body = body.into_inner();
+    let verified_body: AccountAliasBody =
+        domain::verify(&network.auth_keys, &body, path.as_str()).await?;
+    rate_limit::charge_signer(&req, &body.payload.address)?;
+
+    // The replaced alias keeps redirecting, so its entries are dropped as well
+    let mut scopes =
+        domain::account::get_cache_scopes(&context.app_db, &network.name, path.as_str()).await?;
 
     domain::account::create_or_update_account_alias(
-        &context.aptos_full_index_db,
+        &network.aptos_full_index_db,
         &context.app_db,
+        &network.name,
         path.as_str(),
         &Some(verified_body.alias),
     )
     .await?;
 
+    scopes.extend(
+        domain::account::get_cache_scopes(&context.app_db, &network.name, path.as_str()).await?,
+    );
+    context
+        .cache
+        .invalidate_scopes(&network.name, &scopes)
+        .await;
+
+    Ok(HttpResponse::Ok())
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+    ),
+    request_body(content = SignedBody, description = "Signed `AccountProfileBody`"),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+        (status = 401, description = "Not signed by the account", body = ErrorEnvelope),
+    )
+)]
+#[post("/{address}/profile")]
+async fn account_profile(
+    req: HttpRequest,
+    context: Data<ApiContext>,
+    network: Network,
+    path: web::Path<String>,
+    body: web::Json<SignedBody>,
+) -> Result<impl Responder, Error> {
+    let body = body.into_inner();
+    let verified_body: AccountProfileBody =
+        domain::verify(&network.auth_keys, &body, path.as_str()).await?;
+    rate_limit::charge_signer(&req, &body.payload.address)?;
+
+    let social_links = verified_body
+        .social_links
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|_| Error::InvalidParams {
+            msg: "cannot parse social_links".to_string(),
+        })?;
+
+    let new_account_profile = NewAccountProfile::new(
+        path.as_str(),
+        &verified_body.display_name,
+        &verified_body.avatar_url,
+        &verified_body.website,
+        &social_links,
+        &verified_body.description,
+        &verified_body.domain,
+    );
+
+    domain::profile::create_or_update_account_profile(
+        &context.app_db,
+        &network.name,
+        &new_account_profile,
+    )
+    .await?;
+
+    let scopes =
+        domain::account::get_cache_scopes(&context.app_db, &network.name, path.as_str()).await?;
+    context
+        .cache
+        .invalidate_scopes(&network.name, &scopes)
+        .await;
+
     Ok(HttpResponse::Ok())
 }
 
+/// Signed with a domain-verification challenge of `/api/v1/sessions/challenge`, the domain comes
+/// from the saved profile
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+    ),
+    request_body(content = SignedBody, description = "Signed `ChallengeAnswer` of a domain-verification challenge"),
+    responses(
+        (status = 200, description = "OK", body = AccountDetail),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+        (status = 401, description = "Not signed by the account, unknown or spent challenge", body = ErrorEnvelope),
+    )
+)]
+#[post("/{address}/profile/domain-verification")]
+async fn account_domain_verification(
+    req: HttpRequest,
+    context: Data<ApiContext>,
+    network: Network,
+    path: web::Path<String>,
+    body: web::Json<SignedBody>,
+) -> Result<impl Responder, Error> {
+    let body = body.into_inner();
+    let verified_body: ChallengeAnswer =
+        domain::verify(&network.auth_keys, &body, path.as_str()).await?;
+    rate_limit::charge_signer(&req, &body.payload.address)?;
+    domain::session::take_challenge(
+        &context.app_db,
+        &verified_body.challenge,
+        &body.payload.address,
+        CHALLENGE_PURPOSE_DOMAIN_VERIFICATION,
+    )
+    .await?;
+
+    let account_detail = domain::profile::verify_account_domain(
+        &context.app_db,
+        &network.name,
+        &context.domain_verifier,
+        path.as_str(),
+    )
+    .await?;
+
+    let scopes =
+        domain::account::get_cache_scopes(&context.app_db, &network.name, path.as_str()).await?;
+    context
+        .cache
+        .invalidate_scopes(&network.name, &scopes)
+        .await;
+
+    Ok(web::Json(account_detail))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ResourceAccountsResponse),
+    )
+)]
+#[get("/{address}/resource-accounts")]
+async fn resource_accounts(
+    context: Data<ApiContext>,
+    network: Network,
+    path: web::Path<String>,
+) -> Result<impl Responder, Error> {
+    let resource_accounts = domain::resource_account::get_resource_accounts_by_owner(
+        &context.app_db,
+        &network.name,
+        path.as_str(),
+    )
+    .await?;
+
+    Ok(web::Json(Response::new(
+        resource_accounts.len() as i64,
+        resource_accounts,
+        None,
+        None,
+    )))
+}
+
+/// Signed by the owner, the resource address is derived from the seed
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+    ),
+    request_body(content = SignedBody, description = "Signed `ResourceAccountBody`"),
+    responses(
+        (status = 200, description = "OK", body = ResourceAccount),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+        (status = 401, description = "Not signed by the account", body = ErrorEnvelope),
+    )
+)]
+#[post("/{address}/resource-accounts")]
+async fn register_resource_account(
+    req: HttpRequest,
+    context: Data<ApiContext>,
+    network: Network,
+    path: web::Path<String>,
+    body: web::Json<SignedBody>,
+) -> Result<impl Responder, Error> {
+    let body = body.into_inner();
+    let verified_body: ResourceAccountBody =
+        domain::verify(&network.auth_keys, &body, path.as_str()).await?;
+    rate_limit::charge_signer(&req, &body.payload.address)?;
+
+    // Resource accounts cannot register seeds on behalf of their owner
+    if body.payload.address != path.as_str() {
+        return Err(Error::UnAuthorized {});
+    }
+
+    let seed = domain::resource_account::parse_seed(&verified_body.seed, &verified_body.seed_hex)?;
+
+    let resource_account = domain::resource_account::register_resource_account(
+        &context.app_db,
+        &network.name,
+        path.as_str(),
+        &seed,
+    )
+    .await?;
+
+    Ok(web::Json(resource_account))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("module_name" = String, Path, description = "Module name"),
+    ),
+    request_body(content = SignedBody, description = "Signed `ModuleDetailBody`"),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+        (status = 401, description = "Not signed by the account", body = ErrorEnvelope),
+        (status = 404, description = "On-chain module not found", body = ErrorEnvelope),
+    )
+)]
 #[post("/{address}/modules/{module_name}")]
 async fn module_detail(
+    req: HttpRequest,
     context: Data<ApiContext>,
+    network: Network,
     path: web::Path<ModulePostParams>,
     body: web::Json<SignedBody>,
 ) -> Result<impl Responder, Error> {
-    let verified_body: ModuleDetailBody = domain::verify(&body.into_inner(), &path.address).await?;
+    let body = body.into_inner();
+    let verified_body: ModuleDetailBody = domain::verify_owner(
+        &context.app_db,
+        &network.name,
+        &network.auth_keys,
+        &body,
+        &path.address,
+    )
+    .await?;
+    rate_limit::charge_signer(&req, &body.payload.address)?;
 
     let new_module_detail = NewModuleDetail::new(
         &path.address,
@@ -146,32 +868,71 @@ async fn module_detail(
         &verified_body.subdir,
     );
 
+    let mut scopes =
+        domain::account::get_cache_scopes(&context.app_db, &network.name, &path.address).await?;
+
     domain::account::create_or_update_account_alias(
-        &context.aptos_full_index_db,
+        &network.aptos_full_index_db,
         &context.app_db,
+        &network.name,
         &path.address,
         &verified_body.alias,
     )
     .await?;
 
     domain::module::create_or_update_module_info(
-        &context.aptos_full_index_db,
+        &network.aptos_full_index_db,
         &context.app_db,
+        &network.name,
         &new_module_detail,
     )
     .await?;
 
+    scopes.extend(
+        domain::account::get_cache_scopes(&context.app_db, &network.name, &path.address).await?,
+    );
+    context
+        .cache
+        .invalidate_scopes(&network.name, &scopes)
+        .await;
+
     Ok(HttpResponse::Ok())
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("module_name" = String, Path, description = "Module name"),
+        ("function_name" = String, Path, description = "Function name"),
+    ),
+    request_body(content = SignedBody, description = "Signed `FunctionDetailBody`"),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+        (status = 401, description = "Not signed by the account", body = ErrorEnvelope),
+        (status = 404, description = "On-chain function not found", body = ErrorEnvelope),
+    )
+)]
 #[post("/{address}/modules/{module_name}/functions/{function_name}")]
 async fn function_detail(
+    req: HttpRequest,
     context: Data<ApiContext>,
+    network: Network,
     path: web::Path<FunctionPostParams>,
     body: web::Json<SignedBody>,
 ) -> Result<impl Responder, Error> {
-    let verified_body: FunctionDetailBody =
-        domain::verify(&body.into_inner(), &path.address).await?;
+    let body = body.into_inner();
+    let verified_body: FunctionDetailBody = domain::verify_owner(
+        &context.app_db,
+        &network.name,
+        &network.auth_keys,
+        &body,
+        &path.address,
+    )
+    .await?;
+    rate_limit::charge_signer(&req, &body.payload.address)?;
 
     let new_function_detail = NewModuleFunctionDetail::new(
         &path.address,
@@ -183,12 +944,81 @@ async fn function_detail(
     );
 
     domain::function::create_or_update_function_detail(
-        &context.function_index_db,
+        &network.function_index_db,
         &context.app_db,
+        &network.name,
         &new_function_detail,
     )
     .await?;
 
+    let scopes =
+        domain::account::get_cache_scopes(&context.app_db, &network.name, &path.address).await?;
+    context
+        .cache
+        .invalidate_scopes(&network.name, &scopes)
+        .await;
+
+    Ok(HttpResponse::Ok())
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/accounts",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("module_name" = String, Path, description = "Module name"),
+        ("struct_name" = String, Path, description = "Struct name"),
+    ),
+    request_body(content = SignedBody, description = "Signed `StructDetailBody`"),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+        (status = 401, description = "Not signed by the account", body = ErrorEnvelope),
+        (status = 404, description = "On-chain struct not found", body = ErrorEnvelope),
+    )
+)]
+#[post("/{address}/modules/{module_name}/structs/{struct_name}")]
+async fn struct_detail(
+    req: HttpRequest,
+    context: Data<ApiContext>,
+    network: Network,
+    path: web::Path<StructPostParams>,
+    body: web::Json<SignedBody>,
+) -> Result<impl Responder, Error> {
+    let body = body.into_inner();
+    let verified_body: StructDetailBody = domain::verify_owner(
+        &context.app_db,
+        &network.name,
+        &network.auth_keys,
+        &body,
+        &path.address,
+    )
+    .await?;
+    rate_limit::charge_signer(&req, &body.payload.address)?;
+
+    let new_struct_detail = NewModuleStructDetail::new(
+        &path.address,
+        &path.module_name,
+        &path.struct_name,
+        verified_body.description,
+        verified_body.field_descriptions,
+    );
+
+    domain::move_struct::create_or_update_struct_detail(
+        &network.aptos_full_index_db,
+        &context.app_db,
+        &network.name,
+        &new_struct_detail,
+    )
+    .await?;
+
+    let scopes =
+        domain::account::get_cache_scopes(&context.app_db, &network.name, &path.address).await?;
+    context
+        .cache
+        .invalidate_scopes(&network.name, &scopes)
+        .await;
+
     Ok(HttpResponse::Ok())
 }
 
@@ -197,7 +1027,19 @@ pub fn routers(scope: actix_web::Scope) -> actix_web::Scope {
         .service(check_module_auth)
         .service(modules_by_address_or_alias)
         .service(functions_by_account_and_module_name)
+        .service(structs_by_account_and_module_name)
+        .service(struct_by_account_and_module_name)
+        .service(module_versions)
+        .service(module_abi_diff)
+        .service(module_dependencies)
+        .service(module_dependents)
+        .service(module_friends)
         .service(account_alias)
+        .service(account_profile)
+        .service(account_domain_verification)
+        .service(resource_accounts)
+        .service(register_resource_account)
         .service(module_detail)
         .service(function_detail)
+        .service(struct_detail)
 }
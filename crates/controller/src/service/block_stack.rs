@@ -1,6 +1,11 @@
 use crate::config::ApiContext;
 use crate::service::{
-    domain::{block_stack, Response},
+    domain::{
+        block_stack,
+        cursor::{self, BlockStackCursor},
+        Response,
+    },
+    network::Network,
     Error,
 };
 
@@ -12,14 +17,18 @@ use actix_web::{
 
 use database::models::block_stack::stack::NewBlockStack;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct BlockStackQueryParams {
     pub offset: Option<i64>,
     pub limit: Option<i64>,
+    /// Opaque `next_cursor` of a previous page, takes precedence over offset
+    pub cursor: Option<String>,
 }
 
-#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct Block {
     pub r#type: String,
     pub function: String,
@@ -27,7 +36,7 @@ pub struct Block {
     pub arguments: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BlockStackBody {
     pub name: String,
     pub blocks: Vec<Block>,
@@ -46,91 +55,219 @@ impl TryFrom<BlockStackBody> for NewBlockStack {
     }
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/block-stacks",
+    tag = "block-stacks",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        BlockStackQueryParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = BlockStacksResponse),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+    )
+)]
 #[get("/{address}")]
 async fn block_stacks(
     context: Data<ApiContext>,
+    network: Network,
     address: web::Path<String>,
     params: Query<BlockStackQueryParams>,
 ) -> Result<impl Responder, Error> {
     let address = address.into_inner();
-    let offset = params.offset.unwrap_or(0);
+    let cursor: Option<BlockStackCursor> = cursor::decode_optional(&params.cursor)?;
+    let offset = match cursor {
+        Some(_) => 0,
+        None => params.offset.unwrap_or(0),
+    };
     let limit = params.limit.unwrap_or(10);
 
-    let block_stacks =
-        block_stack::get_my_block_stacks(&context.app_db, &address, offset, limit).await;
+    let block_stacks = block_stack::get_my_block_stacks(
+        &context.app_db,
+        &network.name,
+        &address,
+        &cursor,
+        offset,
+        limit,
+    )
+    .await?;
 
-    let count = block_stack::get_my_block_stacks_count(&context.app_db, &address).await;
+    let count =
+        block_stack::get_my_block_stacks_count(&context.app_db, &network.name, &address).await?;
 
-    Ok(web::Json(Response::new(
-        count.count,
-        block_stacks,
-        Some(offset),
-        Some(limit),
-    )))
+    let next_cursor = cursor::next_cursor(&block_stacks, limit, |b| BlockStackCursor::from(b));
+    let offset = Some(offset).filter(|_| cursor.is_none());
+
+    Ok(web::Json(
+        Response::new(count.count, block_stacks, offset, Some(limit)).with_next_cursor(next_cursor),
+    ))
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/block-stacks",
+    tag = "block-stacks",
+    params(
+        ("address" = String, Path, description = "Account address"),
+    ),
+    request_body = BlockStackBody,
+    responses(
+        (status = 200, description = "Id of the created block stack", body = i32),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+    )
+)]
 #[post("/{address}")]
 async fn create_block_stack(
     context: Data<ApiContext>,
+    network: Network,
     address: web::Path<String>,
     body: web::Json<BlockStackBody>,
 ) -> Result<impl Responder, Error> {
     let address = address.into_inner();
-    let latest_stack_id = block_stack::create_my_block_stack(&context.app_db, address, body.into_inner().try_into()?)
-        .await?;
+    let latest_stack_id = block_stack::create_my_block_stack(
+        &context.app_db,
+        &context.settings.compile,
+        &context.compile_jobs,
+        &network.name,
+        address,
+        body.into_inner().try_into()?,
+    )
+    .await?;
 
     Ok(web::Json(latest_stack_id))
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/block-stacks",
+    tag = "block-stacks",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("id" = i32, Path, description = "Block stack id"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = BlockStack),
+        (status = 404, description = "Block stack not found", body = ErrorEnvelope),
+    )
+)]
 #[get("/{address}/stacks/{id}")]
 async fn get_block_stack(
     context: Data<ApiContext>,
+    network: Network,
     path: web::Path<(String, i32)>,
 ) -> Result<impl Responder, Error> {
-    let (_, id) = path.into_inner();
-    let bs = block_stack::get_block_stack(&context.app_db, id).await;
+    let (address, id) = path.into_inner();
+    let bs = block_stack::get_block_stack(&context.app_db, &network.name, &address, id)
+        .await?
+        .ok_or_else(|| Error::NotFound {
+            msg: "block stack not found".to_string(),
+        })?;
     Ok(web::Json(bs))
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/block-stacks",
+    tag = "block-stacks",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("id" = i32, Path, description = "Block stack id"),
+    ),
+    responses(
+        (status = 200, description = "Compiled script bytecode", body = String),
+        (status = 404, description = "Block stack not found", body = ErrorEnvelope),
+    )
+)]
 #[get("/{address}/stacks/{id}/atomic")]
 async fn get_script_bytecode(
     context: Data<ApiContext>,
+    network: Network,
     path: web::Path<(String, i32)>,
 ) -> Result<impl Responder, Error> {
-    let (_, id) = path.into_inner();
-    let bs = block_stack::get_script_bytecode(&context.app_db, id).await;
+    let (address, id) = path.into_inner();
+    let bs = block_stack::get_script_bytecode(&context.app_db, &network.name, &address, id)
+        .await?
+        .ok_or_else(|| Error::NotFound {
+            msg: "block stack not found".to_string(),
+        })?;
     Ok(web::Json(bs))
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/block-stacks",
+    tag = "block-stacks",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("id" = i32, Path, description = "Block stack id"),
+    ),
+    request_body = BlockStackBody,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+        (status = 404, description = "Block stack not found", body = ErrorEnvelope),
+    )
+)]
 #[patch("/{address}/stacks/{id}")]
 async fn update_block_stack(
     context: Data<ApiContext>,
+    network: Network,
     path: web::Path<(String, i32)>,
     body: web::Json<BlockStackBody>,
 ) -> Result<impl Responder, Error> {
     let (address, id) = path.into_inner();
-    block_stack::update_my_block_stack(&context.app_db, address, id, body.into_inner().try_into()?)
-        .await?;
+    block_stack::update_my_block_stack(
+        &context.app_db,
+        &context.settings.compile,
+        &context.compile_jobs,
+        &network.name,
+        address,
+        id,
+        body.into_inner().try_into()?,
+    )
+    .await?;
     Ok(HttpResponse::Ok())
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/block-stacks",
+    tag = "block-stacks",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("id" = i32, Path, description = "Block stack id"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Block stack not found", body = ErrorEnvelope),
+    )
+)]
 #[delete("/{address}/stacks/{id}")]
 async fn delete_block_stack(
     context: Data<ApiContext>,
+    network: Network,
     path: web::Path<(String, i32)>,
 ) -> Result<impl Responder, Error> {
     let (address, id) = path.into_inner();
-    block_stack::delete_my_block_stack(&context.app_db, address, id).await?;
+    block_stack::delete_my_block_stack(&context.app_db, &network.name, address, id).await?;
     Ok(HttpResponse::Ok())
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/block-stacks",
+    tag = "block-stacks",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("id" = i32, Path, description = "Block stack id"),
+    ),
+    responses(
+        (status = 200, description = "Hash of the submitted transaction", body = String),
+        (status = 404, description = "Block stack not found", body = ErrorEnvelope),
+    )
+)]
 #[get("/{address}/execute/stacks/{id}")]
 async fn execute_script(
     context: Data<ApiContext>,
+    network: Network,
     path: web::Path<(String, i32)>,
 ) -> Result<impl Responder, Error> {
     let (address, id) = path.into_inner();
-    let res = block_stack::execute_script(&context.app_db, address, id).await?;
+    let res = block_stack::execute_script(&context.app_db, &network, address, id).await?;
 
     Ok(web::Json(res))
 }
@@ -1,21 +1,60 @@
+pub mod abi;
 pub mod account;
+pub mod auth_key;
 pub mod block_stack;
+pub mod bytecode;
+pub mod cursor;
+pub mod dependency;
 pub mod function;
+pub mod health;
 pub mod module;
+pub mod move_struct;
+pub mod profile;
+pub mod resource_account;
+pub mod session;
+pub mod usage;
+pub mod workspace;
 
+use crate::service::domain::auth_key::{AuthKeySource, SignerKey};
 use crate::service::Error;
-use aptos_sdk::crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
-use aptos_sdk::crypto::{Signature, ValidCryptoMaterialStringExt};
 use std::ops::Add;
 use std::str::FromStr;
 
 use aptos_sdk::types::account_address::{create_resource_address, AccountAddress};
 
+use database::models::block_stack::stack::BlockStack;
+use database::models::module_hub::composite::function::{
+    ModuleFunctionWithAccountDetail, ModuleFunctionWithOwnAndAccountDetail,
+};
+use database::models::module_hub::composite::module::MoveModuleWithFunctionsAccountAndDetails;
+use database::models::module_hub::composite::move_struct::MoveStructWithDetail;
+use database::models::module_hub::core::dependency::ModuleNode;
+use database::models::module_hub::core::function::ModuleFunction;
+use database::models::module_hub::core::module::{ModuleVersion, MoveModule};
+use database::models::module_hub::core::usage::FunctionUsage;
+use database::models::module_hub::detail::resource_account::ResourceAccount;
+use database::repository::resource_account::ResourceAccountRepository;
+
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize)]
+/// `data` with the pagination to fetch the following page, aliased per payload in the OpenAPI document
+#[derive(Serialize, Deserialize, ToSchema)]
+#[aliases(
+    ModulesResponse = Response<Vec<MoveModule>>,
+    ModuleResponse = Response<MoveModuleWithFunctionsAccountAndDetails>,
+    StructsResponse = Response<Vec<MoveStructWithDetail>>,
+    ModuleVersionsResponse = Response<Vec<ModuleVersion>>,
+    ModuleNodesResponse = Response<Vec<ModuleNode>>,
+    ResourceAccountsResponse = Response<Vec<ResourceAccount>>,
+    BlockStacksResponse = Response<Vec<BlockStack>>,
+    FunctionsResponse = Response<Vec<ModuleFunctionWithAccountDetail>>,
+    EntryFunctionsResponse = Response<Vec<ModuleFunctionWithOwnAndAccountDetail>>,
+    StructFunctionsResponse = Response<Vec<ModuleFunction>>,
+    FunctionUsagesResponse = Response<Vec<FunctionUsage>>
+)]
 pub struct Response<T>
 where
     T: Serialize,
@@ -24,13 +63,15 @@ where
     pagination: Pagination,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct Pagination {
     /// Only Some when queried with pagination params
     pub offset: Option<i64>,
     /// Only Some when queried with pagination params
     pub limit: Option<i64>,
     pub total_len: i64,
+    /// Only Some when a following page may exist, pass it back as `cursor`
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, FromRow)]
@@ -49,12 +90,18 @@ where
                 offset,
                 limit,
                 total_len,
+                next_cursor: None,
             },
         }
     }
+
+    pub fn with_next_cursor(mut self, next_cursor: Option<String>) -> Self {
+        self.pagination.next_cursor = next_cursor;
+        self
+    }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct SignedData {
     pub address: String,
     pub application: String,
@@ -67,33 +114,86 @@ pub struct SignedData {
     pub signature: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// `payload.message` holds the json body of the endpoint, signed by `payload.address`
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct SignedBody {
     pub payload: SignedData,
     pub public_key: String,
 }
 
+/// The resource account created with seed `[0]`, registered seeds are looked up separately
 pub fn get_resource_account(source: &str) -> Result<String, Error> {
+    get_resource_account_with_seed(source, &[0])
+}
+
+pub fn get_resource_account_with_seed(source: &str, seed: &[u8]) -> Result<String, Error> {
     Ok(String::from("0x").add(
         create_resource_address(
             AccountAddress::from_str(source).map_err(|_| Error::InvalidParams {
                 msg: "cannot parse address".to_string(),
             })?,
-            &[0],
+            seed,
         )
         .to_string()
         .as_str(),
     ))
 }
 
-pub async fn verify<T>(body: &SignedBody, address: &str) -> Result<T, Error>
+/// Whether `signer` is `address` or created it as its default resource account
+fn acts_for(signer: &str, address: &str) -> Result<bool, Error> {
+    Ok(signer == address || get_resource_account(signer)? == address)
+}
+
+/// Signed with an ed25519 or a multi-ed25519 key, which must be the key `payload.address` holds
+/// on chain so rotated keys are honoured and former keys rejected
+pub async fn verify<T>(
+    auth_keys: &AuthKeySource,
+    body: &SignedBody,
+    address: &str,
+) -> Result<T, Error>
+where
+    T: Serialize + DeserializeOwned + std::fmt::Debug,
+{
+    if !acts_for(&body.payload.address, address)? {
+        return Err(Error::UnAuthorized {});
+    }
+
+    verify_signer(auth_keys, body).await
+}
+
+/// `verify` which also accepts the signer owning `address` as a registered resource account
+pub async fn verify_owner<T>(
+    app_db: &impl ResourceAccountRepository,
+    network: &str,
+    auth_keys: &AuthKeySource,
+    body: &SignedBody,
+    address: &str,
+) -> Result<T, Error>
+where
+    T: Serialize + DeserializeOwned + std::fmt::Debug,
+{
+    if !acts_for(&body.payload.address, address)?
+        && !resource_account::is_resource_account_of(
+            app_db,
+            network,
+            &body.payload.address,
+            address,
+        )
+        .await?
+    {
+        return Err(Error::UnAuthorized {});
+    }
+
+    verify_signer(auth_keys, body).await
+}
+
+/// Checks the signature and the on-chain key of `payload.address`, whom it may act for is left
+/// to the caller
+async fn verify_signer<T>(auth_keys: &AuthKeySource, body: &SignedBody) -> Result<T, Error>
 where
     T: Serialize + DeserializeOwned + std::fmt::Debug,
 {
-    let signature = Ed25519Signature::from_encoded_string(&body.payload.signature)
-        .map_err(anyhow::Error::new)?;
-    let public_key =
-        Ed25519PublicKey::from_encoded_string(&body.public_key).map_err(anyhow::Error::new)?;
+    let signer = SignerKey::parse(&body.public_key, &body.payload.signature)?;
 
     if !body.payload.full_message.contains(&body.payload.message) {
         return Err(Error::NotFound {
@@ -101,11 +201,9 @@ where
         });
     }
 
-    signature.verify_arbitrary_msg(body.payload.full_message.as_bytes(), &public_key)?;
-
-    let resource_account = get_resource_account(&body.payload.address)?;
+    signer.verify(body.payload.full_message.as_bytes())?;
 
-    if body.payload.address != address && resource_account != address {
+    if !auth_keys.controls(&signer, &body.payload.address).await? {
         return Err(Error::UnAuthorized {});
     }
 
@@ -114,9 +212,11 @@ where
 
 #[cfg(test)]
 mod signature {
-    use crate::service::domain::{verify, SignedBody};
+    use crate::service::domain::{auth_key::AuthKeySource, verify, SignedBody};
+    use crate::service::Error;
     use serde::{Deserialize, Serialize};
     use serde_json::from_str;
+    use std::collections::HashMap;
 
     #[test]
     fn test_sign() {
@@ -134,7 +234,7 @@ mod signature {
     async fn test_sign_inner() {
         let msg = r#"
         {
-            "data": {
+            "payload": {
                 "address": "0x44ed46e3943de0ec15ea8edf0a40aa111dc82f8cfcdd82a712ad1352079f21b2",
                 "application": "https://petra.app",
                 "chainId": 42,
@@ -149,10 +249,12 @@ mod signature {
         "#;
 
         let signed_body: SignedBody = from_str(msg).unwrap();
+        let address = "0x44ed46e3943de0ec15ea8edf0a40aa111dc82f8cfcdd82a712ad1352079f21b2";
 
         let module_detail = verify::<ModuleDetailBody>(
+            &AuthKeySource::Fixed(HashMap::new()),
             &signed_body,
-            "0x4f96cac93f78add3aabff81f6bbb5d1d9a0f7aa7fffbd2e13c3acf9c35f69bda",
+            address,
         )
         .await
         .unwrap();
@@ -164,6 +266,22 @@ mod signature {
                 github_url: Some("github.com".to_string()),
                 r#type: Some("NFT".to_string()),
             }
-        )
+        );
+
+        // The key was rotated away from the signer
+        let rotated = AuthKeySource::Fixed(HashMap::from([(
+            address.to_string(),
+            format!("0x{}", "b".repeat(64)),
+        )]));
+        let res = verify::<ModuleDetailBody>(&rotated, &signed_body, address).await;
+        assert!(matches!(res, Err(Error::UnAuthorized {})));
+
+        let mut tampered = signed_body.clone();
+        tampered.payload.full_message = tampered.payload.full_message.replace("NFT", "DeFi");
+        tampered.payload.message = tampered.payload.message.replace("NFT", "DeFi");
+        let res =
+            verify::<ModuleDetailBody>(&AuthKeySource::Fixed(HashMap::new()), &tampered, address)
+                .await;
+        assert!(res.is_err());
     }
 }
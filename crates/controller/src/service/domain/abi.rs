@@ -0,0 +1,273 @@
+use database::models::module_hub::core::function::ExposedFunction;
+use database::models::module_hub::core::module::MoveModule;
+use database::models::module_hub::core::move_struct::MoveStruct;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FunctionChange {
+    pub name: String,
+    /// Any of `visibility`, `is_entry`, `generic_type_params`, `params` and `return`
+    pub changed: Vec<String>,
+    pub before: ExposedFunction,
+    pub after: ExposedFunction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct StructChange {
+    pub name: String,
+    pub added_fields: Vec<String>,
+    pub removed_fields: Vec<String>,
+    /// Fields kept under the same name with a different type
+    pub changed_fields: Vec<String>,
+    pub abilities_changed: bool,
+    pub generic_type_params_changed: bool,
+    pub before: MoveStruct,
+    pub after: MoveStruct,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AbiDiff {
+    pub from_version: i64,
+    pub to_version: i64,
+    pub added_functions: Vec<ExposedFunction>,
+    pub removed_functions: Vec<ExposedFunction>,
+    pub changed_functions: Vec<FunctionChange>,
+    pub added_structs: Vec<MoveStruct>,
+    pub removed_structs: Vec<MoveStruct>,
+    pub changed_structs: Vec<StructChange>,
+    /// True when a block stack calling an entry function of `from` may stop working on `to`
+    pub breaks_entry_functions: bool,
+}
+
+fn diff_function(before: &ExposedFunction, after: &ExposedFunction) -> Option<FunctionChange> {
+    let changed = [
+        ("visibility", before.visibility != after.visibility),
+        ("is_entry", before.is_entry != after.is_entry),
+        (
+            "generic_type_params",
+            before.generic_type_params != after.generic_type_params,
+        ),
+        ("params", before.params != after.params),
+        ("return", before.return_types != after.return_types),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(field, _)| field.to_string())
+    .collect::<Vec<_>>();
+
+    if changed.is_empty() {
+        return None;
+    }
+
+    Some(FunctionChange {
+        name: after.name.clone(),
+        changed,
+        before: before.clone(),
+        after: after.clone(),
+    })
+}
+
+fn diff_struct(before: &MoveStruct, after: &MoveStruct) -> Option<StructChange> {
+    let field_type = |s: &MoveStruct, name: &str| {
+        s.fields
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.r#type.clone())
+    };
+
+    let added_fields = after
+        .fields
+        .iter()
+        .filter(|f| field_type(before, &f.name).is_none())
+        .map(|f| f.name.clone())
+        .collect::<Vec<_>>();
+    let removed_fields = before
+        .fields
+        .iter()
+        .filter(|f| field_type(after, &f.name).is_none())
+        .map(|f| f.name.clone())
+        .collect::<Vec<_>>();
+    let changed_fields = after
+        .fields
+        .iter()
+        .filter(|f| matches!(field_type(before, &f.name), Some(t) if t != f.r#type))
+        .map(|f| f.name.clone())
+        .collect::<Vec<_>>();
+
+    let abilities_changed = before.abilities != after.abilities;
+    let generic_type_params_changed = before.generic_type_params != after.generic_type_params;
+
+    if added_fields.is_empty()
+        && removed_fields.is_empty()
+        && changed_fields.is_empty()
+        && !abilities_changed
+        && !generic_type_params_changed
+    {
+        return None;
+    }
+
+    Some(StructChange {
+        name: after.name.clone(),
+        added_fields,
+        removed_fields,
+        changed_fields,
+        abilities_changed,
+        generic_type_params_changed,
+        before: before.clone(),
+        after: after.clone(),
+    })
+}
+
+/// Items are matched by name, so a rename shows up as one removal and one addition
+pub fn diff_modules(from: &MoveModule, to: &MoveModule) -> AbiDiff {
+    let (from_functions, to_functions) =
+        (from.parse_exposed_functions(), to.parse_exposed_functions());
+    let (from_structs, to_structs) = (from.parse_structs(), to.parse_structs());
+
+    let find_function = |functions: &[ExposedFunction], name: &str| {
+        functions.iter().find(|f| f.name == name).cloned()
+    };
+    let find_struct =
+        |structs: &[MoveStruct], name: &str| structs.iter().find(|s| s.name == name).cloned();
+
+    let added_functions = to_functions
+        .iter()
+        .filter(|f| find_function(&from_functions, &f.name).is_none())
+        .cloned()
+        .collect::<Vec<_>>();
+    let removed_functions = from_functions
+        .iter()
+        .filter(|f| find_function(&to_functions, &f.name).is_none())
+        .cloned()
+        .collect::<Vec<_>>();
+    let changed_functions = to_functions
+        .iter()
+        .filter_map(|f| {
+            find_function(&from_functions, &f.name).and_then(|before| diff_function(&before, f))
+        })
+        .collect::<Vec<_>>();
+
+    let added_structs = to_structs
+        .iter()
+        .filter(|s| find_struct(&from_structs, &s.name).is_none())
+        .cloned()
+        .collect();
+    let removed_structs = from_structs
+        .iter()
+        .filter(|s| find_struct(&to_structs, &s.name).is_none())
+        .cloned()
+        .collect();
+    let changed_structs = to_structs
+        .iter()
+        .filter_map(|s| {
+            find_struct(&from_structs, &s.name).and_then(|before| diff_struct(&before, s))
+        })
+        .collect();
+
+    let breaks_entry_functions = removed_functions.iter().any(|f| f.is_entry)
+        || changed_functions.iter().any(|c| {
+            c.before.is_entry
+                && c.changed
+                    .iter()
+                    .any(|field| field != "visibility" && field != "return")
+        });
+
+    AbiDiff {
+        from_version: from.transaction_version,
+        to_version: to.transaction_version,
+        added_functions,
+        removed_functions,
+        changed_functions,
+        added_structs,
+        removed_structs,
+        changed_structs,
+        breaks_entry_functions,
+    }
+}
+
+#[cfg(test)]
+mod diff {
+    use crate::service::domain::abi::diff_modules;
+    use database::models::module_hub::core::module::MoveModule;
+    use serde_json::json;
+
+    fn module(
+        transaction_version: i64,
+        functions: serde_json::Value,
+        structs: serde_json::Value,
+    ) -> MoveModule {
+        MoveModule {
+            transaction_version,
+            name: "coin".to_string(),
+            address: "0x1".to_string(),
+            exposed_functions: Some(functions),
+            structs: Some(structs),
+            ..Default::default()
+        }
+    }
+
+    fn function(name: &str, is_entry: bool, params: &[&str]) -> serde_json::Value {
+        json!({
+            "name": name,
+            "visibility": "public",
+            "is_entry": is_entry,
+            "generic_type_params": [],
+            "params": params,
+            "return": []
+        })
+    }
+
+    fn coin_struct(fields: serde_json::Value) -> serde_json::Value {
+        json!({
+            "name": "Coin",
+            "is_native": false,
+            "abilities": ["store"],
+            "generic_type_params": [],
+            "fields": fields
+        })
+    }
+
+    #[test]
+    fn test_diff_modules() {
+        let from = module(
+            1,
+            json!([
+                function("transfer", true, &["&signer", "address", "u64"]),
+                function("burn", true, &["&signer", "u64"]),
+                function("value", false, &["&Coin"]),
+            ]),
+            json!([coin_struct(json!([{ "name": "value", "type": "u64" }]))]),
+        );
+        let to = module(
+            2,
+            json!([
+                function("transfer", true, &["&signer", "address", "u128"]),
+                function("value", false, &["&Coin"]),
+                function("mint", true, &["&signer", "u64"]),
+            ]),
+            json!([coin_struct(json!([
+                { "name": "value", "type": "u128" },
+                { "name": "frozen", "type": "bool" }
+            ]))]),
+        );
+
+        let diff = diff_modules(&from, &to);
+
+        assert_eq!((diff.from_version, diff.to_version), (1, 2));
+        assert_eq!(diff.added_functions[0].name, "mint");
+        assert_eq!(diff.removed_functions[0].name, "burn");
+        assert_eq!(diff.changed_functions.len(), 1);
+        assert_eq!(diff.changed_functions[0].name, "transfer");
+        assert_eq!(diff.changed_functions[0].changed, vec!["params"]);
+        assert!(diff.added_structs.is_empty() && diff.removed_structs.is_empty());
+        assert_eq!(diff.changed_structs[0].added_fields, vec!["frozen"]);
+        assert_eq!(diff.changed_structs[0].changed_fields, vec!["value"]);
+        assert!(diff.breaks_entry_functions);
+
+        let same = diff_modules(&from, &from);
+        assert!(same.changed_functions.is_empty() && same.changed_structs.is_empty());
+        assert!(!same.breaks_entry_functions);
+    }
+}
@@ -1,93 +1,441 @@
-use crate::service::{domain::Count, Error};
+use crate::metrics;
+use crate::service::{domain::bytecode::standardize_address, Error};
 
-use database::db::PostgresPool;
-use database::models::module_hub::detail::account::AccountDetail;
+use database::models::module_hub::detail::account::{AccountAliasHistory, AccountDetail};
+use database::repository::account::AccountDetailRepository;
+use database::repository::module::OnChainModuleRepository;
 
-use sqlx::postgres::PgQueryResult;
-use sqlx::{query, query_as};
+pub const ALIAS_MIN_LEN: usize = 3;
+pub const ALIAS_MAX_LEN: usize = 32;
 
-pub(crate) async fn get_address_by_alias(app_db: &PostgresPool, alias: &str) -> Option<String> {
-    let maybe_address_by_alias: Option<AccountDetail> =
-        query_as("SELECT * FROM account_detail WHERE alias = $1")
-            .bind(alias)
-            .fetch_one(app_db)
-            .await
-            .ok();
+/// Days a replaced alias keeps redirecting and cannot be claimed by another account
+pub const ALIAS_GRACE_PERIOD_DAYS: i32 = 30;
+
+/// Route segments and names users would mistake for official accounts
+const RESERVED_ALIASES: [&str; 14] = [
+    "accounts",
+    "admin",
+    "api",
+    "aptos",
+    "block-stacks",
+    "framework",
+    "functions",
+    "modules",
+    "null",
+    "root",
+    "std",
+    "structs",
+    "system",
+    "undefined",
+];
 
-    match maybe_address_by_alias {
-        Some(account_detail) => Some(account_detail.address),
-        None => None,
+fn is_address_shaped(alias: &str) -> bool {
+    match alias.strip_prefix("0x") {
+        Some(hex) => hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => false,
     }
 }
 
+/// Lowercase letters, digits, `-` and `_`, starting with a letter or digit
+pub fn validate_alias(alias: &str) -> Result<(), Error> {
+    let invalid = |msg: &str| {
+        Err(Error::InvalidParams {
+            msg: format!("alias {}", msg),
+        })
+    };
+
+    if !(ALIAS_MIN_LEN..=ALIAS_MAX_LEN).contains(&alias.len()) {
+        return invalid(&format!(
+            "must be {} to {} characters",
+            ALIAS_MIN_LEN, ALIAS_MAX_LEN
+        ));
+    }
+
+    if !alias
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    {
+        return invalid("may only contain lowercase letters, digits, '-' and '_'");
+    }
+
+    if !alias.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return invalid("must start with a letter or digit");
+    }
+
+    if is_address_shaped(alias) {
+        return invalid("must not look like an address");
+    }
+
+    if RESERVED_ALIASES.contains(&alias) {
+        return invalid("is reserved");
+    }
+
+    Ok(())
+}
+
+/// Falls back to aliases replaced within the grace period so old links keep working
+pub(crate) async fn get_address_by_alias(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    alias: &str,
+) -> Result<Option<String>, Error> {
+    let _timer = metrics::query_timer("account::get_address_by_alias");
+
+    let maybe_address_by_alias = app_db
+        .get_account_detail_by_alias(network, alias)
+        .await
+        .map_err(Error::DbError)?;
+
+    if let Some(account_detail) = maybe_address_by_alias {
+        return Ok(Some(account_detail.address));
+    }
+
+    Ok(get_recent_alias_history(app_db, network, alias)
+        .await?
+        .map(|history| history.address))
+}
+
+async fn get_recent_alias_history(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    alias: &str,
+) -> Result<Option<AccountAliasHistory>, Error> {
+    app_db
+        .get_recent_alias_history(network, alias, ALIAS_GRACE_PERIOD_DAYS)
+        .await
+        .map_err(Error::DbError)
+}
+
 pub(crate) async fn get_account_detail(
-    app_db: &PostgresPool,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
     address: &str,
-) -> Option<AccountDetail> {
-    query_as("SELECT * FROM account_detail WHERE address = $1")
-        .bind(address)
-        .fetch_one(app_db)
+) -> Result<Option<AccountDetail>, Error> {
+    let _timer = metrics::query_timer("account::get_account_detail");
+
+    app_db
+        .get_account_detail(network, address)
         .await
-        .ok()
+        .map_err(Error::DbError)
+}
+
+/// Scope of cached reads about `address_or_alias`, addresses in their standard form so short and
+/// long forms share entries
+pub(crate) fn cache_scope(address_or_alias: &str) -> String {
+    match is_address_shaped(address_or_alias) {
+        true => standardize_address(address_or_alias),
+        false => address_or_alias.to_string(),
+    }
+}
+
+/// Cached reads about an account are scoped by its address or alias, these are its standardized
+/// address and current alias
+pub(crate) async fn get_cache_scopes(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    address: &str,
+) -> Result<Vec<String>, Error> {
+    let _timer = metrics::query_timer("account::get_cache_scopes");
+
+    let mut scopes = vec![cache_scope(address)];
+    if let Some(alias) = get_account_detail(app_db, network, address)
+        .await?
+        .and_then(|account_detail| account_detail.alias)
+    {
+        scopes.push(alias);
+    }
+
+    Ok(scopes)
+}
+
+/// Claimed aliases, aliases in their grace period and module names are rejected as conflicts
+async fn check_alias_available(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    address: &str,
+    alias: &str,
+) -> Result<(), Error> {
+    let conflict = |msg: &str| {
+        Err(Error::Conflict {
+            msg: msg.to_string(),
+        })
+    };
+
+    let owner = app_db
+        .get_account_detail_by_alias(network, alias)
+        .await
+        .map_err(Error::DbError)?;
+
+    if matches!(owner, Some(owner) if owner.address != address) {
+        return conflict("alias already taken");
+    }
+
+    if matches!(
+        get_recent_alias_history(app_db, network, alias).await?,
+        Some(history) if history.address != address
+    ) {
+        return conflict("alias was recently released and is still redirecting");
+    }
+
+    let module_count = aptos_full_index_db
+        .count_modules_by_name(alias)
+        .await
+        .map_err(Error::DbError)?;
+
+    if module_count > 0 {
+        return conflict("alias collides with a module name");
+    }
+
+    Ok(())
 }
 
 pub(crate) async fn create_or_update_account_alias(
-    aptos_full_index_db: &PostgresPool,
-    app_db: &PostgresPool,
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
     address: &str,
     alias: &Option<String>,
-) -> Result<PgQueryResult, Error> {
-    let account_count: Count = query_as(
-        "
-            SELECT COUNT(address) as count
-            FROM move_modules
-            WHERE address = $1
-        ",
-    )
-    .bind(address)
-    .fetch_one(aptos_full_index_db)
-    .await
-    .unwrap_or_default();
-
-    if account_count.count == 0 {
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("account::create_or_update_account_alias");
+
+    let module_count = aptos_full_index_db
+        .count_modules(&[address.to_string()])
+        .await
+        .map_err(Error::DbError)?;
+
+    if module_count == 0 {
         return Err(Error::NotFound {
             msg: "on-chain account not found".to_string(),
         });
     }
 
-    let maybe_account_detail: Option<AccountDetail> = query_as(
-        "
-                SELECT *
-                FROM account_detail
-                WHERE address = $1
-                ORDER BY id DESC
-            ",
-    )
-    .bind(address)
-    .fetch_one(app_db)
-    .await
-    .ok();
-
-    let query = match maybe_account_detail {
-        Some(account) => query(
-            "
-                    UPDATE account_detail SET alias = $1
-                    WHERE id = $2
-                ",
-        )
-        .bind(alias)
-        .bind(account.id),
-        None => query(
-            "
-                    INSERT INTO account_detail (address, alias) VALUES ($1, $2)
-                ",
-        )
-        .bind(address)
-        .bind(alias),
+    if let Some(alias) = alias {
+        validate_alias(alias)?;
+        check_alias_available(aptos_full_index_db, app_db, network, address, alias).await?;
+    }
+
+    app_db
+        .set_account_alias(network, address, alias.as_deref())
+        .await
+        .map_err(|e| match e {
+            database::error::Error::UniqueViolation(_) => Error::Conflict {
+                msg: "alias already taken".to_string(),
+            },
+            e => Error::DbError(e),
+        })
+}
+
+#[cfg(test)]
+mod alias {
+    use crate::service::domain::account::{
+        create_or_update_account_alias, get_address_by_alias, validate_alias,
     };
+    use crate::service::Error;
+    use database::models::module_hub::core::module::MoveModule;
+    use database::repository::memory::MemoryRepository;
+
+    const NETWORK: &str = "devnet";
+
+    async fn set_alias(
+        repository: &MemoryRepository,
+        address: &str,
+        alias: &str,
+    ) -> Result<(), Error> {
+        create_or_update_account_alias(
+            repository,
+            repository,
+            NETWORK,
+            address,
+            &Some(alias.to_string()),
+        )
+        .await
+    }
+
+    #[test]
+    fn test_validate_alias() {
+        for alias in ["pontem", "move-block", "liquid_swap", "3xpool"] {
+            assert!(validate_alias(alias).is_ok(), "{}", alias);
+        }
+
+        for alias in [
+            "ab",
+            "a-very-long-alias-that-goes-past-the-limit",
+            "Pontem",
+            "move block",
+            "-leading",
+            "0x1",
+            "0xcafe",
+            "aptos",
+            "admin",
+        ] {
+            assert!(validate_alias(alias).is_err(), "{}", alias);
+        }
+    }
+    #[actix_rt::test]
+    async fn test_replaced_alias_keeps_redirecting() {
+        let repository = MemoryRepository::default();
+        repository
+            .modules
+            .lock()
+            .unwrap()
+            .extend(["0xa", "0xb"].map(|address| MoveModule {
+                name: "coin".to_string(),
+                address: address.to_string(),
+                ..Default::default()
+            }));
+
+        set_alias(&repository, "0xa", "alice").await.unwrap();
+        set_alias(&repository, "0xa", "alicia").await.unwrap();
+        assert_eq!(
+            get_address_by_alias(&repository, NETWORK, "alice")
+                .await
+                .unwrap(),
+            Some("0xa".to_string())
+        );
+        assert_eq!(
+            get_address_by_alias(&repository, "testnet", "alice")
+                .await
+                .unwrap(),
+            None
+        );
+
+        assert!(matches!(
+            set_alias(&repository, "0xb", "alice").await,
+            Err(Error::Conflict { .. })
+        ));
+        assert!(matches!(
+            set_alias(&repository, "0xb", "alicia").await,
+            Err(Error::Conflict { .. })
+        ));
+        assert!(matches!(
+            set_alias(&repository, "0xb", "coin").await,
+            Err(Error::Conflict { .. })
+        ));
+        assert!(matches!(
+            set_alias(&repository, "0xc", "carol").await,
+            Err(Error::NotFound { .. })
+        ));
+
+        set_alias(&repository, "0xa", "alice").await.unwrap();
+        assert!(repository
+            .alias_history
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(_, h)| h.alias != "alice"));
+    }
+}
+
+#[cfg(test)]
+mod cache_scope {
+    use crate::cache::Cache;
+    use crate::config::settings::CacheSettings;
+    use crate::service::domain::{account, module, profile};
+    use crate::service::etag::json_with_etag;
+    use crate::service::Error;
+    use actix_web::http::header::{HeaderValue, ETAG};
+    use actix_web::test::TestRequest;
+    use database::models::module_hub::core::module::MoveModule;
+    use database::models::module_hub::detail::account::NewAccountProfile;
+    use database::repository::memory::MemoryRepository;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const NETWORK: &str = "devnet";
+
+    async fn module_page_etag(
+        cache: &Cache,
+        repository: &MemoryRepository,
+        address_or_alias: &str,
+    ) -> HeaderValue {
+        let page = cache
+            .get_or_load_scoped(
+                NETWORK,
+                &account::cache_scope(address_or_alias),
+                "module:coin",
+                || {
+                    module::get_module_with_functions_and_details(
+                        repository,
+                        repository,
+                        repository,
+                        NETWORK,
+                        address_or_alias,
+                        "coin",
+                    )
+                },
+            )
+            .await
+            .unwrap();
+
+        let res = json_with_etag(&TestRequest::default().to_http_request(), &page).unwrap();
+        res.headers().get(ETAG).unwrap().clone()
+    }
 
-    query
-        .execute(app_db)
+    #[actix_rt::test]
+    async fn test_profile_write_changes_etag() {
+        let cache = Cache::new(&CacheSettings::default()).unwrap();
+        let repository = MemoryRepository::default();
+        repository.modules.lock().unwrap().push(MoveModule {
+            name: "coin".to_string(),
+            address: "0xa".to_string(),
+            ..Default::default()
+        });
+        account::create_or_update_account_alias(
+            &repository,
+            &repository,
+            NETWORK,
+            "0xa",
+            &Some("alice".to_string()),
+        )
         .await
-        .map_err(|e| Error::DbError(e.into()))
+        .unwrap();
+
+        let before = module_page_etag(&cache, &repository, "0xa").await;
+
+        let new_profile = NewAccountProfile {
+            address: "0xa".to_string(),
+            display_name: Some("Alice".to_string()),
+            ..Default::default()
+        };
+        profile::create_or_update_account_profile(&repository, NETWORK, &new_profile)
+            .await
+            .unwrap();
+        assert_eq!(module_page_etag(&cache, &repository, "0xa").await, before);
+
+        let scopes = account::get_cache_scopes(&repository, NETWORK, "0xa")
+            .await
+            .unwrap();
+        assert!(scopes.contains(&"alice".to_string()));
+
+        cache.invalidate_scopes(NETWORK, &scopes).await;
+        assert_ne!(module_page_etag(&cache, &repository, "0xa").await, before);
+    }
+
+    async fn count_modules(cache: &Cache, address_or_alias: &str, loads: &AtomicUsize) -> i64 {
+        cache
+            .get_or_load_scoped(
+                NETWORK,
+                &account::cache_scope(address_or_alias),
+                "count:modules",
+                || async { Ok::<_, Error>(loads.fetch_add(1, Ordering::SeqCst) as i64) },
+            )
+            .await
+            .unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_address_forms_share_a_scope() {
+        let cache = Cache::new(&CacheSettings::default()).unwrap();
+        let repository = MemoryRepository::default();
+        let loads = AtomicUsize::new(0);
+
+        assert_eq!(count_modules(&cache, "0x00A", &loads).await, 0);
+        assert_eq!(count_modules(&cache, "0xa", &loads).await, 0);
+
+        let scopes = account::get_cache_scopes(&repository, NETWORK, "0xa")
+            .await
+            .unwrap();
+        cache.invalidate_scopes(NETWORK, &scopes).await;
+        assert_eq!(count_modules(&cache, "0x00A", &loads).await, 1);
+    }
 }
@@ -0,0 +1,210 @@
+use crate::metrics;
+use crate::service::domain::bytecode::standardize_address;
+use crate::service::Error;
+
+use aptos_sdk::crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+use aptos_sdk::crypto::multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature};
+use aptos_sdk::crypto::{Signature, ValidCryptoMaterialStringExt};
+use aptos_sdk::types::transaction::authenticator::AuthenticationKey;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const NODE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The key material a body was signed with, told apart by the length of `public_key`
+pub enum SignerKey {
+    Ed25519(Ed25519PublicKey, Ed25519Signature),
+    MultiEd25519(MultiEd25519PublicKey, MultiEd25519Signature),
+}
+
+impl SignerKey {
+    pub fn parse(public_key: &str, signature: &str) -> Result<Self, Error> {
+        if let Ok(public_key) = Ed25519PublicKey::from_encoded_string(public_key) {
+            let signature = Ed25519Signature::from_encoded_string(signature).map_err(|_| {
+                Error::InvalidParams {
+                    msg: "signature is not an ed25519 signature".to_string(),
+                }
+            })?;
+            return Ok(SignerKey::Ed25519(public_key, signature));
+        }
+
+        let public_key = MultiEd25519PublicKey::from_encoded_string(public_key).map_err(|_| {
+            Error::InvalidParams {
+                msg: "public_key is neither an ed25519 nor a multi-ed25519 key".to_string(),
+            }
+        })?;
+        let signature = MultiEd25519Signature::from_encoded_string(signature).map_err(|_| {
+            Error::InvalidParams {
+                msg: "signature is not a multi-ed25519 signature".to_string(),
+            }
+        })?;
+
+        Ok(SignerKey::MultiEd25519(public_key, signature))
+    }
+
+    /// A multi-ed25519 signature must carry at least `threshold` signatures of its keys
+    pub fn verify(&self, message: &[u8]) -> Result<(), Error> {
+        match self {
+            SignerKey::Ed25519(public_key, signature) => {
+                signature.verify_arbitrary_msg(message, public_key)?
+            }
+            SignerKey::MultiEd25519(public_key, signature) => {
+                check_threshold(
+                    signature.bitmap(),
+                    *public_key.threshold(),
+                    public_key.public_keys().len(),
+                )?;
+                signature.verify_arbitrary_msg(message, public_key)?
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn authentication_key(&self) -> AuthenticationKey {
+        match self {
+            SignerKey::Ed25519(public_key, _) => AuthenticationKey::ed25519(public_key),
+            SignerKey::MultiEd25519(public_key, _) => AuthenticationKey::multi_ed25519(public_key),
+        }
+    }
+}
+
+/// Bit `i` of the bitmap, counted from the most significant bit of the first byte, marks a
+/// signature by key `i`
+pub fn check_threshold(bitmap: &[u8; 4], threshold: u8, keys: usize) -> Result<(), Error> {
+    let signers = (0..32)
+        .filter(|i| bitmap[i / 8] & (0b1000_0000 >> (i % 8)) != 0)
+        .collect::<Vec<_>>();
+
+    if signers.iter().any(|signer| *signer >= keys) {
+        return Err(Error::InvalidParams {
+            msg: format!("signature bitmap names keys beyond the {} given", keys),
+        });
+    }
+
+    if signers.len() < threshold as usize {
+        return Err(Error::UnAuthorized {});
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeAccount {
+    authentication_key: String,
+}
+
+/// Where the authentication key an account holds on chain is read from
+#[derive(Clone, Debug)]
+pub enum AuthKeySource {
+    /// The REST api of a node, `{node_url}/accounts/{address}`
+    Node {
+        node_url: String,
+        client: reqwest::Client,
+    },
+    /// Keys by standardized address, a stand-in for a node
+    Fixed(HashMap<String, String>),
+}
+
+impl AuthKeySource {
+    /// The client is shared by every check against the node
+    pub fn node(node_url: &str) -> Result<Self, Error> {
+        let client = reqwest::Client::builder()
+            .timeout(NODE_TIMEOUT)
+            .build()
+            .map_err(|e| Error::AnyError(e.into()))?;
+
+        Ok(AuthKeySource::Node {
+            node_url: node_url.trim_end_matches('/').to_string(),
+            client,
+        })
+    }
+
+    /// None for an account not created yet
+    async fn fetch(&self, address: &str) -> Result<Option<String>, Error> {
+        let (node_url, client) = match self {
+            AuthKeySource::Node { node_url, client } => (node_url, client),
+            AuthKeySource::Fixed(keys) => return Ok(keys.get(address).cloned()),
+        };
+
+        let res = client
+            .get(format!("{}/accounts/{}", node_url, address))
+            .send()
+            .await
+            .map_err(|e| Error::AnyError(e.into()))?;
+
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let account: NodeAccount = res
+            .error_for_status()
+            .map_err(|e| Error::AnyError(e.into()))?
+            .json()
+            .await
+            .map_err(|e| Error::AnyError(e.into()))?;
+
+        Ok(Some(account.authentication_key))
+    }
+
+    /// Until an account is created its authentication key is its address, rotating it later
+    /// keeps the address and replaces the key
+    pub async fn authentication_key(&self, address: &str) -> Result<String, Error> {
+        let _timer = metrics::query_timer("auth_key::authentication_key");
+
+        let address = standardize_address(address);
+        let authentication_key = self.fetch(&address).await?;
+
+        Ok(standardize_address(&authentication_key.unwrap_or(address)))
+    }
+
+    /// Whether `address` is currently controlled by the key material of `signer`
+    pub async fn controls(&self, signer: &SignerKey, address: &str) -> Result<bool, Error> {
+        let expected = self.authentication_key(address).await?;
+        let derived = hex::encode(signer.authentication_key().to_vec());
+
+        Ok(standardize_address(&derived) == expected)
+    }
+}
+
+#[cfg(test)]
+mod rotation {
+    use crate::service::domain::auth_key::{check_threshold, AuthKeySource};
+    use crate::service::Error;
+    use std::collections::HashMap;
+
+    #[actix_rt::test]
+    async fn test_rotated_key_replaces_address() {
+        let rotated = format!("0x{}", "b".repeat(64));
+        let source = AuthKeySource::Fixed(HashMap::from([(
+            format!("0x{:0>64}", "a"),
+            rotated.clone(),
+        )]));
+
+        assert_eq!(source.authentication_key("0xA").await.unwrap(), rotated);
+        assert_eq!(
+            source.authentication_key("0xc").await.unwrap(),
+            format!("0x{:0>64}", "c")
+        );
+    }
+
+    #[test]
+    fn test_check_threshold() {
+        // Keys 0 and 2 of 3 signed
+        let bitmap = [0b1010_0000, 0, 0, 0];
+
+        assert!(check_threshold(&bitmap, 2, 3).is_ok());
+        assert!(matches!(
+            check_threshold(&bitmap, 3, 3),
+            Err(Error::UnAuthorized {})
+        ));
+        assert!(matches!(
+            check_threshold(&bitmap, 1, 2),
+            Err(Error::InvalidParams { .. })
+        ));
+        assert!(check_threshold(&[0, 0, 0, 0b0000_0001], 1, 32).is_ok());
+    }
+}
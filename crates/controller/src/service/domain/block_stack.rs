@@ -1,174 +1,299 @@
+use crate::compile::{CompileGuard, CompileJobs};
+use crate::config::{settings::CompileSettings, NetworkContext};
+use crate::metrics;
 use crate::service::block_stack::Block;
-use crate::service::domain::{module, Count};
+use crate::service::domain::{
+    bytecode::standardize_address, cursor::BlockStackCursor, module, Count,
+};
 use crate::service::Error;
 
 use aptos_sdk::crypto::ed25519::Ed25519PrivateKey;
 use aptos_sdk::crypto::{PrivateKey, ValidCryptoMaterialStringExt};
 use aptos_sdk::move_types::account_address::AccountAddress;
-use aptos_sdk::rest_client::Account;
 use aptos_sdk::transaction_builder::TransactionBuilder;
 use aptos_sdk::types::chain_id::ChainId;
 use aptos_sdk::types::transaction::{Script, TransactionPayload};
+use std::collections::BTreeSet;
 use std::str::FromStr;
+use std::time::Instant;
 
-use database::db::PostgresPool;
-use database::models::block_stack::stack::{BlockStack, NewBlockStack};
-use move_generator::{Dependency, Function, MoveScript};
+use database::models::block_stack::compile_job::{
+    COMPILE_JOB_STATUS_FAILED, COMPILE_JOB_STATUS_INTERRUPTED, COMPILE_JOB_STATUS_RUNNING,
+    COMPILE_JOB_STATUS_SUCCEEDED,
+};
+use database::models::block_stack::stack::{BlockStack, NewBlockStack, BLOCK_STACK_STATUS_BROKEN};
+use database::models::module_hub::core::function::ExposedFunction;
+use database::repository::block_stack::BlockStackRepository;
+use database::repository::module::ModuleDetailRepository;
+use move_generator::{CompileResult, Dependency, Function, MoveScript};
 
-use sqlx::postgres::PgQueryResult;
 use sqlx::types::JsonValue;
-use sqlx::{query, query_as};
 use url::Url;
 
-pub async fn get_my_block_stacks_count(app_db: &PostgresPool, address: &str) -> Count {
-    query_as(
-        "
-                SELECT COUNT(id) as count
-                FROM block_stack
-                WHERE address = $1
-            ",
-    )
-    .bind(address)
-    .fetch_one(app_db)
-    .await
-    .ok()
-    .unwrap_or_default()
+pub async fn get_my_block_stacks_count(
+    app_db: &impl BlockStackRepository,
+    network: &str,
+    address: &str,
+) -> Result<Count, Error> {
+    let _timer = metrics::query_timer("block_stack::get_my_block_stacks_count");
+
+    app_db
+        .count_block_stacks_by_address(network, &standardize_address(address))
+        .await
+        .map(|count| Count { count })
+        .map_err(Error::DbError)
 }
 
 pub(crate) async fn get_my_block_stacks(
-    app_db: &PostgresPool,
+    app_db: &impl BlockStackRepository,
+    network: &str,
     address: &str,
+    cursor: &Option<BlockStackCursor>,
     offset: i64,
     limit: i64,
-) -> Option<Vec<BlockStack>> {
-    query_as(
-        "
-                SELECT *
-                FROM block_stack
-                WHERE address = $1
-                ORDER BY id ASC
-                OFFSET $2
-                LIMIT $3
-            ",
-    )
-    .bind(address)
-    .bind(offset)
-    .bind(limit)
-    .fetch_all(app_db)
-    .await
-    .ok()
+) -> Result<Vec<BlockStack>, Error> {
+    let _timer = metrics::query_timer("block_stack::get_my_block_stacks");
+
+    app_db
+        .get_block_stacks_by_address(
+            network,
+            &standardize_address(address),
+            cursor.as_ref().map(|c| c.id),
+            offset,
+            limit,
+        )
+        .await
+        .map_err(Error::DbError)
 }
 
-pub(crate) async fn get_block_stack(app_db: &PostgresPool, id: i32) -> Option<BlockStack> {
-    query_as(
-        "
-                SELECT *
-                FROM block_stack
-                WHERE id = $1
-            ",
-    )
-    .bind(id)
-    .fetch_one(app_db)
-    .await
-    .ok()
+/// `0x1` and `0x0000...0001` own the same stacks
+fn is_owner(address: &str, block_stack: &BlockStack) -> bool {
+    standardize_address(address) == standardize_address(&block_stack.address)
 }
 
-pub(crate) async fn get_script_bytecode(app_db: &PostgresPool, id: i32) -> Option<String> {
-    let maybe_block_stack: Option<BlockStack> = query_as(
-        "
-                SELECT *
-                FROM block_stack
-                WHERE id = $1
-            ",
-    )
-    .bind(id)
-    .fetch_one(app_db)
-    .await
-    .ok();
-
-    if let Some(block_stack) = maybe_block_stack {
-        Some(
-            String::from_utf8_lossy(block_stack.bytecode.unwrap_or_default().as_slice())
-                .to_string(),
-        )
-    } else {
-        None
-    }
+/// None for a stack of another account as well, so ids of other accounts are not revealed
+pub(crate) async fn get_block_stack(
+    app_db: &impl BlockStackRepository,
+    network: &str,
+    address: &str,
+    id: i32,
+) -> Result<Option<BlockStack>, Error> {
+    let _timer = metrics::query_timer("block_stack::get_block_stack");
+
+    Ok(app_db
+        .get_block_stack(network, id)
+        .await
+        .map_err(Error::DbError)?
+        .filter(|block_stack| is_owner(address, block_stack)))
 }
 
-pub(crate) async fn get_last_inserted_stack_id_by_address(
-    app_db: &PostgresPool,
+pub(crate) async fn get_script_bytecode(
+    app_db: &impl BlockStackRepository,
+    network: &str,
     address: &str,
-) -> i32 {
-    let last_stack: Option<BlockStack> = query_as(
-        "
-                SELECT *
-                FROM block_stack
-                WHERE address = $1
-                ORDER BY id DESC
-                limit 1
-            ",
-    )
-    .bind(address)
-    .fetch_one(app_db)
-    .await
-    .ok();
+    id: i32,
+) -> Result<Option<String>, Error> {
+    let _timer = metrics::query_timer("block_stack::get_script_bytecode");
 
-    last_stack.unwrap_or_default().id
+    let maybe_block_stack = get_block_stack(app_db, network, address, id).await?;
+
+    Ok(maybe_block_stack.map(|block_stack| {
+        String::from_utf8_lossy(block_stack.bytecode.unwrap_or_default().as_slice()).to_string()
+    }))
 }
 
-/// Auth-checked address at upper layer
-pub(crate) async fn create_my_block_stack(
-    app_db: &PostgresPool,
+/// Auth-checked address at upper layer, stored standardized
+pub(crate) async fn create_my_block_stack<R>(
+    app_db: &R,
+    compile: &CompileSettings,
+    compile_jobs: &CompileJobs,
+    network: &str,
     address: String,
     new_block_stack: NewBlockStack,
-) -> Result<i32, Error> {
-    let db = app_db.clone();
-    let stack = new_block_stack.stack.clone();
-    let account = address.clone();
-
-    let res = query(
-        "
-                INSERT INTO block_stack
-                (address, name, stack) VALUES ($1, $2, $3)
-             ",
+) -> Result<i32, Error>
+where
+    R: BlockStackRepository + ModuleDetailRepository + Clone + 'static,
+{
+    let _timer = metrics::query_timer("block_stack::create_my_block_stack");
+
+    parse_stack(&new_block_stack.stack)?;
+    let guard = start_compile(compile_jobs)?;
+    let id = app_db
+        .insert_block_stack(network, &standardize_address(&address), &new_block_stack)
+        .await
+        .map_err(Error::DbError)?;
+
+    spawn_create_bytecode(
+        app_db.clone(),
+        compile.clone(),
+        guard,
+        network.to_string(),
+        new_block_stack.stack,
+        id,
     )
-    .bind(&address)
-    .bind(&new_block_stack.name)
-    .bind(&new_block_stack.stack)
-    .execute(app_db)
-    .await
-    .map_err(|e| Error::DbError(e.into()));
-
-    if res.is_ok() {
-        actix_rt::task::spawn_blocking(move || {
-            futures::executor::block_on(create_bytecode(db, stack, account)).unwrap_or_default();
-        });
+    .await?;
+
+    Ok(id)
+}
+
+/// Taken before anything is written, so a refused compile leaves nothing behind
+pub(crate) fn start_compile(compile_jobs: &CompileJobs) -> Result<CompileGuard, Error> {
+    compile_jobs.start().ok_or_else(|| Error::Unavailable {
+        msg: "server is shutting down, compiles are not accepted".to_string(),
+    })
+}
+
+/// Records the compile job and compiles in the background until the guard is dropped
+pub(crate) async fn spawn_create_bytecode<R>(
+    app_db: R,
+    compile: CompileSettings,
+    guard: CompileGuard,
+    network: String,
+    stack: JsonValue,
+    id: i32,
+) -> Result<(), Error>
+where
+    R: BlockStackRepository + ModuleDetailRepository + 'static,
+{
+    let job_id = app_db
+        .insert_compile_job(&network, id)
+        .await
+        .map_err(Error::DbError)?;
+
+    actix_rt::spawn(async move {
+        if let Err(e) = run_compile_job(&app_db, &compile, &network, stack, id, job_id).await {
+            log::warn!("compile job {} of block stack {} failed: {}", job_id, id, e);
+        }
+        drop(guard);
+    });
+
+    Ok(())
+}
+
+/// Compiles the stack and stores its bytecode, every attempt is recorded as a compile job
+pub async fn create_bytecode<R>(
+    app_db: R,
+    compile: CompileSettings,
+    network: String,
+    stack: JsonValue,
+    id: i32,
+) -> Result<(), Error>
+where
+    R: BlockStackRepository + ModuleDetailRepository,
+{
+    let job_id = app_db
+        .insert_compile_job(&network, id)
+        .await
+        .map_err(Error::DbError)?;
+
+    run_compile_job(&app_db, &compile, &network, stack, id, job_id).await
+}
+
+/// Jobs the previous run left unfinished are marked interrupted and their stacks compiled again.
+/// Assumes no other server shares the app database
+pub async fn resume_compile_jobs<R>(
+    app_db: &R,
+    compile: &CompileSettings,
+    compile_jobs: &CompileJobs,
+    network: &str,
+) -> Result<usize, Error>
+where
+    R: BlockStackRepository + ModuleDetailRepository + Clone + 'static,
+{
+    let unfinished = app_db
+        .get_compile_jobs(network, Some(COMPILE_JOB_STATUS_RUNNING), i64::MAX)
+        .await
+        .map_err(Error::DbError)?;
+
+    let mut ids = BTreeSet::new();
+    for job in unfinished {
+        app_db
+            .finish_compile_job(
+                job.id,
+                COMPILE_JOB_STATUS_INTERRUPTED,
+                Some("server stopped before the compile finished"),
+            )
+            .await
+            .map_err(Error::DbError)?;
+        ids.insert(job.block_stack_id);
+    }
+
+    let mut resumed = 0;
+    for id in ids {
+        let stack = match app_db
+            .get_block_stack(network, id)
+            .await
+            .map_err(Error::DbError)?
+        {
+            Some(stack) => stack,
+            None => continue,
+        };
+
+        spawn_create_bytecode(
+            app_db.clone(),
+            compile.clone(),
+            start_compile(compile_jobs)?,
+            network.to_string(),
+            stack.stack,
+            id,
+        )
+        .await?;
+        resumed += 1;
     }
 
-    Ok(get_last_inserted_stack_id_by_address(app_db, &address).await)
+    Ok(resumed)
 }
 
-async fn create_bytecode(
-    app_db: PostgresPool,
+async fn run_compile_job<R>(
+    app_db: &R,
+    compile: &CompileSettings,
+    network: &str,
     stack: JsonValue,
-    address: String,
-) -> Result<(), Error> {
+    id: i32,
+    job_id: i32,
+) -> Result<(), Error>
+where
+    R: BlockStackRepository + ModuleDetailRepository,
+{
+    let res = compile_bytecode(app_db, compile, network, stack, id).await;
+    let (status, error) = match &res {
+        Ok(()) => (COMPILE_JOB_STATUS_SUCCEEDED, None),
+        Err(e) => (COMPILE_JOB_STATUS_FAILED, Some(e.to_string())),
+    };
+
+    app_db
+        .finish_compile_job(job_id, status, error.as_deref())
+        .await
+        .map_err(Error::DbError)?;
+
+    res
+}
+
+async fn compile_bytecode<R>(
+    app_db: &R,
+    compile: &CompileSettings,
+    network: &str,
+    stack: JsonValue,
+    id: i32,
+) -> Result<(), Error>
+where
+    R: BlockStackRepository + ModuleDetailRepository,
+{
     async fn parse_deps_and_functions(
-        app_db: &PostgresPool,
+        app_db: &impl ModuleDetailRepository,
+        network: &str,
         stack: &JsonValue,
-    ) -> (Vec<Dependency>, Vec<Function>) {
+    ) -> Result<(Vec<Dependency>, Vec<Function>), Error> {
         let mut dependencies = Vec::new();
         let mut functions = Vec::new();
 
-        let stack: Vec<Block> =
-            serde_json::from_value::<Vec<Block>>(stack.clone()).unwrap_or_default();
-
-        for block in stack {
-            let split_function = block.function.split("::").collect::<Vec<_>>();
+        for block in parse_stack(stack)? {
             let (address, module_name, _) =
-                (split_function[0], split_function[1], split_function[2]);
-            let maybe_module_detail = module::get_module_detail(app_db, address, module_name).await;
+                split_function(&block.function).ok_or_else(|| Error::InvalidParams {
+                    msg: format!("{} is not address::module::function", block.function),
+                })?;
+            let maybe_module_detail =
+                module::get_module_detail(app_db, network, address, module_name).await?;
             if let Some(module_detail) = maybe_module_detail {
                 let dependency = Dependency::new(
                     &module_detail.github_url.unwrap_or_default(),
@@ -187,26 +312,61 @@ async fn create_bytecode(
             }
         }
 
-        (dependencies, functions)
+        Ok((dependencies, functions))
     }
 
-    let (dependencies, functions) = parse_deps_and_functions(&app_db, &stack).await;
+    let (dependencies, functions) = parse_deps_and_functions(app_db, network, &stack).await?;
     if functions.is_empty() {
         return Err(Error::NotFound {
             msg: "module detail missing".to_string(),
         });
     }
 
-    let mut move_script = MoveScript::new()
-        .init()
+    let move_script = MoveScript::new()
+        .init(&compile.script_dir, &compile.github_raw_base)
         .add_dependencies(dependencies)
         .add_functions(functions);
+    let workspace = move_script.dir.clone();
+
+    let started = Instant::now();
+    let res = match compile_script(move_script).await {
+        Ok(compile_res) => store_bytecode(app_db, id, compile_res, started).await,
+        Err(e) => {
+            metrics::observe_compile(started, false);
+            Err(e)
+        }
+    };
+
+    // Comment this line out on debug
+    tokio::fs::remove_dir_all(workspace)
+        .await
+        .unwrap_or_default();
+    res
+}
 
-    let compile_res = move_script
-        .generate_script()
+/// The aptos cli blocks until it exits, so it runs off the async workers
+async fn compile_script(mut move_script: MoveScript) -> Result<CompileResult, Error> {
+    move_script
+        .generate_package()
         .await
         .map_err(|e| Error::AnyError(anyhow::Error::new(e)))?;
 
+    actix_rt::task::spawn_blocking(move || move_script.compile())
+        .await
+        .map_err(|e| Error::AnyError(e.into()))?
+        .map_err(|e| Error::AnyError(anyhow::Error::new(e)))
+}
+
+/// Without a script in the build the stack is marked broken with the compiler output
+async fn store_bytecode<R>(
+    app_db: &R,
+    id: i32,
+    compile_res: CompileResult,
+    started: Instant,
+) -> Result<(), Error>
+where
+    R: BlockStackRepository,
+{
     let path = compile_res
         .dir
         .join("build")
@@ -214,117 +374,224 @@ async fn create_bytecode(
         .join("bytecode_scripts")
         .join("main.mv");
 
-    match tokio::fs::read(path).await {
+    let compiled_script = tokio::fs::read(path).await;
+    metrics::observe_compile(started, compiled_script.is_ok());
+
+    match compiled_script {
         Ok(compiled_script) => {
-            println!(
-                "{}",
+            log::debug!(
+                "block stack {} compiled: {}",
+                id,
                 String::from_utf8_lossy(compile_res.output.stdout.as_slice())
             );
 
             let hex_encoded_script = hex::encode(compiled_script);
-            query(
-                "
-                UPDATE block_stack SET bytecode = $1
-                WHERE address = $2
-            ",
+            app_db
+                .update_block_stack_bytecode(id, hex_encoded_script.as_bytes())
+                .await
+                .map_err(Error::DbError)
+        }
+        Err(_) => {
+            let stderr = String::from_utf8_lossy(compile_res.output.stderr.as_slice());
+            log::error!("block stack {} failed to compile: {}", id, stderr);
+
+            update_block_stack_status(
+                app_db,
+                id,
+                BLOCK_STACK_STATUS_BROKEN,
+                Some("compilation failed".to_string()),
             )
-            .bind(hex_encoded_script.as_bytes())
-            .bind(address)
-            .execute(&app_db)
             .await
             .ok();
-        }
-        Err(_) => {
-            println!(
-                "{}",
-                String::from_utf8_lossy(compile_res.output.stderr.as_slice())
-            );
+
+            Err(Error::CompileError {
+                msg: stderr.trim().to_string(),
+            })
         }
     }
-
-    // Comment this line out on debug
-    move_script.destroy_self().await.unwrap_or_default();
-    Ok(())
 }
 
-pub(crate) async fn update_my_block_stack(
-    app_db: &PostgresPool,
+pub(crate) async fn update_my_block_stack<R>(
+    app_db: &R,
+    compile: &CompileSettings,
+    compile_jobs: &CompileJobs,
+    network: &str,
     address: String,
     id: i32,
     new_block_stack: NewBlockStack,
-) -> Result<(), Error> {
-    let db = app_db.clone();
-    let stack = new_block_stack.stack.clone();
-    let account = address.clone();
-
-    let target_block_stack: BlockStack = query_as(
-        "
-                SELECT *
-                FROM block_stack
-                WHERE id = $1
-            ",
-    )
-    .bind(id)
-    .fetch_one(app_db)
-    .await
-    .map_err(|_| Error::NotFound {
-        msg: "block stack not found".to_string(),
-    })?;
+) -> Result<(), Error>
+where
+    R: BlockStackRepository + ModuleDetailRepository + Clone + 'static,
+{
+    let _timer = metrics::query_timer("block_stack::update_my_block_stack");
+
+    let target_block_stack = app_db
+        .get_block_stack(network, id)
+        .await
+        .map_err(Error::DbError)?
+        .ok_or_else(|| Error::NotFound {
+            msg: "block stack not found".to_string(),
+        })?;
 
     if id != target_block_stack.id {
         return Err(Error::UnAuthorized {});
     }
 
-    if address != target_block_stack.address {
+    if !is_owner(&address, &target_block_stack) {
         return Err(Error::UnAuthorized {});
     }
 
-    let res = query(
-        "
-                UPDATE block_stack
-                    SET
-                        stack = $1,
-                        name = $2,
-                        last_edit_datetime = now()
-                WHERE id = $3
-            ",
-    )
-    .bind(&new_block_stack.stack)
-    .bind(&new_block_stack.name)
-    .bind(id)
-    .execute(app_db)
-    .await
-    .map_err(|e| Error::DbError(e.into()));
-
-    if target_block_stack.stack != new_block_stack.stack && res.is_ok() {
-        actix_rt::task::spawn_blocking(move || {
-            futures::executor::block_on(create_bytecode(db, stack, account)).unwrap_or_default();
-        });
+    parse_stack(&new_block_stack.stack)?;
+    let guard = if target_block_stack.stack != new_block_stack.stack {
+        Some(start_compile(compile_jobs)?)
+    } else {
+        None
+    };
+
+    app_db
+        .update_block_stack(id, &new_block_stack)
+        .await
+        .map_err(Error::DbError)?;
+
+    if let Some(guard) = guard {
+        spawn_create_bytecode(
+            app_db.clone(),
+            compile.clone(),
+            guard,
+            network.to_string(),
+            new_block_stack.stack,
+            id,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Stacks with a block calling into the module, block addresses may be in short form
+pub(crate) async fn get_block_stacks_using_module(
+    app_db: &impl BlockStackRepository,
+    network: &str,
+    address: &str,
+    module_name: &str,
+) -> Result<Vec<BlockStack>, Error> {
+    let _timer = metrics::query_timer("block_stack::get_block_stacks_using_module");
+
+    let candidates = app_db
+        .get_block_stacks_by_module_name(network, module_name)
+        .await
+        .map_err(Error::DbError)?;
+
+    let address = standardize_address(address);
+
+    Ok(candidates
+        .into_iter()
+        .filter(|block_stack| {
+            parse_blocks(&block_stack.stack).iter().any(|block| {
+                match split_function(&block.function) {
+                    Some((block_address, block_module_name, _)) => {
+                        standardize_address(block_address) == address
+                            && block_module_name == module_name
+                    }
+                    None => false,
+                }
+            })
+        })
+        .collect())
+}
+
+pub(crate) async fn update_block_stack_status(
+    app_db: &impl BlockStackRepository,
+    id: i32,
+    status: &str,
+    status_reason: Option<String>,
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("block_stack::update_block_stack_status");
+
+    app_db
+        .update_block_stack_status(id, status, status_reason.as_deref())
+        .await
+        .map_err(Error::DbError)
+}
+
+/// Lenient for the read-only checks, a malformed stack has no blocks
+pub(crate) fn parse_blocks(stack: &JsonValue) -> Vec<Block> {
+    parse_stack(stack).unwrap_or_default()
+}
+
+/// Stacks are written and compiled only when every block parses
+pub(crate) fn parse_stack(stack: &JsonValue) -> Result<Vec<Block>, Error> {
+    serde_json::from_value(stack.clone()).map_err(|e| Error::InvalidParams {
+        msg: format!("malformed block stack: {}", e),
+    })
+}
+
+/// `address::module_name::function_name`
+pub(crate) fn split_function(function: &str) -> Option<(&str, &str, &str)> {
+    let mut split = function.split("::");
+    match (split.next(), split.next(), split.next(), split.next()) {
+        (Some(address), Some(module_name), Some(function_name), None) => {
+            Some((address, module_name, function_name))
+        }
+        _ => None,
+    }
+}
+
+/// Mirrors the call `MoveScript` generates, `function<type_arguments>(&user, arguments)`
+pub(crate) fn check_block(block: &Block, function: Option<&ExposedFunction>) -> Result<(), String> {
+    let function = function.ok_or_else(|| format!("{} was removed", block.function))?;
+
+    if function.visibility != "public" {
+        return Err(format!("{} is no longer public", block.function));
+    }
+
+    let generic_type_params_len = function
+        .generic_type_params
+        .as_array()
+        .map(|params| params.len())
+        .unwrap_or_default();
+    if generic_type_params_len != block.type_arguments.len() {
+        return Err(format!(
+            "{} takes {} type arguments, the block has {}",
+            block.function,
+            generic_type_params_len,
+            block.type_arguments.len()
+        ));
+    }
+
+    let params = function.params.as_array().cloned().unwrap_or_default();
+    if params.first().and_then(|p| p.as_str()) != Some("&signer") {
+        return Err(format!("{} does not take &signer first", block.function));
+    }
+    if params.len() - 1 != block.arguments.len() {
+        return Err(format!(
+            "{} takes {} arguments, the block has {}",
+            block.function,
+            params.len() - 1,
+            block.arguments.len()
+        ));
     }
 
     Ok(())
 }
 
 pub(crate) async fn delete_my_block_stack(
-    app_db: &PostgresPool,
+    app_db: &impl BlockStackRepository,
+    network: &str,
     address: String,
     id: i32,
-) -> Result<PgQueryResult, Error> {
-    let target_block_stack: BlockStack = query_as(
-        "
-                SELECT *
-                FROM block_stack
-                WHERE id = $1
-            ",
-    )
-    .bind(id)
-    .fetch_one(app_db)
-    .await
-    .map_err(|_| Error::NotFound {
-        msg: "block stack not found".to_string(),
-    })?;
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("block_stack::delete_my_block_stack");
 
-    if address != target_block_stack.address {
+    let target_block_stack = app_db
+        .get_block_stack(network, id)
+        .await
+        .map_err(Error::DbError)?
+        .ok_or_else(|| Error::NotFound {
+            msg: "block stack not found".to_string(),
+        })?;
+
+    if !is_owner(&address, &target_block_stack) {
         return Err(Error::UnAuthorized {});
     }
 
@@ -332,39 +599,26 @@ pub(crate) async fn delete_my_block_stack(
         return Err(Error::UnAuthorized {});
     }
 
-    query(
-        "
-                DELETE
-                FROM block_stack
-                WHERE id = $1
-            ",
-    )
-    .bind(id)
-    .execute(app_db)
-    .await
-    .map_err(|e| Error::DbError(e.into()))
+    app_db.delete_block_stack(id).await.map_err(Error::DbError)
 }
 
 pub(crate) async fn execute_script(
-    app_db: &PostgresPool,
+    app_db: &impl BlockStackRepository,
+    network: &NetworkContext,
     address: String,
     id: i32,
 ) -> Result<String, Error> {
-    let target_block_stack: BlockStack = query_as(
-        "
-                SELECT *
-                FROM block_stack
-                WHERE id = $1
-            ",
-    )
-    .bind(id)
-    .fetch_one(app_db)
-    .await
-    .map_err(|_| Error::NotFound {
-        msg: "block stack not found".to_string(),
-    })?;
+    let _timer = metrics::query_timer("block_stack::execute_script");
+
+    let target_block_stack = app_db
+        .get_block_stack(&network.name, id)
+        .await
+        .map_err(Error::DbError)?
+        .ok_or_else(|| Error::NotFound {
+            msg: "block stack not found".to_string(),
+        })?;
 
-    if address != target_block_stack.address {
+    if !is_owner(&address, &target_block_stack) {
         return Err(Error::UnAuthorized {});
     }
 
@@ -372,32 +626,42 @@ pub(crate) async fn execute_script(
         return Err(Error::UnAuthorized {});
     }
 
-    let private_key = Ed25519PrivateKey::from_encoded_string(
-        "0xefa26dc276fb632899c821fb613bbf69af13f333114894cb46324c4db52ee36a",
-    )
-    .unwrap();
+    let bytecode = target_block_stack
+        .bytecode
+        .filter(|bytecode| !bytecode.is_empty())
+        .ok_or_else(|| Error::NotFound {
+            msg: "block stack has not been compiled yet".to_string(),
+        })?;
+    let decoded_byte_code = hex::decode(bytecode).map_err(|_| Error::InvalidParams {
+        msg: "block stack bytecode is not hex encoded".to_string(),
+    })?;
+
+    let executor = &network.executor;
+    if !executor.is_configured() {
+        return Err(Error::AnyError(anyhow::anyhow!(
+            "no executor account is configured for {}",
+            network.name
+        )));
+    }
+    let private_key = Ed25519PrivateKey::from_encoded_string(&executor.private_key)
+        .map_err(|e| Error::AnyError(e.into()))?;
     let public_key = private_key.public_key();
-    let account = AccountAddress::from_str(
-        "0x8010b2a97a2a458e6f58c796c647c3c08fef39057502cbc508bbf2534929b8a3",
-    )
-    .unwrap();
-    let account_info: Account = reqwest::get(format!(
-        "https://fullnode.devnet.aptoslabs.com/v1/accounts/{}",
-        account
-    ))
-    .await
-    .unwrap()
-    .json()
-    .await
-    .unwrap();
-
-    let decoded_byte_code =
-        hex::decode(target_block_stack.bytecode.unwrap_or_default()).expect("wrong bytecode");
+    let account = AccountAddress::from_hex_literal(&executor.address)
+        .map_err(|e| Error::AnyError(e.into()))?;
+
+    let client = aptos_sdk::rest_client::Client::new(
+        Url::from_str(&network.node_url).map_err(|e| Error::AnyError(e.into()))?,
+    );
+    let account_info = client
+        .get_account(account)
+        .await
+        .map_err(|e| Error::AnyError(e.into()))?
+        .into_inner();
 
     let tx = TransactionBuilder::new(
         TransactionPayload::Script(Script::new(decoded_byte_code, vec![], vec![])),
         32425224034,
-        ChainId::new(43),
+        ChainId::new(network.chain_id),
     )
     .max_gas_amount(10000)
     .gas_unit_price(100)
@@ -405,12 +669,461 @@ pub(crate) async fn execute_script(
     .sender(account)
     .build();
 
-    let signed_tx = tx.sign(&private_key, public_key).unwrap();
+    let signed_tx = tx
+        .sign(&private_key, public_key)
+        .map_err(|e| Error::AnyError(e.into()))?;
 
-    let client = aptos_sdk::rest_client::Client::new(
-        Url::from_str("https://fullnode.devnet.aptoslabs.com/v1").unwrap(),
-    );
-    let res = client.submit(&signed_tx).await.unwrap();
+    let res = client.submit(&signed_tx).await;
+    metrics::observe_transaction_submission(res.is_ok());
+    let res = res.map_err(|e| Error::AnyError(e.into()))?;
 
     Ok(res.into_inner().hash.to_string())
 }
+
+#[cfg(test)]
+mod block_check {
+    use crate::config::settings::CompileSettings;
+    use crate::config::{ExecutorSettings, NetworkContext};
+    use crate::service::block_stack::Block;
+    use crate::service::domain::auth_key::AuthKeySource;
+    use crate::service::domain::block_stack::{
+        check_block, compile_bytecode, execute_script, get_block_stack, split_function,
+    };
+    use crate::service::Error;
+    use database::db::new_postgres_pool;
+    use database::models::block_stack::stack::NewBlockStack;
+    use database::models::module_hub::core::function::ExposedFunction;
+    use database::repository::block_stack::BlockStackRepository;
+    use database::repository::memory::MemoryRepository;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn transfer(visibility: &str, params: serde_json::Value) -> ExposedFunction {
+        ExposedFunction {
+            name: "transfer".to_string(),
+            visibility: visibility.to_string(),
+            is_entry: true,
+            generic_type_params: json!([{ "constraints": [] }]),
+            params,
+            return_types: json!([]),
+        }
+    }
+
+    #[test]
+    fn test_check_block() {
+        let block = Block {
+            r#type: "function".to_string(),
+            function: "0x1::coin::transfer".to_string(),
+            type_arguments: vec!["0x1::aptos_coin::AptosCoin".to_string()],
+            arguments: vec!["0x2".to_string(), "100".to_string()],
+        };
+
+        let function = transfer("public", json!(["&signer", "address", "u64"]));
+        assert!(check_block(&block, Some(&function)).is_ok());
+
+        assert!(check_block(&block, None).is_err());
+        assert!(check_block(&block, Some(&transfer("friend", function.params.clone()))).is_err());
+        assert!(check_block(
+            &block,
+            Some(&transfer(
+                "public",
+                json!(["&signer", "address", "u64", "bool"])
+            ))
+        )
+        .is_err());
+        assert!(check_block(&block, Some(&transfer("public", json!(["address", "u64"])))).is_err());
+
+        assert_eq!(
+            split_function("0x1::coin::transfer"),
+            Some(("0x1", "coin", "transfer"))
+        );
+        assert_eq!(split_function("0x1::coin"), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_malformed_function_is_rejected() {
+        let stack = json!([{
+            "type": "function",
+            "function": "0x1::coin",
+            "type_arguments": [],
+            "arguments": [],
+        }]);
+
+        let res = compile_bytecode(
+            &MemoryRepository::default(),
+            &CompileSettings::default(),
+            "mainnet",
+            stack,
+            1,
+        )
+        .await;
+        assert!(matches!(res, Err(Error::InvalidParams { .. })));
+
+        // A block missing its arguments is not compiled as an empty stack
+        let res = compile_bytecode(
+            &MemoryRepository::default(),
+            &CompileSettings::default(),
+            "mainnet",
+            json!([{ "type": "function", "function": "0x1::coin::transfer" }]),
+            1,
+        )
+        .await;
+        assert!(matches!(res, Err(Error::InvalidParams { .. })));
+    }
+
+    #[actix_rt::test]
+    async fn test_uncompiled_stack_is_not_executed() {
+        let pool =
+            new_postgres_pool("postgres://localhost/test", 1, Duration::from_secs(1)).unwrap();
+        let network = NetworkContext {
+            name: "devnet".to_string(),
+            aptos_full_index_db: pool.clone(),
+            function_index_db: pool,
+            node_url: "http://127.0.0.1:1/v1".to_string(),
+            chain_id: 4,
+            auth_keys: AuthKeySource::Fixed(HashMap::new()),
+            executor: ExecutorSettings::default(),
+        };
+
+        let app_db = MemoryRepository::default();
+        let new_block_stack = NewBlockStack {
+            name: "transfer".to_string(),
+            stack: json!([]),
+        };
+        let id = app_db
+            .insert_block_stack("devnet", "0x1", &new_block_stack)
+            .await
+            .unwrap();
+
+        let res = execute_script(&app_db, &network, "0x1".to_string(), id).await;
+        assert!(matches!(res, Err(Error::NotFound { .. })));
+
+        app_db
+            .update_block_stack_bytecode(id, b"not hex")
+            .await
+            .unwrap();
+        let res = execute_script(&app_db, &network, "0x1".to_string(), id).await;
+        assert!(matches!(res, Err(Error::InvalidParams { .. })));
+
+        // Compiled, but no account to submit it from
+        app_db
+            .update_block_stack_bytecode(id, b"a11ceb0b")
+            .await
+            .unwrap();
+        let res = execute_script(&app_db, &network, "0x1".to_string(), id).await;
+        assert!(matches!(res, Err(Error::AnyError(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_stacks_of_other_accounts_are_not_found() {
+        let app_db = MemoryRepository::default();
+        let new_block_stack = NewBlockStack {
+            name: "transfer".to_string(),
+            stack: json!([]),
+        };
+        let id = app_db
+            .insert_block_stack("devnet", "0xa", &new_block_stack)
+            .await
+            .unwrap();
+
+        let found = get_block_stack(&app_db, "devnet", &format!("0x{:0>64}", "a"), id)
+            .await
+            .unwrap();
+        assert!(found.is_some());
+
+        let found = get_block_stack(&app_db, "devnet", "0xb", id).await.unwrap();
+        assert!(found.is_none());
+    }
+}
+
+#[cfg(test)]
+mod ownership {
+    use crate::compile::CompileJobs;
+    use crate::config::settings::CompileSettings;
+    use crate::service::domain::block_stack::{
+        delete_my_block_stack, get_block_stacks_using_module, get_my_block_stacks,
+        get_my_block_stacks_count, update_my_block_stack,
+    };
+    use crate::service::domain::bytecode::standardize_address;
+    use crate::service::domain::cursor::BlockStackCursor;
+    use crate::service::Error;
+    use database::models::block_stack::stack::NewBlockStack;
+    use database::repository::block_stack::BlockStackRepository;
+    use database::repository::memory::MemoryRepository;
+    use serde_json::json;
+
+    const DEVNET: &str = "devnet";
+
+    fn new_block_stack(name: &str, function: &str) -> NewBlockStack {
+        NewBlockStack {
+            name: name.to_string(),
+            stack: json!([{
+                "type": "function",
+                "function": function,
+                "type_arguments": [],
+                "arguments": [],
+            }]),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_only_owner_can_modify() {
+        let repository = MemoryRepository::default();
+        let stack = new_block_stack("transfer", "0x1::coin::transfer");
+        let id = repository
+            .insert_block_stack(DEVNET, &standardize_address("0xa"), &stack)
+            .await
+            .unwrap();
+
+        let res = delete_my_block_stack(&repository, DEVNET, "0xb".to_string(), id).await;
+        assert!(matches!(res, Err(Error::UnAuthorized {})));
+
+        let res = delete_my_block_stack(&repository, DEVNET, "0xa".to_string(), id + 1).await;
+        assert!(matches!(res, Err(Error::NotFound { .. })));
+
+        // An unchanged stack is not recompiled
+        let renamed = NewBlockStack {
+            name: "renamed".to_string(),
+            ..stack
+        };
+        let compile = CompileSettings::default();
+        update_my_block_stack(
+            &repository,
+            &compile,
+            &CompileJobs::default(),
+            DEVNET,
+            "0xa".to_string(),
+            id,
+            renamed,
+        )
+        .await
+        .unwrap();
+        let block_stack = repository
+            .get_block_stack(DEVNET, id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(block_stack.name, "renamed");
+
+        delete_my_block_stack(&repository, DEVNET, "0xa".to_string(), id)
+            .await
+            .unwrap();
+        assert_eq!(
+            get_my_block_stacks_count(&repository, DEVNET, "0xa")
+                .await
+                .unwrap()
+                .count,
+            0
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_short_address_owns_its_stacks() {
+        let repository = MemoryRepository::default();
+        let stack = new_block_stack("transfer", "0x1::coin::transfer");
+        let short_id = repository
+            .insert_block_stack(DEVNET, "0xA", &stack)
+            .await
+            .unwrap();
+        let id = repository
+            .insert_block_stack(DEVNET, &standardize_address("0xa"), &stack)
+            .await
+            .unwrap();
+
+        let renamed = NewBlockStack {
+            name: "renamed".to_string(),
+            ..stack
+        };
+        update_my_block_stack(
+            &repository,
+            &CompileSettings::default(),
+            &CompileJobs::default(),
+            DEVNET,
+            "0x0a".to_string(),
+            id,
+            renamed,
+        )
+        .await
+        .unwrap();
+
+        for id in [short_id, id] {
+            delete_my_block_stack(&repository, DEVNET, "0xa".to_string(), id)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_pages_by_cursor() {
+        let repository = MemoryRepository::default();
+        for name in ["a", "b", "c"] {
+            let stack = new_block_stack(name, "0x1::coin::transfer");
+            repository
+                .insert_block_stack(DEVNET, &standardize_address("0xa"), &stack)
+                .await
+                .unwrap();
+        }
+        let stack = new_block_stack("other", "0x1::coin::transfer");
+        repository
+            .insert_block_stack(DEVNET, &standardize_address("0xb"), &stack)
+            .await
+            .unwrap();
+
+        let first = get_my_block_stacks(&repository, DEVNET, "0xa", &None, 0, 2)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 2);
+
+        let cursor = Some(BlockStackCursor::from(first.last().unwrap()));
+        let second = get_my_block_stacks(&repository, DEVNET, "0xa", &cursor, 0, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            second.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(),
+            ["c"]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_using_module_matches_short_addresses() {
+        let repository = MemoryRepository::default();
+        for (name, function) in [
+            ("short", "0x1::coin::transfer"),
+            ("other address", "0x2::coin::transfer"),
+            ("other module", "0x1::aptos_account::transfer"),
+        ] {
+            let stack = new_block_stack(name, function);
+            repository
+                .insert_block_stack(DEVNET, &standardize_address("0xa"), &stack)
+                .await
+                .unwrap();
+        }
+
+        let address = format!("0x{:0>64}", 1);
+        let block_stacks = get_block_stacks_using_module(&repository, DEVNET, &address, "coin")
+            .await
+            .unwrap();
+        assert_eq!(
+            block_stacks
+                .iter()
+                .map(|b| b.name.as_str())
+                .collect::<Vec<_>>(),
+            ["short"]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_stacks_stay_on_their_network() {
+        let repository = MemoryRepository::default();
+        let stack = new_block_stack("transfer", "0x1::coin::transfer");
+        let id = repository
+            .insert_block_stack(DEVNET, &standardize_address("0xa"), &stack)
+            .await
+            .unwrap();
+        repository
+            .insert_block_stack("mainnet", &standardize_address("0xa"), &stack)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            get_my_block_stacks_count(&repository, "mainnet", "0xa")
+                .await
+                .unwrap()
+                .count,
+            1
+        );
+        assert!(repository
+            .get_block_stack("mainnet", id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let res = delete_my_block_stack(&repository, "mainnet", "0xa".to_string(), id).await;
+        assert!(matches!(res, Err(Error::NotFound { .. })));
+    }
+}
+
+#[cfg(test)]
+mod resume {
+    use crate::compile::CompileJobs;
+    use crate::config::settings::CompileSettings;
+    use crate::service::domain::block_stack::{create_my_block_stack, resume_compile_jobs};
+    use crate::service::Error;
+    use database::models::block_stack::compile_job::{
+        COMPILE_JOB_STATUS_FAILED, COMPILE_JOB_STATUS_INTERRUPTED,
+    };
+    use database::models::block_stack::stack::NewBlockStack;
+    use database::repository::block_stack::BlockStackRepository;
+    use database::repository::memory::MemoryRepository;
+    use serde_json::json;
+    use std::time::Duration;
+
+    const DEVNET: &str = "devnet";
+
+    #[actix_rt::test]
+    async fn test_unfinished_jobs_are_resumed_once_per_stack() {
+        let repository = MemoryRepository::default();
+        let stack = NewBlockStack {
+            name: "transfer".to_string(),
+            stack: json!([]),
+        };
+        let id = repository
+            .insert_block_stack(DEVNET, "0xa", &stack)
+            .await
+            .unwrap();
+        repository.insert_compile_job(DEVNET, id).await.unwrap();
+        repository.insert_compile_job(DEVNET, id).await.unwrap();
+
+        let compile_jobs = CompileJobs::default();
+        let resumed = resume_compile_jobs(
+            &repository,
+            &CompileSettings::default(),
+            &compile_jobs,
+            DEVNET,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resumed, 1);
+        // The empty stack fails to compile, which finishes its job
+        assert_eq!(compile_jobs.drain(Duration::from_secs(5)).await, 0);
+
+        let jobs = repository.get_compile_jobs(DEVNET, None, 10).await.unwrap();
+        let statuses = jobs.iter().map(|j| j.status.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                COMPILE_JOB_STATUS_FAILED,
+                COMPILE_JOB_STATUS_INTERRUPTED,
+                COMPILE_JOB_STATUS_INTERRUPTED,
+            ]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_closed_compile_jobs_refuse_new_compiles() {
+        let repository = MemoryRepository::default();
+        let compile_jobs = CompileJobs::default();
+        compile_jobs.close();
+
+        let stack = NewBlockStack {
+            name: "transfer".to_string(),
+            stack: json!([]),
+        };
+        let res = create_my_block_stack(
+            &repository,
+            &CompileSettings::default(),
+            &compile_jobs,
+            DEVNET,
+            "0xa".to_string(),
+            stack,
+        )
+        .await;
+        assert!(matches!(res, Err(Error::Unavailable { .. })));
+
+        let stacks = repository.get_block_stacks(DEVNET, None).await.unwrap();
+        assert!(stacks.is_empty());
+        let jobs = repository.get_compile_jobs(DEVNET, None, 10).await.unwrap();
+        assert!(jobs.is_empty());
+    }
+}
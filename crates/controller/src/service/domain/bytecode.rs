@@ -0,0 +1,326 @@
+use crate::service::Error;
+
+const MAGIC: [u8; 4] = [0xA1, 0x1C, 0xEB, 0x0B];
+
+const MODULE_HANDLES: u8 = 0x1;
+const FUNCTION_HANDLES: u8 = 0x3;
+const IDENTIFIERS: u8 = 0x7;
+const ADDRESS_IDENTIFIERS: u8 = 0x8;
+
+const ADDRESS_LENGTH: usize = 32;
+
+/// Kind, offset and length take a byte each at least
+const MIN_TABLE_HEADER_LENGTH: usize = 3;
+
+/// Function handles gained optional access specifiers in version 7
+const ACCESS_SPECIFIERS_VERSION: u32 = 7;
+/// And a list of one byte attributes in version 8
+const FUNCTION_ATTRIBUTES_VERSION: u32 = 8;
+/// Later layouts are refused rather than misread
+const MAX_FUNCTION_HANDLE_VERSION: u32 = 8;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ModuleHandle {
+    pub address: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FunctionHandle {
+    pub module: ModuleHandle,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BytecodeHandles {
+    pub version: u32,
+    pub module_handles: Vec<ModuleHandle>,
+    pub function_handles: Vec<FunctionHandle>,
+}
+
+/// `0x1` and `0x0000...0001` are the same account, the indexer stores the long form
+pub fn standardize_address(address: &str) -> String {
+    let hex = address.strip_prefix("0x").unwrap_or(address).to_lowercase();
+    format!("0x{:0>64}", hex)
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Cursor { bytes, position: 0 }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len());
+
+        match end {
+            Some(end) => {
+                let bytes = &self.bytes[self.position..end];
+                self.position = end;
+                Ok(bytes)
+            }
+            None => Err(malformed("unexpected end of bytecode")),
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_uleb128(&mut self) -> Result<u64, Error> {
+        let mut value: u64 = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(malformed("uleb128 overflow"))
+    }
+
+    fn read_index(&mut self) -> Result<usize, Error> {
+        Ok(self.read_uleb128()? as usize)
+    }
+}
+
+fn malformed(msg: &str) -> Error {
+    Error::InvalidParams {
+        msg: format!("malformed bytecode: {}", msg),
+    }
+}
+
+fn unsupported(msg: &str) -> Error {
+    Error::InvalidParams {
+        msg: format!("unsupported bytecode: {}", msg),
+    }
+}
+
+/// Decodes only the handle tables, which compiled modules and scripts lay out the same way.
+/// Function handles of versions above `MAX_FUNCTION_HANDLE_VERSION`, or declaring access
+/// specifiers, are an error
+pub fn parse_handles(bytecode: &[u8]) -> Result<BytecodeHandles, Error> {
+    let mut cursor = Cursor::new(bytecode);
+
+    if cursor.read_bytes(MAGIC.len())? != MAGIC {
+        return Err(malformed("bad magic"));
+    }
+
+    // Upper byte may carry a binary flavor
+    let version = cursor.read_u32()? & 0x00FF_FFFF;
+
+    // Bounded by the bytes left before allocating, the count comes from untrusted bytecode
+    let table_count = cursor.read_index()?;
+    if table_count > (bytecode.len() - cursor.position) / MIN_TABLE_HEADER_LENGTH {
+        return Err(malformed("table count exceeds the bytecode"));
+    }
+    let mut tables = Vec::with_capacity(table_count);
+    for _ in 0..table_count {
+        let kind = cursor.read_u8()?;
+        let offset = cursor.read_index()?;
+        let len = cursor.read_index()?;
+        tables.push((kind, offset, len));
+    }
+
+    let content = &bytecode[cursor.position..];
+    let table = |kind: u8| -> Result<Option<Cursor>, Error> {
+        match tables.iter().find(|(k, _, _)| *k == kind) {
+            Some((_, offset, len)) => {
+                let end = offset
+                    .checked_add(*len)
+                    .filter(|end| *end <= content.len())
+                    .ok_or_else(|| malformed("table out of bounds"))?;
+                Ok(Some(Cursor::new(&content[*offset..end])))
+            }
+            None => Ok(None),
+        }
+    };
+
+    let mut identifiers = Vec::new();
+    if let Some(mut cursor) = table(IDENTIFIERS)? {
+        while cursor.position < cursor.bytes.len() {
+            let len = cursor.read_index()?;
+            let identifier = std::str::from_utf8(cursor.read_bytes(len)?)
+                .map_err(|_| malformed("identifier is not utf8"))?;
+            identifiers.push(identifier.to_string());
+        }
+    }
+
+    let mut addresses = Vec::new();
+    if let Some(mut cursor) = table(ADDRESS_IDENTIFIERS)? {
+        while cursor.position < cursor.bytes.len() {
+            addresses.push(format!(
+                "0x{}",
+                hex::encode(cursor.read_bytes(ADDRESS_LENGTH)?)
+            ));
+        }
+    }
+
+    let identifier = |index: usize| {
+        identifiers
+            .get(index)
+            .cloned()
+            .ok_or_else(|| malformed("identifier index"))
+    };
+
+    let mut module_handles = Vec::new();
+    if let Some(mut cursor) = table(MODULE_HANDLES)? {
+        while cursor.position < cursor.bytes.len() {
+            let address = addresses
+                .get(cursor.read_index()?)
+                .cloned()
+                .ok_or_else(|| malformed("address index"))?;
+            let name = identifier(cursor.read_index()?)?;
+            module_handles.push(ModuleHandle { address, name });
+        }
+    }
+
+    let mut function_handles = Vec::new();
+    if let Some(mut cursor) = table(FUNCTION_HANDLES)? {
+        if version > MAX_FUNCTION_HANDLE_VERSION {
+            return Err(unsupported(&format!(
+                "function handles of version {}",
+                version
+            )));
+        }
+
+        while cursor.position < cursor.bytes.len() {
+            let module = module_handles
+                .get(cursor.read_index()?)
+                .cloned()
+                .ok_or_else(|| malformed("module handle index"))?;
+            let name = identifier(cursor.read_index()?)?;
+            // parameters and return signature indices
+            cursor.read_index()?;
+            cursor.read_index()?;
+            // one ability set per type parameter
+            let type_parameter_count = cursor.read_index()?;
+            cursor.read_bytes(type_parameter_count)?;
+
+            // an option tag, specifiers are only declared by hand so they are not decoded
+            if version >= ACCESS_SPECIFIERS_VERSION && cursor.read_u8()? != 0 {
+                return Err(unsupported("access specifiers"));
+            }
+            if version >= FUNCTION_ATTRIBUTES_VERSION {
+                let attribute_count = cursor.read_index()?;
+                cursor.read_bytes(attribute_count)?;
+            }
+
+            function_handles.push(FunctionHandle { module, name });
+        }
+    }
+
+    Ok(BytecodeHandles {
+        version,
+        module_handles,
+        function_handles,
+    })
+}
+
+#[cfg(test)]
+mod handles {
+    use crate::service::domain::bytecode::{
+        parse_handles, standardize_address, FunctionHandle, ModuleHandle,
+    };
+
+    fn table(kind: u8, offset: usize, content: &[u8]) -> Vec<u8> {
+        vec![kind, offset as u8, content.len() as u8]
+    }
+
+    /// A module `0x1::coin` calling `0x1::account::create`, `handle_suffix` follows the
+    /// abilities of the function handle
+    fn bytecode(version: u8, handle_suffix: &[u8]) -> Vec<u8> {
+        let module_handles = vec![0, 0, 0, 1];
+        let mut function_handles = vec![1, 2, 0, 0, 1, 0];
+        function_handles.extend_from_slice(handle_suffix);
+        let mut identifiers = vec![4];
+        identifiers.extend_from_slice(b"coin");
+        identifiers.push(7);
+        identifiers.extend_from_slice(b"account");
+        identifiers.push(6);
+        identifiers.extend_from_slice(b"create");
+        let mut address = vec![0; 32];
+        address[31] = 1;
+
+        let mut bytes = vec![0xA1, 0x1C, 0xEB, 0x0B, version, 0, 0, 0, 4];
+        let mut offset = 0;
+        for (kind, content) in [
+            (0x1, &module_handles),
+            (0x3, &function_handles),
+            (0x7, &identifiers),
+            (0x8, &address),
+        ] {
+            bytes.extend(table(kind, offset, content));
+            offset += content.len();
+        }
+        for content in [&module_handles, &function_handles, &identifiers, &address] {
+            bytes.extend_from_slice(content);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn test_parse_handles() {
+        let handles = parse_handles(&bytecode(6, &[])).unwrap();
+        let framework = standardize_address("0x1");
+
+        assert_eq!(
+            handles.module_handles,
+            vec![
+                ModuleHandle {
+                    address: framework.clone(),
+                    name: "coin".to_string()
+                },
+                ModuleHandle {
+                    address: framework.clone(),
+                    name: "account".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            handles.function_handles,
+            vec![FunctionHandle {
+                module: ModuleHandle {
+                    address: framework,
+                    name: "account".to_string()
+                },
+                name: "create".to_string()
+            }]
+        );
+
+        // No access specifiers, then a persistent attribute
+        assert_eq!(
+            parse_handles(&bytecode(7, &[0])).unwrap().function_handles,
+            handles.function_handles
+        );
+        assert_eq!(
+            parse_handles(&bytecode(8, &[0, 1, 1]))
+                .unwrap()
+                .function_handles,
+            handles.function_handles
+        );
+        assert!(parse_handles(&bytecode(7, &[1, 0])).is_err());
+        assert!(parse_handles(&bytecode(9, &[0, 0])).is_err());
+        assert!(parse_handles(&[0xA1, 0x1C]).is_err());
+        assert!(
+            parse_handles(&[0xA1, 0x1C, 0xEB, 0x0B, 6, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0x0f])
+                .is_err()
+        );
+    }
+}
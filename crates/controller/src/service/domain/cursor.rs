@@ -0,0 +1,121 @@
+use crate::service::Error;
+
+use database::models::block_stack::stack::BlockStack;
+use database::models::module_hub::core::function::ModuleFunction;
+use database::models::module_hub::core::module::MoveModule;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Sort keys of `module_function` listings
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionCursor {
+    pub module_address: String,
+    pub module_name: String,
+    pub name: String,
+}
+
+impl From<&ModuleFunction> for FunctionCursor {
+    fn from(function: &ModuleFunction) -> Self {
+        FunctionCursor {
+            module_address: function.module_address.clone(),
+            module_name: function.module_name.clone(),
+            name: function.name.clone(),
+        }
+    }
+}
+
+/// Sort keys of `move_modules` listings
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleCursor {
+    pub name: String,
+    pub address: String,
+}
+
+impl From<&MoveModule> for ModuleCursor {
+    fn from(module: &MoveModule) -> Self {
+        ModuleCursor {
+            name: module.name.clone(),
+            address: module.address.clone(),
+        }
+    }
+}
+
+/// Sort key of `block_stack` listings
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockStackCursor {
+    pub id: i32,
+}
+
+impl From<&BlockStack> for BlockStackCursor {
+    fn from(block_stack: &BlockStack) -> Self {
+        BlockStackCursor { id: block_stack.id }
+    }
+}
+
+/// Cursors are handed out as hex encoded json, clients must treat them as opaque
+pub fn encode<T>(cursor: &T) -> String
+where
+    T: Serialize,
+{
+    hex::encode(serde_json::to_vec(cursor).unwrap_or_default())
+}
+
+pub fn decode<T>(cursor: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    hex::decode(cursor)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| Error::InvalidParams {
+            msg: "cannot parse cursor".to_string(),
+        })
+}
+
+pub fn decode_optional<T>(cursor: &Option<String>) -> Result<Option<T>, Error>
+where
+    T: DeserializeOwned,
+{
+    cursor.as_deref().map(decode).transpose()
+}
+
+/// A full page means there may be more rows, so the last row becomes the next cursor
+pub fn next_cursor<T, C, F>(rows: &[T], limit: i64, to_cursor: F) -> Option<String>
+where
+    C: Serialize,
+    F: Fn(&T) -> C,
+{
+    if limit <= 0 || (rows.len() as i64) < limit {
+        return None;
+    }
+
+    rows.last().map(|row| encode(&to_cursor(row)))
+}
+
+#[cfg(test)]
+mod pagination {
+    use crate::service::domain::cursor::{decode, encode, next_cursor, FunctionCursor};
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = FunctionCursor {
+            module_address: "0x1".to_string(),
+            module_name: "coin".to_string(),
+            name: "transfer".to_string(),
+        };
+
+        let decoded: FunctionCursor = decode(&encode(&cursor)).unwrap();
+        assert_eq!(decoded, cursor);
+
+        assert!(decode::<FunctionCursor>("not a cursor").is_err());
+    }
+
+    #[test]
+    fn test_next_cursor_only_on_full_page() {
+        let rows = vec![1, 2, 3];
+
+        assert_eq!(next_cursor(&rows, 4, |row| *row), None);
+        assert_eq!(next_cursor(&rows, 3, |row| *row), Some(encode(&3)));
+    }
+}
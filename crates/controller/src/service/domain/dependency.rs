@@ -0,0 +1,175 @@
+use crate::metrics;
+use crate::service::{
+    domain::{account, bytecode::standardize_address},
+    Error,
+};
+
+use database::models::module_hub::core::dependency::{
+    ModuleNode, DEPENDENCY_KIND_FRIEND, DEPENDENCY_KIND_USES,
+};
+use database::repository::account::AccountDetailRepository;
+use database::repository::dependency::ModuleDependencyRepository;
+
+pub const MAX_DEPTH: i32 = 5;
+
+pub(crate) fn validate_depth(depth: Option<i32>) -> Result<i32, Error> {
+    match depth.unwrap_or(1) {
+        depth if (1..=MAX_DEPTH).contains(&depth) => Ok(depth),
+        _ => Err(Error::InvalidParams {
+            msg: format!("depth must be between 1 and {}", MAX_DEPTH),
+        }),
+    }
+}
+
+/// Graph rows hold standardized addresses, aliases are resolved first
+pub(crate) async fn resolve_address(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    address_or_alias: &str,
+) -> Result<String, Error> {
+    let _timer = metrics::query_timer("dependency::resolve_address");
+
+    Ok(
+        match account::get_address_by_alias(app_db, network, address_or_alias).await? {
+            Some(address) => standardize_address(&address),
+            None => standardize_address(address_or_alias),
+        },
+    )
+}
+
+/// Modules used by the given module, transitively up to `depth`
+pub(crate) async fn get_dependencies(
+    app_db: &impl ModuleDependencyRepository,
+    network: &str,
+    address: &str,
+    module_name: &str,
+    depth: i32,
+) -> Result<Vec<ModuleNode>, Error> {
+    let _timer = metrics::query_timer("dependency::get_dependencies");
+
+    app_db
+        .get_dependencies(network, address, module_name, DEPENDENCY_KIND_USES, depth)
+        .await
+        .map_err(Error::DbError)
+}
+
+/// Modules using the given module, transitively up to `depth`
+pub(crate) async fn get_dependents(
+    app_db: &impl ModuleDependencyRepository,
+    network: &str,
+    address: &str,
+    module_name: &str,
+    depth: i32,
+) -> Result<Vec<ModuleNode>, Error> {
+    let _timer = metrics::query_timer("dependency::get_dependents");
+
+    app_db
+        .get_dependents(network, address, module_name, DEPENDENCY_KIND_USES, depth)
+        .await
+        .map_err(Error::DbError)
+}
+
+/// Friendship is not transitive, so only declared friends are returned
+pub(crate) async fn get_friends(
+    app_db: &impl ModuleDependencyRepository,
+    network: &str,
+    address: &str,
+    module_name: &str,
+) -> Result<Vec<ModuleNode>, Error> {
+    let _timer = metrics::query_timer("dependency::get_friends");
+
+    app_db
+        .get_dependencies(network, address, module_name, DEPENDENCY_KIND_FRIEND, 1)
+        .await
+        .map_err(Error::DbError)
+}
+
+/// Modules whose bytecode calls the given function
+pub(crate) async fn get_function_dependents(
+    app_db: &impl ModuleDependencyRepository,
+    network: &str,
+    address: &str,
+    module_name: &str,
+    function_name: &str,
+) -> Result<Vec<ModuleNode>, Error> {
+    let _timer = metrics::query_timer("dependency::get_function_dependents");
+
+    app_db
+        .get_function_dependents(network, address, module_name, function_name)
+        .await
+        .map_err(Error::DbError)
+}
+
+#[cfg(test)]
+mod graph {
+    use crate::service::domain::dependency::{get_dependencies, get_dependents, get_friends};
+    use database::models::module_hub::core::dependency::{
+        ModuleDependency, ModuleNode, DEPENDENCY_KIND_FRIEND, DEPENDENCY_KIND_USES,
+    };
+    use database::repository::memory::MemoryRepository;
+
+    const NETWORK: &str = "devnet";
+
+    fn edge(id: i32, from: &str, to: &str, kind: &str) -> (String, ModuleDependency) {
+        (
+            NETWORK.to_string(),
+            ModuleDependency {
+                id,
+                address: "0x1".to_string(),
+                module_name: from.to_string(),
+                dependency_address: "0x1".to_string(),
+                dependency_module_name: to.to_string(),
+                kind: kind.to_string(),
+                transaction_version: 1,
+            },
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_walk_graph() {
+        let repository = MemoryRepository::default();
+        repository.module_dependencies.lock().unwrap().extend([
+            edge(1, "pool", "coin", DEPENDENCY_KIND_USES),
+            edge(2, "pool", "math", DEPENDENCY_KIND_USES),
+            edge(3, "math", "coin", DEPENDENCY_KIND_USES),
+            edge(4, "coin", "event", DEPENDENCY_KIND_USES),
+            edge(5, "coin", "pool", DEPENDENCY_KIND_FRIEND),
+        ]);
+        let names = |nodes: Vec<ModuleNode>| {
+            nodes
+                .into_iter()
+                .map(|n| (n.module_name, n.depth))
+                .collect::<Vec<_>>()
+        };
+
+        let dependencies = get_dependencies(&repository, NETWORK, "0x1", "pool", 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            names(dependencies),
+            [
+                ("coin".to_string(), 1),
+                ("math".to_string(), 1),
+                ("event".to_string(), 2)
+            ]
+        );
+
+        let dependents = get_dependents(&repository, NETWORK, "0x1", "coin", 5)
+            .await
+            .unwrap();
+        assert_eq!(
+            names(dependents),
+            [("math".to_string(), 1), ("pool".to_string(), 1)]
+        );
+
+        let friends = get_friends(&repository, NETWORK, "0x1", "coin")
+            .await
+            .unwrap();
+        assert_eq!(names(friends), [("pool".to_string(), 1)]);
+
+        let other_network = get_dependencies(&repository, "testnet", "0x1", "pool", 2)
+            .await
+            .unwrap();
+        assert!(other_network.is_empty());
+    }
+}
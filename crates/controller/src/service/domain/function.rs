@@ -1,486 +1,402 @@
+use crate::metrics;
 use crate::service::{
-    domain::{account, Count},
+    domain::{account, cursor::FunctionCursor, Count},
     Error,
 };
 
-use database::db::PostgresPool;
 use database::models::module_hub::composite::function::{
     ModuleFunctionWithAccountDetail, ModuleFunctionWithDetail,
     ModuleFunctionWithOwnAndAccountDetail,
 };
 use database::models::module_hub::core::function::ModuleFunction;
+use database::models::module_hub::core::usage::FunctionUsage;
 use database::models::module_hub::detail::function::{
     ModuleFunctionDetail, NewModuleFunctionDetail,
 };
+use database::repository::account::AccountDetailRepository;
+use database::repository::function::{
+    FunctionDetailRepository, FunctionFilter, FunctionIndexRepository,
+};
 
-use futures::future::join_all;
+use futures::future::try_join_all;
 use serde::{Deserialize, Serialize};
-use sqlx::postgres::PgQueryResult;
-use sqlx::{query, query_as};
-
-pub(crate) async fn get_functions_count(function_indexer_db: &PostgresPool) -> Count {
-    query_as(
-        "
-                SELECT COUNT(id) as count
-                FROM module_function
-            ",
-    )
-    .fetch_one(function_indexer_db)
-    .await
-    .ok()
-    .unwrap_or_default()
+
+fn function_key(cursor: &Option<FunctionCursor>) -> Option<(&str, &str, &str)> {
+    cursor.as_ref().map(|c| {
+        (
+            c.module_address.as_str(),
+            c.module_name.as_str(),
+            c.name.as_str(),
+        )
+    })
 }
 
-pub(crate) async fn get_entry_functions_count(function_indexer_db: &PostgresPool) -> Count {
-    query_as(
-        "
-                SELECT COUNT(id) as count
-                FROM module_function
-                WHERE is_entry = TRUE
-            ",
-    )
-    .fetch_one(function_indexer_db)
-    .await
-    .ok()
-    .unwrap_or_default()
+/// Matches `keyword` as an address, alias, module name or function name
+async fn keyword_filter(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    keyword: &str,
+    entry_only: bool,
+) -> Result<FunctionFilter, Error> {
+    Ok(FunctionFilter {
+        keyword: Some(keyword.to_string()),
+        alias_address: account::get_address_by_alias(app_db, network, keyword).await?,
+        entry_only,
+    })
+}
+
+async fn with_account_detail(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    functions: Vec<ModuleFunction>,
+) -> Result<Vec<ModuleFunctionWithAccountDetail>, Error> {
+    let tasks = functions
+        .into_iter()
+        .map(|function| async {
+            let account_detail =
+                account::get_account_detail(app_db, network, &function.module_address).await?;
+
+            Ok::<_, Error>(ModuleFunctionWithAccountDetail::compose(
+                function,
+                account_detail,
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    try_join_all(tasks).await
+}
+
+async fn with_own_and_account_detail(
+    app_db: &(impl AccountDetailRepository + FunctionDetailRepository),
+    network: &str,
+    functions: Vec<ModuleFunction>,
+) -> Result<Vec<ModuleFunctionWithOwnAndAccountDetail>, Error> {
+    let tasks = functions
+        .into_iter()
+        .map(|function| async {
+            let account_detail =
+                account::get_account_detail(app_db, network, &function.module_address).await?;
+
+            let function_detail = get_function_detail(
+                app_db,
+                network,
+                &function.module_address,
+                &function.module_name,
+                &function.name,
+            )
+            .await?;
+
+            Ok::<_, Error>(ModuleFunctionWithOwnAndAccountDetail::compose(
+                function,
+                account_detail,
+                function_detail,
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    try_join_all(tasks).await
+}
+
+pub(crate) async fn get_functions_count(
+    function_indexer_db: &impl FunctionIndexRepository,
+) -> Result<Count, Error> {
+    let _timer = metrics::query_timer("function::get_functions_count");
+
+    function_indexer_db
+        .count_functions(false)
+        .await
+        .map(|count| Count { count })
+        .map_err(Error::DbError)
+}
+
+pub(crate) async fn get_entry_functions_count(
+    function_indexer_db: &impl FunctionIndexRepository,
+) -> Result<Count, Error> {
+    let _timer = metrics::query_timer("function::get_entry_functions_count");
+
+    function_indexer_db
+        .count_functions(true)
+        .await
+        .map(|count| Count { count })
+        .map_err(Error::DbError)
 }
 
 pub(crate) async fn get_function_count_filtered_by_keyword(
-    function_indexer_db: &PostgresPool,
-    app_db: &PostgresPool,
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
     keyword: &str,
-) -> Count {
-    let address_by_alias = account::get_address_by_alias(app_db, keyword).await;
-    query_as(
-        "
-                SELECT COUNT (1)
-                FROM (
-                    SELECT DISTINCT ON (module_address, module_name, name) *
-                        FROM module_function
-                        WHERE
-                            module_address = $1
-                            OR
-                            module_address = $2
-                            OR
-                            module_name = $1
-                            OR
-                            name = $1
-                        ORDER BY module_address, module_name, name, id DESC
-                ) count
-            ",
-    )
-    .bind(keyword)
-    .bind(address_by_alias.unwrap_or_default())
-    .fetch_one(function_indexer_db)
-    .await
-    .ok()
-    .unwrap_or_default()
+) -> Result<Count, Error> {
+    let _timer = metrics::query_timer("function::get_function_count_filtered_by_keyword");
+
+    let filter = keyword_filter(app_db, network, keyword, false).await?;
+    function_indexer_db
+        .count_latest_functions(&filter)
+        .await
+        .map(|count| Count { count })
+        .map_err(Error::DbError)
 }
 
 pub(crate) async fn get_entry_function_count_filtered_by_keyword(
-    function_indexer_db: &PostgresPool,
-    app_db: &PostgresPool,
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
     keyword: &str,
-) -> Count {
-    let address_by_alias = account::get_address_by_alias(app_db, keyword).await;
-    query_as(
-        "
-                SELECT COUNT (1)
-                FROM (
-                    SELECT DISTINCT ON (module_address, module_name, name) *
-                        FROM module_function
-                        WHERE
-                            (
-                                module_address = $1
-                                OR
-                                module_address = $2
-                                OR
-                                module_name = $1
-                                OR
-                                name = $1
-                            )
-                            AND
-                            is_entry = TRUE
-                        ORDER BY module_address, module_name, name, id DESC
-                ) count
-            ",
-    )
-    .bind(keyword)
-    .bind(address_by_alias.unwrap_or_default())
-    .fetch_one(function_indexer_db)
-    .await
-    .ok()
-    .unwrap_or_default()
+) -> Result<Count, Error> {
+    let _timer = metrics::query_timer("function::get_entry_function_count_filtered_by_keyword");
+
+    let filter = keyword_filter(app_db, network, keyword, true).await?;
+    function_indexer_db
+        .count_latest_functions(&filter)
+        .await
+        .map(|count| Count { count })
+        .map_err(Error::DbError)
 }
 
 pub(crate) async fn get_function_detail(
-    app_db: &PostgresPool,
+    app_db: &impl FunctionDetailRepository,
+    network: &str,
     address: &str,
     module_name: &str,
     function_name: &str,
-) -> Option<ModuleFunctionDetail> {
-    query_as(
-        "
-                SELECT DISTINCT ON (address, module_name, function_name) *
-                FROM module_function_detail
-                WHERE
-                    address = $1
-                    AND
-                    module_name = $2
-                    AND
-                    function_name = $3
-                ORDER BY address, module_name, function_name, id DESC
-            ",
-    )
-    .bind(address)
-    .bind(module_name)
-    .bind(function_name)
-    .fetch_one(app_db)
-    .await
-    .ok()
+) -> Result<Option<ModuleFunctionDetail>, Error> {
+    let _timer = metrics::query_timer("function::get_function_detail");
+
+    app_db
+        .get_function_detail(network, address, module_name, function_name)
+        .await
+        .map_err(Error::DbError)
 }
 
 pub async fn get_function_with_detail(
-    function_db: &PostgresPool,
-    app_db: &PostgresPool,
+    function_db: &impl FunctionIndexRepository,
+    app_db: &impl FunctionDetailRepository,
+    network: &str,
     address: &str,
     module_name: &str,
     function_name: &str,
-) -> Option<ModuleFunctionWithDetail> {
-    let maybe_function: Option<ModuleFunction> = query_as(
-        "
-                SELECT DISTINCT ON (module_address, module_name, name) *
-                FROM module_function
-                WHERE
-                    module_address = $1
-                    AND
-                    module_name = $2
-                    AND
-                    name = $3
-                ORDER BY module_address, module_name, name, id DESC
-            ",
-    )
-    .bind(address)
-    .bind(module_name)
-    .bind(function_name)
-    .fetch_one(function_db)
-    .await
-    .ok();
+) -> Result<Option<ModuleFunctionWithDetail>, Error> {
+    let _timer = metrics::query_timer("function::get_function_with_detail");
+
+    let maybe_function = function_db
+        .get_latest_function(address, module_name, function_name)
+        .await
+        .map_err(Error::DbError)?;
 
     match maybe_function {
         Some(function) => {
-            let function_detail: Option<ModuleFunctionDetail> = query_as(
-                "
-                        SELECT DISTINCT ON (address, module_name, function_name) *
-                        FROM module_function_detail
-                        WHERE
-                            address = $1
-                            AND
-                            module_name = $2
-                            AND
-                            function_name = $3
-                        ORDER BY address, module_name, function_name, id DESC
-                    ",
-            )
-            .bind(&function.module_address)
-            .bind(&function.module_name)
-            .bind(&function.name)
-            .fetch_one(app_db)
-            .await
-            .ok();
-
-            Some(ModuleFunctionWithDetail::compose(function, function_detail))
+            let function_detail = app_db
+                .get_function_detail(
+                    network,
+                    &function.module_address,
+                    &function.module_name,
+                    &function.name,
+                )
+                .await
+                .map_err(Error::DbError)?;
+
+            Ok(Some(ModuleFunctionWithDetail::compose(
+                function,
+                function_detail,
+            )))
         }
-        None => None,
+        None => Ok(None),
     }
 }
 
 pub(crate) async fn get_functions_by_keyword_with_account_detail(
-    function_indexer_db: &PostgresPool,
-    app_db: &PostgresPool,
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
     keyword: &str,
+    cursor: &Option<FunctionCursor>,
     offset: i64,
     limit: i64,
-) -> Option<Vec<ModuleFunctionWithAccountDetail>> {
-    let address_by_alias = account::get_address_by_alias(app_db, keyword).await;
-
-    let functions: Vec<ModuleFunction> = query_as(
-        "
-            SELECT DISTINCT ON (module_address, module_name, name) *
-            FROM module_function
-            WHERE
-                module_address = $1
-                OR
-                module_address = $2
-                OR
-                module_name = $1
-                OR
-                name = $1
-            ORDER BY module_address, module_name, name, id DESC
-            OFFSET $3
-            LIMIT $4
-           ",
-    )
-    .bind(keyword)
-    .bind(address_by_alias.unwrap_or_default())
-    .bind(offset)
-    .bind(limit)
-    .fetch_all(function_indexer_db)
-    .await
-    .ok()
-    .unwrap_or_default();
+) -> Result<Vec<ModuleFunctionWithAccountDetail>, Error> {
+    let _timer = metrics::query_timer("function::get_functions_by_keyword_with_account_detail");
 
-    let tasks = functions
-        .into_iter()
-        .map(|function| async {
-            let account_detail =
-                account::get_account_detail(app_db, &function.module_address).await;
-
-            ModuleFunctionWithAccountDetail::compose(function, account_detail)
-        })
-        .collect::<Vec<_>>();
-
-    let module_functions_with_account_detail: Vec<ModuleFunctionWithAccountDetail> =
-        join_all(tasks).await;
+    let filter = keyword_filter(app_db, network, keyword, false).await?;
+    let functions = function_indexer_db
+        .get_latest_functions(&filter, function_key(cursor), offset, limit)
+        .await
+        .map_err(Error::DbError)?;
 
-    Some(module_functions_with_account_detail)
+    with_account_detail(app_db, network, functions).await
 }
 
 pub async fn get_paginated_functions_with_account_detail(
-    function_indexer_db: &PostgresPool,
-    app_db: &PostgresPool,
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    cursor: &Option<FunctionCursor>,
     offset: i64,
     limit: i64,
-) -> Option<Vec<ModuleFunctionWithAccountDetail>> {
-    let functions: Vec<ModuleFunction> = query_as(
-        "
-            SELECT DISTINCT ON (module_address, module_name, name) *
-            FROM module_function
-            ORDER BY module_address, module_name, name, id DESC
-            OFFSET $1
-            LIMIT $2
-           ",
-    )
-    .bind(offset)
-    .bind(limit)
-    .fetch_all(function_indexer_db)
-    .await
-    .ok()
-    .unwrap_or_default();
-
-    let tasks = functions
-        .into_iter()
-        .map(|function| async {
-            let account_detail =
-                account::get_account_detail(app_db, &function.module_address).await;
-
-            ModuleFunctionWithAccountDetail::compose(function, account_detail)
-        })
-        .collect::<Vec<_>>();
-
-    let module_functions_with_account_detail: Vec<ModuleFunctionWithAccountDetail> =
-        join_all(tasks).await;
+) -> Result<Vec<ModuleFunctionWithAccountDetail>, Error> {
+    let _timer = metrics::query_timer("function::get_paginated_functions_with_account_detail");
+
+    let functions = function_indexer_db
+        .get_latest_functions(
+            &FunctionFilter::default(),
+            function_key(cursor),
+            offset,
+            limit,
+        )
+        .await
+        .map_err(Error::DbError)?;
 
-    Some(module_functions_with_account_detail)
+    with_account_detail(app_db, network, functions).await
 }
 
 pub(crate) async fn get_functions_by_keyword_with_function_detail(
-    function_indexer_db: &PostgresPool,
-    app_db: &PostgresPool,
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &(impl AccountDetailRepository + FunctionDetailRepository),
+    network: &str,
     keyword: &str,
+    cursor: &Option<FunctionCursor>,
     offset: i64,
     limit: i64,
 ) -> Result<Vec<ModuleFunctionWithOwnAndAccountDetail>, Error> {
-    let address_by_alias = account::get_address_by_alias(app_db, keyword).await;
-
-    let entry_functions: Vec<ModuleFunction> = query_as(
-        "
-            SELECT DISTINCT ON (module_address, module_name, name) *
-            FROM module_function
-            WHERE
-                (
-                    module_address = $1
-                    OR
-                    module_address = $2
-                    OR
-                    module_name = $1
-                    OR
-                    name = $1
-                )
-                AND
-                is_entry = TRUE
-            ORDER BY module_address, module_name, name, id DESC
-            OFFSET $3
-            LIMIT $4
-           ",
-    )
-    .bind(keyword)
-    .bind(address_by_alias.unwrap_or_default())
-    .bind(offset)
-    .bind(limit)
-    .fetch_all(function_indexer_db)
-    .await
-    .ok()
-    .unwrap_or_default();
-
-    let tasks = entry_functions
-        .into_iter()
-        .map(|entry_function| async {
-            let account_detail =
-                account::get_account_detail(app_db, &entry_function.module_address).await;
-
-            let function_detail = get_function_detail(
-                app_db,
-                &entry_function.module_address,
-                &entry_function.module_name,
-                &entry_function.name,
-            )
-            .await;
-
-            ModuleFunctionWithOwnAndAccountDetail::compose(
-                entry_function,
-                account_detail,
-                function_detail,
-            )
-        })
-        .collect::<Vec<_>>();
+    let _timer = metrics::query_timer("function::get_functions_by_keyword_with_function_detail");
 
-    let module_functions_with_own_and_account_detail: Vec<ModuleFunctionWithOwnAndAccountDetail> =
-        join_all(tasks).await;
+    let filter = keyword_filter(app_db, network, keyword, true).await?;
+    let entry_functions = function_indexer_db
+        .get_latest_functions(&filter, function_key(cursor), offset, limit)
+        .await
+        .map_err(Error::DbError)?;
 
-    Ok(module_functions_with_own_and_account_detail)
+    with_own_and_account_detail(app_db, network, entry_functions).await
 }
 
 pub(crate) async fn get_paginated_functions_by_keyword_with_function_detail(
-    function_indexer_db: &PostgresPool,
-    app_db: &PostgresPool,
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &(impl AccountDetailRepository + FunctionDetailRepository),
+    network: &str,
+    cursor: &Option<FunctionCursor>,
     offset: i64,
     limit: i64,
 ) -> Result<Vec<ModuleFunctionWithOwnAndAccountDetail>, Error> {
-    let entry_functions: Vec<ModuleFunction> = query_as(
-        "
-            SELECT DISTINCT ON (module_address, module_name, name) *
-            FROM module_function
-            WHERE is_entry = TRUE
-            ORDER BY module_address, module_name, name, id DESC
-            OFFSET $1
-            LIMIT $2
-           ",
-    )
-    .bind(offset)
-    .bind(limit)
-    .fetch_all(function_indexer_db)
-    .await
-    .ok()
-    .unwrap_or_default();
+    let _timer =
+        metrics::query_timer("function::get_paginated_functions_by_keyword_with_function_detail");
 
-    let tasks = entry_functions
-        .into_iter()
-        .map(|entry_function| async {
-            let account_detail =
-                account::get_account_detail(app_db, &entry_function.module_address).await;
+    let filter = FunctionFilter {
+        entry_only: true,
+        ..Default::default()
+    };
+    let entry_functions = function_indexer_db
+        .get_latest_functions(&filter, function_key(cursor), offset, limit)
+        .await
+        .map_err(Error::DbError)?;
+
+    with_own_and_account_detail(app_db, network, entry_functions).await
+}
+
+pub async fn get_functions_by_address_and_module_name_with_detail(
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &impl FunctionDetailRepository,
+    network: &str,
+    address: &str,
+    module_name: &str,
+) -> Result<Vec<ModuleFunctionWithDetail>, Error> {
+    let _timer =
+        metrics::query_timer("function::get_functions_by_address_and_module_name_with_detail");
+
+    let functions = function_indexer_db
+        .get_latest_module_functions(address, module_name)
+        .await
+        .map_err(Error::DbError)?;
 
+    let tasks = functions
+        .into_iter()
+        .map(|function| async {
             let function_detail = get_function_detail(
                 app_db,
-                &entry_function.module_address,
-                &entry_function.module_name,
-                &entry_function.name,
+                network,
+                &function.module_address,
+                &function.module_name,
+                &function.name,
             )
-            .await;
+            .await?;
 
-            ModuleFunctionWithOwnAndAccountDetail::compose(
-                entry_function,
-                account_detail,
-                function_detail,
-            )
+            Ok::<_, Error>(ModuleFunctionWithDetail::compose(function, function_detail))
         })
         .collect::<Vec<_>>();
 
-    let module_functions_with_own_and_account_detail: Vec<ModuleFunctionWithOwnAndAccountDetail> =
-        join_all(tasks).await;
+    try_join_all(tasks).await
+}
 
-    Ok(module_functions_with_own_and_account_detail)
+async fn get_latest_function(
+    function_indexer_db: &impl FunctionIndexRepository,
+    usage: &FunctionUsage,
+) -> Result<Option<ModuleFunction>, Error> {
+    function_indexer_db
+        .get_latest_function(
+            &usage.module_address,
+            &usage.module_name,
+            &usage.function_name,
+        )
+        .await
+        .map_err(Error::DbError)
 }
 
-pub async fn get_functions_by_address_and_module_name_with_detail(
-    function_indexer_db: &PostgresPool,
-    app_db: &PostgresPool,
-    address: &str,
-    module_name: &str,
-) -> Option<Vec<ModuleFunctionWithDetail>> {
-    let maybe_functions: Option<Vec<ModuleFunction>> = query_as(
-        "
-                SELECT DISTINCT ON (module_address, module_name, name) *
-                FROM module_function
-                WHERE
-                    module_address = $1
-                    AND
-                    module_name = $2
-                ORDER BY module_address, module_name, name, id DESC
-            ",
-    )
-    .bind(address)
-    .bind(module_name)
-    .fetch_all(function_indexer_db)
-    .await
-    .ok();
-
-    match maybe_functions {
-        Some(functions) => {
-            let tasks = functions
-                .into_iter()
-                .map(|function| async {
-                    let function_detail = get_function_detail(
-                        app_db,
-                        &function.module_address,
-                        &function.module_name,
-                        &function.name,
-                    )
-                    .await;
-
-                    ModuleFunctionWithDetail::compose(function, function_detail)
-                })
-                .collect::<Vec<_>>();
-
-            let module_functions_with_detail: Vec<ModuleFunctionWithDetail> = join_all(tasks).await;
-
-            Some(module_functions_with_detail)
-        }
-        None => None,
-    }
+/// Keeps the ranking of `usages`, functions no longer indexed are dropped
+pub(crate) async fn get_popular_functions_with_account_detail(
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    usages: &[FunctionUsage],
+) -> Result<Vec<ModuleFunctionWithAccountDetail>, Error> {
+    let _timer = metrics::query_timer("function::get_popular_functions_with_account_detail");
+
+    let tasks = usages
+        .iter()
+        .map(|usage| get_latest_function(function_indexer_db, usage))
+        .collect::<Vec<_>>();
+    let functions = try_join_all(tasks).await?.into_iter().flatten().collect();
+
+    with_account_detail(app_db, network, functions).await
+}
+
+pub(crate) async fn get_popular_functions_with_function_detail(
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &(impl AccountDetailRepository + FunctionDetailRepository),
+    network: &str,
+    usages: &[FunctionUsage],
+) -> Result<Vec<ModuleFunctionWithOwnAndAccountDetail>, Error> {
+    let _timer = metrics::query_timer("function::get_popular_functions_with_function_detail");
+
+    let tasks = usages
+        .iter()
+        .map(|usage| get_latest_function(function_indexer_db, usage))
+        .collect::<Vec<_>>();
+    let functions = try_join_all(tasks).await?.into_iter().flatten().collect();
+
+    with_own_and_account_detail(app_db, network, functions).await
 }
 
 pub(crate) async fn create_or_update_function_detail(
-    function_indexer_db: &PostgresPool,
-    app_db: &PostgresPool,
+    function_indexer_db: &impl FunctionIndexRepository,
+    app_db: &impl FunctionDetailRepository,
+    network: &str,
     function_detail: &NewModuleFunctionDetail,
-) -> Result<PgQueryResult, Error> {
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("function::create_or_update_function_detail");
+
     #[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
     struct GenericTypeParams {
         constraints: Vec<String>,
     }
 
-    let target_function: ModuleFunction = query_as(
-        "
-            SELECT DISTINCT ON (module_address, module_name, name) *
-            FROM module_function
-            WHERE
-                module_address = $1
-                AND
-                module_name = $2
-                AND
-                name = $3
-            ORDER BY module_address, module_name, name, id DESC
-        ",
-    )
-    .bind(&function_detail.address)
-    .bind(&function_detail.module_name)
-    .bind(&function_detail.function_name)
-    .fetch_one(function_indexer_db)
-    .await
-    .map_err(|_| Error::NotFound {
-        msg: "onchain function not found".to_string(),
-    })?;
+    let target_function = function_indexer_db
+        .get_latest_function(
+            &function_detail.address,
+            &function_detail.module_name,
+            &function_detail.function_name,
+        )
+        .await
+        .map_err(Error::DbError)?
+        .ok_or_else(|| Error::NotFound {
+            msg: "onchain function not found".to_string(),
+        })?;
 
     let target_function_params: Vec<String> =
         serde_json::from_value(target_function.params.clone().unwrap_or_default())
@@ -518,58 +434,219 @@ pub(crate) async fn create_or_update_function_detail(
         });
     }
 
-    let maybe_function_detail: Option<ModuleFunctionDetail> = query_as(
-        "
-                SELECT *
-                FROM module_function_detail
-                WHERE
-                    address = $1
-                    AND
-                    module_name = $2
-                    AND
-                    function_name = $3
-                ORDER BY id DESC
-            ",
-    )
-    .bind(&function_detail.address)
-    .bind(&function_detail.module_name)
-    .bind(&function_detail.function_name)
-    .fetch_one(app_db)
-    .await
-    .ok();
-
-    let query = match maybe_function_detail {
-        Some(current_function_detail) => query(
-            "
-                    UPDATE module_function_detail SET
-                        description = $1,
-                        param_names = $2,
-                        generic_type_params = $3
-                    WHERE id = $4
-                ",
-        )
-        .bind(&function_detail.description)
-        .bind(&function_detail.param_names)
-        .bind(&function_detail.generic_type_params)
-        .bind(current_function_detail.id),
-        None => query(
-            "
-                    INSERT INTO module_function_detail
-                        (address, module_name, function_name, description, param_names, generic_type_params)
-                        VALUES
-                        ($1, $2, $3, $4, $5, $6)
-                ",
+    let maybe_function_detail = app_db
+        .get_function_detail(
+            network,
+            &function_detail.address,
+            &function_detail.module_name,
+            &function_detail.function_name,
         )
-        .bind(&function_detail.address)
-        .bind(&function_detail.module_name)
-        .bind(&function_detail.function_name)
-        .bind(&function_detail.description)
-        .bind(&function_detail.param_names)
-        .bind(&function_detail.generic_type_params),
+        .await
+        .map_err(Error::DbError)?;
+
+    match maybe_function_detail {
+        Some(current_function_detail) => {
+            app_db
+                .update_function_detail(current_function_detail.id, function_detail)
+                .await
+        }
+        None => {
+            app_db
+                .insert_function_detail(network, function_detail)
+                .await
+        }
+    }
+    .map_err(Error::DbError)
+}
+
+#[cfg(test)]
+mod search {
+    use crate::service::domain::cursor::FunctionCursor;
+    use crate::service::domain::function::{
+        create_or_update_function_detail, get_entry_function_count_filtered_by_keyword,
+        get_entry_functions_count, get_function_with_detail,
+        get_functions_by_keyword_with_account_detail, get_functions_count,
     };
+    use crate::service::Error;
+    use database::models::module_hub::core::function::ModuleFunction;
+    use database::models::module_hub::detail::function::NewModuleFunctionDetail;
+    use database::repository::memory::MemoryRepository;
+    use serde_json::json;
+
+    fn function(id: i32, address: &str, module_name: &str, name: &str) -> ModuleFunction {
+        ModuleFunction {
+            id,
+            module_address: address.to_string(),
+            module_name: module_name.to_string(),
+            move_modules_transaction_version: id.into(),
+            move_modules_write_set_change_index: 0,
+            name: name.to_string(),
+            visibility: "public".to_string(),
+            is_entry: name != "balance",
+            generic_type_params: Some(json!([])),
+            params: Some(json!(["&signer", "address", "u64"])),
+            return_types: Some(json!([])),
+            is_deleted: false,
+        }
+    }
+
+    fn repository() -> MemoryRepository {
+        let repository = MemoryRepository::default();
+        repository.functions.lock().unwrap().extend([
+            function(1, "0xa", "coin", "transfer"),
+            function(2, "0xa", "coin", "balance"),
+            function(3, "0xa", "bank", "deposit"),
+            function(4, "0xb", "coin", "mint"),
+            // Upgrade of the first one
+            function(5, "0xa", "coin", "transfer"),
+        ]);
+        repository
+    }
 
-    query
-        .execute(app_db)
+    #[actix_rt::test]
+    async fn test_keyword_search_pages_latest_functions() {
+        let repository = repository();
+
+        let first = get_functions_by_keyword_with_account_detail(
+            &repository,
+            &repository,
+            "devnet",
+            "0xa",
+            &None,
+            0,
+            2,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            first
+                .iter()
+                .map(|f| (f.module_function.name.as_str(), f.module_function.id))
+                .collect::<Vec<_>>(),
+            [("deposit", 3), ("balance", 2)]
+        );
+
+        let cursor = Some(FunctionCursor::from(&first[1].module_function));
+        let second = get_functions_by_keyword_with_account_detail(
+            &repository,
+            &repository,
+            "devnet",
+            "0xa",
+            &cursor,
+            0,
+            2,
+        )
         .await
-        .map_err(|e| Error::DbError(e.into()))
+        .unwrap();
+        assert_eq!(
+            second
+                .iter()
+                .map(|f| (f.module_function.name.as_str(), f.module_function.id))
+                .collect::<Vec<_>>(),
+            [("transfer", 5)]
+        );
+
+        let count = get_entry_function_count_filtered_by_keyword(
+            &repository,
+            &repository,
+            "devnet",
+            "coin",
+        )
+        .await
+        .unwrap();
+        assert_eq!(count.count, 2);
+
+        // Upgrades are not counted again
+        assert_eq!(get_functions_count(&repository).await.unwrap().count, 4);
+        assert_eq!(
+            get_entry_functions_count(&repository).await.unwrap().count,
+            3
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_function_detail_must_match_params() {
+        let repository = repository();
+        let detail = |param_names: Vec<&str>| {
+            NewModuleFunctionDetail::new(
+                "0xa",
+                "coin",
+                "transfer",
+                Some("moves coins".to_string()),
+                Some(param_names.into_iter().map(str::to_string).collect()),
+                Some(vec![]),
+            )
+        };
+
+        let res = create_or_update_function_detail(
+            &repository,
+            &repository,
+            "devnet",
+            &detail(vec!["to"]),
+        )
+        .await;
+        assert!(matches!(res, Err(Error::InvalidParams { .. })));
+
+        create_or_update_function_detail(
+            &repository,
+            &repository,
+            "devnet",
+            &detail(vec!["signer", "to", "amount"]),
+        )
+        .await
+        .unwrap();
+
+        let function = get_function_with_detail(
+            &repository,
+            &repository,
+            "devnet",
+            "0xa",
+            "coin",
+            "transfer",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(function.module_function.id, 5);
+        assert_eq!(
+            function.function_detail.unwrap().description.as_deref(),
+            Some("moves coins")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_functions_dropped_by_an_upgrade_are_hidden() {
+        let repository = repository();
+        // The upgrade of 0xa::coin at version 6 drops balance
+        repository.functions.lock().unwrap().push(ModuleFunction {
+            is_deleted: true,
+            ..function(6, "0xa", "coin", "balance")
+        });
+
+        let functions = get_functions_by_keyword_with_account_detail(
+            &repository,
+            &repository,
+            "devnet",
+            "0xa",
+            &None,
+            0,
+            10,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            functions
+                .iter()
+                .map(|f| f.module_function.name.as_str())
+                .collect::<Vec<_>>(),
+            ["deposit", "transfer"]
+        );
+
+        assert_eq!(get_functions_count(&repository).await.unwrap().count, 3);
+        let function =
+            get_function_with_detail(&repository, &repository, "devnet", "0xa", "coin", "balance")
+                .await
+                .unwrap();
+        assert!(function.is_none());
+    }
 }
@@ -0,0 +1,299 @@
+use crate::config::ApiContext;
+
+use database::repository::health::HealthRepository;
+
+use futures::future::{join_all, FutureExt};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const STATUS_UP: &str = "up";
+pub const STATUS_DEGRADED: &str = "degraded";
+pub const STATUS_DOWN: &str = "down";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DependencyStatus {
+    pub name: String,
+    /// up, degraded or down
+    pub status: &'static str,
+    /// The service cannot serve requests while a critical dependency is down
+    pub critical: bool,
+    pub latency_ms: u128,
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    /// down when a critical dependency is down, degraded when anything else is not up
+    pub status: &'static str,
+    pub dependencies: Vec<DependencyStatus>,
+}
+
+impl HealthReport {
+    pub fn new(dependencies: Vec<DependencyStatus>) -> Self {
+        let status = if dependencies
+            .iter()
+            .any(|d| d.critical && d.status == STATUS_DOWN)
+        {
+            STATUS_DOWN
+        } else if dependencies.iter().any(|d| d.status != STATUS_UP) {
+            STATUS_DEGRADED
+        } else {
+            STATUS_UP
+        };
+
+        HealthReport {
+            status,
+            dependencies,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status != STATUS_DOWN
+    }
+}
+
+/// Outcome of a single probe, the detail is kept for up dependencies too
+type Probe = (&'static str, Option<String>);
+
+/// Times the probe and turns a timeout into down
+async fn check<F>(name: String, critical: bool, timeout: Duration, probe: F) -> DependencyStatus
+where
+    F: Future<Output = Probe>,
+{
+    let started = Instant::now();
+    let (status, detail) = match actix_rt::time::timeout(timeout, probe).await {
+        Ok(probe) => probe,
+        Err(_) => (STATUS_DOWN, Some(format!("timed out after {:?}", timeout))),
+    };
+
+    DependencyStatus {
+        name,
+        status,
+        critical,
+        latency_ms: started.elapsed().as_millis(),
+        detail,
+    }
+}
+
+async fn probe_pool(pool: &impl HealthRepository) -> Probe {
+    match pool.ping().await {
+        Ok(_) => (STATUS_UP, None),
+        Err(e) => (STATUS_DOWN, Some(e.to_string())),
+    }
+}
+
+/// Block stacks cannot be compiled without the aptos cli, everything else still works
+async fn probe_compiler() -> Probe {
+    let output = actix_rt::task::spawn_blocking(|| {
+        std::process::Command::new("aptos")
+            .arg("--version")
+            .output()
+    })
+    .await;
+
+    match output {
+        Ok(Ok(output)) if output.status.success() => (
+            STATUS_UP,
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        ),
+        Ok(Ok(output)) => (
+            STATUS_DEGRADED,
+            Some(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        ),
+        Ok(Err(e)) => (STATUS_DEGRADED, Some(format!("aptos cli not found: {}", e))),
+        Err(e) => (STATUS_DEGRADED, Some(e.to_string())),
+    }
+}
+
+/// Writes and removes a probe file where scripts are generated
+async fn probe_script_workspace(script_dir: &Path) -> Probe {
+    let probe_file: PathBuf = script_dir.join(".health-probe");
+
+    let res = async {
+        tokio::fs::create_dir_all(script_dir).await?;
+        tokio::fs::write(&probe_file, b"ok").await?;
+        tokio::fs::remove_file(&probe_file).await
+    }
+    .await;
+
+    match res {
+        Ok(_) => (STATUS_UP, Some(script_dir.display().to_string())),
+        Err(e) => (
+            STATUS_DEGRADED,
+            Some(format!("{} is not writable: {}", script_dir.display(), e)),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LedgerInfo {
+    ledger_version: String,
+}
+
+/// Versions the full indexer is behind the node, degraded past `max_lag`
+async fn probe_indexer_lag(
+    aptos_full_index_db: &impl HealthRepository,
+    node_url: &str,
+    max_lag: i64,
+) -> Probe {
+    let indexed = match aptos_full_index_db.get_indexed_version().await {
+        Ok(version) => version.unwrap_or(-1),
+        Err(e) => return (STATUS_DEGRADED, Some(e.to_string())),
+    };
+
+    let ledger = match reqwest::get(node_url).await {
+        Ok(res) => res.json::<LedgerInfo>().await.ok(),
+        Err(_) => None,
+    };
+    let ledger_version = match ledger.and_then(|l| l.ledger_version.parse::<i64>().ok()) {
+        Some(ledger_version) => ledger_version,
+        None => {
+            return (
+                STATUS_DEGRADED,
+                Some(format!(
+                    "indexed up to {}, cannot read ledger version of {}",
+                    indexed, node_url
+                )),
+            )
+        }
+    };
+
+    let lag = ledger_version - indexed;
+    let detail = Some(format!(
+        "indexed {} of {}, {} versions behind",
+        indexed, ledger_version, lag
+    ));
+
+    if lag > max_lag {
+        return (STATUS_DEGRADED, detail);
+    }
+
+    (STATUS_UP, detail)
+}
+
+/// Every dependency is probed concurrently, each bounded by the configured timeout. Databases of
+/// networks other than the default are prefixed with the network name and never critical
+pub(crate) async fn check_dependencies(context: &ApiContext) -> HealthReport {
+    let settings = &context.settings;
+    let timeout = Duration::from_millis(settings.health.timeout_ms);
+
+    let mut checks = vec![
+        check(
+            "app_db".to_string(),
+            true,
+            timeout,
+            probe_pool(&context.app_db),
+        )
+        .boxed_local(),
+        check(
+            "move_compiler".to_string(),
+            false,
+            timeout,
+            probe_compiler(),
+        )
+        .boxed_local(),
+        check(
+            "script_workspace".to_string(),
+            false,
+            timeout,
+            probe_script_workspace(&settings.compile.script_dir),
+        )
+        .boxed_local(),
+    ];
+
+    for network in &context.networks {
+        let is_default = network.name == context.default_network().name;
+        let prefix = match is_default {
+            true => String::new(),
+            false => format!("{}.", network.name),
+        };
+
+        checks.push(
+            check(
+                format!("{}full_indexer_db", prefix),
+                is_default,
+                timeout,
+                probe_pool(&network.aptos_full_index_db),
+            )
+            .boxed_local(),
+        );
+        checks.push(
+            check(
+                format!("{}function_indexer_db", prefix),
+                is_default,
+                timeout,
+                probe_pool(&network.function_index_db),
+            )
+            .boxed_local(),
+        );
+        checks.push(
+            check(
+                format!("{}indexer_lag", prefix),
+                false,
+                timeout,
+                probe_indexer_lag(
+                    &network.aptos_full_index_db,
+                    &network.node_url,
+                    settings.health.max_indexer_lag,
+                ),
+            )
+            .boxed_local(),
+        );
+    }
+
+    HealthReport::new(join_all(checks).await)
+}
+
+#[cfg(test)]
+mod report {
+    use crate::service::domain::health::{
+        check, probe_script_workspace, DependencyStatus, HealthReport, STATUS_DEGRADED,
+        STATUS_DOWN, STATUS_UP,
+    };
+    use std::time::Duration;
+
+    fn dependency(status: &'static str, critical: bool) -> DependencyStatus {
+        DependencyStatus {
+            name: "dependency".to_string(),
+            status,
+            critical,
+            latency_ms: 0,
+            detail: None,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_report_status() {
+        let up = HealthReport::new(vec![
+            dependency(STATUS_UP, true),
+            dependency(STATUS_UP, false),
+        ]);
+        assert_eq!(up.status, STATUS_UP);
+
+        let degraded = HealthReport::new(vec![
+            dependency(STATUS_UP, true),
+            dependency(STATUS_DEGRADED, false),
+        ]);
+        assert_eq!(degraded.status, STATUS_DEGRADED);
+        assert!(degraded.is_ready());
+
+        let down = HealthReport::new(vec![
+            dependency(STATUS_DOWN, true),
+            dependency(STATUS_UP, false),
+        ]);
+        assert_eq!(down.status, STATUS_DOWN);
+        assert!(!down.is_ready());
+
+        let timed_out = check("slow".to_string(), true, Duration::from_millis(10), async {
+            actix_rt::time::sleep(Duration::from_secs(1)).await;
+            (STATUS_UP, None)
+        })
+        .await;
+        assert_eq!(timed_out.status, STATUS_DOWN);
+
+        let workspace = std::env::temp_dir().join("moveblock-health-test");
+        assert_eq!(probe_script_workspace(&workspace).await.0, STATUS_UP);
+    }
+}
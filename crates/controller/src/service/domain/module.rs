@@ -1,225 +1,391 @@
+use crate::config::settings::CompileSettings;
+use crate::metrics;
 use crate::service::{
-    domain::{account, function, Count},
+    domain::{account, cursor::ModuleCursor, function, Count},
     Error,
 };
 
-use database::db::PostgresPool;
 use database::models::module_hub::composite::module::{
     MoveModuleWithDetail, MoveModuleWithFunctionsAccountAndDetails,
 };
-use database::models::module_hub::core::module::MoveModule;
+use database::models::module_hub::core::module::{ModuleVersion, MoveModule};
 use database::models::module_hub::detail::module::{ModuleDetail, NewModuleDetail};
+use database::repository::account::AccountDetailRepository;
+use database::repository::function::{FunctionDetailRepository, FunctionIndexRepository};
+use database::repository::module::{ModuleDetailRepository, OnChainModuleRepository};
+use move_generator::package_name_parser;
 
-use sqlx::postgres::PgQueryResult;
-use sqlx::{query, query_as};
+/// The address itself and, when it is an alias, the address it points to
+async fn resolve_addresses(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    address_or_alias: &str,
+) -> Result<Vec<String>, Error> {
+    let mut addresses = vec![address_or_alias.to_string()];
+    addresses.extend(account::get_address_by_alias(app_db, network, address_or_alias).await?);
+
+    Ok(addresses)
+}
 
 pub(crate) async fn get_modules_by_account(
-    aptos_full_index_db: &PostgresPool,
-    app_db: &PostgresPool,
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
     address_or_alias: &str,
-) -> Option<Vec<MoveModule>> {
-    let address_by_alias = account::get_address_by_alias(app_db, address_or_alias).await;
-
-    query_as(
-        "
-                SELECT DISTINCT ON (name, address) *
-                FROM move_modules
-                WHERE
-                    address = $1
-                    OR
-                    address = $2
-                ORDER BY name, address, transaction_version DESC
-            ",
-    )
-    .bind(address_or_alias)
-    .bind(address_by_alias.unwrap_or_default())
-    .fetch_all(aptos_full_index_db)
-    .await
-    .ok()
+    cursor: &Option<ModuleCursor>,
+    limit: Option<i64>,
+) -> Result<Vec<MoveModule>, Error> {
+    let _timer = metrics::query_timer("module::get_modules_by_account");
+
+    let addresses = resolve_addresses(app_db, network, address_or_alias).await?;
+
+    aptos_full_index_db
+        .get_latest_modules(
+            &addresses,
+            cursor
+                .as_ref()
+                .map(|c| (c.name.as_str(), c.address.as_str())),
+            limit,
+        )
+        .await
+        .map_err(Error::DbError)
+}
+
+pub(crate) async fn get_modules_count_by_account(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    address_or_alias: &str,
+) -> Result<Count, Error> {
+    let _timer = metrics::query_timer("module::get_modules_count_by_account");
+
+    let addresses = resolve_addresses(app_db, network, address_or_alias).await?;
+
+    aptos_full_index_db
+        .count_modules(&addresses)
+        .await
+        .map(|count| Count { count })
+        .map_err(Error::DbError)
 }
 
 pub(crate) async fn get_module_by_address_name_with_detail(
-    aptos_full_index_db: &PostgresPool,
-    app_db: &PostgresPool,
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &impl ModuleDetailRepository,
+    network: &str,
     address: &str,
     module_name: &str,
-) -> Option<MoveModuleWithDetail> {
-    let maybe_module: Option<MoveModule> = query_as(
-        "
-                SELECT DISTINCT ON (name, address) *
-                FROM move_modules
-                WHERE
-                    address = $1
-                    AND
-                    name = $2
-                ORDER BY name, address, transaction_version DESC
-            ",
-    )
-    .bind(address.to_string())
-    .bind(module_name.to_string())
-    .fetch_one(aptos_full_index_db)
-    .await
-    .ok();
+) -> Result<Option<MoveModuleWithDetail>, Error> {
+    let _timer = metrics::query_timer("module::get_module_by_address_name_with_detail");
+
+    let maybe_module = aptos_full_index_db
+        .get_latest_module(address, module_name)
+        .await
+        .map_err(Error::DbError)?;
 
     match maybe_module {
         Some(module) => {
-            let module_detail: Option<ModuleDetail> = query_as(
-                "
-                        SELECT DISTINCT ON (address, module_name) *
-                        FROM module_detail
-                        WHERE
-                            address = $1
-                            AND
-                            module_name = $2
-                        ORDER BY address, module_name, id DESC
-                    ",
-            )
-            .bind(&module.address)
-            .bind(&module.name)
-            .fetch_one(app_db)
-            .await
-            .ok();
+            let module_detail = app_db
+                .get_module_detail(network, &module.address, &module.name)
+                .await
+                .map_err(Error::DbError)?;
 
-            Some(MoveModuleWithDetail::compose(module, module_detail))
+            Ok(Some(MoveModuleWithDetail::compose(module, module_detail)))
         }
-        None => None,
+        None => Ok(None),
     }
 }
 
 pub(crate) async fn get_module_with_functions_and_details(
-    aptos_full_index_db: &PostgresPool,
-    function_index_db: &PostgresPool,
-    app_db: &PostgresPool,
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    function_index_db: &impl FunctionIndexRepository,
+    app_db: &(impl AccountDetailRepository + ModuleDetailRepository + FunctionDetailRepository),
+    network: &str,
     address: &str,
     module_name: &str,
-) -> Option<MoveModuleWithFunctionsAccountAndDetails> {
-    let maybe_move_module_with_detail =
-        get_module_by_address_name_with_detail(aptos_full_index_db, app_db, address, module_name)
-            .await;
+) -> Result<Option<MoveModuleWithFunctionsAccountAndDetails>, Error> {
+    let _timer = metrics::query_timer("module::get_module_with_functions_and_details");
+
+    let maybe_move_module_with_detail = get_module_by_address_name_with_detail(
+        aptos_full_index_db,
+        app_db,
+        network,
+        address,
+        module_name,
+    )
+    .await?;
 
     match maybe_move_module_with_detail {
         Some(move_module_with_detail) => {
-            let maybe_functions = function::get_functions_by_address_and_module_name_with_detail(
+            let functions = function::get_functions_by_address_and_module_name_with_detail(
                 function_index_db,
                 app_db,
+                network,
                 address,
                 module_name,
             )
-            .await
-            .unwrap_or_default();
+            .await?;
 
-            let account_detail = account::get_account_detail(app_db, address).await;
-            Some(MoveModuleWithFunctionsAccountAndDetails::compose(
+            let account_detail = account::get_account_detail(app_db, network, address).await?;
+            Ok(Some(MoveModuleWithFunctionsAccountAndDetails::compose(
                 move_module_with_detail,
                 account_detail,
-                maybe_functions,
-            ))
+                functions,
+            )))
         }
-        None => None,
+        None => Ok(None),
     }
 }
 
 pub(crate) async fn create_or_update_module_info(
-    aptos_full_index_db: &PostgresPool,
-    app_db: &PostgresPool,
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &impl ModuleDetailRepository,
+    network: &str,
     module_detail: &NewModuleDetail,
-) -> Result<PgQueryResult, Error> {
-    let target_module_count: Count = query_as(
-        "
-                SELECT COUNT(name) as count
-                FROM move_modules
-                WHERE
-                    address = $1
-                    AND
-                    name = $2
-            ",
-    )
-    .bind(&module_detail.address)
-    .bind(&module_detail.module_name)
-    .fetch_one(aptos_full_index_db)
-    .await
-    .unwrap_or_default();
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("module::create_or_update_module_info");
+
+    let target_module = aptos_full_index_db
+        .get_latest_module(&module_detail.address, &module_detail.module_name)
+        .await
+        .map_err(Error::DbError)?;
 
-    if target_module_count.count == 0 {
+    if target_module.is_none() {
         return Err(Error::NotFound {
             msg: "onchain module not found".to_string(),
         });
     }
 
-    let maybe_module_detail: Option<ModuleDetail> = query_as(
-        "
-                SELECT *
-                FROM module_detail
-                WHERE
-                    address = $1
-                    AND
-                    module_name = $2
-                ORDER BY id DESC
-            ",
-    )
-    .bind(&module_detail.address)
-    .bind(&module_detail.module_name)
-    .fetch_one(app_db)
-    .await
-    .ok();
-
-    let query = match maybe_module_detail {
-        Some(current_module) => query(
-            "
-                        UPDATE module_detail SET
-                            description = $1,
-                            github_url = $2,
-                            rev = $3,
-                            subdir = $4
-                        WHERE id = $5
-                    ",
-        )
-        .bind(&module_detail.description)
-        .bind(&module_detail.github_url)
-        .bind(&module_detail.rev)
-        .bind(&module_detail.subdir)
-        .bind(current_module.id),
-        None => query(
-            "
-                        INSERT INTO module_detail
-                            (address, module_name, description, github_url, rev, subdir)
-                            VALUES
-                            ($1, $2, $3, $4, $5, $6)
-                    ",
-        )
-        .bind(&module_detail.address)
-        .bind(&module_detail.module_name)
-        .bind(&module_detail.description)
-        .bind(&module_detail.github_url)
-        .bind(&module_detail.rev)
-        .bind(&module_detail.subdir),
-    };
-
-    query
-        .execute(app_db)
+    let maybe_module_detail = app_db
+        .get_module_detail(network, &module_detail.address, &module_detail.module_name)
         .await
-        .map_err(|e| Error::DbError(e.into()))
+        .map_err(Error::DbError)?;
+
+    match maybe_module_detail {
+        Some(current_module) => {
+            app_db
+                .update_module_detail(current_module.id, module_detail)
+                .await
+        }
+        None => app_db.insert_module_detail(network, module_detail).await,
+    }
+    .map_err(Error::DbError)
 }
 
 pub async fn get_module_detail(
-    app_db: &PostgresPool,
+    app_db: &impl ModuleDetailRepository,
+    network: &str,
     address: &str,
     module_name: &str,
-) -> Option<ModuleDetail> {
-    query_as(
-        "
-                SELECT DISTINCT ON (address, module_name) *
-                FROM module_detail
-                WHERE
-                    address = $1
-                    AND
-                    module_name = $2
-                ORDER BY address, module_name, id DESC
-            ",
+) -> Result<Option<ModuleDetail>, Error> {
+    let _timer = metrics::query_timer("module::get_module_detail");
+
+    app_db
+        .get_module_detail(network, address, module_name)
+        .await
+        .map_err(Error::DbError)
+}
+
+/// Checks a registered source still belongs to a live on-chain module and its `Move.toml` can be
+/// read, returns the package name it declares
+pub async fn verify_module_source(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    compile: &CompileSettings,
+    module_detail: &NewModuleDetail,
+) -> Result<String, Error> {
+    let _timer = metrics::query_timer("module::verify_module_source");
+
+    let target_module = aptos_full_index_db
+        .get_latest_module(&module_detail.address, &module_detail.module_name)
+        .await
+        .map_err(Error::DbError)?;
+
+    if target_module.is_none_or(|module| module.is_deleted) {
+        return Err(Error::NotFound {
+            msg: "onchain module not found".to_string(),
+        });
+    }
+
+    package_name_parser(
+        &compile.github_raw_base,
+        module_detail.github_url.as_deref().unwrap_or_default(),
+        module_detail.rev.as_deref().unwrap_or_default(),
+        module_detail.subdir.as_deref().unwrap_or_default(),
     )
-    .bind(address)
-    .bind(module_name)
-    .fetch_one(app_db)
     .await
-    .ok()
+    .map_err(|e| Error::InvalidParams {
+        msg: format!("source is unreadable: {}", e),
+    })
+}
+
+/// Oldest first, aliases are resolved like `get_modules_by_account`
+pub(crate) async fn get_module_versions(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    address_or_alias: &str,
+    module_name: &str,
+) -> Result<Vec<ModuleVersion>, Error> {
+    let _timer = metrics::query_timer("module::get_module_versions");
+
+    let addresses = resolve_addresses(app_db, network, address_or_alias).await?;
+
+    aptos_full_index_db
+        .get_module_versions(&addresses, module_name)
+        .await
+        .map_err(Error::DbError)
+}
+
+/// The module as published at `transaction_version`, the latest one when None
+pub(crate) async fn get_module_at_version(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    address_or_alias: &str,
+    module_name: &str,
+    transaction_version: Option<i64>,
+) -> Result<Option<MoveModule>, Error> {
+    let _timer = metrics::query_timer("module::get_module_at_version");
+
+    let addresses = resolve_addresses(app_db, network, address_or_alias).await?;
+
+    aptos_full_index_db
+        .get_module_at_version(&addresses, module_name, transaction_version)
+        .await
+        .map_err(Error::DbError)
+}
+
+/// The version an upgrade at `transaction_version` replaced, None for a first publish
+pub(crate) async fn get_previous_module_version(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    address: &str,
+    module_name: &str,
+    transaction_version: i64,
+) -> Result<Option<MoveModule>, Error> {
+    let _timer = metrics::query_timer("module::get_previous_module_version");
+
+    aptos_full_index_db
+        .get_previous_module_version(address, module_name, transaction_version)
+        .await
+        .map_err(Error::DbError)
+}
+
+#[cfg(test)]
+mod lookup {
+    use crate::service::domain::module::{
+        create_or_update_module_info, get_module_detail, get_module_versions,
+        get_modules_by_account,
+    };
+    use crate::service::Error;
+    use database::models::module_hub::core::module::MoveModule;
+    use database::models::module_hub::detail::account::AccountDetail;
+    use database::models::module_hub::detail::module::NewModuleDetail;
+    use database::repository::memory::MemoryRepository;
+
+    fn module(address: &str, name: &str, transaction_version: i64) -> MoveModule {
+        MoveModule {
+            transaction_version,
+            name: name.to_string(),
+            address: address.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn repository() -> MemoryRepository {
+        let repository = MemoryRepository::default();
+        repository.modules.lock().unwrap().extend([
+            module("0xa", "coin", 1),
+            module("0xa", "coin", 5),
+            module("0xa", "bank", 2),
+            module("0xb", "coin", 3),
+        ]);
+        repository.account_details.lock().unwrap().push((
+            "devnet".to_string(),
+            AccountDetail {
+                id: 1,
+                address: "0xa".to_string(),
+                alias: Some("alice".to_string()),
+                display_name: None,
+                avatar_url: None,
+                website: None,
+                social_links: None,
+                description: None,
+                domain: None,
+                domain_verified_by: None,
+                domain_verified_at: None,
+            },
+        ));
+        repository
+    }
+
+    #[actix_rt::test]
+    async fn test_modules_by_alias() {
+        let repository = repository();
+
+        let modules =
+            get_modules_by_account(&repository, &repository, "devnet", "Alice", &None, None)
+                .await
+                .unwrap();
+        assert_eq!(
+            modules
+                .iter()
+                .map(|m| (m.name.as_str(), m.transaction_version))
+                .collect::<Vec<_>>(),
+            [("bank", 2), ("coin", 5)]
+        );
+
+        let versions = get_module_versions(&repository, &repository, "devnet", "alice", "coin")
+            .await
+            .unwrap();
+        assert_eq!(
+            versions
+                .iter()
+                .map(|v| v.transaction_version)
+                .collect::<Vec<_>>(),
+            [1, 5]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_create_or_update_module_info() {
+        let repository = repository();
+        let detail = |address: &str, description: &str| {
+            NewModuleDetail::new(
+                address,
+                "coin",
+                &Some(description.to_string()),
+                &None,
+                &None,
+                &None,
+            )
+        };
+
+        let res =
+            create_or_update_module_info(&repository, &repository, "devnet", &detail("0xc", "x"))
+                .await;
+        assert!(matches!(res, Err(Error::NotFound { .. })));
+
+        for description in ["first", "second"] {
+            create_or_update_module_info(
+                &repository,
+                &repository,
+                "devnet",
+                &detail("0xa", description),
+            )
+            .await
+            .unwrap();
+        }
+
+        let module_detail = get_module_detail(&repository, "devnet", "0xa", "coin")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(module_detail.description.as_deref(), Some("second"));
+        assert_eq!(repository.module_details.lock().unwrap().len(), 1);
+
+        // Annotations of one network are not shown on another
+        let module_detail = get_module_detail(&repository, "mainnet", "0xa", "coin")
+            .await
+            .unwrap();
+        assert!(module_detail.is_none());
+    }
 }
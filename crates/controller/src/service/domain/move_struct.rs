@@ -0,0 +1,295 @@
+use crate::metrics;
+use crate::service::{
+    domain::{account, module, Count},
+    Error,
+};
+
+use database::models::module_hub::composite::move_struct::MoveStructWithDetail;
+use database::models::module_hub::core::function::ModuleFunction;
+use database::models::module_hub::core::move_struct::MoveStruct;
+use database::models::module_hub::detail::move_struct::{
+    ModuleStructDetail, NewModuleStructDetail,
+};
+use database::repository::account::AccountDetailRepository;
+use database::repository::function::FunctionIndexRepository;
+use database::repository::module::{ModuleDetailRepository, OnChainModuleRepository};
+use database::repository::move_struct::StructDetailRepository;
+
+use futures::future::try_join_all;
+use serde_json::Map;
+use sqlx::types::JsonValue;
+
+/// None when the module does not exist
+async fn get_structs(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &impl ModuleDetailRepository,
+    network: &str,
+    address: &str,
+    module_name: &str,
+) -> Result<Option<Vec<MoveStruct>>, Error> {
+    Ok(module::get_module_by_address_name_with_detail(
+        aptos_full_index_db,
+        app_db,
+        network,
+        address,
+        module_name,
+    )
+    .await?
+    .map(|module| module.move_module.parse_structs()))
+}
+
+pub(crate) async fn get_struct_detail(
+    app_db: &impl StructDetailRepository,
+    network: &str,
+    address: &str,
+    module_name: &str,
+    struct_name: &str,
+) -> Result<Option<ModuleStructDetail>, Error> {
+    let _timer = metrics::query_timer("move_struct::get_struct_detail");
+
+    app_db
+        .get_struct_detail(network, address, module_name, struct_name)
+        .await
+        .map_err(Error::DbError)
+}
+
+pub(crate) async fn get_structs_with_detail(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &(impl AccountDetailRepository + ModuleDetailRepository + StructDetailRepository),
+    network: &str,
+    address_or_alias: &str,
+    module_name: &str,
+) -> Result<Option<Vec<MoveStructWithDetail>>, Error> {
+    let _timer = metrics::query_timer("move_struct::get_structs_with_detail");
+
+    let address = account::get_address_by_alias(app_db, network, address_or_alias)
+        .await?
+        .unwrap_or_else(|| address_or_alias.to_string());
+
+    let structs =
+        match get_structs(aptos_full_index_db, app_db, network, &address, module_name).await? {
+            Some(structs) => structs,
+            None => return Ok(None),
+        };
+
+    let tasks = structs
+        .into_iter()
+        .map(|move_struct| async {
+            let struct_detail =
+                get_struct_detail(app_db, network, &address, module_name, &move_struct.name)
+                    .await?;
+
+            Ok::<_, Error>(MoveStructWithDetail::compose(move_struct, struct_detail))
+        })
+        .collect::<Vec<_>>();
+
+    try_join_all(tasks).await.map(Some)
+}
+
+pub(crate) async fn get_struct_with_detail(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &(impl AccountDetailRepository + ModuleDetailRepository + StructDetailRepository),
+    network: &str,
+    address_or_alias: &str,
+    module_name: &str,
+    struct_name: &str,
+) -> Result<Option<MoveStructWithDetail>, Error> {
+    let _timer = metrics::query_timer("move_struct::get_struct_with_detail");
+
+    let address = account::get_address_by_alias(app_db, network, address_or_alias)
+        .await?
+        .unwrap_or_else(|| address_or_alias.to_string());
+
+    let move_struct = get_structs(aptos_full_index_db, app_db, network, &address, module_name)
+        .await?
+        .unwrap_or_default()
+        .into_iter()
+        .find(|s| s.name == struct_name);
+
+    let move_struct = match move_struct {
+        Some(move_struct) => move_struct,
+        None => return Ok(None),
+    };
+
+    let struct_detail =
+        get_struct_detail(app_db, network, &address, module_name, struct_name).await?;
+
+    Ok(Some(MoveStructWithDetail::compose(
+        move_struct,
+        struct_detail,
+    )))
+}
+
+pub(crate) async fn create_or_update_struct_detail(
+    aptos_full_index_db: &impl OnChainModuleRepository,
+    app_db: &(impl ModuleDetailRepository + StructDetailRepository),
+    network: &str,
+    struct_detail: &NewModuleStructDetail,
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("move_struct::create_or_update_struct_detail");
+
+    let target_struct = get_structs(
+        aptos_full_index_db,
+        app_db,
+        network,
+        &struct_detail.address,
+        &struct_detail.module_name,
+    )
+    .await?
+    .unwrap_or_default()
+    .into_iter()
+    .find(|s| s.name == struct_detail.struct_name)
+    .ok_or_else(|| Error::NotFound {
+        msg: "onchain struct not found".to_string(),
+    })?;
+
+    let field_descriptions: Map<String, JsonValue> = serde_json::from_value(
+        struct_detail
+            .field_descriptions
+            .clone()
+            .unwrap_or_else(|| JsonValue::Object(Map::new())),
+    )
+    .map_err(|_| Error::InvalidParams {
+        msg: "field_descriptions".to_string(),
+    })?;
+
+    if field_descriptions
+        .keys()
+        .any(|field| !target_struct.fields.iter().any(|f| &f.name == field))
+    {
+        return Err(Error::InvalidParams {
+            msg: "field_descriptions contains unknown field".to_string(),
+        });
+    }
+
+    let maybe_struct_detail = app_db
+        .get_struct_detail(
+            network,
+            &struct_detail.address,
+            &struct_detail.module_name,
+            &struct_detail.struct_name,
+        )
+        .await
+        .map_err(Error::DbError)?;
+
+    match maybe_struct_detail {
+        Some(current_struct_detail) => {
+            app_db
+                .update_struct_detail(current_struct_detail.id, struct_detail)
+                .await
+        }
+        None => app_db.insert_struct_detail(network, struct_detail).await,
+    }
+    .map_err(Error::DbError)
+}
+
+/// `address::module::Struct` without type arguments, its parts are plain identifiers
+fn validate_struct_type(struct_type: &str) -> Result<(), Error> {
+    let split = struct_type.split("::").collect::<Vec<_>>();
+
+    let is_valid = split.len() == 3
+        && split
+            .iter()
+            .all(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+
+    if !is_valid {
+        return Err(Error::InvalidParams {
+            msg: "struct type must be address::module::Struct".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn get_functions_count_by_struct_type(
+    function_indexer_db: &impl FunctionIndexRepository,
+    struct_type: &str,
+) -> Result<Count, Error> {
+    let _timer = metrics::query_timer("move_struct::get_functions_count_by_struct_type");
+
+    validate_struct_type(struct_type)?;
+
+    function_indexer_db
+        .count_latest_functions_by_struct_type(struct_type)
+        .await
+        .map(|count| Count { count })
+        .map_err(Error::DbError)
+}
+
+pub(crate) async fn get_functions_by_struct_type(
+    function_indexer_db: &impl FunctionIndexRepository,
+    struct_type: &str,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<ModuleFunction>, Error> {
+    let _timer = metrics::query_timer("move_struct::get_functions_by_struct_type");
+
+    validate_struct_type(struct_type)?;
+
+    function_indexer_db
+        .get_latest_functions_by_struct_type(struct_type, offset, limit)
+        .await
+        .map_err(Error::DbError)
+}
+
+#[cfg(test)]
+mod struct_type {
+    use crate::service::domain::move_struct::{
+        get_functions_by_struct_type, get_functions_count_by_struct_type, validate_struct_type,
+    };
+    use database::models::module_hub::core::function::ModuleFunction;
+    use database::repository::memory::MemoryRepository;
+    use serde_json::json;
+
+    fn function(id: i32, name: &str, param: &str) -> ModuleFunction {
+        ModuleFunction {
+            id,
+            module_address: "0x1".to_string(),
+            module_name: "coin".to_string(),
+            move_modules_transaction_version: id.into(),
+            move_modules_write_set_change_index: 0,
+            name: name.to_string(),
+            visibility: "public".to_string(),
+            is_entry: false,
+            generic_type_params: Some(json!([])),
+            params: Some(json!([param])),
+            return_types: Some(json!([])),
+            is_deleted: false,
+        }
+    }
+
+    #[test]
+    fn test_validate_struct_type() {
+        assert!(validate_struct_type("0x1::coin::Coin").is_ok());
+        assert!(validate_struct_type("0x1::coin").is_err());
+        assert!(validate_struct_type("0x1::coin::Coin.*").is_err());
+        assert!(validate_struct_type("0x1::coin::Coin<T0>").is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_whole_type_names_match() {
+        let repository = MemoryRepository::default();
+        repository.functions.lock().unwrap().extend([
+            function(1, "value", "&0x1::coin::Coin<T0>"),
+            function(2, "balance", "&0x1::coin::CoinStore<T0>"),
+        ]);
+
+        let functions = get_functions_by_struct_type(&repository, "0x1::coin::Coin", 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            functions
+                .iter()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>(),
+            ["value"]
+        );
+        assert_eq!(
+            get_functions_count_by_struct_type(&repository, "0x1::coin::Coin")
+                .await
+                .unwrap()
+                .count,
+            1
+        );
+    }
+}
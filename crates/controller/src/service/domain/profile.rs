@@ -0,0 +1,377 @@
+use crate::metrics;
+use crate::service::Error;
+
+use database::models::module_hub::detail::account::{
+    AccountDetail, NewAccountProfile, DOMAIN_VERIFIED_BY_DNS_TXT, DOMAIN_VERIFIED_BY_WELL_KNOWN,
+};
+use database::repository::account::AccountDetailRepository;
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use url::Url;
+
+pub const DISPLAY_NAME_MAX_LEN: usize = 64;
+pub const DESCRIPTION_MAX_LEN: usize = 2000;
+pub const URL_MAX_LEN: usize = 2048;
+pub const SOCIAL_LINKS_MAX_LEN: usize = 10;
+
+/// Expected content of the TXT record at `_moveblock.<domain>`
+pub const DNS_TXT_PREFIX: &str = "moveblock-verification=";
+
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidParams { msg }
+}
+
+fn validate_url(field: &str, value: &str) -> Result<(), Error> {
+    if value.len() > URL_MAX_LEN {
+        return Err(invalid(format!("{} is too long", field)));
+    }
+
+    match Url::parse(value) {
+        Ok(url) if url.scheme() == "https" || url.scheme() == "http" => Ok(()),
+        _ => Err(invalid(format!("{} must be an http(s) url", field))),
+    }
+}
+
+/// A bare host name like `example.com`, without scheme, port or path
+pub fn validate_domain(domain: &str) -> Result<(), Error> {
+    let is_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    };
+
+    let labels = domain.split('.').collect::<Vec<_>>();
+    if domain.len() > 253 || labels.len() < 2 || !labels.iter().all(|l| is_label(l)) {
+        return Err(invalid("domain must be a lowercase host name".to_string()));
+    }
+
+    Ok(())
+}
+
+pub fn validate_profile(profile: &NewAccountProfile) -> Result<(), Error> {
+    if matches!(&profile.display_name, Some(name) if name.chars().count() > DISPLAY_NAME_MAX_LEN) {
+        return Err(invalid(format!(
+            "display_name must be at most {} characters",
+            DISPLAY_NAME_MAX_LEN
+        )));
+    }
+
+    if matches!(&profile.description, Some(d) if d.chars().count() > DESCRIPTION_MAX_LEN) {
+        return Err(invalid(format!(
+            "description must be at most {} characters",
+            DESCRIPTION_MAX_LEN
+        )));
+    }
+
+    if let Some(avatar_url) = &profile.avatar_url {
+        validate_url("avatar_url", avatar_url)?;
+    }
+
+    if let Some(website) = &profile.website {
+        validate_url("website", website)?;
+    }
+
+    if let Some(social_links) = &profile.social_links {
+        let social_links: HashMap<String, String> = serde_json::from_value(social_links.clone())
+            .map_err(|_| invalid("social_links must map names to urls".to_string()))?;
+
+        if social_links.len() > SOCIAL_LINKS_MAX_LEN {
+            return Err(invalid(format!(
+                "at most {} social_links are allowed",
+                SOCIAL_LINKS_MAX_LEN
+            )));
+        }
+
+        for (name, link) in social_links {
+            validate_url(&format!("social_links.{}", name), &link)?;
+        }
+    }
+
+    if let Some(domain) = &profile.domain {
+        validate_domain(domain)?;
+    }
+
+    Ok(())
+}
+
+/// Domain verification is reset whenever the domain changes
+pub(crate) async fn create_or_update_account_profile(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    profile: &NewAccountProfile,
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("profile::create_or_update_account_profile");
+
+    validate_profile(profile)?;
+
+    let maybe_account_detail = app_db
+        .get_account_detail(network, &profile.address)
+        .await
+        .map_err(Error::DbError)?;
+
+    match maybe_account_detail {
+        Some(account) => app_db.update_account_profile(account.id, profile).await,
+        None => app_db.insert_account_profile(network, profile).await,
+    }
+    .map_err(Error::DbError)
+}
+
+#[derive(Debug, Deserialize)]
+struct DnsAnswer {
+    data: String,
+}
+
+/// Answer format of the JSON dns-over-https API of Google and Cloudflare
+#[derive(Debug, Deserialize)]
+struct DnsResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DnsAnswer>,
+}
+
+/// Proves a domain belongs to an account, either by a well-known file or a TXT record
+#[derive(Clone, Debug)]
+pub struct DomainVerifier {
+    /// `{domain}` is replaced, the file must contain the account address
+    pub well_known_url: String,
+    /// Queried with `name` and `type` like `https://dns.google/resolve`
+    pub dns_over_https_url: String,
+    client: reqwest::Client,
+}
+
+impl DomainVerifier {
+    /// The client is shared by every verification
+    pub fn new(well_known_url: &str, dns_over_https_url: &str) -> Result<Self, Error> {
+        let client = reqwest::Client::builder()
+            .timeout(VERIFY_TIMEOUT)
+            .build()
+            .map_err(|e| Error::AnyError(e.into()))?;
+
+        Ok(DomainVerifier {
+            well_known_url: well_known_url.to_string(),
+            dns_over_https_url: dns_over_https_url.to_string(),
+            client,
+        })
+    }
+
+    async fn verify_well_known(&self, domain: &str, address: &str) -> bool {
+        let url = self.well_known_url.replace("{domain}", domain);
+        let body = match self.client.get(url).send().await {
+            Ok(res) if res.status().is_success() => res.text().await.unwrap_or_default(),
+            _ => return false,
+        };
+
+        body.split_whitespace()
+            .any(|line| line.eq_ignore_ascii_case(address))
+    }
+
+    async fn verify_dns_txt(&self, domain: &str, address: &str) -> bool {
+        let name = format!("_moveblock.{}", domain);
+        let res = self
+            .client
+            .get(&self.dns_over_https_url)
+            .query(&[("name", name.as_str()), ("type", "TXT")])
+            .header("accept", "application/dns-json")
+            .send()
+            .await;
+
+        let body = match res {
+            Ok(res) if res.status().is_success() => res.text().await.unwrap_or_default(),
+            _ => return false,
+        };
+
+        let expected = format!("{}{}", DNS_TXT_PREFIX, address.to_lowercase());
+        serde_json::from_str::<DnsResponse>(&body)
+            .map(|dns| {
+                dns.answer
+                    .iter()
+                    .any(|a| a.data.trim_matches('"').to_lowercase() == expected)
+            })
+            .unwrap_or_default()
+    }
+
+    /// The method which proved the domain, None when neither did
+    pub async fn verify(&self, domain: &str, address: &str) -> Option<&'static str> {
+        if self.verify_well_known(domain, address).await {
+            return Some(DOMAIN_VERIFIED_BY_WELL_KNOWN);
+        }
+
+        if self.verify_dns_txt(domain, address).await {
+            return Some(DOMAIN_VERIFIED_BY_DNS_TXT);
+        }
+
+        None
+    }
+}
+
+pub(crate) async fn verify_account_domain(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    verifier: &DomainVerifier,
+    address: &str,
+) -> Result<AccountDetail, Error> {
+    let _timer = metrics::query_timer("profile::verify_account_domain");
+
+    let account_detail = app_db
+        .get_account_detail(network, address)
+        .await
+        .map_err(Error::DbError)?
+        .ok_or_else(|| Error::NotFound {
+            msg: "account profile not found".to_string(),
+        })?;
+
+    let domain = account_detail
+        .domain
+        .clone()
+        .ok_or_else(|| Error::NotFound {
+            msg: "profile has no domain".to_string(),
+        })?;
+
+    let verified_by =
+        verifier
+            .verify(&domain, address)
+            .await
+            .ok_or_else(|| Error::InvalidParams {
+                msg: format!("cannot verify {} for {}", domain, address),
+            })?;
+
+    app_db
+        .set_domain_verified(account_detail.id, verified_by)
+        .await
+        .map_err(Error::DbError)
+}
+
+#[cfg(test)]
+mod domain_proof {
+    use crate::service::domain::profile::{
+        create_or_update_account_profile, validate_domain, verify_account_domain, DomainVerifier,
+    };
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use database::models::module_hub::detail::account::{
+        NewAccountProfile, DOMAIN_VERIFIED_BY_DNS_TXT, DOMAIN_VERIFIED_BY_WELL_KNOWN,
+    };
+    use database::repository::account::AccountDetailRepository;
+    use database::repository::memory::MemoryRepository;
+
+    const ADDRESS: &str = "0x44ed46e3943de0ec15ea8edf0a40aa111dc82f8cfcdd82a712ad1352079f21b2";
+    const NETWORK: &str = "devnet";
+
+    /// Serves `well-known.example` by file and `txt.example` by dns record
+    fn stand_in() -> String {
+        let server = HttpServer::new(|| {
+            App::new()
+                .route(
+                    "/well-known.example/.well-known/moveblock.txt",
+                    web::get().to(|| async { HttpResponse::Ok().body(format!("{}\n", ADDRESS)) }),
+                )
+                .route(
+                    "/resolve",
+                    web::get().to(|query: web::Query<Vec<(String, String)>>| async move {
+                        let is_txt_example = query
+                            .iter()
+                            .any(|(k, v)| k == "name" && v == "_moveblock.txt.example");
+                        let answer = if is_txt_example {
+                            format!(
+                                r#"[{{"name":"_moveblock.txt.example","type":16,"data":"\"moveblock-verification={}\""}}]"#,
+                                ADDRESS
+                            )
+                        } else {
+                            "[]".to_string()
+                        };
+                        HttpResponse::Ok().body(format!(r#"{{"Status":0,"Answer":{}}}"#, answer))
+                    }),
+                )
+        })
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+
+        let address = server.addrs()[0];
+        actix_rt::spawn(server.run());
+
+        format!("http://{}", address)
+    }
+
+    #[actix_rt::test]
+    async fn test_verify_domain() {
+        let base = stand_in();
+        let verifier = DomainVerifier::new(
+            &format!("{}/{{domain}}/.well-known/moveblock.txt", base),
+            &format!("{}/resolve", base),
+        )
+        .unwrap();
+
+        assert_eq!(
+            verifier.verify("well-known.example", ADDRESS).await,
+            Some(DOMAIN_VERIFIED_BY_WELL_KNOWN)
+        );
+        assert_eq!(
+            verifier.verify("txt.example", ADDRESS).await,
+            Some(DOMAIN_VERIFIED_BY_DNS_TXT)
+        );
+        assert_eq!(verifier.verify("other.example", ADDRESS).await, None);
+        assert_eq!(verifier.verify("well-known.example", "0x1").await, None);
+
+        assert!(validate_domain("moveblock.xyz").is_ok());
+        assert!(validate_domain("https://moveblock.xyz").is_err());
+        assert!(validate_domain("localhost").is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_changed_domain_resets_verification() {
+        let base = stand_in();
+        let verifier = DomainVerifier::new(
+            &format!("{}/{{domain}}/.well-known/moveblock.txt", base),
+            &format!("{}/resolve", base),
+        )
+        .unwrap();
+        let repository = MemoryRepository::default();
+        let profile = |domain: &str| NewAccountProfile {
+            address: ADDRESS.to_string(),
+            domain: Some(domain.to_string()),
+            ..Default::default()
+        };
+
+        create_or_update_account_profile(&repository, NETWORK, &profile("well-known.example"))
+            .await
+            .unwrap();
+        let verified = verify_account_domain(&repository, NETWORK, &verifier, ADDRESS)
+            .await
+            .unwrap();
+        assert_eq!(
+            verified.domain_verified_by.as_deref(),
+            Some(DOMAIN_VERIFIED_BY_WELL_KNOWN)
+        );
+
+        create_or_update_account_profile(&repository, NETWORK, &profile("well-known.example"))
+            .await
+            .unwrap();
+        let kept = repository
+            .get_account_detail(NETWORK, ADDRESS)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(kept.domain_verified_at.is_some());
+
+        create_or_update_account_profile(&repository, NETWORK, &profile("other.example"))
+            .await
+            .unwrap();
+        let reset = repository
+            .get_account_detail(NETWORK, ADDRESS)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reset.domain_verified_by, None);
+        assert!(
+            verify_account_domain(&repository, NETWORK, &verifier, ADDRESS)
+                .await
+                .is_err()
+        );
+    }
+}
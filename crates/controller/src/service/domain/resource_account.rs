@@ -0,0 +1,148 @@
+use crate::metrics;
+use crate::service::domain::{bytecode::standardize_address, get_resource_account_with_seed};
+use crate::service::Error;
+
+use database::models::module_hub::detail::resource_account::ResourceAccount;
+use database::repository::resource_account::{self, ResourceAccountRepository};
+
+use sqlx::types::JsonValue;
+use sqlx::{Postgres, Transaction};
+
+/// Exactly one of a utf8 seed like `b"pool"` or a hex encoded one
+pub fn parse_seed(seed: &Option<String>, seed_hex: &Option<String>) -> Result<Vec<u8>, Error> {
+    match (seed, seed_hex) {
+        (Some(seed), None) => Ok(seed.as_bytes().to_vec()),
+        (None, Some(seed_hex)) => hex::decode(seed_hex.strip_prefix("0x").unwrap_or(seed_hex))
+            .map_err(|_| Error::InvalidParams {
+                msg: "cannot parse seed_hex".to_string(),
+            }),
+        _ => Err(Error::InvalidParams {
+            msg: "either seed or seed_hex is required".to_string(),
+        }),
+    }
+}
+
+/// Resource addresses held by a `0x1::resource_account::Container`
+pub fn parse_container_addresses(data: &JsonValue) -> Vec<String> {
+    data.pointer("/store/data")
+        .and_then(|entries| entries.as_array())
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| entry.get("key").and_then(|key| key.as_str()))
+                .map(standardize_address)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Auth-checked owner at upper layer, the resource address is derived and never trusted
+pub(crate) async fn register_resource_account(
+    app_db: &impl ResourceAccountRepository,
+    network: &str,
+    owner_address: &str,
+    seed: &[u8],
+) -> Result<ResourceAccount, Error> {
+    let _timer = metrics::query_timer("resource_account::register_resource_account");
+
+    let resource_address =
+        standardize_address(&get_resource_account_with_seed(owner_address, seed)?);
+
+    app_db
+        .save_registered_resource_account(
+            network,
+            &standardize_address(owner_address),
+            &resource_address,
+            &hex::encode(seed),
+        )
+        .await
+        .map_err(Error::DbError)
+}
+
+/// Keeps the seed of an account registered before it was discovered
+pub(crate) async fn save_discovered_resource_account(
+    tx: &mut Transaction<'_, Postgres>,
+    network: &str,
+    owner_address: &str,
+    resource_address: &str,
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("resource_account::save_discovered_resource_account");
+
+    resource_account::save_discovered_resource_account(
+        tx,
+        network,
+        &standardize_address(owner_address),
+        resource_address,
+    )
+    .await
+    .map_err(Error::DbError)
+}
+
+pub(crate) async fn get_resource_accounts_by_owner(
+    app_db: &impl ResourceAccountRepository,
+    network: &str,
+    owner_address: &str,
+) -> Result<Vec<ResourceAccount>, Error> {
+    let _timer = metrics::query_timer("resource_account::get_resource_accounts_by_owner");
+
+    app_db
+        .get_resource_accounts_by_owner(network, &standardize_address(owner_address))
+        .await
+        .map_err(Error::DbError)
+}
+
+pub(crate) async fn is_resource_account_of(
+    app_db: &impl ResourceAccountRepository,
+    network: &str,
+    owner_address: &str,
+    resource_address: &str,
+) -> Result<bool, Error> {
+    let _timer = metrics::query_timer("resource_account::is_resource_account_of");
+
+    let maybe_resource_account = app_db
+        .get_resource_account(
+            network,
+            &standardize_address(owner_address),
+            &standardize_address(resource_address),
+        )
+        .await
+        .map_err(Error::DbError)?;
+
+    Ok(maybe_resource_account.is_some())
+}
+
+#[cfg(test)]
+mod seed {
+    use crate::service::domain::bytecode::standardize_address;
+    use crate::service::domain::resource_account::{parse_container_addresses, parse_seed};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_seed_and_container() {
+        assert_eq!(
+            parse_seed(&Some("pool".to_string()), &None).unwrap(),
+            b"pool"
+        );
+        assert_eq!(
+            parse_seed(&None, &Some("0x0102".to_string())).unwrap(),
+            vec![1, 2]
+        );
+        assert!(parse_seed(&None, &None).is_err());
+        assert!(parse_seed(&Some("pool".to_string()), &Some("01".to_string())).is_err());
+        assert!(parse_seed(&None, &Some("zz".to_string())).is_err());
+
+        let container = json!({
+            "store": {
+                "data": [
+                    { "key": "0xa", "value": { "account": "0xa" } },
+                    { "key": "0xb", "value": { "account": "0xb" } }
+                ]
+            }
+        });
+        assert_eq!(
+            parse_container_addresses(&container),
+            vec![standardize_address("0xa"), standardize_address("0xb")]
+        );
+        assert!(parse_container_addresses(&json!({})).is_empty());
+    }
+}
@@ -0,0 +1,676 @@
+use crate::config::settings::SessionSettings;
+use crate::metrics;
+use crate::service::domain::bytecode::standardize_address;
+use crate::service::network::NETWORK_HEADER;
+use crate::service::Error;
+
+use database::models::session::{
+    LoginChallenge, NewSession, Session, CHALLENGE_PURPOSE_DOMAIN_VERIFICATION,
+    CHALLENGE_PURPOSE_LOGIN, CHALLENGE_PURPOSE_REVOKE_SESSIONS,
+};
+use database::repository::session::SessionRepository;
+
+use actix_web::HttpRequest;
+use chrono::{Duration, NaiveDateTime, Utc};
+use futures::future::LocalBoxFuture;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+/// Sign `message` as `payload.message` before `expires_at`, it is accepted once and only by the
+/// endpoint of its purpose
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Challenge {
+    pub challenge: String,
+    pub message: String,
+    pub expires_at: NaiveDateTime,
+}
+
+/// `token` is sent as `Authorization: Bearer`, `refresh_token` exchanged for a new pair
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionTokens {
+    pub address: String,
+    pub token: String,
+    pub expires_at: NaiveDateTime,
+    pub refresh_token: String,
+    pub refresh_expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RevokedSessions {
+    pub revoked: u64,
+}
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+fn after_secs(from: NaiveDateTime, secs: u64) -> NaiveDateTime {
+    from + Duration::seconds(secs as i64)
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Only hashes are stored, a leaked table does not hand out sessions
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn is_live(session: &Session, now: NaiveDateTime) -> bool {
+    session.revoked_at.is_none() && session.expires_at > now
+}
+
+pub async fn create_challenge(
+    app_db: &impl SessionRepository,
+    settings: &SessionSettings,
+    address: &str,
+    purpose: &str,
+) -> Result<Challenge, Error> {
+    let _timer = metrics::query_timer("session::create_challenge");
+
+    let purposes = [
+        CHALLENGE_PURPOSE_LOGIN,
+        CHALLENGE_PURPOSE_REVOKE_SESSIONS,
+        CHALLENGE_PURPOSE_DOMAIN_VERIFICATION,
+    ];
+    if !purposes.contains(&purpose) {
+        return Err(Error::InvalidParams {
+            msg: format!("unknown challenge purpose {}", purpose),
+        });
+    }
+
+    // Anyone may ask for challenges, so expired ones are dropped before adding another
+    let issued_at = now();
+    app_db
+        .delete_expired_login_challenges(issued_at)
+        .await
+        .map_err(Error::DbError)?;
+
+    let login_challenge = LoginChallenge {
+        challenge: random_token(),
+        address: standardize_address(address),
+        purpose: purpose.to_string(),
+        expires_at: after_secs(issued_at, settings.challenge_ttl_secs),
+    };
+    app_db
+        .insert_login_challenge(&login_challenge)
+        .await
+        .map_err(Error::DbError)?;
+
+    let message = serde_json::json!({
+        "challenge": login_challenge.challenge,
+        "purpose": login_challenge.purpose,
+    })
+    .to_string();
+    Ok(Challenge {
+        challenge: login_challenge.challenge,
+        message,
+        expires_at: login_challenge.expires_at,
+    })
+}
+
+/// Consumes `challenge` whether or not it was issued to `address` for `purpose`, the caller
+/// verified a signature over it
+pub async fn take_challenge(
+    app_db: &impl SessionRepository,
+    challenge: &str,
+    address: &str,
+    purpose: &str,
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("session::take_challenge");
+
+    let login_challenge = app_db
+        .take_login_challenge(challenge, now())
+        .await
+        .map_err(Error::DbError)?;
+
+    match login_challenge {
+        Some(login_challenge)
+            if login_challenge.address == standardize_address(address)
+                && login_challenge.purpose == purpose =>
+        {
+            Ok(())
+        }
+        _ => Err(Error::UnAuthorized {}),
+    }
+}
+
+async fn issue_session(
+    app_db: &impl SessionRepository,
+    settings: &SessionSettings,
+    network: String,
+    address: String,
+    public_key: String,
+) -> Result<SessionTokens, Error> {
+    let issued_at = now();
+    let token = random_token();
+    let refresh_token = random_token();
+    let new_session = NewSession {
+        network,
+        address,
+        public_key,
+        token_hash: hash_token(&token),
+        refresh_token_hash: hash_token(&refresh_token),
+        expires_at: after_secs(issued_at, settings.token_ttl_secs),
+        refresh_expires_at: after_secs(issued_at, settings.refresh_ttl_secs),
+    };
+    app_db
+        .insert_session(&new_session)
+        .await
+        .map_err(Error::DbError)?;
+
+    Ok(SessionTokens {
+        address: new_session.address,
+        token,
+        expires_at: new_session.expires_at,
+        refresh_token,
+        refresh_expires_at: new_session.refresh_expires_at,
+    })
+}
+
+/// `address` and `public_key` come from a body whose signature was verified over `challenge`
+/// against the key the account holds on `network`, the only network the session is accepted on
+pub async fn login(
+    app_db: &impl SessionRepository,
+    settings: &SessionSettings,
+    network: &str,
+    address: &str,
+    public_key: &str,
+    challenge: &str,
+) -> Result<SessionTokens, Error> {
+    let _timer = metrics::query_timer("session::login");
+
+    take_challenge(app_db, challenge, address, CHALLENGE_PURPOSE_LOGIN).await?;
+    issue_session(
+        app_db,
+        settings,
+        network.to_string(),
+        standardize_address(address),
+        public_key.to_string(),
+    )
+    .await
+}
+
+/// The refresh token is spent, a second exchange of it is rejected
+pub async fn refresh(
+    app_db: &impl SessionRepository,
+    settings: &SessionSettings,
+    refresh_token: &str,
+) -> Result<SessionTokens, Error> {
+    let _timer = metrics::query_timer("session::refresh");
+
+    let session = app_db
+        .get_session_by_refresh_token_hash(&hash_token(refresh_token))
+        .await
+        .map_err(Error::DbError)?
+        .filter(|session| session.refresh_expires_at > now())
+        .ok_or(Error::UnAuthorized {})?;
+
+    // Revoking first lets only one of two concurrent exchanges through
+    let revoked = app_db
+        .revoke_session(session.id, now())
+        .await
+        .map_err(Error::DbError)?;
+    if !revoked {
+        return Err(Error::UnAuthorized {});
+    }
+
+    issue_session(
+        app_db,
+        settings,
+        session.network,
+        session.address,
+        session.public_key,
+    )
+    .await
+}
+
+pub async fn logout(app_db: &impl SessionRepository, token: &str) -> Result<(), Error> {
+    let _timer = metrics::query_timer("session::logout");
+
+    let session = app_db
+        .get_session_by_token_hash(&hash_token(token))
+        .await
+        .map_err(Error::DbError)?
+        .ok_or(Error::UnAuthorized {})?;
+
+    app_db
+        .revoke_session(session.id, now())
+        .await
+        .map_err(Error::DbError)?;
+    Ok(())
+}
+
+/// Ends every session of the address on the network, for a signer who lost a token. `challenge`
+/// was signed like for `login`
+pub async fn revoke_sessions(
+    app_db: &impl SessionRepository,
+    network: &str,
+    address: &str,
+    challenge: &str,
+) -> Result<RevokedSessions, Error> {
+    let _timer = metrics::query_timer("session::revoke_sessions");
+
+    take_challenge(
+        app_db,
+        challenge,
+        address,
+        CHALLENGE_PURPOSE_REVOKE_SESSIONS,
+    )
+    .await?;
+    let revoked = app_db
+        .revoke_sessions_by_address(network, &standardize_address(address), now())
+        .await
+        .map_err(Error::DbError)?;
+    Ok(RevokedSessions { revoked })
+}
+
+/// None unless the token is live and was issued to `address` on `network`
+pub async fn resolve_session(
+    app_db: &impl SessionRepository,
+    network: &str,
+    token: &str,
+    address: &str,
+) -> Result<Option<Session>, Error> {
+    let _timer = metrics::query_timer("session::resolve_session");
+
+    let session = app_db
+        .get_session_by_token_hash(&hash_token(token))
+        .await
+        .map_err(Error::DbError)?;
+
+    Ok(session.filter(|session| {
+        is_live(session, now())
+            && session.network == network
+            && session.address == standardize_address(address)
+    }))
+}
+
+/// Resolves the bearer tokens `AptosAuth` is given against the app database, on the network
+/// `NETWORK_HEADER` names
+pub struct SessionLookup<R> {
+    app_db: R,
+    default_network: String,
+}
+
+impl<R> SessionLookup<R> {
+    pub fn new(app_db: R, default_network: &str) -> Self {
+        SessionLookup {
+            app_db,
+            default_network: default_network.to_string(),
+        }
+    }
+}
+
+impl<R> middleware::SessionResolver for SessionLookup<R>
+where
+    R: SessionRepository + Clone + 'static,
+{
+    fn resolve(
+        &self,
+        req: &HttpRequest,
+        token: String,
+        address: String,
+    ) -> LocalBoxFuture<'static, Result<Option<middleware::Session>, actix_web::Error>> {
+        let app_db = self.app_db.clone();
+        // An unknown network has no sessions
+        let network = match req.headers().get(NETWORK_HEADER) {
+            Some(name) => name.to_str().unwrap_or_default().to_string(),
+            None => self.default_network.clone(),
+        };
+
+        Box::pin(async move {
+            let session = resolve_session(&app_db, &network, &token, &address).await?;
+            Ok(session.map(|session| middleware::Session {
+                address: session.address,
+                public_key: session.public_key,
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod lifecycle {
+    use crate::config::settings::SessionSettings;
+    use crate::service::domain::session::{
+        create_challenge, login, logout, refresh, resolve_session, revoke_sessions, take_challenge,
+        SessionLookup,
+    };
+    use crate::service::network::NETWORK_HEADER;
+    use crate::service::Error;
+    use actix_web::http::{header, StatusCode};
+    use actix_web::test::{call_service, init_service, read_body, try_call_service, TestRequest};
+    use actix_web::{web, App, HttpMessage, HttpRequest, HttpResponse};
+    use database::models::session::{
+        CHALLENGE_PURPOSE_DOMAIN_VERIFICATION, CHALLENGE_PURPOSE_LOGIN,
+        CHALLENGE_PURPOSE_REVOKE_SESSIONS,
+    };
+    use database::repository::memory::MemoryRepository;
+    use middleware::{AptosAuth, Session, SessionResolver};
+    use std::sync::Arc;
+
+    const PUBLIC_KEY: &str = "0xkey";
+    const DEVNET: &str = "devnet";
+    const MAINNET: &str = "mainnet";
+
+    async fn sign_in(
+        app_db: &MemoryRepository,
+        settings: &SessionSettings,
+        address: &str,
+    ) -> String {
+        let challenge = create_challenge(app_db, settings, address, CHALLENGE_PURPOSE_LOGIN)
+            .await
+            .unwrap();
+        login(
+            app_db,
+            settings,
+            DEVNET,
+            address,
+            PUBLIC_KEY,
+            &challenge.challenge,
+        )
+        .await
+        .unwrap()
+        .token
+    }
+
+    #[actix_rt::test]
+    async fn test_challenge_signs_in_once() {
+        let app_db = MemoryRepository::default();
+        let settings = SessionSettings::default();
+
+        let challenge = create_challenge(&app_db, &settings, "0xa", CHALLENGE_PURPOSE_LOGIN)
+            .await
+            .unwrap();
+        assert_eq!(
+            challenge.message,
+            format!(
+                "{{\"challenge\":\"{}\",\"purpose\":\"login\"}}",
+                challenge.challenge
+            )
+        );
+
+        // Issued to another address, and consumed by the attempt
+        let other = create_challenge(&app_db, &settings, "0xb", CHALLENGE_PURPOSE_LOGIN)
+            .await
+            .unwrap();
+        let res = login(
+            &app_db,
+            &settings,
+            DEVNET,
+            "0xa",
+            PUBLIC_KEY,
+            &other.challenge,
+        )
+        .await;
+        assert!(matches!(res, Err(Error::UnAuthorized {})));
+
+        let tokens = login(
+            &app_db,
+            &settings,
+            DEVNET,
+            "0xA",
+            PUBLIC_KEY,
+            &challenge.challenge,
+        )
+        .await
+        .unwrap();
+        let res = login(
+            &app_db,
+            &settings,
+            DEVNET,
+            "0xa",
+            PUBLIC_KEY,
+            &challenge.challenge,
+        )
+        .await;
+        assert!(matches!(res, Err(Error::UnAuthorized {})));
+
+        let session = resolve_session(&app_db, DEVNET, &tokens.token, "0xa")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(session.public_key, PUBLIC_KEY);
+        assert_eq!(session.address, tokens.address);
+        assert!(resolve_session(&app_db, DEVNET, &tokens.token, "0xb")
+            .await
+            .unwrap()
+            .is_none());
+
+        // Checked against the devnet key only
+        assert!(resolve_session(&app_db, MAINNET, &tokens.token, "0xa")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_challenges_serve_their_purpose_only() {
+        let app_db = MemoryRepository::default();
+        let settings = SessionSettings::default();
+
+        let res = create_challenge(&app_db, &settings, "0xa", "anything").await;
+        assert!(matches!(res, Err(Error::InvalidParams { .. })));
+
+        let challenge = create_challenge(&app_db, &settings, "0xa", CHALLENGE_PURPOSE_LOGIN)
+            .await
+            .unwrap();
+        let res = revoke_sessions(&app_db, DEVNET, "0xa", &challenge.challenge).await;
+        assert!(matches!(res, Err(Error::UnAuthorized {})));
+
+        let challenge = create_challenge(
+            &app_db,
+            &settings,
+            "0xa",
+            CHALLENGE_PURPOSE_DOMAIN_VERIFICATION,
+        )
+        .await
+        .unwrap();
+        let take = || {
+            take_challenge(
+                &app_db,
+                &challenge.challenge,
+                "0xa",
+                CHALLENGE_PURPOSE_DOMAIN_VERIFICATION,
+            )
+        };
+        assert!(take().await.is_ok());
+        assert!(matches!(take().await, Err(Error::UnAuthorized {})));
+    }
+
+    #[actix_rt::test]
+    async fn test_refresh_logout_and_revoke() {
+        let app_db = MemoryRepository::default();
+        let settings = SessionSettings::default();
+
+        let challenge = create_challenge(&app_db, &settings, "0xa", CHALLENGE_PURPOSE_LOGIN)
+            .await
+            .unwrap();
+        let tokens = login(
+            &app_db,
+            &settings,
+            DEVNET,
+            "0xa",
+            PUBLIC_KEY,
+            &challenge.challenge,
+        )
+        .await
+        .unwrap();
+
+        // The old pair stops working once exchanged, the new one stays on the same network
+        let refreshed = refresh(&app_db, &settings, &tokens.refresh_token)
+            .await
+            .unwrap();
+        assert!(refresh(&app_db, &settings, &tokens.refresh_token)
+            .await
+            .is_err());
+        assert!(resolve_session(&app_db, DEVNET, &tokens.token, "0xa")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(resolve_session(&app_db, DEVNET, &refreshed.token, "0xa")
+            .await
+            .unwrap()
+            .is_some());
+
+        logout(&app_db, &refreshed.token).await.unwrap();
+        assert!(resolve_session(&app_db, DEVNET, &refreshed.token, "0xa")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(refresh(&app_db, &settings, &refreshed.refresh_token)
+            .await
+            .is_err());
+
+        let token = sign_in(&app_db, &settings, "0xa").await;
+        let challenge =
+            create_challenge(&app_db, &settings, "0xa", CHALLENGE_PURPOSE_REVOKE_SESSIONS)
+                .await
+                .unwrap();
+        let revoked = revoke_sessions(&app_db, MAINNET, "0xa", &challenge.challenge)
+            .await
+            .unwrap();
+        assert_eq!(revoked.revoked, 0);
+
+        // A signed revocation is not accepted twice
+        let res = revoke_sessions(&app_db, DEVNET, "0xa", &challenge.challenge).await;
+        assert!(matches!(res, Err(Error::UnAuthorized {})));
+
+        let challenge =
+            create_challenge(&app_db, &settings, "0xa", CHALLENGE_PURPOSE_REVOKE_SESSIONS)
+                .await
+                .unwrap();
+        let revoked = revoke_sessions(&app_db, DEVNET, "0xa", &challenge.challenge)
+            .await
+            .unwrap();
+        assert_eq!(revoked.revoked, 1);
+        assert!(resolve_session(&app_db, DEVNET, &token, "0xa")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_expired_challenge_is_rejected() {
+        let app_db = MemoryRepository::default();
+        let settings = SessionSettings {
+            challenge_ttl_secs: 0,
+            ..SessionSettings::default()
+        };
+
+        let challenge = create_challenge(&app_db, &settings, "0xa", CHALLENGE_PURPOSE_LOGIN)
+            .await
+            .unwrap();
+        actix_rt::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        let res = login(
+            &app_db,
+            &settings,
+            DEVNET,
+            "0xa",
+            PUBLIC_KEY,
+            &challenge.challenge,
+        )
+        .await;
+        assert!(matches!(res, Err(Error::UnAuthorized {})));
+    }
+
+    #[actix_rt::test]
+    async fn test_expired_challenges_are_dropped_on_create() {
+        let app_db = MemoryRepository::default();
+        let settings = SessionSettings {
+            challenge_ttl_secs: 0,
+            ..SessionSettings::default()
+        };
+
+        for _ in 0..3 {
+            create_challenge(&app_db, &settings, "0xa", CHALLENGE_PURPOSE_LOGIN)
+                .await
+                .unwrap();
+            actix_rt::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert_eq!(app_db.login_challenges.lock().unwrap().len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_aptos_auth_accepts_session_tokens() {
+        let app_db = MemoryRepository::default();
+        let settings = SessionSettings::default();
+        let token = sign_in(&app_db, &settings, "0xa").await;
+        let refresh_token = {
+            let challenge = create_challenge(&app_db, &settings, "0xa", CHALLENGE_PURPOSE_LOGIN)
+                .await
+                .unwrap();
+            login(
+                &app_db,
+                &settings,
+                DEVNET,
+                "0xa",
+                PUBLIC_KEY,
+                &challenge.challenge,
+            )
+            .await
+            .unwrap()
+            .refresh_token
+        };
+
+        let resolver: Arc<dyn SessionResolver> = Arc::new(SessionLookup::new(app_db, DEVNET));
+        let app = init_service(
+            App::new().app_data(web::Data::from(resolver)).service(
+                web::scope("/api/v1/block-stacks")
+                    .wrap(AptosAuth::build())
+                    .route(
+                        "/{address}",
+                        web::get().to(|req: HttpRequest| async move {
+                            let session = req.extensions().get::<Session>().cloned().unwrap();
+                            HttpResponse::Ok().body(session.public_key)
+                        }),
+                    ),
+            ),
+        )
+        .await;
+
+        let req = |address: &str, token: &str| {
+            TestRequest::get()
+                .uri(&format!("/api/v1/block-stacks/{}", address))
+                .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+        };
+
+        let res = call_service(&app, req("0xa", &token).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, PUBLIC_KEY);
+
+        let res = call_service(
+            &app,
+            req("0xa", &token)
+                .insert_header((NETWORK_HEADER, DEVNET))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // Naming the address is no authentication
+        let unauthenticated = TestRequest::get()
+            .uri("/api/v1/block-stacks/0xa")
+            .insert_header(("aptos-auth", "0xa"));
+
+        // Issued to another address or network, or not a session token at all
+        for req in [
+            req("0xb", &token),
+            req("0xa", &token).insert_header((NETWORK_HEADER, MAINNET)),
+            req("0xa", &refresh_token),
+            unauthenticated,
+        ] {
+            let err = try_call_service(&app, req.to_request()).await.unwrap_err();
+            assert_eq!(
+                err.as_response_error().status_code(),
+                StatusCode::UNAUTHORIZED
+            );
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::metrics;
+use crate::service::domain::{account, bytecode, bytecode::standardize_address, Count};
+use crate::service::Error;
+
+use database::models::module_hub::core::usage::{
+    FunctionUsage, CALL_SOURCE_ENTRY_FUNCTION, CALL_SOURCE_SCRIPT,
+};
+use database::repository::account::AccountDetailRepository;
+use database::repository::usage::{FunctionUsageRepository, UsageFilter};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FunctionCall {
+    pub module_address: String,
+    pub module_name: String,
+    pub function_name: String,
+    pub source: &'static str,
+}
+
+impl FunctionCall {
+    fn new(address: &str, module_name: &str, function_name: &str, source: &'static str) -> Self {
+        FunctionCall {
+            module_address: standardize_address(address),
+            module_name: module_name.to_string(),
+            function_name: function_name.to_string(),
+            source,
+        }
+    }
+}
+
+/// An entry function payload calls one function, a script every function it has a handle of
+pub fn calls_of_transaction(
+    entry_function_id: Option<&str>,
+    script_bytecode: Option<&str>,
+) -> Vec<FunctionCall> {
+    if let Some(entry_function_id) = entry_function_id {
+        let mut split = entry_function_id.split("::");
+        return match (split.next(), split.next(), split.next()) {
+            (Some(address), Some(module_name), Some(function_name)) => vec![FunctionCall::new(
+                address,
+                module_name,
+                function_name,
+                CALL_SOURCE_ENTRY_FUNCTION,
+            )],
+            _ => vec![],
+        };
+    }
+
+    let script = script_bytecode
+        .and_then(|code| hex::decode(code.strip_prefix("0x").unwrap_or(code)).ok())
+        .and_then(|code| match bytecode::parse_handles(&code) {
+            Ok(handles) => Some(handles),
+            Err(e) => {
+                log::warn!("cannot read script bytecode: {}", e);
+                None
+            }
+        });
+
+    match script {
+        Some(handles) => handles
+            .function_handles
+            .into_iter()
+            .map(|handle| {
+                FunctionCall::new(
+                    &handle.module.address,
+                    &handle.module.name,
+                    &handle.name,
+                    CALL_SOURCE_SCRIPT,
+                )
+            })
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Rebuilds the rollup of every window and drops days no window covers anymore
+pub(crate) async fn refresh_function_usage(
+    app_db: &impl FunctionUsageRepository,
+    network: &str,
+    windows: &[i32],
+) -> Result<(), Error> {
+    let _timer = metrics::query_timer("usage::refresh_function_usage");
+
+    app_db
+        .refresh_function_usage(network, windows)
+        .await
+        .map_err(Error::DbError)
+}
+
+async fn usage_filter(
+    app_db: &impl AccountDetailRepository,
+    network: &str,
+    keyword: &Option<String>,
+    window: i32,
+    entry_only: bool,
+) -> Result<UsageFilter, Error> {
+    let alias_address = match keyword {
+        Some(keyword) => account::get_address_by_alias(app_db, network, keyword).await?,
+        None => None,
+    };
+
+    Ok(UsageFilter {
+        window_days: window,
+        keyword: keyword.clone(),
+        alias_address,
+        entry_only,
+    })
+}
+
+/// Most called first, functions without calls in the window are not ranked
+pub(crate) async fn get_popular_functions(
+    app_db: &(impl AccountDetailRepository + FunctionUsageRepository),
+    network: &str,
+    keyword: &Option<String>,
+    window: i32,
+    entry_only: bool,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<FunctionUsage>, Error> {
+    let _timer = metrics::query_timer("usage::get_popular_functions");
+
+    let filter = usage_filter(app_db, network, keyword, window, entry_only).await?;
+
+    app_db
+        .get_function_usages(network, &filter, offset, limit)
+        .await
+        .map_err(Error::DbError)
+}
+
+pub(crate) async fn get_popular_functions_count(
+    app_db: &(impl AccountDetailRepository + FunctionUsageRepository),
+    network: &str,
+    keyword: &Option<String>,
+    window: i32,
+    entry_only: bool,
+) -> Result<Count, Error> {
+    let _timer = metrics::query_timer("usage::get_popular_functions_count");
+
+    let filter = usage_filter(app_db, network, keyword, window, entry_only).await?;
+
+    app_db
+        .count_function_usages(network, &filter)
+        .await
+        .map(|count| Count { count })
+        .map_err(Error::DbError)
+}
+
+/// One row per window the function was called in
+pub(crate) async fn get_function_usage(
+    app_db: &impl FunctionUsageRepository,
+    network: &str,
+    address: &str,
+    module_name: &str,
+    function_name: &str,
+) -> Result<Vec<FunctionUsage>, Error> {
+    let _timer = metrics::query_timer("usage::get_function_usage");
+
+    app_db
+        .get_function_usage(
+            network,
+            &standardize_address(address),
+            module_name,
+            function_name,
+        )
+        .await
+        .map_err(Error::DbError)
+}
+
+pub(crate) fn validate_window(windows: &[i32], window: Option<i32>) -> Result<i32, Error> {
+    let window = window
+        .or_else(|| windows.first().copied())
+        .unwrap_or_default();
+
+    if !windows.contains(&window) {
+        return Err(Error::InvalidParams {
+            msg: format!("window must be one of {:?}", windows),
+        });
+    }
+
+    Ok(window)
+}
+
+#[cfg(test)]
+mod calls {
+    use crate::service::domain::bytecode::standardize_address;
+    use crate::service::domain::usage::{calls_of_transaction, validate_window};
+    use database::models::module_hub::core::usage::CALL_SOURCE_ENTRY_FUNCTION;
+
+    #[test]
+    fn test_calls_of_transaction() {
+        let calls = calls_of_transaction(Some("0x1::coin::transfer"), None);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].module_address, standardize_address("0x1"));
+        assert_eq!(calls[0].module_name, "coin");
+        assert_eq!(calls[0].function_name, "transfer");
+        assert_eq!(calls[0].source, CALL_SOURCE_ENTRY_FUNCTION);
+
+        assert!(calls_of_transaction(Some("0x1::coin"), None).is_empty());
+        assert!(calls_of_transaction(None, Some("0xzz")).is_empty());
+        assert!(calls_of_transaction(None, None).is_empty());
+
+        assert_eq!(validate_window(&[7, 30], None).unwrap(), 7);
+        assert_eq!(validate_window(&[7, 30], Some(30)).unwrap(), 30);
+        assert!(validate_window(&[7, 30], Some(1)).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::service::Error;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// `MoveScript` compiles every script in a directory named by a hyphenated uuid
+fn is_workspace_name(name: &str) -> bool {
+    name.len() == 36
+        && name.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// Removes script workspaces under `script_dir` untouched for `older_than`, left behind by
+/// compiles that never finished. Other entries are kept, returns the removed directories
+pub async fn purge_script_workspaces(
+    script_dir: &Path,
+    older_than: Duration,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut entries = match tokio::fs::read_dir(script_dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(Error::AnyError(e.into())),
+    };
+
+    let now = SystemTime::now();
+    let mut removed = vec![];
+
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| Error::AnyError(e.into()))?
+    {
+        let is_workspace = entry.file_name().to_str().is_some_and(is_workspace_name);
+        let metadata = entry
+            .metadata()
+            .await
+            .map_err(|e| Error::AnyError(e.into()))?;
+        if !is_workspace || !metadata.is_dir() {
+            continue;
+        }
+
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        if age < older_than {
+            continue;
+        }
+
+        tokio::fs::remove_dir_all(entry.path())
+            .await
+            .map_err(|e| Error::AnyError(e.into()))?;
+        removed.push(entry.path());
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod purge {
+    use crate::service::domain::workspace::{is_workspace_name, purge_script_workspaces};
+    use std::time::Duration;
+
+    #[test]
+    fn test_is_workspace_name() {
+        assert!(is_workspace_name("67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert!(!is_workspace_name("67e55044-10b1-426f-9247-bb680e5fe0c"));
+        assert!(!is_workspace_name("67e55044x10b1-426f-9247-bb680e5fe0c8"));
+        assert!(!is_workspace_name("block-stack-cache"));
+    }
+
+    #[actix_rt::test]
+    async fn test_only_old_workspaces_are_removed() {
+        let script_dir =
+            std::env::temp_dir().join(format!("moveblock-purge-{}", std::process::id()));
+        let workspace = script_dir.join("67e55044-10b1-426f-9247-bb680e5fe0c8");
+        let other = script_dir.join("keep");
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+
+        let removed = purge_script_workspaces(&script_dir, Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert!(removed.is_empty());
+
+        let removed = purge_script_workspaces(&script_dir, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(removed, vec![workspace.clone()]);
+        assert!(!workspace.exists());
+        assert!(other.exists());
+
+        std::fs::remove_dir_all(&script_dir).unwrap();
+        assert!(purge_script_workspaces(&script_dir, Duration::ZERO)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::service::Error;
+
+use actix_web::http::header::{ContentType, EntityTag, Header, IfNoneMatch, ETAG};
+use actix_web::{HttpRequest, HttpResponse};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Weak, the same value serialized by another build may differ byte for byte
+pub fn etag_of(body: &[u8]) -> EntityTag {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+
+    EntityTag::new_weak(format!("{:016x}", hasher.finish()))
+}
+
+/// Json of `value` tagged with an ETag, 304 without a body when `If-None-Match` already names it
+pub fn json_with_etag<T: Serialize>(req: &HttpRequest, value: &T) -> Result<HttpResponse, Error> {
+    let body = serde_json::to_vec(value).map_err(|e| Error::AnyError(e.into()))?;
+    let etag = etag_of(&body);
+
+    let is_fresh = match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        Err(_) => false,
+    };
+
+    if is_fresh {
+        return Ok(HttpResponse::NotModified()
+            .insert_header((ETAG, etag.to_string()))
+            .finish());
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header((ETAG, etag.to_string()))
+        .content_type(ContentType::json())
+        .body(body))
+}
+
+#[cfg(test)]
+mod conditional {
+    use crate::service::etag::json_with_etag;
+    use actix_web::http::{header, StatusCode};
+    use actix_web::test::TestRequest;
+    use serde_json::json;
+
+    #[test]
+    fn test_not_modified_when_etag_matches() {
+        let value = json!({ "count": 1 });
+
+        let res = json_with_etag(&TestRequest::default().to_http_request(), &value).unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_http_request();
+        let res = json_with_etag(&req, &value).unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG), Some(&etag));
+
+        let res = json_with_etag(&req, &json!({ "count": 2 })).unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
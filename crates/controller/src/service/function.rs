@@ -1,145 +1,486 @@
 use crate::config::ApiContext;
 use crate::service::{
-    domain::{function, Response},
+    domain::{
+        account,
+        cursor::{self, FunctionCursor},
+        dependency, function, move_struct, usage, Response,
+    },
+    etag,
+    network::Network,
     Error,
 };
 
 use actix_web::{
     get,
     web::{self, Data, Query},
-    Responder,
+    HttpRequest, Responder,
 };
 
 use serde::Deserialize;
+use utoipa::IntoParams;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FunctionQueryParams {
     pub offset: Option<i64>,
     pub limit: Option<i64>,
     pub keyword: Option<String>,
+    /// Opaque `next_cursor` of a previous page, takes precedence over offset
+    pub cursor: Option<String>,
+    /// `name` by default, `popularity` ranks by calls within `window`
+    pub sort: Option<String>,
+    /// Days of usage ranked by, one of the configured windows
+    pub window: Option<i32>,
 }
 
+pub const SORT_BY_NAME: &str = "name";
+pub const SORT_BY_POPULARITY: &str = "popularity";
+
+/// Popularity pages are ranked by a rollup, so they are paginated by offset only
+fn is_popularity_sort(params: &FunctionQueryParams) -> Result<bool, Error> {
+    match params.sort.as_deref() {
+        None | Some(SORT_BY_NAME) => Ok(false),
+        Some(SORT_BY_POPULARITY) if params.cursor.is_some() => Err(Error::InvalidParams {
+            msg: "cursor is not supported with sort=popularity".to_string(),
+        }),
+        Some(SORT_BY_POPULARITY) => Ok(true),
+        Some(sort) => Err(Error::InvalidParams {
+            msg: format!("unknown sort {}", sort),
+        }),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/functions",
+    tag = "functions",
+    params(
+        FunctionQueryParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = FunctionsResponse),
+        (status = 304, description = "Unchanged since the ETag in If-None-Match"),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+    )
+)]
 #[get("")]
 async fn functions_by_params(
+    req: HttpRequest,
     context: Data<ApiContext>,
+    network: Network,
     params: Query<FunctionQueryParams>,
 ) -> Result<impl Responder, Error> {
-    let offset = params.offset.unwrap_or(0);
+    if is_popularity_sort(&params)? {
+        let window =
+            usage::validate_window(&context.settings.function_usage.windows, params.window)?;
+        let offset = params.offset.unwrap_or(0);
+        let limit = params.limit.unwrap_or(10);
+
+        let usages = usage::get_popular_functions(
+            &context.app_db,
+            &network.name,
+            &params.keyword,
+            window,
+            false,
+            offset,
+            limit,
+        )
+        .await?;
+        let count = usage::get_popular_functions_count(
+            &context.app_db,
+            &network.name,
+            &params.keyword,
+            window,
+            false,
+        )
+        .await?;
+
+        let functions = function::get_popular_functions_with_account_detail(
+            &network.function_index_db,
+            &context.app_db,
+            &network.name,
+            &usages,
+        )
+        .await?;
+
+        return etag::json_with_etag(
+            &req,
+            &Response::new(count.count, functions, Some(offset), Some(limit)),
+        );
+    }
+
+    let cursor: Option<FunctionCursor> = cursor::decode_optional(&params.cursor)?;
+    let offset = match cursor {
+        Some(_) => 0,
+        None => params.offset.unwrap_or(0),
+    };
     let limit = params.limit.unwrap_or(10);
 
     let (total_len, functions, offset, limit) = match &params.keyword {
         Some(keyword) => {
             let functions = function::get_functions_by_keyword_with_account_detail(
-                &context.function_index_db,
+                &network.function_index_db,
                 &context.app_db,
+                &network.name,
                 keyword,
+                &cursor,
                 offset,
                 limit,
             )
-            .await
-            .unwrap_or_default();
+            .await?;
 
-            let count = function::get_function_count_filtered_by_keyword(
-                &context.function_index_db,
-                &context.app_db,
-                keyword,
-            )
-            .await;
+            // A keyword naming an alias shares its scope, so alias changes drop the count
+            let key = format!("count:functions:{}", keyword);
+            let count = context
+                .cache
+                .get_or_load_scoped(&network.name, &account::cache_scope(keyword), &key, || {
+                    function::get_function_count_filtered_by_keyword(
+                        &network.function_index_db,
+                        &context.app_db,
+                        &network.name,
+                        keyword,
+                    )
+                })
+                .await?;
             (count.count, functions, Some(offset), Some(limit))
         }
         None => {
             let functions = function::get_paginated_functions_with_account_detail(
-                &context.function_index_db,
+                &network.function_index_db,
                 &context.app_db,
+                &network.name,
+                &cursor,
                 offset,
                 limit,
             )
-            .await
-            .unwrap_or_default();
+            .await?;
 
-            let count = function::get_functions_count(&context.function_index_db).await;
+            let count = context
+                .cache
+                .get_or_load(&network.name, "count:functions", || {
+                    function::get_functions_count(&network.function_index_db)
+                })
+                .await?;
 
             (count.count, functions, Some(offset), Some(limit))
         }
     };
 
+    let next_cursor = cursor::next_cursor(&functions, limit.unwrap_or_default(), |f| {
+        FunctionCursor::from(&f.module_function)
+    });
+    let offset = offset.filter(|_| cursor.is_none());
+
+    etag::json_with_etag(
+        &req,
+        &Response::new(total_len, functions, offset, limit).with_next_cursor(next_cursor),
+    )
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StructTypeQueryParams {
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+    /// address::module_name::StructName
+    pub struct_type: String,
+}
+
+/// Functions taking or returning the given struct type
+#[utoipa::path(
+    context_path = "/api/v1/functions",
+    tag = "functions",
+    params(
+        StructTypeQueryParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = StructFunctionsResponse),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+    )
+)]
+#[get("/by-struct")]
+async fn functions_by_struct_type(
+    network: Network,
+    params: Query<StructTypeQueryParams>,
+) -> Result<impl Responder, Error> {
+    let offset = params.offset.unwrap_or(0);
+    let limit = params.limit.unwrap_or(10);
+
+    let functions = move_struct::get_functions_by_struct_type(
+        &network.function_index_db,
+        &params.struct_type,
+        offset,
+        limit,
+    )
+    .await?;
+
+    let count = move_struct::get_functions_count_by_struct_type(
+        &network.function_index_db,
+        &params.struct_type,
+    )
+    .await?;
+
     Ok(web::Json(Response::new(
-        total_len, functions, offset, limit,
+        count.count,
+        functions,
+        Some(offset),
+        Some(limit),
     )))
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/functions",
+    tag = "functions",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("module_name" = String, Path, description = "Module name"),
+        ("function_name" = String, Path, description = "Function name"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ModuleFunctionWithDetail),
+        (status = 304, description = "Unchanged since the ETag in If-None-Match"),
+        (status = 404, description = "Function not found", body = ErrorEnvelope),
+    )
+)]
 #[get("/{address}/{module_name}/{function_name}")]
 async fn function_detail(
+    req: HttpRequest,
     context: Data<ApiContext>,
+    network: Network,
     params: web::Path<(String, String, String)>,
 ) -> Result<impl Responder, Error> {
     let (address, module_name, function_name) = params.into_inner();
-    println!("{} {} {}", address, module_name, function_name);
-    let function_with_detail = function::get_function_with_detail(
-        &context.function_index_db,
+    let key = format!("function:{}:{}", module_name, function_name);
+
+    let function_with_detail = context
+        .cache
+        .get_or_load_scoped(&network.name, &account::cache_scope(&address), &key, || {
+            function::get_function_with_detail(
+                &network.function_index_db,
+                &context.app_db,
+                &network.name,
+                &address,
+                &module_name,
+                &function_name,
+            )
+        })
+        .await?
+        .ok_or_else(|| Error::NotFound {
+            msg: "function not found".to_string(),
+        })?;
+
+    etag::json_with_etag(&req, &function_with_detail)
+}
+
+/// Modules whose bytecode calls the function, the blast radius of changing it
+#[utoipa::path(
+    context_path = "/api/v1/functions",
+    tag = "functions",
+    params(
+        ("address" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+        ("function_name" = String, Path, description = "Function name"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ModuleNodesResponse),
+    )
+)]
+#[get("/{address}/{module_name}/{function_name}/dependents")]
+async fn function_dependents(
+    context: Data<ApiContext>,
+    network: Network,
+    params: web::Path<(String, String, String)>,
+) -> Result<impl Responder, Error> {
+    let (address, module_name, function_name) = params.into_inner();
+    let address = dependency::resolve_address(&context.app_db, &network.name, &address).await?;
+
+    let modules = dependency::get_function_dependents(
         &context.app_db,
+        &network.name,
         &address,
         &module_name,
         &function_name,
     )
-    .await;
+    .await?;
 
-    Ok(web::Json(function_with_detail))
+    Ok(web::Json(Response::new(
+        modules.len() as i64,
+        modules,
+        None,
+        None,
+    )))
 }
 
+/// Call counts per configured window, only windows the function was called in
+#[utoipa::path(
+    context_path = "/api/v1/functions",
+    tag = "functions",
+    params(
+        ("address" = String, Path, description = "Account address or alias"),
+        ("module_name" = String, Path, description = "Module name"),
+        ("function_name" = String, Path, description = "Function name"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = FunctionUsagesResponse),
+    )
+)]
+#[get("/{address}/{module_name}/{function_name}/usage")]
+async fn function_usage(
+    context: Data<ApiContext>,
+    network: Network,
+    params: web::Path<(String, String, String)>,
+) -> Result<impl Responder, Error> {
+    let (address, module_name, function_name) = params.into_inner();
+    let address = dependency::resolve_address(&context.app_db, &network.name, &address).await?;
+
+    let usages = usage::get_function_usage(
+        &context.app_db,
+        &network.name,
+        &address,
+        &module_name,
+        &function_name,
+    )
+    .await?;
+
+    Ok(web::Json(Response::new(
+        usages.len() as i64,
+        usages,
+        None,
+        None,
+    )))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/functions",
+    tag = "functions",
+    params(
+        FunctionQueryParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = EntryFunctionsResponse),
+        (status = 304, description = "Unchanged since the ETag in If-None-Match"),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+    )
+)]
 #[get("/entry-functions")]
 async fn entry_functions_by_params(
+    req: HttpRequest,
     context: Data<ApiContext>,
+    network: Network,
     params: Query<FunctionQueryParams>,
 ) -> Result<impl Responder, Error> {
-    let offset = params.offset.unwrap_or(0);
+    if is_popularity_sort(&params)? {
+        let window =
+            usage::validate_window(&context.settings.function_usage.windows, params.window)?;
+        let offset = params.offset.unwrap_or(0);
+        let limit = params.limit.unwrap_or(10);
+
+        let usages = usage::get_popular_functions(
+            &context.app_db,
+            &network.name,
+            &params.keyword,
+            window,
+            true,
+            offset,
+            limit,
+        )
+        .await?;
+        let count = usage::get_popular_functions_count(
+            &context.app_db,
+            &network.name,
+            &params.keyword,
+            window,
+            true,
+        )
+        .await?;
+
+        let functions = function::get_popular_functions_with_function_detail(
+            &network.function_index_db,
+            &context.app_db,
+            &network.name,
+            &usages,
+        )
+        .await?;
+
+        return etag::json_with_etag(
+            &req,
+            &Response::new(count.count, functions, Some(offset), Some(limit)),
+        );
+    }
+
+    let cursor: Option<FunctionCursor> = cursor::decode_optional(&params.cursor)?;
+    let offset = match cursor {
+        Some(_) => 0,
+        None => params.offset.unwrap_or(0),
+    };
     let limit = params.limit.unwrap_or(10);
 
     let (total_len, functions, offset, limit) = match &params.keyword {
         Some(keyword) => {
             let functions = function::get_functions_by_keyword_with_function_detail(
-                &context.function_index_db,
+                &network.function_index_db,
                 &context.app_db,
+                &network.name,
                 keyword,
+                &cursor,
                 offset,
                 limit,
             )
-            .await
-            .unwrap_or_default();
+            .await?;
 
-            let count = function::get_entry_function_count_filtered_by_keyword(
-                &context.function_index_db,
-                &context.app_db,
-                keyword,
-            )
-            .await;
+            let key = format!("count:entry_functions:{}", keyword);
+            let count = context
+                .cache
+                .get_or_load_scoped(&network.name, &account::cache_scope(keyword), &key, || {
+                    function::get_entry_function_count_filtered_by_keyword(
+                        &network.function_index_db,
+                        &context.app_db,
+                        &network.name,
+                        keyword,
+                    )
+                })
+                .await?;
 
             (count.count, functions, Some(offset), Some(limit))
         }
         None => {
             let functions = function::get_paginated_functions_by_keyword_with_function_detail(
-                &context.function_index_db,
+                &network.function_index_db,
                 &context.app_db,
+                &network.name,
+                &cursor,
                 offset,
                 limit,
             )
-            .await
-            .unwrap_or_default();
+            .await?;
 
-            let count = function::get_entry_functions_count(&context.function_index_db).await;
+            let count = context
+                .cache
+                .get_or_load(&network.name, "count:entry_functions", || {
+                    function::get_entry_functions_count(&network.function_index_db)
+                })
+                .await?;
 
             (count.count, functions, Some(offset), Some(limit))
         }
     };
 
-    Ok(web::Json(Response::new(
-        total_len, functions, offset, limit,
-    )))
+    let next_cursor = cursor::next_cursor(&functions, limit.unwrap_or_default(), |f| {
+        FunctionCursor::from(&f.module_function)
+    });
+    let offset = offset.filter(|_| cursor.is_none());
+
+    etag::json_with_etag(
+        &req,
+        &Response::new(total_len, functions, offset, limit).with_next_cursor(next_cursor),
+    )
 }
 
 pub fn routers(scope: actix_web::Scope) -> actix_web::Scope {
     scope
         .service(functions_by_params)
         .service(entry_functions_by_params)
+        .service(functions_by_struct_type)
         .service(function_detail)
+        .service(function_dependents)
+        .service(function_usage)
 }
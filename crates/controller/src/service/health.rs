@@ -0,0 +1,30 @@
+use crate::config::ApiContext;
+use crate::service::domain::health::{self, STATUS_UP};
+
+use actix_http::StatusCode;
+use actix_web::{get, web::Data, HttpResponse, Responder};
+use serde_json::json;
+
+/// The process is serving requests, dependencies are not checked
+#[get("/live")]
+async fn live() -> impl Responder {
+    HttpResponse::Ok().json(json!({ "status": STATUS_UP }))
+}
+
+/// 503 while a critical dependency is down, degraded dependencies are reported with 200
+#[get("/ready")]
+async fn ready(context: Data<ApiContext>) -> impl Responder {
+    let report = health::check_dependencies(&context).await;
+
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    HttpResponse::build(status).json(report)
+}
+
+pub fn routers(scope: actix_web::Scope) -> actix_web::Scope {
+    scope.service(live).service(ready)
+}
@@ -0,0 +1,48 @@
+use crate::config::ApiContext;
+use crate::metrics;
+
+use actix_web::{get, web::Data, HttpResponse, Responder};
+
+/// Pool gauges are sampled on every scrape, pools of networks other than the default are
+/// prefixed with the network name
+#[get("")]
+async fn metrics_text(context: Data<ApiContext>) -> impl Responder {
+    let settings = &context.settings;
+    let database = &settings.database;
+
+    metrics::observe_pool("app_db", &context.app_db, database.app.max_connections);
+
+    for network in &context.networks {
+        let (prefix, full_indexer, function_indexer) = match settings.networks.get(&network.name) {
+            Some(chain) => (
+                format!("{}.", network.name),
+                &chain.full_indexer,
+                &chain.function_indexer,
+            ),
+            None => (
+                String::new(),
+                &database.full_indexer,
+                database.function_indexer.as_ref().unwrap_or(&database.app),
+            ),
+        };
+
+        metrics::observe_pool(
+            &format!("{}aptos_full_index_db", prefix),
+            &network.aptos_full_index_db,
+            full_indexer.max_connections,
+        );
+        metrics::observe_pool(
+            &format!("{}function_index_db", prefix),
+            &network.function_index_db,
+            function_indexer.max_connections,
+        );
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics::encode())
+}
+
+pub fn routers(scope: actix_web::Scope) -> actix_web::Scope {
+    scope.service(metrics_text)
+}
@@ -0,0 +1,109 @@
+use crate::config::{ApiContext, NetworkContext};
+use crate::service::Error;
+
+use actix_web::{dev::Payload, web::Data, FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+use std::ops::Deref;
+
+/// Names the network a request is served from, the default network when absent
+pub const NETWORK_HEADER: &str = "x-aptos-network";
+
+/// The network `NETWORK_HEADER` selects, unknown names are rejected before the handler runs
+pub struct Network(NetworkContext);
+
+impl Deref for Network {
+    type Target = NetworkContext;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+fn select_network(req: &HttpRequest) -> Result<Network, Error> {
+    let context = req
+        .app_data::<Data<ApiContext>>()
+        .ok_or_else(|| Error::AnyError(anyhow::anyhow!("api context is not configured")))?;
+
+    let name = match req.headers().get(NETWORK_HEADER) {
+        Some(name) => name.to_str().map_err(|_| Error::InvalidParams {
+            msg: format!("{} is not valid text", NETWORK_HEADER),
+        })?,
+        None => return Ok(Network(context.default_network().clone())),
+    };
+
+    context
+        .network(name)
+        .cloned()
+        .map(Network)
+        .ok_or_else(|| Error::InvalidParams {
+            msg: format!("unknown network {}", name),
+        })
+}
+
+impl FromRequest for Network {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(select_network(req))
+    }
+}
+
+#[cfg(test)]
+mod selection {
+    use crate::cache::Cache;
+    use crate::config::{ApiContext, ExecutorSettings, NetworkContext, Settings};
+    use crate::service::domain::auth_key::AuthKeySource;
+    use crate::service::domain::profile::DomainVerifier;
+    use crate::service::network::{Network, NETWORK_HEADER};
+    use crate::service::Error;
+    use actix_web::test::TestRequest;
+    use actix_web::{web::Data, FromRequest};
+    use database::db::new_postgres_pool;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn network(name: &str) -> NetworkContext {
+        let pool =
+            new_postgres_pool("postgres://localhost/test", 1, Duration::from_secs(1)).unwrap();
+
+        NetworkContext {
+            name: name.to_string(),
+            aptos_full_index_db: pool.clone(),
+            function_index_db: pool,
+            node_url: format!("https://fullnode.{}.aptoslabs.com/v1", name),
+            chain_id: 1,
+            auth_keys: AuthKeySource::Fixed(HashMap::new()),
+            executor: ExecutorSettings::default(),
+        }
+    }
+
+    fn context() -> Data<ApiContext> {
+        Data::new(ApiContext::new(
+            network("devnet").aptos_full_index_db,
+            network("devnet"),
+            vec![network("mainnet")],
+            DomainVerifier::new("", "").unwrap(),
+            Cache::disabled(),
+            Settings::default(),
+        ))
+    }
+
+    async fn select(header: Option<&str>) -> Result<Network, Error> {
+        let mut req = TestRequest::default().app_data(context());
+        if let Some(header) = header {
+            req = req.insert_header((NETWORK_HEADER, header));
+        }
+
+        Network::extract(&req.to_http_request()).await
+    }
+
+    #[actix_rt::test]
+    async fn test_selects_network_by_header() {
+        assert_eq!(select(None).await.unwrap().name, "devnet");
+        assert_eq!(select(Some("mainnet")).await.unwrap().name, "mainnet");
+
+        let res = select(Some("testnet")).await;
+        assert!(matches!(res, Err(Error::InvalidParams { .. })));
+    }
+}
@@ -0,0 +1,325 @@
+use crate::config::settings::NETWORKS;
+use crate::service::network::NETWORK_HEADER;
+use crate::service::{account, block_stack, domain, function, session, ErrorBody, ErrorEnvelope};
+
+use database::models::block_stack::stack::BlockStack;
+use database::models::module_hub::composite::function::{
+    ModuleFunctionWithAccountDetail, ModuleFunctionWithDetail,
+    ModuleFunctionWithOwnAndAccountDetail,
+};
+use database::models::module_hub::composite::module::{
+    MoveModuleWithDetail, MoveModuleWithFunctionsAccountAndDetails,
+};
+use database::models::module_hub::composite::move_struct::MoveStructWithDetail;
+use database::models::module_hub::core::dependency::ModuleNode;
+use database::models::module_hub::core::function::{ExposedFunction, ModuleFunction};
+use database::models::module_hub::core::module::{ModuleVersion, MoveModule};
+use database::models::module_hub::core::move_struct::{MoveStruct, MoveStructField};
+use database::models::module_hub::core::usage::FunctionUsage;
+use database::models::module_hub::detail::account::AccountDetail;
+use database::models::module_hub::detail::function::ModuleFunctionDetail;
+use database::models::module_hub::detail::module::ModuleDetail;
+use database::models::module_hub::detail::move_struct::ModuleStructDetail;
+use database::models::module_hub::detail::resource_account::ResourceAccount;
+
+use actix_web::{get, HttpResponse, Responder};
+use utoipa::openapi::path::{ParameterBuilder, ParameterIn};
+use utoipa::openapi::{ObjectBuilder, Required, SchemaType};
+use utoipa::{Modify, OpenApi};
+
+/// Covers the versioned api, health and metrics are operational endpoints left out
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "MoveBlock API", version = "1"),
+    paths(
+        account::check_module_auth,
+        account::modules_by_address_or_alias,
+        account::functions_by_account_and_module_name,
+        account::structs_by_account_and_module_name,
+        account::struct_by_account_and_module_name,
+        account::module_versions,
+        account::module_abi_diff,
+        account::module_dependencies,
+        account::module_dependents,
+        account::module_friends,
+        account::account_alias,
+        account::account_profile,
+        account::account_domain_verification,
+        account::resource_accounts,
+        account::register_resource_account,
+        account::module_detail,
+        account::function_detail,
+        account::struct_detail,
+        block_stack::block_stacks,
+        block_stack::create_block_stack,
+        block_stack::get_block_stack,
+        block_stack::get_script_bytecode,
+        block_stack::update_block_stack,
+        block_stack::delete_block_stack,
+        block_stack::execute_script,
+        function::functions_by_params,
+        function::functions_by_struct_type,
+        function::function_detail,
+        function::function_dependents,
+        function::function_usage,
+        function::entry_functions_by_params,
+        session::create_challenge,
+        session::login,
+        session::refresh,
+        session::logout,
+        session::revoke_sessions,
+    ),
+    components(schemas(
+        domain::Pagination,
+        domain::SignedBody,
+        domain::SignedData,
+        domain::ModulesResponse,
+        domain::ModuleResponse,
+        domain::StructsResponse,
+        domain::ModuleVersionsResponse,
+        domain::ModuleNodesResponse,
+        domain::ResourceAccountsResponse,
+        domain::BlockStacksResponse,
+        domain::FunctionsResponse,
+        domain::EntryFunctionsResponse,
+        domain::StructFunctionsResponse,
+        domain::FunctionUsagesResponse,
+        domain::abi::AbiDiff,
+        domain::abi::FunctionChange,
+        domain::abi::StructChange,
+        domain::session::Challenge,
+        domain::session::SessionTokens,
+        domain::session::RevokedSessions,
+        account::AccountAliasBody,
+        account::AccountProfileBody,
+        account::ResourceAccountBody,
+        account::ModuleDetailBody,
+        account::FunctionDetailBody,
+        account::StructDetailBody,
+        block_stack::Block,
+        block_stack::BlockStackBody,
+        session::ChallengeBody,
+        session::ChallengeAnswer,
+        session::RefreshBody,
+        ErrorEnvelope,
+        ErrorBody,
+        BlockStack,
+        MoveModule,
+        ModuleVersion,
+        MoveModuleWithDetail,
+        MoveModuleWithFunctionsAccountAndDetails,
+        ModuleDetail,
+        AccountDetail,
+        ResourceAccount,
+        ModuleFunction,
+        ExposedFunction,
+        ModuleFunctionDetail,
+        ModuleFunctionWithDetail,
+        ModuleFunctionWithAccountDetail,
+        ModuleFunctionWithOwnAndAccountDetail,
+        MoveStruct,
+        MoveStructField,
+        ModuleStructDetail,
+        MoveStructWithDetail,
+        ModuleNode,
+        FunctionUsage,
+    )),
+    tags(
+        (name = "accounts", description = "Modules, structs and profiles of an account"),
+        (name = "block-stacks", description = "Scripts composed of entry function calls"),
+        (name = "functions", description = "Function search and usage"),
+        (name = "sessions", description = "Tokens accepted by block stack routes after one signed login"),
+    ),
+    modifiers(&NetworkHeader)
+)]
+pub struct ApiDoc;
+
+/// Every operation is served from the network the header names
+struct NetworkHeader;
+
+impl Modify for NetworkHeader {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let header = ParameterBuilder::new()
+            .name(NETWORK_HEADER)
+            .parameter_in(ParameterIn::Header)
+            .required(Required::False)
+            .description(Some(
+                "Network to serve the request from, the default network when absent",
+            ))
+            .schema(Some(
+                ObjectBuilder::new()
+                    .schema_type(SchemaType::String)
+                    .enum_values(Some(NETWORKS)),
+            ))
+            .build();
+
+        for operation in openapi
+            .paths
+            .paths
+            .values_mut()
+            .flat_map(|item| item.operations.values_mut())
+        {
+            operation
+                .parameters
+                .get_or_insert_with(Vec::new)
+                .push(header.clone());
+        }
+    }
+}
+
+#[get("")]
+async fn openapi_json() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+pub fn routers(scope: actix_web::Scope) -> actix_web::Scope {
+    scope.service(openapi_json)
+}
+
+#[cfg(test)]
+mod drift {
+    use crate::config;
+    use crate::config::settings::SessionSettings;
+    use crate::service::domain::session::{self, SessionLookup};
+    use crate::service::network::NETWORK_HEADER;
+    use crate::service::openapi::ApiDoc;
+    use crate::service::ErrorEnvelope;
+    use actix_web::http::header;
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::{http::Method, web, App};
+    use database::models::session::CHALLENGE_PURPOSE_LOGIN;
+    use database::repository::memory::MemoryRepository;
+    use middleware::SessionResolver;
+    use std::collections::BTreeSet;
+    use std::sync::Arc;
+    use utoipa::openapi::PathItemType;
+    use utoipa::OpenApi;
+
+    /// Handler sources with the scope `config_service` mounts them under
+    const HANDLERS: [(&str, &str); 4] = [
+        ("/api/v1/accounts", include_str!("account.rs")),
+        ("/api/v1/block-stacks", include_str!("block_stack.rs")),
+        ("/api/v1/functions", include_str!("function.rs")),
+        ("/api/v1/sessions", include_str!("session.rs")),
+    ];
+
+    fn method_of(path_item_type: &PathItemType) -> Method {
+        match path_item_type {
+            PathItemType::Get => Method::GET,
+            PathItemType::Post => Method::POST,
+            PathItemType::Put => Method::PUT,
+            PathItemType::Delete => Method::DELETE,
+            PathItemType::Patch => Method::PATCH,
+            _ => Method::OPTIONS,
+        }
+    }
+
+    fn documented_routes() -> BTreeSet<(String, String)> {
+        ApiDoc::openapi()
+            .paths
+            .paths
+            .iter()
+            .flat_map(|(path, item)| {
+                item.operations
+                    .keys()
+                    .map(|operation| (method_of(operation).to_string(), path.clone()))
+            })
+            .collect()
+    }
+
+    fn declared_routes() -> BTreeSet<(String, String)> {
+        HANDLERS
+            .iter()
+            .flat_map(|(scope, source)| {
+                source.lines().filter_map(move |line| {
+                    let (method, rest) = line.trim().strip_prefix("#[")?.split_once("(\"")?;
+                    let path = rest.strip_suffix("\")]")?;
+                    let method = match method {
+                        "get" | "post" | "patch" | "put" | "delete" => method.to_uppercase(),
+                        _ => return None,
+                    };
+
+                    Some((method, format!("{}{}", scope, path)))
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_every_operation_takes_network_header() {
+        let openapi = ApiDoc::openapi();
+        let operations = openapi
+            .paths
+            .paths
+            .values()
+            .flat_map(|item| item.operations.values());
+
+        for operation in operations {
+            let parameters = operation.parameters.as_deref().unwrap_or_default();
+            assert!(parameters.iter().any(|p| p.name == NETWORK_HEADER));
+        }
+    }
+
+    #[test]
+    fn test_every_route_is_documented() {
+        assert_eq!(documented_routes(), declared_routes());
+    }
+
+    /// Unknown routes fall through to the json not found service, anything else reached a handler
+    #[actix_rt::test]
+    async fn test_every_documented_route_is_served() {
+        // Passes `AptosAuth` for the placeholder address
+        let app_db = MemoryRepository::default();
+        let settings = SessionSettings::default();
+        let network = config::Settings::default().network.name;
+        let challenge = session::create_challenge(&app_db, &settings, "1", CHALLENGE_PURPOSE_LOGIN)
+            .await
+            .unwrap();
+        let tokens = session::login(
+            &app_db,
+            &settings,
+            &network,
+            "1",
+            "0xkey",
+            &challenge.challenge,
+        )
+        .await
+        .unwrap();
+        let resolver: Arc<dyn SessionResolver> = Arc::new(SessionLookup::new(app_db, &network));
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::from(resolver))
+                .configure(|cfg| config::config_service(cfg, &config::CorsSettings::default())),
+        )
+        .await;
+
+        for (method, path) in documented_routes() {
+            let uri = path
+                .split('/')
+                .map(|segment| match segment.starts_with('{') {
+                    true => "1",
+                    false => segment,
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            let req = TestRequest::default()
+                .method(Method::from_bytes(method.as_bytes()).unwrap())
+                .uri(&uri)
+                .insert_header((header::AUTHORIZATION, format!("Bearer {}", tokens.token)))
+                .to_request();
+
+            let res = call_service(&app, req).await;
+            let body = read_body(res).await;
+            let unmatched = serde_json::from_slice::<ErrorEnvelope>(&body)
+                .map(|envelope| envelope.error.message == "route not found")
+                .unwrap_or(false);
+
+            assert!(
+                !unmatched,
+                "{} {} is documented but not served",
+                method, path
+            );
+        }
+    }
+}
@@ -0,0 +1,548 @@
+use crate::config::settings::{BudgetSettings, RateLimitSettings};
+use crate::service::domain::bytecode::standardize_address;
+use crate::service::Error;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use actix_web::{HttpMessage, HttpRequest, ResponseError};
+use futures::future::LocalBoxFuture;
+use middleware::Session;
+use std::collections::{BTreeMap, HashMap};
+use std::future::{ready, Ready};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The least recently used bucket is dropped for each new one beyond this many
+const MAX_BUCKETS: usize = 100_000;
+
+/// The budget a request draws from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    Read,
+    SignedWrite,
+    Compile,
+    Execution,
+}
+
+/// Health checks and metrics are left alone
+pub fn classify(method: &Method, path: &str) -> Option<RouteClass> {
+    let segments = path
+        .strip_prefix("/api/v1/")?
+        .split('/')
+        .collect::<Vec<_>>();
+
+    Some(match (segments[0], method, &segments[1..]) {
+        ("block-stacks", &Method::POST, [_]) => RouteClass::Compile,
+        ("block-stacks", &Method::PATCH, [_, "stacks", _]) => RouteClass::Compile,
+        ("block-stacks", &Method::GET, [_, "execute", "stacks", _]) => RouteClass::Execution,
+        // Unsigned, but every challenge is stored until it expires
+        ("sessions", &Method::POST, ["challenge"]) => RouteClass::SignedWrite,
+        (_, &Method::GET, _) => RouteClass::Read,
+        _ => RouteClass::SignedWrite,
+    })
+}
+
+/// Left in the request extensions by `RateLimit`, the budget of the signer is only known once
+/// its signature or session was verified. The ip is charged together with the signer, or after
+/// the response when no signer was charged
+#[derive(Clone)]
+struct SignerBudget {
+    limiter: RateLimiter,
+    class: RouteClass,
+    ip_keys: Vec<String>,
+    /// Set once `charge_signer` charged or refused the request
+    settled: Arc<AtomicBool>,
+}
+
+fn too_many_requests(wait: Duration) -> Error {
+    Error::TooManyRequests {
+        retry_after_secs: wait.as_secs_f64().ceil().max(1.0) as u64,
+    }
+}
+
+/// Takes a token from the bucket of the verified `signer`, the path address is not trusted since
+/// anyone may send it. A no-op when rate limiting is off or the route is not limited
+pub fn charge_signer(req: &HttpRequest, signer: &str) -> Result<(), Error> {
+    let budget = match req.extensions().get::<SignerBudget>() {
+        Some(budget) => budget.clone(),
+        None => return Ok(()),
+    };
+
+    if budget.settled.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let mut keys = budget.ip_keys.clone();
+    keys.push(format!("address:{}", standardize_address(signer)));
+    budget
+        .limiter
+        .check(budget.class, &keys, Instant::now())
+        .map_err(too_many_requests)
+}
+
+/// Charges the signer of the session `AptosAuth` accepted, wrap it inside `AptosAuth`
+pub fn charge_session<S, B>(
+    req: ServiceRequest,
+    service: &S,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<B>, actix_web::Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+{
+    let session = req.extensions().get::<Session>().cloned();
+    if let Some(session) = session {
+        if let Err(e) = charge_signer(req.request(), &session.address) {
+            return Box::pin(async move { Err(e.into()) });
+        }
+    }
+
+    let fut = service.call(req);
+    Box::pin(fut)
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(budget: &BudgetSettings, now: Instant) -> Self {
+        Bucket {
+            tokens: budget.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, budget: &BudgetSettings, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * budget.per_minute as f64 / 60.0).min(budget.burst as f64);
+        self.updated = now;
+    }
+
+    /// Until the next token, zero when one is left
+    fn wait(&self, budget: &BudgetSettings) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64((1.0 - self.tokens) * 60.0 / budget.per_minute as f64)
+    }
+}
+
+type BucketKey = (RouteClass, String);
+
+/// Buckets by key along with the order they were last used in, so the least recently used is
+/// found without a scan
+#[derive(Default)]
+struct Buckets {
+    entries: HashMap<BucketKey, (Bucket, u64)>,
+    by_use: BTreeMap<u64, BucketKey>,
+    last_use: u64,
+}
+
+impl Buckets {
+    /// Refilled and marked used, a new bucket starts full
+    fn touch(
+        &mut self,
+        key: BucketKey,
+        budget: &BudgetSettings,
+        now: Instant,
+        max_buckets: usize,
+    ) -> &mut Bucket {
+        if !self.entries.contains_key(&key) {
+            while self.entries.len() >= max_buckets {
+                match self.by_use.pop_first() {
+                    Some((_, evicted)) => self.entries.remove(&evicted),
+                    None => break,
+                };
+            }
+        }
+
+        self.last_use += 1;
+        let last_use = self.last_use;
+        let (bucket, used) = self
+            .entries
+            .entry(key.clone())
+            .or_insert_with(|| (Bucket::full(budget, now), last_use));
+        self.by_use.remove(&*used);
+        *used = last_use;
+        self.by_use.insert(last_use, key);
+
+        bucket.refill(budget, now);
+        bucket
+    }
+}
+
+/// Token buckets shared by every worker, keyed by route class and client
+#[derive(Clone)]
+pub struct RateLimiter {
+    settings: RateLimitSettings,
+    buckets: Arc<Mutex<Buckets>>,
+    max_buckets: usize,
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        RateLimiter {
+            settings,
+            buckets: Arc::new(Mutex::new(Buckets::default())),
+            max_buckets: MAX_BUCKETS,
+        }
+    }
+
+    fn budget(&self, class: RouteClass) -> &BudgetSettings {
+        match class {
+            RouteClass::Read => &self.settings.reads,
+            RouteClass::SignedWrite => &self.settings.signed_writes,
+            RouteClass::Compile => &self.settings.compiles,
+            RouteClass::Execution => &self.settings.executions,
+        }
+    }
+
+    /// Takes a token from the bucket of every key, or none of them and returns how long to wait
+    pub fn check(&self, class: RouteClass, keys: &[String], now: Instant) -> Result<(), Duration> {
+        self.take(class, keys, now, true)
+    }
+
+    /// Like `check` without taking the tokens
+    fn peek(&self, class: RouteClass, keys: &[String], now: Instant) -> Result<(), Duration> {
+        self.take(class, keys, now, false)
+    }
+
+    fn take(
+        &self,
+        class: RouteClass,
+        keys: &[String],
+        now: Instant,
+        charge: bool,
+    ) -> Result<(), Duration> {
+        let budget = *self.budget(class);
+        let mut buckets = self.buckets.lock().unwrap();
+
+        let mut wait = Duration::ZERO;
+        for key in keys {
+            let bucket = buckets.touch((class, key.clone()), &budget, now, self.max_buckets);
+            wait = wait.max(bucket.wait(&budget));
+        }
+
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+
+        if charge {
+            for key in keys {
+                if let Some((bucket, _)) = buckets.entries.get_mut(&(class, key.clone())) {
+                    bucket.tokens -= 1.0;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The peer address, or the address a trusted proxy forwarded
+    fn client_ip(&self, req: &ServiceRequest) -> Option<String> {
+        let remote = match self.settings.trust_forwarded_for {
+            true => req.connection_info().realip_remote_addr()?.to_string(),
+            false => return req.peer_addr().map(|addr| addr.ip().to_string()),
+        };
+
+        Some(match remote.parse::<SocketAddr>() {
+            Ok(addr) => addr.ip().to_string(),
+            Err(_) => remote,
+        })
+    }
+}
+
+/// Answers 429 with `Retry-After` once a budget is spent, wrap it inside `NormalizePath`
+pub struct RateLimit {
+    limiter: RateLimiter,
+}
+
+impl RateLimit {
+    pub fn new(limiter: RateLimiter) -> Self {
+        RateLimit { limiter }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service,
+            limiter: self.limiter.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    limiter: RateLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let class = match classify(req.method(), req.path()) {
+            Some(class) if self.limiter.settings.enabled => class,
+            _ => {
+                let fut = self.service.call(req);
+                return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+            }
+        };
+
+        let ip_keys = self
+            .limiter
+            .client_ip(&req)
+            .map(|ip| format!("ip:{}", ip))
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        // Writes charge the ip once the signer is known, so a refused signer costs the ip nothing
+        let (limited, budget) = if class == RouteClass::Read {
+            (
+                self.limiter.check(class, &ip_keys, Instant::now()).err(),
+                None,
+            )
+        } else {
+            let budget = SignerBudget {
+                limiter: self.limiter.clone(),
+                class,
+                ip_keys: ip_keys.clone(),
+                settled: Arc::new(AtomicBool::new(false)),
+            };
+            req.extensions_mut().insert(budget.clone());
+            (
+                self.limiter.peek(class, &ip_keys, Instant::now()).err(),
+                Some(budget),
+            )
+        };
+
+        if let Some(wait) = limited {
+            let res = req.into_response(too_many_requests(wait).error_response());
+            return Box::pin(async move { Ok(res.map_into_right_body()) });
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await;
+            if let Some(budget) = budget {
+                if !budget.settled.load(Ordering::SeqCst) {
+                    budget
+                        .limiter
+                        .check(budget.class, &budget.ip_keys, Instant::now())
+                        .ok();
+                }
+            }
+            Ok(res?.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod buckets {
+    use crate::config::settings::{BudgetSettings, RateLimitSettings};
+    use crate::service::rate_limit::{charge_signer, classify, RateLimit, RateLimiter, RouteClass};
+    use crate::service::Error;
+    use actix_web::http::{header, Method, StatusCode};
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpRequest, HttpResponse};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/block-stacks/0xa"),
+            Some(RouteClass::Compile)
+        );
+        assert_eq!(
+            classify(&Method::PATCH, "/api/v1/block-stacks/0xa/stacks/1"),
+            Some(RouteClass::Compile)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/block-stacks/0xa/execute/stacks/1"),
+            Some(RouteClass::Execution)
+        );
+        assert_eq!(
+            classify(&Method::DELETE, "/api/v1/block-stacks/0xa/stacks/1"),
+            Some(RouteClass::SignedWrite)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/accounts/0xa/profile"),
+            Some(RouteClass::SignedWrite)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/sessions/challenge"),
+            Some(RouteClass::SignedWrite)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/accounts/0xa/modules"),
+            Some(RouteClass::Read)
+        );
+        assert_eq!(classify(&Method::GET, "/health/ready"), None);
+    }
+
+    #[test]
+    fn test_least_recently_used_buckets_are_evicted() {
+        let mut limiter = RateLimiter::new(RateLimitSettings {
+            reads: BudgetSettings::new(2, 1),
+            ..RateLimitSettings::default()
+        });
+        limiter.max_buckets = 10;
+        let now = Instant::now();
+
+        for i in 0..10 {
+            let key = format!("ip:10.0.0.{}", i);
+            let at = now + Duration::from_millis(i);
+            limiter.check(RouteClass::Read, &[key], at).unwrap();
+        }
+        let at = now + Duration::from_millis(10);
+        limiter
+            .check(RouteClass::Read, &["ip:10.0.0.10".to_string()], at)
+            .unwrap();
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.entries.len(), 10);
+        assert_eq!(buckets.by_use.len(), 10);
+        assert!(!buckets
+            .entries
+            .contains_key(&(RouteClass::Read, "ip:10.0.0.0".to_string())));
+        assert!(buckets
+            .entries
+            .contains_key(&(RouteClass::Read, "ip:10.0.0.9".to_string())));
+    }
+
+    #[test]
+    fn test_buckets_refill_and_share_keys() {
+        let limiter = RateLimiter::new(RateLimitSettings {
+            compiles: BudgetSettings::new(2, 60),
+            ..RateLimitSettings::default()
+        });
+        let now = Instant::now();
+        let ip = "ip:10.0.0.1".to_string();
+        let address = "address:0xa".to_string();
+
+        let both = [ip.clone(), address.clone()];
+        assert!(limiter.check(RouteClass::Compile, &both, now).is_ok());
+        assert!(limiter.check(RouteClass::Compile, &both, now).is_ok());
+        assert_eq!(
+            limiter.check(RouteClass::Compile, &both, now),
+            Err(Duration::from_secs(1))
+        );
+
+        // The address is spent from any ip, reads have their own budget
+        let elsewhere = ["ip:10.0.0.2".to_string(), address];
+        assert!(limiter.check(RouteClass::Compile, &elsewhere, now).is_err());
+        assert!(limiter
+            .check(RouteClass::Read, std::slice::from_ref(&ip), now)
+            .is_ok());
+
+        let later = now + Duration::from_secs(1);
+        assert!(limiter.check(RouteClass::Compile, &[ip], later).is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_answers_too_many_requests() {
+        let limiter = RateLimiter::new(RateLimitSettings {
+            reads: BudgetSettings::new(1, 1),
+            ..RateLimitSettings::default()
+        });
+        let app = init_service(
+            App::new()
+                .wrap(RateLimit::new(limiter))
+                .route("/api/v1/functions", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = || {
+            TestRequest::get()
+                .uri("/api/v1/functions")
+                .peer_addr("10.0.0.1:4000".parse().unwrap())
+                .to_request()
+        };
+
+        let res = call_service(&app, req()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = call_service(&app, req()).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "60");
+    }
+
+    /// The `signer` header stands in for the address a verified signature names
+    async fn signed_write(req: HttpRequest) -> Result<HttpResponse, Error> {
+        let signer = req.headers().get("signer").unwrap().to_str().unwrap();
+        charge_signer(&req, signer)?;
+        Ok(HttpResponse::Ok().finish())
+    }
+
+    #[actix_rt::test]
+    async fn test_spoofed_path_address_spends_the_signer_budget() {
+        let limiter = RateLimiter::new(RateLimitSettings {
+            signed_writes: BudgetSettings::new(2, 1),
+            ..RateLimitSettings::default()
+        });
+        let app = init_service(App::new().wrap(RateLimit::new(limiter)).route(
+            "/api/v1/accounts/{address}/profile",
+            web::post().to(signed_write),
+        ))
+        .await;
+
+        let req = |ip: &str, signer: &str| {
+            TestRequest::post()
+                .uri("/api/v1/accounts/0xa/profile")
+                .peer_addr(format!("{}:4000", ip).parse().unwrap())
+                .insert_header(("signer", signer))
+                .to_request()
+        };
+
+        // From fresh ips, every request names the victim but is signed by someone else
+        let mut statuses = vec![];
+        for i in 0..3 {
+            let res = call_service(&app, req(&format!("10.0.0.{}", i), "0xb")).await;
+            statuses.push(res.status());
+        }
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::TOO_MANY_REQUESTS
+            ]
+        );
+
+        let res = call_service(&app, req("10.0.0.9", "0xa")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // The refused signer left the ip a token to spend
+        let res = call_service(&app, req("10.0.0.0", "0xb")).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        let res = call_service(&app, req("10.0.0.0", "0xc")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
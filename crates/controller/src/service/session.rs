@@ -0,0 +1,184 @@
+use crate::config::ApiContext;
+use crate::service::domain::{self, session, SignedBody};
+use crate::service::{network::Network, rate_limit, Error};
+
+use database::models::session::CHALLENGE_PURPOSE_LOGIN;
+
+use actix_web::{
+    delete,
+    http::header,
+    post,
+    web::{self, Data},
+    HttpRequest, HttpResponse, Responder,
+};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChallengeBody {
+    pub address: String,
+    /// `login` when absent, `revoke-sessions` or `domain-verification` otherwise
+    pub purpose: Option<String>,
+}
+
+/// Signed as `payload.message`, the `message` of a challenge issued for the endpoint
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChallengeAnswer {
+    pub challenge: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshBody {
+    pub refresh_token: String,
+}
+
+fn bearer_token(req: &HttpRequest) -> Result<&str, Error> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(Error::UnAuthorized {})
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/sessions",
+    tag = "sessions",
+    request_body = ChallengeBody,
+    responses(
+        (status = 200, description = "OK", body = Challenge),
+        (status = 400, description = "Invalid params", body = ErrorEnvelope),
+    )
+)]
+#[post("/challenge")]
+async fn create_challenge(
+    context: Data<ApiContext>,
+    body: web::Json<ChallengeBody>,
+) -> Result<impl Responder, Error> {
+    let purpose = body.purpose.as_deref().unwrap_or(CHALLENGE_PURPOSE_LOGIN);
+    let challenge = session::create_challenge(
+        &context.app_db,
+        &context.settings.session,
+        &body.address,
+        purpose,
+    )
+    .await?;
+
+    Ok(web::Json(challenge))
+}
+
+/// Signed by `payload.address` with the key the session is bound to, checked against the key the
+/// account holds on the network of the request
+#[utoipa::path(
+    context_path = "/api/v1/sessions",
+    tag = "sessions",
+    request_body(content = SignedBody, description = "Signed `ChallengeAnswer` of a login challenge"),
+    responses(
+        (status = 200, description = "OK", body = SessionTokens),
+        (status = 401, description = "Unknown, expired or foreign challenge", body = ErrorEnvelope),
+    )
+)]
+#[post("")]
+async fn login(
+    req: HttpRequest,
+    context: Data<ApiContext>,
+    network: Network,
+    body: web::Json<SignedBody>,
+) -> Result<impl Responder, Error> {
+    let body = body.into_inner();
+    let verified_body: ChallengeAnswer =
+        domain::verify(&network.auth_keys, &body, &body.payload.address).await?;
+    rate_limit::charge_signer(&req, &body.payload.address)?;
+
+    let tokens = session::login(
+        &context.app_db,
+        &context.settings.session,
+        &network.name,
+        &body.payload.address,
+        &body.public_key,
+        &verified_body.challenge,
+    )
+    .await?;
+
+    Ok(web::Json(tokens))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1/sessions",
+    tag = "sessions",
+    request_body = RefreshBody,
+    responses(
+        (status = 200, description = "OK", body = SessionTokens),
+        (status = 401, description = "Unknown, spent or expired refresh token", body = ErrorEnvelope),
+    )
+)]
+#[post("/refresh")]
+async fn refresh(
+    context: Data<ApiContext>,
+    body: web::Json<RefreshBody>,
+) -> Result<impl Responder, Error> {
+    let tokens = session::refresh(
+        &context.app_db,
+        &context.settings.session,
+        &body.refresh_token,
+    )
+    .await?;
+
+    Ok(web::Json(tokens))
+}
+
+/// Ends the session of the `Authorization: Bearer` token
+#[utoipa::path(
+    context_path = "/api/v1/sessions",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 401, description = "Unknown session token", body = ErrorEnvelope),
+    )
+)]
+#[delete("")]
+async fn logout(context: Data<ApiContext>, req: HttpRequest) -> Result<impl Responder, Error> {
+    session::logout(&context.app_db, bearer_token(&req)?).await?;
+    Ok(HttpResponse::Ok())
+}
+
+/// Ends every session of the signer on the network of the request
+#[utoipa::path(
+    context_path = "/api/v1/sessions",
+    tag = "sessions",
+    request_body(content = SignedBody, description = "Signed `ChallengeAnswer` of a revoke-sessions challenge"),
+    responses(
+        (status = 200, description = "OK", body = RevokedSessions),
+        (status = 401, description = "Invalid signature, unknown or spent challenge", body = ErrorEnvelope),
+    )
+)]
+#[post("/revoke")]
+async fn revoke_sessions(
+    req: HttpRequest,
+    context: Data<ApiContext>,
+    network: Network,
+    body: web::Json<SignedBody>,
+) -> Result<impl Responder, Error> {
+    let body = body.into_inner();
+    let verified_body: ChallengeAnswer =
+        domain::verify(&network.auth_keys, &body, &body.payload.address).await?;
+    rate_limit::charge_signer(&req, &body.payload.address)?;
+
+    let revoked = session::revoke_sessions(
+        &context.app_db,
+        &network.name,
+        &body.payload.address,
+        &verified_body.challenge,
+    )
+    .await?;
+    Ok(web::Json(revoked))
+}
+
+pub fn routers(scope: actix_web::Scope) -> actix_web::Scope {
+    scope
+        .service(create_challenge)
+        .service(login)
+        .service(refresh)
+        .service(logout)
+        .service(revoke_sessions)
+}
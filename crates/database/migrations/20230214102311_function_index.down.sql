@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS indexer_status;
+
+DROP INDEX IF EXISTS idx_module_function_keys;
+DROP TABLE IF EXISTS module_function;
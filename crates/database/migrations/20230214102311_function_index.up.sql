@@ -0,0 +1,28 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS module_function
+(
+    id                                  SERIAL PRIMARY KEY,
+    module_address                      VARCHAR(66)  NOT NULL,
+    module_name                         VARCHAR(128) NOT NULL,
+    move_modules_transaction_version    BIGINT       NOT NULL,
+    move_modules_write_set_change_index BIGINT       NOT NULL,
+    name                                TEXT         NOT NULL,
+    visibility                          VARCHAR(16)  NOT NULL,
+    is_entry                            BOOLEAN      NOT NULL,
+    generic_type_params                 jsonb,
+    params                              jsonb,
+    return_types                        jsonb,
+    is_deleted                          BOOLEAN      NOT NULL DEFAULT FALSE,
+
+    UNIQUE (module_address, module_name, name, move_modules_transaction_version)
+);
+
+CREATE INDEX idx_module_function_keys ON module_function (module_address, module_name, name);
+
+CREATE TABLE IF NOT EXISTS indexer_status
+(
+    indexer_name         VARCHAR(64) PRIMARY KEY,
+    last_success_version BIGINT      NOT NULL,
+    last_updated         TIMESTAMP   NOT NULL DEFAULT current_timestamp
+);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS idx_module_struct_detail_keys;
+DROP TABLE IF EXISTS module_struct_detail;
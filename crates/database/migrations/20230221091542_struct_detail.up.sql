@@ -0,0 +1,13 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS module_struct_detail
+(
+    id                 SERIAL PRIMARY KEY,
+    address            VARCHAR(66)  NOT NULL,
+    module_name        VARCHAR(128) NOT NULL,
+    struct_name        TEXT         NOT NULL,
+    description        TEXT,
+    field_descriptions jsonb
+);
+
+CREATE INDEX idx_module_struct_detail_keys ON module_struct_detail (address, module_name, struct_name);
@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS idx_module_function_usage_target;
+DROP TABLE IF EXISTS module_function_usage;
+
+DROP INDEX IF EXISTS idx_module_dependency_target;
+DROP TABLE IF EXISTS module_dependency;
@@ -0,0 +1,31 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS module_dependency
+(
+    id                     SERIAL PRIMARY KEY,
+    address                VARCHAR(66)  NOT NULL,
+    module_name            VARCHAR(128) NOT NULL,
+    dependency_address     VARCHAR(66)  NOT NULL,
+    dependency_module_name VARCHAR(128) NOT NULL,
+    kind                   VARCHAR(16)  NOT NULL,
+    transaction_version    BIGINT       NOT NULL,
+
+    UNIQUE (address, module_name, dependency_address, dependency_module_name, kind)
+);
+
+CREATE INDEX idx_module_dependency_target ON module_dependency (dependency_address, dependency_module_name);
+
+CREATE TABLE IF NOT EXISTS module_function_usage
+(
+    id                   SERIAL PRIMARY KEY,
+    address              VARCHAR(66)  NOT NULL,
+    module_name          VARCHAR(128) NOT NULL,
+    function_address     VARCHAR(66)  NOT NULL,
+    function_module_name VARCHAR(128) NOT NULL,
+    function_name        TEXT         NOT NULL,
+    transaction_version  BIGINT       NOT NULL,
+
+    UNIQUE (address, module_name, function_address, function_module_name, function_name)
+);
+
+CREATE INDEX idx_module_function_usage_target ON module_function_usage (function_address, function_module_name, function_name);
@@ -0,0 +1,8 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS idx_block_stack_status;
+
+ALTER TABLE block_stack
+    DROP COLUMN IF EXISTS status,
+    DROP COLUMN IF EXISTS status_reason,
+    DROP COLUMN IF EXISTS status_updated_at;
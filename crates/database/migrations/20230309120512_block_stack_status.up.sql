@@ -0,0 +1,8 @@
+-- Add up migration script here
+
+ALTER TABLE block_stack
+    ADD COLUMN status            VARCHAR(16) NOT NULL DEFAULT 'ok',
+    ADD COLUMN status_reason     TEXT,
+    ADD COLUMN status_updated_at TIMESTAMP;
+
+CREATE INDEX idx_block_stack_status ON block_stack (status);
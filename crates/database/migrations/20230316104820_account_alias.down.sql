@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS idx_account_alias_history_alias;
+DROP TABLE IF EXISTS account_alias_history;
+
+DROP INDEX IF EXISTS idx_account_detail_alias;
@@ -0,0 +1,26 @@
+-- Add up migration script here
+
+-- Aliases are case insensitive from now on, the first account to claim a shared alias keeps it
+UPDATE account_detail
+SET alias = NULL
+WHERE id IN (SELECT id
+             FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY LOWER(alias) ORDER BY id) AS claim
+                   FROM account_detail
+                   WHERE alias IS NOT NULL) claims
+             WHERE claim > 1);
+
+UPDATE account_detail
+SET alias = LOWER(alias)
+WHERE alias IS NOT NULL;
+
+CREATE UNIQUE INDEX idx_account_detail_alias ON account_detail (alias);
+
+CREATE TABLE IF NOT EXISTS account_alias_history
+(
+    id          SERIAL PRIMARY KEY,
+    address     VARCHAR(66)  NOT NULL,
+    alias       VARCHAR(256) NOT NULL,
+    replaced_at TIMESTAMP    NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX idx_account_alias_history_alias ON account_alias_history (alias, replaced_at);
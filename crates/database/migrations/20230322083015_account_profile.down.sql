@@ -0,0 +1,11 @@
+-- Add down migration script here
+
+ALTER TABLE account_detail
+    DROP COLUMN IF EXISTS display_name,
+    DROP COLUMN IF EXISTS avatar_url,
+    DROP COLUMN IF EXISTS website,
+    DROP COLUMN IF EXISTS social_links,
+    DROP COLUMN IF EXISTS description,
+    DROP COLUMN IF EXISTS domain,
+    DROP COLUMN IF EXISTS domain_verified_by,
+    DROP COLUMN IF EXISTS domain_verified_at;
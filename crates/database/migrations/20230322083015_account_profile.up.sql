@@ -0,0 +1,11 @@
+-- Add up migration script here
+
+ALTER TABLE account_detail
+    ADD COLUMN display_name       VARCHAR(64),
+    ADD COLUMN avatar_url         TEXT,
+    ADD COLUMN website            TEXT,
+    ADD COLUMN social_links       jsonb,
+    ADD COLUMN description        TEXT,
+    ADD COLUMN domain             VARCHAR(253),
+    ADD COLUMN domain_verified_by VARCHAR(16),
+    ADD COLUMN domain_verified_at TIMESTAMP;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS idx_resource_account_owner;
+DROP TABLE IF EXISTS resource_account;
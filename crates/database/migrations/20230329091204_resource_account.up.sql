@@ -0,0 +1,16 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS resource_account
+(
+    id               SERIAL PRIMARY KEY,
+    owner_address    VARCHAR(66) NOT NULL,
+    resource_address VARCHAR(66) NOT NULL,
+    -- hex encoded, NULL for discovered accounts whose seed is unknown
+    seed             TEXT,
+    source           VARCHAR(16) NOT NULL,
+    created_at       TIMESTAMP   NOT NULL DEFAULT current_timestamp,
+
+    UNIQUE (resource_address)
+);
+
+CREATE INDEX idx_resource_account_owner ON resource_account (owner_address);
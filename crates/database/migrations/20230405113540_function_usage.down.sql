@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS idx_function_usage_popularity;
+DROP TABLE IF EXISTS function_usage;
+
+DROP INDEX IF EXISTS idx_function_call_daily_day;
+DROP TABLE IF EXISTS function_call_daily;
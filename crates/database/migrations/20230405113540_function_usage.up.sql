@@ -0,0 +1,35 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS function_call_daily
+(
+    module_address VARCHAR(66)  NOT NULL,
+    module_name    VARCHAR(128) NOT NULL,
+    function_name  TEXT         NOT NULL,
+    day            DATE         NOT NULL,
+    sender         VARCHAR(66)  NOT NULL,
+    -- entry_function or script payload
+    source         VARCHAR(16)  NOT NULL,
+    call_count     BIGINT       NOT NULL,
+    last_called_at TIMESTAMP    NOT NULL,
+
+    PRIMARY KEY (module_address, module_name, function_name, day, sender, source)
+);
+
+CREATE INDEX idx_function_call_daily_day ON function_call_daily (day);
+
+CREATE TABLE IF NOT EXISTS function_usage
+(
+    module_address   VARCHAR(66)  NOT NULL,
+    module_name      VARCHAR(128) NOT NULL,
+    function_name    TEXT         NOT NULL,
+    window_days      INT          NOT NULL,
+    call_count       BIGINT       NOT NULL,
+    entry_call_count BIGINT       NOT NULL,
+    distinct_callers BIGINT       NOT NULL,
+    last_called_at   TIMESTAMP    NOT NULL,
+    updated_at       TIMESTAMP    NOT NULL DEFAULT current_timestamp,
+
+    PRIMARY KEY (module_address, module_name, function_name, window_days)
+);
+
+CREATE INDEX idx_function_usage_popularity ON function_usage (window_days, call_count DESC);
@@ -0,0 +1,24 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS idx_module_struct_detail_keys;
+CREATE INDEX idx_module_struct_detail_keys ON module_struct_detail (address, module_name, struct_name);
+ALTER TABLE module_struct_detail
+    DROP COLUMN IF EXISTS network;
+
+DROP INDEX IF EXISTS idx_module_function_detail_keys;
+ALTER TABLE module_function_detail
+    DROP COLUMN IF EXISTS network;
+
+DROP INDEX IF EXISTS idx_module_detail_keys;
+ALTER TABLE module_detail
+    DROP COLUMN IF EXISTS network;
+
+DROP INDEX IF EXISTS idx_block_stack_network_address;
+CREATE INDEX idx_block_stack_address ON block_stack (address);
+
+ALTER TABLE block_stack
+    DROP CONSTRAINT IF EXISTS block_stack_network_address_name_key;
+ALTER TABLE block_stack
+    DROP COLUMN IF EXISTS network;
+ALTER TABLE block_stack
+    ADD CONSTRAINT block_stack_address_name_key UNIQUE (address, name);
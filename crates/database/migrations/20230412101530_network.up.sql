@@ -0,0 +1,39 @@
+-- Add up migration script here
+
+-- Rows written before networks existed all belong to devnet
+ALTER TABLE block_stack
+    ADD COLUMN network VARCHAR(16) NOT NULL DEFAULT 'devnet';
+ALTER TABLE block_stack
+    ALTER COLUMN network DROP DEFAULT;
+
+ALTER TABLE block_stack
+    DROP CONSTRAINT IF EXISTS block_stack_address_name_key;
+ALTER TABLE block_stack
+    ADD CONSTRAINT block_stack_network_address_name_key UNIQUE (network, address, name);
+
+DROP INDEX IF EXISTS idx_block_stack_address;
+CREATE INDEX idx_block_stack_network_address ON block_stack (network, address);
+
+ALTER TABLE module_detail
+    ADD COLUMN network VARCHAR(16) NOT NULL DEFAULT 'devnet';
+ALTER TABLE module_detail
+    ALTER COLUMN network DROP DEFAULT;
+
+CREATE INDEX idx_module_detail_keys ON module_detail (network, address, module_name);
+
+ALTER TABLE module_function_detail
+    ADD COLUMN network VARCHAR(16) NOT NULL DEFAULT 'devnet';
+ALTER TABLE module_function_detail
+    ALTER COLUMN network DROP DEFAULT;
+
+CREATE INDEX idx_module_function_detail_keys
+    ON module_function_detail (network, address, module_name, function_name);
+
+ALTER TABLE module_struct_detail
+    ADD COLUMN network VARCHAR(16) NOT NULL DEFAULT 'devnet';
+ALTER TABLE module_struct_detail
+    ALTER COLUMN network DROP DEFAULT;
+
+DROP INDEX IF EXISTS idx_module_struct_detail_keys;
+CREATE INDEX idx_module_struct_detail_keys
+    ON module_struct_detail (network, address, module_name, struct_name);
@@ -0,0 +1,14 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS compile_job
+(
+    id             SERIAL PRIMARY KEY,
+    block_stack_id INT         NOT NULL REFERENCES block_stack (id) ON DELETE CASCADE,
+    network        VARCHAR(16) NOT NULL,
+    status         VARCHAR(16) NOT NULL,
+    error          TEXT,
+    started_at     TIMESTAMP   NOT NULL DEFAULT current_timestamp,
+    finished_at    TIMESTAMP
+);
+
+CREATE INDEX idx_compile_job_network_status ON compile_job (network, status);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS session;
+DROP TABLE IF EXISTS login_challenge;
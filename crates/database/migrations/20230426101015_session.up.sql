@@ -0,0 +1,25 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS login_challenge
+(
+    challenge  VARCHAR(64) PRIMARY KEY,
+    address    VARCHAR(66) NOT NULL,
+    purpose    VARCHAR(32) NOT NULL,
+    expires_at TIMESTAMP   NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS session
+(
+    id                 SERIAL PRIMARY KEY,
+    network            VARCHAR(16) NOT NULL,
+    address            VARCHAR(66) NOT NULL,
+    public_key         TEXT        NOT NULL,
+    token_hash         VARCHAR(64) NOT NULL UNIQUE,
+    refresh_token_hash VARCHAR(64) NOT NULL UNIQUE,
+    expires_at         TIMESTAMP   NOT NULL,
+    refresh_expires_at TIMESTAMP   NOT NULL,
+    revoked_at         TIMESTAMP,
+    created_at         TIMESTAMP   NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX idx_session_network_address ON session (network, address);
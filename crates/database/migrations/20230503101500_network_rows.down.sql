@@ -0,0 +1,99 @@
+-- Add down migration script here
+
+DELETE FROM indexer_status WHERE indexer_name LIKE '%.module_dependency';
+
+UPDATE indexer_status
+SET indexer_name = substring(indexer_name FROM length('devnet.') + 1)
+WHERE indexer_name IN ('devnet.resource_account', 'devnet.function_usage', 'devnet.block_stack_checker');
+
+DELETE FROM indexer_status
+WHERE indexer_name LIKE '%.resource_account'
+   OR indexer_name LIKE '%.function_usage'
+   OR indexer_name LIKE '%.block_stack_checker';
+
+DROP INDEX IF EXISTS idx_module_function_usage_target;
+DROP TABLE IF EXISTS module_function_usage;
+
+DROP INDEX IF EXISTS idx_module_dependency_target;
+DROP TABLE IF EXISTS module_dependency;
+
+CREATE TABLE IF NOT EXISTS module_dependency
+(
+    id                     SERIAL PRIMARY KEY,
+    address                VARCHAR(66)  NOT NULL,
+    module_name            VARCHAR(128) NOT NULL,
+    dependency_address     VARCHAR(66)  NOT NULL,
+    dependency_module_name VARCHAR(128) NOT NULL,
+    kind                   VARCHAR(16)  NOT NULL,
+    transaction_version    BIGINT       NOT NULL,
+
+    UNIQUE (address, module_name, dependency_address, dependency_module_name, kind)
+);
+
+CREATE INDEX idx_module_dependency_target ON module_dependency (dependency_address, dependency_module_name);
+
+CREATE TABLE IF NOT EXISTS module_function_usage
+(
+    id                   SERIAL PRIMARY KEY,
+    address              VARCHAR(66)  NOT NULL,
+    module_name          VARCHAR(128) NOT NULL,
+    function_address     VARCHAR(66)  NOT NULL,
+    function_module_name VARCHAR(128) NOT NULL,
+    function_name        TEXT         NOT NULL,
+    transaction_version  BIGINT       NOT NULL,
+
+    UNIQUE (address, module_name, function_address, function_module_name, function_name)
+);
+
+CREATE INDEX idx_module_function_usage_target ON module_function_usage (function_address, function_module_name, function_name);
+
+-- Rows of other networks cannot be told apart once the column is gone
+DELETE FROM function_usage WHERE network <> 'devnet';
+
+DROP INDEX IF EXISTS idx_function_usage_popularity;
+CREATE INDEX idx_function_usage_popularity ON function_usage (window_days, call_count DESC);
+
+ALTER TABLE function_usage
+    DROP CONSTRAINT IF EXISTS function_usage_pkey;
+ALTER TABLE function_usage
+    DROP COLUMN IF EXISTS network;
+ALTER TABLE function_usage
+    ADD PRIMARY KEY (module_address, module_name, function_name, window_days);
+
+DELETE FROM function_call_daily WHERE network <> 'devnet';
+
+ALTER TABLE function_call_daily
+    DROP CONSTRAINT IF EXISTS function_call_daily_pkey;
+ALTER TABLE function_call_daily
+    DROP COLUMN IF EXISTS network;
+ALTER TABLE function_call_daily
+    ADD PRIMARY KEY (module_address, module_name, function_name, day, sender, source);
+
+DELETE FROM resource_account WHERE network <> 'devnet';
+
+DROP INDEX IF EXISTS idx_resource_account_network_owner;
+CREATE INDEX idx_resource_account_owner ON resource_account (owner_address);
+
+ALTER TABLE resource_account
+    DROP CONSTRAINT IF EXISTS resource_account_network_resource_address_key;
+ALTER TABLE resource_account
+    DROP COLUMN IF EXISTS network;
+ALTER TABLE resource_account
+    ADD CONSTRAINT resource_account_resource_address_key UNIQUE (resource_address);
+
+DELETE FROM account_alias_history WHERE network <> 'devnet';
+
+DROP INDEX IF EXISTS idx_account_alias_history_network_alias;
+CREATE INDEX idx_account_alias_history_alias ON account_alias_history (alias, replaced_at);
+ALTER TABLE account_alias_history
+    DROP COLUMN IF EXISTS network;
+
+DELETE FROM account_detail WHERE network <> 'devnet';
+
+DROP INDEX IF EXISTS idx_account_detail_network_address;
+CREATE INDEX idx_account_detail_address on account_detail (address);
+
+DROP INDEX IF EXISTS idx_account_detail_network_alias;
+ALTER TABLE account_detail
+    DROP COLUMN IF EXISTS network;
+CREATE UNIQUE INDEX idx_account_detail_alias ON account_detail (alias);
@@ -0,0 +1,108 @@
+-- Add up migration script here
+
+-- Rows written before networks existed all belong to devnet, like in the network migration
+ALTER TABLE account_detail
+    ADD COLUMN network VARCHAR(16) NOT NULL DEFAULT 'devnet';
+ALTER TABLE account_detail
+    ALTER COLUMN network DROP DEFAULT;
+
+DROP INDEX IF EXISTS idx_account_detail_alias;
+CREATE UNIQUE INDEX idx_account_detail_network_alias ON account_detail (network, alias);
+
+DROP INDEX IF EXISTS idx_account_detail_address;
+CREATE INDEX idx_account_detail_network_address ON account_detail (network, address);
+
+ALTER TABLE account_alias_history
+    ADD COLUMN network VARCHAR(16) NOT NULL DEFAULT 'devnet';
+ALTER TABLE account_alias_history
+    ALTER COLUMN network DROP DEFAULT;
+
+DROP INDEX IF EXISTS idx_account_alias_history_alias;
+CREATE INDEX idx_account_alias_history_network_alias
+    ON account_alias_history (network, alias, replaced_at);
+
+ALTER TABLE resource_account
+    ADD COLUMN network VARCHAR(16) NOT NULL DEFAULT 'devnet';
+ALTER TABLE resource_account
+    ALTER COLUMN network DROP DEFAULT;
+
+ALTER TABLE resource_account
+    DROP CONSTRAINT IF EXISTS resource_account_resource_address_key;
+ALTER TABLE resource_account
+    ADD CONSTRAINT resource_account_network_resource_address_key UNIQUE (network, resource_address);
+
+DROP INDEX IF EXISTS idx_resource_account_owner;
+CREATE INDEX idx_resource_account_network_owner ON resource_account (network, owner_address);
+
+ALTER TABLE function_call_daily
+    ADD COLUMN network VARCHAR(16) NOT NULL DEFAULT 'devnet';
+ALTER TABLE function_call_daily
+    ALTER COLUMN network DROP DEFAULT;
+
+ALTER TABLE function_call_daily
+    DROP CONSTRAINT IF EXISTS function_call_daily_pkey;
+ALTER TABLE function_call_daily
+    ADD PRIMARY KEY (network, module_address, module_name, function_name, day, sender, source);
+
+ALTER TABLE function_usage
+    ADD COLUMN network VARCHAR(16) NOT NULL DEFAULT 'devnet';
+ALTER TABLE function_usage
+    ALTER COLUMN network DROP DEFAULT;
+
+ALTER TABLE function_usage
+    DROP CONSTRAINT IF EXISTS function_usage_pkey;
+ALTER TABLE function_usage
+    ADD PRIMARY KEY (network, module_address, module_name, function_name, window_days);
+
+DROP INDEX IF EXISTS idx_function_usage_popularity;
+CREATE INDEX idx_function_usage_popularity ON function_usage (network, window_days, call_count DESC);
+
+-- The module graph is derived from move_modules, the dependency indexer rebuilds it per network
+DROP INDEX IF EXISTS idx_module_function_usage_target;
+DROP TABLE IF EXISTS module_function_usage;
+
+DROP INDEX IF EXISTS idx_module_dependency_target;
+DROP TABLE IF EXISTS module_dependency;
+
+CREATE TABLE IF NOT EXISTS module_dependency
+(
+    id                     SERIAL PRIMARY KEY,
+    network                VARCHAR(16)  NOT NULL,
+    address                VARCHAR(66)  NOT NULL,
+    module_name            VARCHAR(128) NOT NULL,
+    dependency_address     VARCHAR(66)  NOT NULL,
+    dependency_module_name VARCHAR(128) NOT NULL,
+    kind                   VARCHAR(16)  NOT NULL,
+    transaction_version    BIGINT       NOT NULL,
+
+    CONSTRAINT module_dependency_edge_key
+        UNIQUE (network, address, module_name, dependency_address, dependency_module_name, kind)
+);
+
+CREATE INDEX idx_module_dependency_target
+    ON module_dependency (network, dependency_address, dependency_module_name);
+
+CREATE TABLE IF NOT EXISTS module_function_usage
+(
+    id                   SERIAL PRIMARY KEY,
+    network              VARCHAR(16)  NOT NULL,
+    address              VARCHAR(66)  NOT NULL,
+    module_name          VARCHAR(128) NOT NULL,
+    function_address     VARCHAR(66)  NOT NULL,
+    function_module_name VARCHAR(128) NOT NULL,
+    function_name        TEXT         NOT NULL,
+    transaction_version  BIGINT       NOT NULL,
+
+    CONSTRAINT module_function_usage_edge_key
+        UNIQUE (network, address, module_name, function_address, function_module_name, function_name)
+);
+
+CREATE INDEX idx_module_function_usage_target
+    ON module_function_usage (network, function_address, function_module_name, function_name);
+
+-- Indexers writing into the app database keep a checkpoint per network
+DELETE FROM indexer_status WHERE indexer_name = 'module_dependency';
+
+UPDATE indexer_status
+SET indexer_name = 'devnet.' || indexer_name
+WHERE indexer_name IN ('resource_account', 'function_usage', 'block_stack_checker');
@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+UPDATE indexer_status
+SET indexer_name = 'module_function'
+WHERE indexer_name = 'devnet.module_function';
+
+DELETE FROM indexer_status WHERE indexer_name LIKE '%.module_function';
@@ -0,0 +1,6 @@
+-- Add up migration script here
+
+-- The function indexer keeps its checkpoint per network like the other indexers
+UPDATE indexer_status
+SET indexer_name = 'devnet.module_function'
+WHERE indexer_name = 'module_function';
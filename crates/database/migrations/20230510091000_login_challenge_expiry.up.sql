@@ -0,0 +1,4 @@
+-- Add up migration script here
+
+-- Expired challenges are dropped whenever one is created or answered
+CREATE INDEX IF NOT EXISTS idx_login_challenge_expires_at ON login_challenge (expires_at);
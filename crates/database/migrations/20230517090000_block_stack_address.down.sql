@@ -0,0 +1,3 @@
+-- Add down migration script here
+
+-- The short forms are not kept, standardized addresses stay valid
@@ -0,0 +1,6 @@
+-- Add up migration script here
+
+-- Stacks are listed by the standardized address of their owner
+UPDATE block_stack
+SET address = '0x' || lpad(lower(regexp_replace(address, '^0x', '')), 64, '0')
+WHERE address <> '0x' || lpad(lower(regexp_replace(address, '^0x', '')), 64, '0');
@@ -4,13 +4,14 @@ use std::time::Duration;
 
 pub type PostgresPool = Pool<Postgres>;
 
-pub fn new_postgres_pool(env_var: &str) -> PostgresPool {
-    let url_from_env =
-        std::env::var(env_var).unwrap_or_else(|_| panic!("env var {} not found", env_var));
-
+/// Connects lazily, only a malformed url fails here
+pub fn new_postgres_pool(
+    url: &str,
+    max_connections: u32,
+    acquire_timeout: Duration,
+) -> Result<PostgresPool, sqlx::Error> {
     PgPoolOptions::new()
-        .max_connections(10)
-        .acquire_timeout(Duration::from_secs(2))
-        .connect_lazy(&url_from_env)
-        .unwrap()
+        .max_connections(max_connections)
+        .acquire_timeout(acquire_timeout)
+        .connect_lazy(url)
 }
@@ -7,11 +7,27 @@ pub enum Error {
 
     #[error("var error")]
     VarError(std::env::VarError),
+
+    #[error("migrate error: {0}")]
+    MigrateError(sqlx::migrate::MigrateError),
+
+    /// A write collided with a unique constraint, holds the constraint name when known
+    #[error("unique violation: {0}")]
+    UniqueViolation(String),
 }
 
+const UNIQUE_VIOLATION: &str = "23505";
+
 impl From<sqlx::Error> for Error {
     fn from(e: sqlx::Error) -> Self {
-        Self::SqlXError(e)
+        match e {
+            sqlx::Error::Database(ref db_error)
+                if db_error.code().as_deref() == Some(UNIQUE_VIOLATION) =>
+            {
+                Self::UniqueViolation(db_error.constraint().unwrap_or_default().to_string())
+            }
+            e => Self::SqlXError(e),
+        }
     }
 }
 
@@ -20,3 +36,9 @@ impl From<std::env::VarError> for Error {
         Self::VarError(e)
     }
 }
+
+impl From<sqlx::migrate::MigrateError> for Error {
+    fn from(e: sqlx::migrate::MigrateError) -> Self {
+        Self::MigrateError(e)
+    }
+}
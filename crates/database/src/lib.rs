@@ -1,3 +1,5 @@
 pub mod db;
 pub mod error;
 pub mod models;
+pub mod repository;
+pub mod schema;
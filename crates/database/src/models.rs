@@ -1,2 +1,4 @@
 pub mod block_stack;
+pub mod indexer;
 pub mod module_hub;
+pub mod session;
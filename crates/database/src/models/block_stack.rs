@@ -1 +1,2 @@
+pub mod compile_job;
 pub mod stack;
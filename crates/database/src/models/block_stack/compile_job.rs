@@ -0,0 +1,22 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Queued or compiling, a stopped server may leave jobs running that nothing compiles anymore
+pub const COMPILE_JOB_STATUS_RUNNING: &str = "running";
+pub const COMPILE_JOB_STATUS_SUCCEEDED: &str = "succeeded";
+pub const COMPILE_JOB_STATUS_FAILED: &str = "failed";
+/// Left running by a stopped server, a new job compiles the stack again
+pub const COMPILE_JOB_STATUS_INTERRUPTED: &str = "interrupted";
+
+/// One compilation of a block stack into script bytecode
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct CompileJob {
+    pub id: i32,
+    pub block_stack_id: i32,
+    pub network: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
@@ -2,15 +2,30 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::types::JsonValue;
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+/// Bytecode matches the on-chain modules it calls
+pub const BLOCK_STACK_STATUS_OK: &str = "ok";
+/// A called function changed on upgrade, the stored bytecode should be recompiled
+pub const BLOCK_STACK_STATUS_STALE: &str = "stale";
+/// The stack cannot be compiled against the current modules anymore
+pub const BLOCK_STACK_STATUS_BROKEN: &str = "broken";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct BlockStack {
     pub id: i32,
+    /// One of the configured networks, stacks compile against that network's modules
+    pub network: String,
     pub address: String,
     pub name: String,
+    #[schema(value_type = Value)]
     pub stack: JsonValue,
     pub last_edit_datetime: NaiveDateTime,
+    #[schema(value_type = Option<Vec<u32>>)]
     pub bytecode: Option<Vec<u8>>,
+    pub status: String,
+    pub status_reason: Option<String>,
+    pub status_updated_at: Option<NaiveDateTime>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
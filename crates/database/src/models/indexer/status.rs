@@ -0,0 +1,10 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct IndexerStatus {
+    pub indexer_name: String,
+    pub last_success_version: i64,
+    pub last_updated: NaiveDateTime,
+}
@@ -4,8 +4,9 @@ use crate::models::module_hub::detail::account::AccountDetail;
 use crate::models::module_hub::detail::function::ModuleFunctionDetail;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct ModuleFunctionWithAccountDetail {
     pub module_function: ModuleFunction,
     pub account_detail: Option<AccountDetail>,
@@ -20,7 +21,7 @@ impl ModuleFunctionWithAccountDetail {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct ModuleFunctionWithDetail {
     pub module_function: ModuleFunction,
     pub function_detail: Option<ModuleFunctionDetail>,
@@ -38,7 +39,7 @@ impl ModuleFunctionWithDetail {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct ModuleFunctionWithOwnAndAccountDetail {
     pub module_function: ModuleFunction,
     pub account_detail: Option<AccountDetail>,
@@ -1,2 +1,3 @@
 pub mod function;
 pub mod module;
+pub mod move_struct;
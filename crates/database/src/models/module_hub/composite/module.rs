@@ -5,8 +5,9 @@ use crate::models::module_hub::detail::module::ModuleDetail;
 
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct MoveModuleWithDetail {
     pub move_module: MoveModule,
     pub module_detail: Option<ModuleDetail>,
@@ -21,7 +22,7 @@ impl MoveModuleWithDetail {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct MoveModuleWithFunctionsAccountAndDetails {
     pub move_module_with_detail: MoveModuleWithDetail,
     pub account_detail: Option<AccountDetail>,
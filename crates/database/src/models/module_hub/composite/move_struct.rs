@@ -0,0 +1,20 @@
+use crate::models::module_hub::core::move_struct::MoveStruct;
+use crate::models::module_hub::detail::move_struct::ModuleStructDetail;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct MoveStructWithDetail {
+    pub move_struct: MoveStruct,
+    pub struct_detail: Option<ModuleStructDetail>,
+}
+
+impl MoveStructWithDetail {
+    pub fn compose(move_struct: MoveStruct, struct_detail: Option<ModuleStructDetail>) -> Self {
+        MoveStructWithDetail {
+            move_struct,
+            struct_detail,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+pub const DEPENDENCY_KIND_USES: &str = "uses";
+pub const DEPENDENCY_KIND_FRIEND: &str = "friend";
+
+/// Edge from a module to a module it uses or declares as friend
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct ModuleDependency {
+    pub id: i32,
+    pub address: String,
+    pub module_name: String,
+    pub dependency_address: String,
+    pub dependency_module_name: String,
+    pub kind: String,
+    pub transaction_version: i64,
+}
+
+/// Edge from a module to a function its bytecode calls
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct ModuleFunctionUsage {
+    pub id: i32,
+    pub address: String,
+    pub module_name: String,
+    pub function_address: String,
+    pub function_module_name: String,
+    pub function_name: String,
+    pub transaction_version: i64,
+}
+
+/// A module reached while walking the graph
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ModuleNode {
+    pub address: String,
+    pub module_name: String,
+    /// 1 for direct neighbours
+    pub depth: i32,
+}
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 use sqlx::types::JsonValue;
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct ModuleFunction {
     pub id: i32,
     pub module_address: String,
@@ -12,7 +13,27 @@ pub struct ModuleFunction {
     pub name: String,
     pub visibility: String,
     pub is_entry: bool,
+    #[schema(value_type = Option<Value>)]
     pub generic_type_params: Option<JsonValue>,
+    #[schema(value_type = Option<Value>)]
     pub params: Option<JsonValue>,
+    #[schema(value_type = Option<Value>)]
     pub return_types: Option<JsonValue>,
+    /// Reads leave out functions whose latest row is deleted
+    pub is_deleted: bool,
+}
+
+/// An entry of `MoveModule.exposed_functions`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ExposedFunction {
+    pub name: String,
+    pub visibility: String,
+    pub is_entry: bool,
+    #[schema(value_type = Value)]
+    pub generic_type_params: JsonValue,
+    #[schema(value_type = Value)]
+    pub params: JsonValue,
+    #[serde(rename = "return")]
+    #[schema(value_type = Value)]
+    pub return_types: JsonValue,
 }
@@ -1,2 +1,5 @@
+pub mod dependency;
 pub mod function;
 pub mod module;
+pub mod move_struct;
+pub mod usage;
@@ -1,19 +1,48 @@
+use crate::models::module_hub::core::function::ExposedFunction;
+use crate::models::module_hub::core::move_struct::MoveStruct;
+
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::types::JsonValue;
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct MoveModule {
     pub transaction_version: i64,
     pub write_set_change_index: i64,
     pub transaction_block_height: i64,
     pub name: String,
     pub address: String,
+    #[schema(value_type = Option<Vec<u32>>)]
     pub bytecode: Option<Vec<u8>>,
+    #[schema(value_type = Option<Value>)]
     pub friends: Option<JsonValue>,
+    #[schema(value_type = Option<Value>)]
     pub exposed_functions: Option<JsonValue>,
+    #[schema(value_type = Option<Value>)]
     pub structs: Option<JsonValue>,
     pub is_deleted: bool,
     pub inserted_at: NaiveDateTime,
 }
+
+impl MoveModule {
+    /// Malformed abi is treated as a module without functions
+    pub fn parse_exposed_functions(&self) -> Vec<ExposedFunction> {
+        serde_json::from_value(self.exposed_functions.clone().unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    pub fn parse_structs(&self) -> Vec<MoveStruct> {
+        serde_json::from_value(self.structs.clone().unwrap_or_default()).unwrap_or_default()
+    }
+}
+
+/// A published version of a module, upgrades keep the name and address
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ModuleVersion {
+    pub transaction_version: i64,
+    pub transaction_block_height: i64,
+    pub is_deleted: bool,
+    pub inserted_at: NaiveDateTime,
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::JsonValue;
+use utoipa::ToSchema;
+
+/// An entry of `MoveModule.structs`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct MoveStruct {
+    pub name: String,
+    pub is_native: bool,
+    pub abilities: Vec<String>,
+    #[schema(value_type = Value)]
+    pub generic_type_params: JsonValue,
+    pub fields: Vec<MoveStructField>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct MoveStructField {
+    pub name: String,
+    pub r#type: String,
+}
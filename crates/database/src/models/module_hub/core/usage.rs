@@ -0,0 +1,22 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+pub const CALL_SOURCE_ENTRY_FUNCTION: &str = "entry_function";
+pub const CALL_SOURCE_SCRIPT: &str = "script";
+
+/// Calls of a function within the last `window_days`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FunctionUsage {
+    pub module_address: String,
+    pub module_name: String,
+    pub function_name: String,
+    pub window_days: i32,
+    pub call_count: i64,
+    /// Calls made directly by an entry function payload
+    pub entry_call_count: i64,
+    pub distinct_callers: i64,
+    pub last_called_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
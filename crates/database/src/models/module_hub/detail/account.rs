@@ -1,9 +1,68 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use sqlx::types::JsonValue;
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub const DOMAIN_VERIFIED_BY_WELL_KNOWN: &str = "well_known";
+pub const DOMAIN_VERIFIED_BY_DNS_TXT: &str = "dns_txt";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct AccountDetail {
     pub id: i32,
     pub address: String,
     pub alias: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub website: Option<String>,
+    /// Platform name to profile url
+    #[schema(value_type = Option<Value>)]
+    pub social_links: Option<JsonValue>,
+    pub description: Option<String>,
+    pub domain: Option<String>,
+    /// Only Some once `domain` was proven to belong to the account
+    pub domain_verified_by: Option<String>,
+    pub domain_verified_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewAccountProfile {
+    pub address: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub website: Option<String>,
+    pub social_links: Option<JsonValue>,
+    pub description: Option<String>,
+    pub domain: Option<String>,
+}
+
+impl NewAccountProfile {
+    pub fn new(
+        address: &str,
+        display_name: &Option<String>,
+        avatar_url: &Option<String>,
+        website: &Option<String>,
+        social_links: &Option<JsonValue>,
+        description: &Option<String>,
+        domain: &Option<String>,
+    ) -> Self {
+        NewAccountProfile {
+            address: address.to_string(),
+            display_name: display_name.clone(),
+            avatar_url: avatar_url.clone(),
+            website: website.clone(),
+            social_links: social_links.clone(),
+            description: description.clone(),
+            domain: domain.clone(),
+        }
+    }
+}
+
+/// A replaced alias, still redirecting to its former account during the grace period
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct AccountAliasHistory {
+    pub id: i32,
+    pub address: String,
+    pub alias: String,
+    pub replaced_at: NaiveDateTime,
 }
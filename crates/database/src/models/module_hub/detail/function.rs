@@ -1,15 +1,18 @@
 use serde::{Deserialize, Serialize};
 use sqlx::types::JsonValue;
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct ModuleFunctionDetail {
     pub id: i32,
     pub address: String,
     pub module_name: String,
     pub function_name: String,
     pub description: Option<String>,
+    #[schema(value_type = Option<Value>)]
     pub param_names: Option<JsonValue>,
+    #[schema(value_type = Option<Value>)]
     pub generic_type_params: Option<JsonValue>,
 }
 
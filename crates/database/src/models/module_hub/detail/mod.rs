@@ -1,3 +1,5 @@
 pub mod account;
 pub mod function;
 pub mod module;
+pub mod move_struct;
+pub mod resource_account;
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::JsonValue;
+use sqlx::FromRow;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ModuleStructDetail {
+    pub id: i32,
+    pub address: String,
+    pub module_name: String,
+    pub struct_name: String,
+    pub description: Option<String>,
+    /// Field name to description
+    #[schema(value_type = Option<Value>)]
+    pub field_descriptions: Option<JsonValue>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewModuleStructDetail {
+    pub address: String,
+    pub module_name: String,
+    pub struct_name: String,
+    pub description: Option<String>,
+    pub field_descriptions: Option<JsonValue>,
+}
+
+impl NewModuleStructDetail {
+    pub fn new(
+        address: &str,
+        module_name: &str,
+        struct_name: &str,
+        description: Option<String>,
+        field_descriptions: Option<HashMap<String, String>>,
+    ) -> Self {
+        NewModuleStructDetail {
+            address: address.to_string(),
+            module_name: module_name.to_string(),
+            struct_name: struct_name.to_string(),
+            description,
+            field_descriptions: field_descriptions
+                .map(|f| serde_json::to_value(f).unwrap_or_default()),
+        }
+    }
+}
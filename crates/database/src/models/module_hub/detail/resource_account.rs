@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Registered by the owner with a signed seed
+pub const RESOURCE_ACCOUNT_SOURCE_REGISTERED: &str = "registered";
+/// Found in a `0x1::resource_account::Container` of the owner
+pub const RESOURCE_ACCOUNT_SOURCE_DISCOVERED: &str = "discovered";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ResourceAccount {
+    pub id: i32,
+    pub owner_address: String,
+    pub resource_address: String,
+    /// Hex encoded, None for discovered accounts
+    pub seed: Option<String>,
+    pub source: String,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,44 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const CHALLENGE_PURPOSE_LOGIN: &str = "login";
+pub const CHALLENGE_PURPOSE_REVOKE_SESSIONS: &str = "revoke-sessions";
+pub const CHALLENGE_PURPOSE_DOMAIN_VERIFICATION: &str = "domain-verification";
+
+/// Issued to an address for one `purpose`, signed once and consumed by the request it was issued
+/// for so a signed body cannot be replayed
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct LoginChallenge {
+    pub challenge: String,
+    pub address: String,
+    pub purpose: String,
+    pub expires_at: NaiveDateTime,
+}
+
+/// Tokens are stored hashed, the plain tokens are only handed to the client
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct Session {
+    pub id: i32,
+    /// Only accepted on the network whose node the login was checked against
+    pub network: String,
+    pub address: String,
+    pub public_key: String,
+    pub token_hash: String,
+    pub refresh_token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub refresh_expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewSession {
+    pub network: String,
+    pub address: String,
+    pub public_key: String,
+    pub token_hash: String,
+    pub refresh_token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub refresh_expires_at: NaiveDateTime,
+}
@@ -0,0 +1,11 @@
+pub mod account;
+pub mod block_stack;
+pub mod dependency;
+pub mod function;
+pub mod health;
+pub mod memory;
+pub mod module;
+pub mod move_struct;
+pub mod resource_account;
+pub mod session;
+pub mod usage;
@@ -0,0 +1,288 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+use crate::models::module_hub::detail::account::{
+    AccountAliasHistory, AccountDetail, NewAccountProfile,
+};
+
+use async_trait::async_trait;
+use sqlx::{query, query_as};
+
+/// Accounts, their aliases and profiles are kept per network
+#[async_trait]
+pub trait AccountDetailRepository: Send + Sync {
+    async fn get_account_detail(
+        &self,
+        network: &str,
+        address: &str,
+    ) -> Result<Option<AccountDetail>, Error>;
+
+    /// Aliases are stored lowercase, `alias` is matched case insensitively
+    async fn get_account_detail_by_alias(
+        &self,
+        network: &str,
+        alias: &str,
+    ) -> Result<Option<AccountDetail>, Error>;
+
+    /// The latest replacement of `alias` within the last `grace_period_days`
+    async fn get_recent_alias_history(
+        &self,
+        network: &str,
+        alias: &str,
+        grace_period_days: i32,
+    ) -> Result<Option<AccountAliasHistory>, Error>;
+
+    /// Sets or clears the alias in one transaction, the replaced alias starts its grace period
+    /// and reclaiming an own former alias ends its redirect. `UniqueViolation` when it is taken
+    async fn set_account_alias(
+        &self,
+        network: &str,
+        address: &str,
+        alias: Option<&str>,
+    ) -> Result<(), Error>;
+
+    async fn insert_account_profile(
+        &self,
+        network: &str,
+        profile: &NewAccountProfile,
+    ) -> Result<(), Error>;
+
+    /// Domain verification is reset when the domain changes
+    async fn update_account_profile(
+        &self,
+        id: i32,
+        profile: &NewAccountProfile,
+    ) -> Result<(), Error>;
+
+    async fn set_domain_verified(&self, id: i32, verified_by: &str)
+        -> Result<AccountDetail, Error>;
+}
+
+#[async_trait]
+impl AccountDetailRepository for PostgresPool {
+    async fn get_account_detail(
+        &self,
+        network: &str,
+        address: &str,
+    ) -> Result<Option<AccountDetail>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM account_detail
+                WHERE
+                    network = $1
+                    AND
+                    address = $2
+                ORDER BY id DESC
+            ",
+        )
+        .bind(network)
+        .bind(address)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn get_account_detail_by_alias(
+        &self,
+        network: &str,
+        alias: &str,
+    ) -> Result<Option<AccountDetail>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM account_detail
+                WHERE
+                    network = $1
+                    AND
+                    alias = LOWER($2)
+            ",
+        )
+        .bind(network)
+        .bind(alias)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn get_recent_alias_history(
+        &self,
+        network: &str,
+        alias: &str,
+        grace_period_days: i32,
+    ) -> Result<Option<AccountAliasHistory>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM account_alias_history
+                WHERE
+                    network = $1
+                    AND
+                    alias = LOWER($2)
+                    AND
+                    replaced_at > now() - make_interval(days => $3)
+                ORDER BY replaced_at DESC
+                LIMIT 1
+            ",
+        )
+        .bind(network)
+        .bind(alias)
+        .bind(grace_period_days)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn set_account_alias(
+        &self,
+        network: &str,
+        address: &str,
+        alias: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut tx = self.begin().await?;
+
+        let maybe_account_detail: Option<AccountDetail> = query_as(
+            "
+                SELECT *
+                FROM account_detail
+                WHERE
+                    network = $1
+                    AND
+                    address = $2
+                ORDER BY id DESC
+            ",
+        )
+        .bind(network)
+        .bind(address)
+        .fetch_optional(&mut tx)
+        .await?;
+
+        let replaced_alias = maybe_account_detail
+            .as_ref()
+            .and_then(|account| account.alias.as_deref())
+            .filter(|replaced| Some(*replaced) != alias);
+
+        if let Some(replaced_alias) = replaced_alias {
+            query(
+                "INSERT INTO account_alias_history (network, address, alias) VALUES ($1, $2, $3)",
+            )
+            .bind(network)
+            .bind(address)
+            .bind(replaced_alias)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        if let Some(alias) = alias {
+            query(
+                "
+                    DELETE FROM account_alias_history
+                    WHERE
+                        network = $1
+                        AND
+                        address = $2
+                        AND
+                        alias = $3
+                ",
+            )
+            .bind(network)
+            .bind(address)
+            .bind(alias)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        match maybe_account_detail {
+            Some(account) => query("UPDATE account_detail SET alias = $1 WHERE id = $2")
+                .bind(alias)
+                .bind(account.id),
+            None => {
+                query("INSERT INTO account_detail (network, address, alias) VALUES ($1, $2, $3)")
+                    .bind(network)
+                    .bind(address)
+                    .bind(alias)
+            }
+        }
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn insert_account_profile(
+        &self,
+        network: &str,
+        profile: &NewAccountProfile,
+    ) -> Result<(), Error> {
+        query(
+            "
+                INSERT INTO account_detail
+                    (network, address, display_name, avatar_url, website, social_links, description, domain)
+                    VALUES
+                    ($1, $2, $3, $4, $5, $6, $7, $8)
+            ",
+        )
+        .bind(network)
+        .bind(&profile.address)
+        .bind(&profile.display_name)
+        .bind(&profile.avatar_url)
+        .bind(&profile.website)
+        .bind(&profile.social_links)
+        .bind(&profile.description)
+        .bind(&profile.domain)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_account_profile(
+        &self,
+        id: i32,
+        profile: &NewAccountProfile,
+    ) -> Result<(), Error> {
+        query(
+            "
+                UPDATE account_detail SET
+                    display_name = $1,
+                    avatar_url = $2,
+                    website = $3,
+                    social_links = $4,
+                    description = $5,
+                    domain = $6,
+                    domain_verified_by = CASE WHEN domain IS NOT DISTINCT FROM $6 THEN domain_verified_by END,
+                    domain_verified_at = CASE WHEN domain IS NOT DISTINCT FROM $6 THEN domain_verified_at END
+                WHERE id = $7
+            ",
+        )
+        .bind(&profile.display_name)
+        .bind(&profile.avatar_url)
+        .bind(&profile.website)
+        .bind(&profile.social_links)
+        .bind(&profile.description)
+        .bind(&profile.domain)
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_domain_verified(
+        &self,
+        id: i32,
+        verified_by: &str,
+    ) -> Result<AccountDetail, Error> {
+        Ok(query_as(
+            "
+                UPDATE account_detail SET
+                    domain_verified_by = $1,
+                    domain_verified_at = now()
+                WHERE id = $2
+                RETURNING *
+            ",
+        )
+        .bind(verified_by)
+        .bind(id)
+        .fetch_one(self)
+        .await?)
+    }
+}
@@ -0,0 +1,395 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+use crate::models::block_stack::compile_job::{CompileJob, COMPILE_JOB_STATUS_RUNNING};
+use crate::models::block_stack::stack::{BlockStack, NewBlockStack, BLOCK_STACK_STATUS_OK};
+
+use async_trait::async_trait;
+use sqlx::{query, query_as};
+
+#[async_trait]
+pub trait BlockStackRepository: Send + Sync {
+    async fn count_block_stacks_by_address(
+        &self,
+        network: &str,
+        address: &str,
+    ) -> Result<i64, Error>;
+
+    /// Ordered by id, only stacks after `after_id` when Some
+    async fn get_block_stacks_by_address(
+        &self,
+        network: &str,
+        address: &str,
+        after_id: Option<i32>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<BlockStack>, Error>;
+
+    /// None for stacks of other networks
+    async fn get_block_stack(&self, network: &str, id: i32) -> Result<Option<BlockStack>, Error>;
+
+    /// Stacks with a block calling into a module named `module_name` at any address
+    async fn get_block_stacks_by_module_name(
+        &self,
+        network: &str,
+        module_name: &str,
+    ) -> Result<Vec<BlockStack>, Error>;
+
+    /// Returns the id of the new stack
+    async fn insert_block_stack(
+        &self,
+        network: &str,
+        address: &str,
+        new_block_stack: &NewBlockStack,
+    ) -> Result<i32, Error>;
+
+    async fn update_block_stack(
+        &self,
+        id: i32,
+        new_block_stack: &NewBlockStack,
+    ) -> Result<(), Error>;
+
+    /// Stores compiled bytecode and marks the stack ok
+    async fn update_block_stack_bytecode(&self, id: i32, bytecode: &[u8]) -> Result<(), Error>;
+
+    async fn update_block_stack_status(
+        &self,
+        id: i32,
+        status: &str,
+        status_reason: Option<&str>,
+    ) -> Result<(), Error>;
+
+    async fn delete_block_stack(&self, id: i32) -> Result<(), Error>;
+
+    /// Every stack of the network ordered by id, only those in `ids` when Some
+    async fn get_block_stacks(
+        &self,
+        network: &str,
+        ids: Option<&[i32]>,
+    ) -> Result<Vec<BlockStack>, Error>;
+
+    /// Returns the id of the new job, running until finished
+    async fn insert_compile_job(&self, network: &str, block_stack_id: i32) -> Result<i32, Error>;
+
+    async fn finish_compile_job(
+        &self,
+        id: i32,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<(), Error>;
+
+    /// Newest first, every status when None
+    async fn get_compile_jobs(
+        &self,
+        network: &str,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<CompileJob>, Error>;
+}
+
+#[async_trait]
+impl BlockStackRepository for PostgresPool {
+    async fn count_block_stacks_by_address(
+        &self,
+        network: &str,
+        address: &str,
+    ) -> Result<i64, Error> {
+        let (count,): (i64,) = query_as(
+            "
+                SELECT COUNT(id) as count
+                FROM block_stack
+                WHERE
+                    network = $1
+                    AND
+                    address = $2
+            ",
+        )
+        .bind(network)
+        .bind(address)
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn get_block_stacks_by_address(
+        &self,
+        network: &str,
+        address: &str,
+        after_id: Option<i32>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<BlockStack>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM block_stack
+                WHERE
+                    network = $1
+                    AND
+                    address = $2
+                    AND
+                    (
+                        $3::INT IS NULL
+                        OR
+                        id > $3
+                    )
+                ORDER BY id ASC
+                OFFSET $4
+                LIMIT $5
+            ",
+        )
+        .bind(network)
+        .bind(address)
+        .bind(after_id)
+        .bind(offset)
+        .bind(limit)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn get_block_stack(&self, network: &str, id: i32) -> Result<Option<BlockStack>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM block_stack
+                WHERE
+                    id = $1
+                    AND
+                    network = $2
+            ",
+        )
+        .bind(id)
+        .bind(network)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn get_block_stacks_by_module_name(
+        &self,
+        network: &str,
+        module_name: &str,
+    ) -> Result<Vec<BlockStack>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM block_stack
+                WHERE
+                    network = $1
+                    AND
+                    EXISTS (
+                        SELECT 1
+                        FROM jsonb_array_elements(stack) block
+                        WHERE block->>'function' LIKE '%::' || $2 || '::%'
+                    )
+            ",
+        )
+        .bind(network)
+        .bind(module_name)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn insert_block_stack(
+        &self,
+        network: &str,
+        address: &str,
+        new_block_stack: &NewBlockStack,
+    ) -> Result<i32, Error> {
+        let (id,): (i32,) = query_as(
+            "
+                INSERT INTO block_stack
+                (network, address, name, stack) VALUES ($1, $2, $3, $4)
+                RETURNING id
+            ",
+        )
+        .bind(network)
+        .bind(address)
+        .bind(&new_block_stack.name)
+        .bind(&new_block_stack.stack)
+        .fetch_one(self)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn update_block_stack(
+        &self,
+        id: i32,
+        new_block_stack: &NewBlockStack,
+    ) -> Result<(), Error> {
+        query(
+            "
+                UPDATE block_stack
+                    SET
+                        stack = $1,
+                        name = $2,
+                        last_edit_datetime = now()
+                WHERE id = $3
+            ",
+        )
+        .bind(&new_block_stack.stack)
+        .bind(&new_block_stack.name)
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_block_stack_bytecode(&self, id: i32, bytecode: &[u8]) -> Result<(), Error> {
+        query(
+            "
+                UPDATE block_stack SET
+                    bytecode = $1,
+                    status = $2,
+                    status_reason = NULL,
+                    status_updated_at = now()
+                WHERE id = $3
+            ",
+        )
+        .bind(bytecode)
+        .bind(BLOCK_STACK_STATUS_OK)
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_block_stack_status(
+        &self,
+        id: i32,
+        status: &str,
+        status_reason: Option<&str>,
+    ) -> Result<(), Error> {
+        query(
+            "
+                UPDATE block_stack
+                    SET
+                        status = $1,
+                        status_reason = $2,
+                        status_updated_at = now()
+                WHERE id = $3
+            ",
+        )
+        .bind(status)
+        .bind(status_reason)
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_block_stack(&self, id: i32) -> Result<(), Error> {
+        query(
+            "
+                DELETE
+                FROM block_stack
+                WHERE id = $1
+            ",
+        )
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_block_stacks(
+        &self,
+        network: &str,
+        ids: Option<&[i32]>,
+    ) -> Result<Vec<BlockStack>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM block_stack
+                WHERE
+                    network = $1
+                    AND
+                    (
+                        $2::INT[] IS NULL
+                        OR
+                        id = ANY($2)
+                    )
+                ORDER BY id ASC
+            ",
+        )
+        .bind(network)
+        .bind(ids)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn insert_compile_job(&self, network: &str, block_stack_id: i32) -> Result<i32, Error> {
+        let (id,): (i32,) = query_as(
+            "
+                INSERT INTO compile_job
+                (block_stack_id, network, status) VALUES ($1, $2, $3)
+                RETURNING id
+            ",
+        )
+        .bind(block_stack_id)
+        .bind(network)
+        .bind(COMPILE_JOB_STATUS_RUNNING)
+        .fetch_one(self)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn finish_compile_job(
+        &self,
+        id: i32,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<(), Error> {
+        query(
+            "
+                UPDATE compile_job
+                    SET
+                        status = $1,
+                        error = $2,
+                        finished_at = now()
+                WHERE id = $3
+            ",
+        )
+        .bind(status)
+        .bind(error)
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_compile_jobs(
+        &self,
+        network: &str,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<CompileJob>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM compile_job
+                WHERE
+                    network = $1
+                    AND
+                    (
+                        $2::TEXT IS NULL
+                        OR
+                        status = $2
+                    )
+                ORDER BY id DESC
+                LIMIT $3
+            ",
+        )
+        .bind(network)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(self)
+        .await?)
+    }
+}
@@ -0,0 +1,176 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+use crate::models::module_hub::core::dependency::ModuleNode;
+
+use async_trait::async_trait;
+use sqlx::query_as;
+
+/// The module graph of each network, addresses are stored standardized
+#[async_trait]
+pub trait ModuleDependencyRepository: Send + Sync {
+    /// Modules the given module points at by `kind` edges, transitively up to `depth`. Ordered
+    /// by depth, a module reached twice is kept at its lowest depth
+    async fn get_dependencies(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        kind: &str,
+        depth: i32,
+    ) -> Result<Vec<ModuleNode>, Error>;
+
+    /// Modules pointing at the given module by `kind` edges, transitively up to `depth`
+    async fn get_dependents(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        kind: &str,
+        depth: i32,
+    ) -> Result<Vec<ModuleNode>, Error>;
+
+    /// Modules whose bytecode calls the given function, at depth 1
+    async fn get_function_dependents(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Vec<ModuleNode>, Error>;
+}
+
+#[async_trait]
+impl ModuleDependencyRepository for PostgresPool {
+    async fn get_dependencies(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        kind: &str,
+        depth: i32,
+    ) -> Result<Vec<ModuleNode>, Error> {
+        Ok(query_as(
+            "
+                WITH RECURSIVE graph AS (
+                    SELECT dependency_address, dependency_module_name, 1 AS depth
+                    FROM module_dependency
+                    WHERE
+                        network = $5
+                        AND
+                        address = $1
+                        AND
+                        module_name = $2
+                        AND
+                        kind = $3
+                    UNION
+                    SELECT d.dependency_address, d.dependency_module_name, graph.depth + 1
+                    FROM module_dependency d
+                    JOIN graph ON
+                        d.network = $5
+                        AND
+                        d.address = graph.dependency_address
+                        AND
+                        d.module_name = graph.dependency_module_name
+                    WHERE
+                        graph.depth < $4
+                        AND
+                        d.kind = $3
+                )
+                SELECT
+                    dependency_address as address,
+                    dependency_module_name as module_name,
+                    MIN(depth) as depth
+                FROM graph
+                GROUP BY dependency_address, dependency_module_name
+                ORDER BY depth, address, module_name
+            ",
+        )
+        .bind(address)
+        .bind(module_name)
+        .bind(kind)
+        .bind(depth)
+        .bind(network)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn get_dependents(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        kind: &str,
+        depth: i32,
+    ) -> Result<Vec<ModuleNode>, Error> {
+        Ok(query_as(
+            "
+                WITH RECURSIVE graph AS (
+                    SELECT address, module_name, 1 AS depth
+                    FROM module_dependency
+                    WHERE
+                        network = $5
+                        AND
+                        dependency_address = $1
+                        AND
+                        dependency_module_name = $2
+                        AND
+                        kind = $3
+                    UNION
+                    SELECT d.address, d.module_name, graph.depth + 1
+                    FROM module_dependency d
+                    JOIN graph ON
+                        d.network = $5
+                        AND
+                        d.dependency_address = graph.address
+                        AND
+                        d.dependency_module_name = graph.module_name
+                    WHERE
+                        graph.depth < $4
+                        AND
+                        d.kind = $3
+                )
+                SELECT address, module_name, MIN(depth) as depth
+                FROM graph
+                GROUP BY address, module_name
+                ORDER BY depth, address, module_name
+            ",
+        )
+        .bind(address)
+        .bind(module_name)
+        .bind(kind)
+        .bind(depth)
+        .bind(network)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn get_function_dependents(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Vec<ModuleNode>, Error> {
+        Ok(query_as(
+            "
+                SELECT address, module_name, 1 as depth
+                FROM module_function_usage
+                WHERE
+                    network = $4
+                    AND
+                    function_address = $1
+                    AND
+                    function_module_name = $2
+                    AND
+                    function_name = $3
+                ORDER BY address, module_name
+            ",
+        )
+        .bind(address)
+        .bind(module_name)
+        .bind(function_name)
+        .bind(network)
+        .fetch_all(self)
+        .await?)
+    }
+}
@@ -0,0 +1,405 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+use crate::models::module_hub::core::function::ModuleFunction;
+use crate::models::module_hub::detail::function::{ModuleFunctionDetail, NewModuleFunctionDetail};
+
+use async_trait::async_trait;
+use sqlx::{query, query_as};
+
+/// Narrows the latest row per function, the default matches every function
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FunctionFilter {
+    /// Matched against the module address, module name and function name
+    pub keyword: Option<String>,
+    /// The address `keyword` resolves to as an account alias
+    pub alias_address: Option<String>,
+    pub entry_only: bool,
+}
+
+/// `module_function` rows of the function indexer, every module upgrade adds rows. Functions
+/// whose latest row is deleted are left out of every read
+#[async_trait]
+pub trait FunctionIndexRepository: Send + Sync {
+    /// Latest row per function, like the lists
+    async fn count_functions(&self, entry_only: bool) -> Result<i64, Error>;
+
+    async fn count_latest_functions(&self, filter: &FunctionFilter) -> Result<i64, Error>;
+
+    /// Ordered by address, module name and name, after `after` when Some
+    async fn get_latest_functions(
+        &self,
+        filter: &FunctionFilter,
+        after: Option<(&str, &str, &str)>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ModuleFunction>, Error>;
+
+    async fn get_latest_module_functions(
+        &self,
+        address: &str,
+        module_name: &str,
+    ) -> Result<Vec<ModuleFunction>, Error>;
+
+    async fn get_latest_function(
+        &self,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Option<ModuleFunction>, Error>;
+
+    /// Latest functions naming `address::module::Struct` as a whole type in their params or
+    /// return types, so `0x1::coin::Coin` does not match `0x1::coin::CoinStore`
+    async fn count_latest_functions_by_struct_type(&self, struct_type: &str) -> Result<i64, Error>;
+
+    async fn get_latest_functions_by_struct_type(
+        &self,
+        struct_type: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ModuleFunction>, Error>;
+}
+
+#[async_trait]
+pub trait FunctionDetailRepository: Send + Sync {
+    /// The latest detail registered for the function on `network`
+    async fn get_function_detail(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Option<ModuleFunctionDetail>, Error>;
+
+    async fn insert_function_detail(
+        &self,
+        network: &str,
+        function_detail: &NewModuleFunctionDetail,
+    ) -> Result<(), Error>;
+
+    async fn update_function_detail(
+        &self,
+        id: i32,
+        function_detail: &NewModuleFunctionDetail,
+    ) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl FunctionIndexRepository for PostgresPool {
+    async fn count_functions(&self, entry_only: bool) -> Result<i64, Error> {
+        let (count,): (i64,) = query_as(
+            "
+                SELECT COUNT (1)
+                FROM (
+                    SELECT DISTINCT ON (module_address, module_name, name) *
+                        FROM module_function
+                        WHERE
+                            $1 = FALSE
+                            OR
+                            is_entry = TRUE
+                        ORDER BY module_address, module_name, name, id DESC
+                ) latest
+                WHERE NOT is_deleted
+            ",
+        )
+        .bind(entry_only)
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn count_latest_functions(&self, filter: &FunctionFilter) -> Result<i64, Error> {
+        let (count,): (i64,) = query_as(
+            "
+                SELECT COUNT (1)
+                FROM (
+                    SELECT DISTINCT ON (module_address, module_name, name) *
+                        FROM module_function
+                        WHERE
+                            (
+                                $1::TEXT IS NULL
+                                OR
+                                module_address = $1
+                                OR
+                                module_address = $2
+                                OR
+                                module_name = $1
+                                OR
+                                name = $1
+                            )
+                            AND
+                            (
+                                $3 = FALSE
+                                OR
+                                is_entry = TRUE
+                            )
+                        ORDER BY module_address, module_name, name, id DESC
+                ) latest
+                WHERE NOT is_deleted
+            ",
+        )
+        .bind(&filter.keyword)
+        .bind(&filter.alias_address)
+        .bind(filter.entry_only)
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn get_latest_functions(
+        &self,
+        filter: &FunctionFilter,
+        after: Option<(&str, &str, &str)>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ModuleFunction>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM (
+                    SELECT DISTINCT ON (module_address, module_name, name) *
+                        FROM module_function
+                        WHERE
+                            (
+                                $1::TEXT IS NULL
+                                OR
+                                module_address = $1
+                                OR
+                                module_address = $2
+                                OR
+                                module_name = $1
+                                OR
+                                name = $1
+                            )
+                            AND
+                            (
+                                $3 = FALSE
+                                OR
+                                is_entry = TRUE
+                            )
+                            AND
+                            (
+                                $4::TEXT IS NULL
+                                OR
+                                (module_address, module_name, name) > ($4, $5, $6)
+                            )
+                        ORDER BY module_address, module_name, name, id DESC
+                ) latest
+                WHERE NOT is_deleted
+                ORDER BY module_address, module_name, name
+                OFFSET $7
+                LIMIT $8
+            ",
+        )
+        .bind(&filter.keyword)
+        .bind(&filter.alias_address)
+        .bind(filter.entry_only)
+        .bind(after.map(|(address, _, _)| address))
+        .bind(after.map(|(_, module_name, _)| module_name))
+        .bind(after.map(|(_, _, name)| name))
+        .bind(offset)
+        .bind(limit)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn get_latest_module_functions(
+        &self,
+        address: &str,
+        module_name: &str,
+    ) -> Result<Vec<ModuleFunction>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM (
+                    SELECT DISTINCT ON (module_address, module_name, name) *
+                        FROM module_function
+                        WHERE
+                            module_address = $1
+                            AND
+                            module_name = $2
+                        ORDER BY module_address, module_name, name, id DESC
+                ) latest
+                WHERE NOT is_deleted
+                ORDER BY module_address, module_name, name
+            ",
+        )
+        .bind(address)
+        .bind(module_name)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn get_latest_function(
+        &self,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Option<ModuleFunction>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM (
+                    SELECT DISTINCT ON (module_address, module_name, name) *
+                        FROM module_function
+                        WHERE
+                            module_address = $1
+                            AND
+                            module_name = $2
+                            AND
+                            name = $3
+                        ORDER BY module_address, module_name, name, id DESC
+                ) latest
+                WHERE NOT is_deleted
+            ",
+        )
+        .bind(address)
+        .bind(module_name)
+        .bind(function_name)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn count_latest_functions_by_struct_type(&self, struct_type: &str) -> Result<i64, Error> {
+        let (count,): (i64,) = query_as(
+            "
+                SELECT COUNT (1)
+                FROM (
+                    SELECT DISTINCT ON (module_address, module_name, name) *
+                        FROM module_function
+                        WHERE
+                            params::TEXT ~ $1
+                            OR
+                            return_types::TEXT ~ $1
+                        ORDER BY module_address, module_name, name, id DESC
+                ) latest
+                WHERE NOT is_deleted
+            ",
+        )
+        .bind(struct_type_pattern(struct_type))
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn get_latest_functions_by_struct_type(
+        &self,
+        struct_type: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ModuleFunction>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM (
+                    SELECT DISTINCT ON (module_address, module_name, name) *
+                        FROM module_function
+                        WHERE
+                            params::TEXT ~ $1
+                            OR
+                            return_types::TEXT ~ $1
+                        ORDER BY module_address, module_name, name, id DESC
+                ) latest
+                WHERE NOT is_deleted
+                ORDER BY module_address, module_name, name
+                OFFSET $2
+                LIMIT $3
+            ",
+        )
+        .bind(struct_type_pattern(struct_type))
+        .bind(offset)
+        .bind(limit)
+        .fetch_all(self)
+        .await?)
+    }
+}
+
+/// `struct_type` is validated at upper layer and holds no regex metacharacters
+fn struct_type_pattern(struct_type: &str) -> String {
+    format!("(?<![A-Za-z0-9_:]){}(?![A-Za-z0-9_:])", struct_type)
+}
+
+#[async_trait]
+impl FunctionDetailRepository for PostgresPool {
+    async fn get_function_detail(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Option<ModuleFunctionDetail>, Error> {
+        Ok(query_as(
+            "
+                SELECT DISTINCT ON (address, module_name, function_name) *
+                FROM module_function_detail
+                WHERE
+                    network = $1
+                    AND
+                    address = $2
+                    AND
+                    module_name = $3
+                    AND
+                    function_name = $4
+                ORDER BY address, module_name, function_name, id DESC
+            ",
+        )
+        .bind(network)
+        .bind(address)
+        .bind(module_name)
+        .bind(function_name)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn insert_function_detail(
+        &self,
+        network: &str,
+        function_detail: &NewModuleFunctionDetail,
+    ) -> Result<(), Error> {
+        query(
+            "
+                INSERT INTO module_function_detail
+                    (network, address, module_name, function_name, description, param_names, generic_type_params)
+                    VALUES
+                    ($1, $2, $3, $4, $5, $6, $7)
+            ",
+        )
+        .bind(network)
+        .bind(&function_detail.address)
+        .bind(&function_detail.module_name)
+        .bind(&function_detail.function_name)
+        .bind(&function_detail.description)
+        .bind(&function_detail.param_names)
+        .bind(&function_detail.generic_type_params)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_function_detail(
+        &self,
+        id: i32,
+        function_detail: &NewModuleFunctionDetail,
+    ) -> Result<(), Error> {
+        query(
+            "
+                UPDATE module_function_detail SET
+                    description = $1,
+                    param_names = $2,
+                    generic_type_params = $3
+                WHERE id = $4
+            ",
+        )
+        .bind(&function_detail.description)
+        .bind(&function_detail.param_names)
+        .bind(&function_detail.generic_type_params)
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+
+use async_trait::async_trait;
+use sqlx::{query, query_as};
+
+#[async_trait]
+pub trait HealthRepository: Send + Sync {
+    /// A round trip to the database
+    async fn ping(&self) -> Result<(), Error>;
+
+    /// The latest transaction the full indexer wrote, None before the first one
+    async fn get_indexed_version(&self) -> Result<Option<i64>, Error>;
+}
+
+#[async_trait]
+impl HealthRepository for PostgresPool {
+    async fn ping(&self) -> Result<(), Error> {
+        query("SELECT 1").execute(self).await?;
+        Ok(())
+    }
+
+    async fn get_indexed_version(&self) -> Result<Option<i64>, Error> {
+        let (version,): (Option<i64>,) =
+            query_as("SELECT MAX(version) as version FROM transactions")
+                .fetch_one(self)
+                .await?;
+
+        Ok(version)
+    }
+}
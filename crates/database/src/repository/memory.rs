@@ -0,0 +1,1384 @@
+use crate::error::Error;
+use crate::models::block_stack::compile_job::{CompileJob, COMPILE_JOB_STATUS_RUNNING};
+use crate::models::block_stack::stack::{BlockStack, NewBlockStack, BLOCK_STACK_STATUS_OK};
+use crate::models::module_hub::core::dependency::{
+    ModuleDependency, ModuleFunctionUsage, ModuleNode,
+};
+use crate::models::module_hub::core::function::ModuleFunction;
+use crate::models::module_hub::core::module::{ModuleVersion, MoveModule};
+use crate::models::module_hub::core::usage::FunctionUsage;
+use crate::models::module_hub::detail::account::{
+    AccountAliasHistory, AccountDetail, NewAccountProfile,
+};
+use crate::models::module_hub::detail::function::{ModuleFunctionDetail, NewModuleFunctionDetail};
+use crate::models::module_hub::detail::module::{ModuleDetail, NewModuleDetail};
+use crate::models::module_hub::detail::move_struct::{ModuleStructDetail, NewModuleStructDetail};
+use crate::models::module_hub::detail::resource_account::{
+    ResourceAccount, RESOURCE_ACCOUNT_SOURCE_REGISTERED,
+};
+use crate::models::session::{LoginChallenge, NewSession, Session};
+use crate::repository::account::AccountDetailRepository;
+use crate::repository::block_stack::BlockStackRepository;
+use crate::repository::dependency::ModuleDependencyRepository;
+use crate::repository::function::{
+    FunctionDetailRepository, FunctionFilter, FunctionIndexRepository,
+};
+use crate::repository::health::HealthRepository;
+use crate::repository::module::{ModuleDetailRepository, OnChainModuleRepository};
+use crate::repository::move_struct::StructDetailRepository;
+use crate::repository::resource_account::ResourceAccountRepository;
+use crate::repository::session::SessionRepository;
+use crate::repository::usage::{FunctionUsageRepository, UsageFilter};
+
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+/// `ModuleDetail` does not carry its module, rows are keyed by network, address and module name
+type ModuleDetailRow = (String, String, String, ModuleDetail);
+
+/// Implements every repository over plain vectors, for testing domain logic without postgres.
+/// Clones share the rows like clones of a pool share the database
+#[derive(Clone, Debug, Default)]
+pub struct MemoryRepository {
+    pub block_stacks: Arc<Mutex<Vec<BlockStack>>>,
+    pub compile_jobs: Arc<Mutex<Vec<CompileJob>>>,
+    /// Keyed by network
+    pub account_details: Arc<Mutex<Vec<(String, AccountDetail)>>>,
+    /// Keyed by network
+    pub alias_history: Arc<Mutex<Vec<(String, AccountAliasHistory)>>>,
+    pub module_details: Arc<Mutex<Vec<ModuleDetailRow>>>,
+    /// Keyed by network
+    pub function_details: Arc<Mutex<Vec<(String, ModuleFunctionDetail)>>>,
+    /// Keyed by network
+    pub struct_details: Arc<Mutex<Vec<(String, ModuleStructDetail)>>>,
+    /// Keyed by network
+    pub resource_accounts: Arc<Mutex<Vec<(String, ResourceAccount)>>>,
+    /// Keyed by network. Calls are not recorded, refreshing keeps the rollup as it is
+    pub function_usages: Arc<Mutex<Vec<(String, FunctionUsage)>>>,
+    /// Keyed by network
+    pub module_dependencies: Arc<Mutex<Vec<(String, ModuleDependency)>>>,
+    /// Keyed by network
+    pub module_function_usages: Arc<Mutex<Vec<(String, ModuleFunctionUsage)>>>,
+    pub modules: Arc<Mutex<Vec<MoveModule>>>,
+    pub functions: Arc<Mutex<Vec<ModuleFunction>>>,
+    pub login_challenges: Arc<Mutex<Vec<LoginChallenge>>>,
+    pub sessions: Arc<Mutex<Vec<Session>>>,
+}
+
+/// The row with the highest `version` per key, ordered by key like `DISTINCT ON`
+fn latest_by_key<T, K, V>(
+    rows: impl IntoIterator<Item = T>,
+    key: impl Fn(&T) -> K,
+    version: impl Fn(&T) -> V,
+) -> Vec<T>
+where
+    K: Ord,
+    V: Ord,
+{
+    let mut latest: BTreeMap<K, T> = BTreeMap::new();
+    for row in rows {
+        match latest.get(&key(&row)) {
+            Some(current) if version(current) >= version(&row) => {}
+            _ => {
+                latest.insert(key(&row), row);
+            }
+        }
+    }
+
+    latest.into_values().collect()
+}
+
+fn next_id(ids: impl Iterator<Item = i32>) -> i32 {
+    ids.max().unwrap_or_default() + 1
+}
+
+fn is_of(addresses: &[String], address: &str) -> bool {
+    addresses.iter().any(|a| a == address)
+}
+
+fn matches_filter(function: &ModuleFunction, filter: &FunctionFilter) -> bool {
+    let matches_keyword = match &filter.keyword {
+        Some(keyword) => {
+            &function.module_address == keyword
+                || filter.alias_address.as_ref() == Some(&function.module_address)
+                || &function.module_name == keyword
+                || &function.name == keyword
+        }
+        None => true,
+    };
+
+    matches_keyword && (!filter.entry_only || function.is_entry)
+}
+
+fn function_key(function: &ModuleFunction) -> (String, String, String) {
+    (
+        function.module_address.clone(),
+        function.module_name.clone(),
+        function.name.clone(),
+    )
+}
+
+fn module_key(module: &MoveModule) -> (String, String) {
+    (module.name.clone(), module.address.clone())
+}
+
+/// Whole type names only, like the lookarounds of the postgres pattern
+fn names_type(types: &Option<serde_json::Value>, struct_type: &str) -> bool {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == ':';
+    let text = types.as_ref().map(|t| t.to_string()).unwrap_or_default();
+
+    text.match_indices(struct_type).any(|(i, _)| {
+        !text[..i].ends_with(is_name_char)
+            && !text[i + struct_type.len()..].starts_with(is_name_char)
+    })
+}
+
+fn matches_usage_filter(usage: &FunctionUsage, filter: &UsageFilter) -> bool {
+    let matches_keyword = match &filter.keyword {
+        Some(keyword) => {
+            &usage.module_address == keyword
+                || filter.alias_address.as_ref() == Some(&usage.module_address)
+                || &usage.module_name == keyword
+                || &usage.function_name == keyword
+        }
+        None => true,
+    };
+
+    usage.window_days == filter.window_days
+        && matches_keyword
+        && (!filter.entry_only || usage.entry_call_count > 0)
+}
+
+/// Breadth first, so every module is kept at the lowest depth it is reached at
+fn walk_graph<'a>(
+    edges: impl Iterator<Item = &'a ModuleDependency> + Clone,
+    start: (&str, &str),
+    kind: &str,
+    depth: i32,
+    reversed: bool,
+) -> Vec<ModuleNode> {
+    let mut reached: BTreeMap<(String, String), i32> = BTreeMap::new();
+    let mut frontier = vec![(start.0.to_string(), start.1.to_string())];
+
+    for level in 1..=depth {
+        let mut next = vec![];
+        for edge in edges.clone().filter(|e| e.kind == kind) {
+            let (from, to) = match reversed {
+                false => (
+                    (&edge.address, &edge.module_name),
+                    (&edge.dependency_address, &edge.dependency_module_name),
+                ),
+                true => (
+                    (&edge.dependency_address, &edge.dependency_module_name),
+                    (&edge.address, &edge.module_name),
+                ),
+            };
+
+            let to = (to.0.clone(), to.1.clone());
+            if frontier.iter().any(|(a, m)| (a, m) == from) && !reached.contains_key(&to) {
+                reached.insert(to.clone(), level);
+                next.push(to);
+            }
+        }
+        frontier = next;
+    }
+
+    let mut nodes = reached
+        .into_iter()
+        .map(|((address, module_name), depth)| ModuleNode {
+            address,
+            module_name,
+            depth,
+        })
+        .collect::<Vec<_>>();
+    nodes.sort_by_key(|n| n.depth);
+
+    nodes
+}
+
+#[async_trait]
+impl BlockStackRepository for MemoryRepository {
+    async fn count_block_stacks_by_address(
+        &self,
+        network: &str,
+        address: &str,
+    ) -> Result<i64, Error> {
+        let block_stacks = self.block_stacks.lock().unwrap();
+        Ok(block_stacks
+            .iter()
+            .filter(|b| b.network == network && b.address == address)
+            .count() as i64)
+    }
+
+    async fn get_block_stacks_by_address(
+        &self,
+        network: &str,
+        address: &str,
+        after_id: Option<i32>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<BlockStack>, Error> {
+        let mut block_stacks = self
+            .block_stacks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|b| {
+                b.network == network && b.address == address && after_id.is_none_or(|id| b.id > id)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        block_stacks.sort_by_key(|b| b.id);
+
+        Ok(block_stacks
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn get_block_stack(&self, network: &str, id: i32) -> Result<Option<BlockStack>, Error> {
+        let block_stacks = self.block_stacks.lock().unwrap();
+        Ok(block_stacks
+            .iter()
+            .find(|b| b.id == id && b.network == network)
+            .cloned())
+    }
+
+    async fn get_block_stacks_by_module_name(
+        &self,
+        network: &str,
+        module_name: &str,
+    ) -> Result<Vec<BlockStack>, Error> {
+        let pattern = format!("::{}::", module_name);
+        let block_stacks = self.block_stacks.lock().unwrap();
+
+        Ok(block_stacks
+            .iter()
+            .filter(|b| b.network == network)
+            .filter(|b| {
+                b.stack.as_array().into_iter().flatten().any(|block| {
+                    block["function"]
+                        .as_str()
+                        .is_some_and(|f| f.contains(&pattern))
+                })
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn insert_block_stack(
+        &self,
+        network: &str,
+        address: &str,
+        new_block_stack: &NewBlockStack,
+    ) -> Result<i32, Error> {
+        let mut block_stacks = self.block_stacks.lock().unwrap();
+        let id = next_id(block_stacks.iter().map(|b| b.id));
+        block_stacks.push(BlockStack {
+            id,
+            network: network.to_string(),
+            address: address.to_string(),
+            name: new_block_stack.name.clone(),
+            stack: new_block_stack.stack.clone(),
+            last_edit_datetime: Utc::now().naive_utc(),
+            status: BLOCK_STACK_STATUS_OK.to_string(),
+            ..Default::default()
+        });
+
+        Ok(id)
+    }
+
+    async fn update_block_stack(
+        &self,
+        id: i32,
+        new_block_stack: &NewBlockStack,
+    ) -> Result<(), Error> {
+        let mut block_stacks = self.block_stacks.lock().unwrap();
+        if let Some(block_stack) = block_stacks.iter_mut().find(|b| b.id == id) {
+            block_stack.name = new_block_stack.name.clone();
+            block_stack.stack = new_block_stack.stack.clone();
+            block_stack.last_edit_datetime = Utc::now().naive_utc();
+        }
+
+        Ok(())
+    }
+
+    async fn update_block_stack_bytecode(&self, id: i32, bytecode: &[u8]) -> Result<(), Error> {
+        let mut block_stacks = self.block_stacks.lock().unwrap();
+        if let Some(block_stack) = block_stacks.iter_mut().find(|b| b.id == id) {
+            block_stack.bytecode = Some(bytecode.to_vec());
+            block_stack.status = BLOCK_STACK_STATUS_OK.to_string();
+            block_stack.status_reason = None;
+            block_stack.status_updated_at = Some(Utc::now().naive_utc());
+        }
+
+        Ok(())
+    }
+
+    async fn update_block_stack_status(
+        &self,
+        id: i32,
+        status: &str,
+        status_reason: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut block_stacks = self.block_stacks.lock().unwrap();
+        if let Some(block_stack) = block_stacks.iter_mut().find(|b| b.id == id) {
+            block_stack.status = status.to_string();
+            block_stack.status_reason = status_reason.map(str::to_string);
+            block_stack.status_updated_at = Some(Utc::now().naive_utc());
+        }
+
+        Ok(())
+    }
+
+    async fn delete_block_stack(&self, id: i32) -> Result<(), Error> {
+        self.block_stacks.lock().unwrap().retain(|b| b.id != id);
+        self.compile_jobs
+            .lock()
+            .unwrap()
+            .retain(|j| j.block_stack_id != id);
+        Ok(())
+    }
+
+    async fn get_block_stacks(
+        &self,
+        network: &str,
+        ids: Option<&[i32]>,
+    ) -> Result<Vec<BlockStack>, Error> {
+        let mut block_stacks = self
+            .block_stacks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|b| b.network == network && ids.is_none_or(|ids| ids.contains(&b.id)))
+            .cloned()
+            .collect::<Vec<_>>();
+        block_stacks.sort_by_key(|b| b.id);
+
+        Ok(block_stacks)
+    }
+
+    async fn insert_compile_job(&self, network: &str, block_stack_id: i32) -> Result<i32, Error> {
+        let mut compile_jobs = self.compile_jobs.lock().unwrap();
+        let id = next_id(compile_jobs.iter().map(|j| j.id));
+        compile_jobs.push(CompileJob {
+            id,
+            block_stack_id,
+            network: network.to_string(),
+            status: COMPILE_JOB_STATUS_RUNNING.to_string(),
+            error: None,
+            started_at: Utc::now().naive_utc(),
+            finished_at: None,
+        });
+
+        Ok(id)
+    }
+
+    async fn finish_compile_job(
+        &self,
+        id: i32,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut compile_jobs = self.compile_jobs.lock().unwrap();
+        if let Some(compile_job) = compile_jobs.iter_mut().find(|j| j.id == id) {
+            compile_job.status = status.to_string();
+            compile_job.error = error.map(str::to_string);
+            compile_job.finished_at = Some(Utc::now().naive_utc());
+        }
+
+        Ok(())
+    }
+
+    async fn get_compile_jobs(
+        &self,
+        network: &str,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<CompileJob>, Error> {
+        let compile_jobs = self.compile_jobs.lock().unwrap();
+
+        Ok(compile_jobs
+            .iter()
+            .rev()
+            .filter(|j| j.network == network && status.is_none_or(|s| j.status == s))
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl AccountDetailRepository for MemoryRepository {
+    async fn get_account_detail(
+        &self,
+        network: &str,
+        address: &str,
+    ) -> Result<Option<AccountDetail>, Error> {
+        let account_details = self.account_details.lock().unwrap();
+        Ok(account_details
+            .iter()
+            .rev()
+            .find(|(n, a)| n == network && a.address == address)
+            .map(|(_, a)| a.clone()))
+    }
+
+    async fn get_account_detail_by_alias(
+        &self,
+        network: &str,
+        alias: &str,
+    ) -> Result<Option<AccountDetail>, Error> {
+        let alias = alias.to_lowercase();
+        let account_details = self.account_details.lock().unwrap();
+        Ok(account_details
+            .iter()
+            .find(|(n, a)| n == network && a.alias.as_ref() == Some(&alias))
+            .map(|(_, a)| a.clone()))
+    }
+
+    async fn get_recent_alias_history(
+        &self,
+        network: &str,
+        alias: &str,
+        grace_period_days: i32,
+    ) -> Result<Option<AccountAliasHistory>, Error> {
+        let alias = alias.to_lowercase();
+        let since = Utc::now().naive_utc() - Duration::days(grace_period_days.into());
+        let alias_history = self.alias_history.lock().unwrap();
+
+        Ok(alias_history
+            .iter()
+            .filter(|(n, h)| n == network && h.alias == alias && h.replaced_at > since)
+            .max_by_key(|(_, h)| h.replaced_at)
+            .map(|(_, h)| h.clone()))
+    }
+
+    async fn set_account_alias(
+        &self,
+        network: &str,
+        address: &str,
+        alias: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut account_details = self.account_details.lock().unwrap();
+        let mut alias_history = self.alias_history.lock().unwrap();
+
+        if alias.is_some()
+            && account_details
+                .iter()
+                .any(|(n, a)| n == network && a.address != address && a.alias.as_deref() == alias)
+        {
+            return Err(Error::UniqueViolation(
+                "idx_account_detail_network_alias".to_string(),
+            ));
+        }
+
+        let next_detail_id = next_id(account_details.iter().map(|(_, a)| a.id));
+        let account = account_details
+            .iter_mut()
+            .rev()
+            .find(|(n, a)| n == network && a.address == address)
+            .map(|(_, a)| a);
+
+        let replaced_alias = account
+            .as_ref()
+            .and_then(|account| account.alias.clone())
+            .filter(|replaced| Some(replaced.as_str()) != alias);
+
+        if let Some(replaced_alias) = replaced_alias {
+            let id = next_id(alias_history.iter().map(|(_, h)| h.id));
+            alias_history.push((
+                network.to_string(),
+                AccountAliasHistory {
+                    id,
+                    address: address.to_string(),
+                    alias: replaced_alias,
+                    replaced_at: Utc::now().naive_utc(),
+                },
+            ));
+        }
+
+        if let Some(alias) = alias {
+            alias_history
+                .retain(|(n, h)| !(n == network && h.address == address && h.alias == alias));
+        }
+
+        match account {
+            Some(account) => account.alias = alias.map(str::to_string),
+            None => {
+                account_details.push((
+                    network.to_string(),
+                    AccountDetail {
+                        id: next_detail_id,
+                        address: address.to_string(),
+                        alias: alias.map(str::to_string),
+                        display_name: None,
+                        avatar_url: None,
+                        website: None,
+                        social_links: None,
+                        description: None,
+                        domain: None,
+                        domain_verified_by: None,
+                        domain_verified_at: None,
+                    },
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn insert_account_profile(
+        &self,
+        network: &str,
+        profile: &NewAccountProfile,
+    ) -> Result<(), Error> {
+        let mut account_details = self.account_details.lock().unwrap();
+        let id = next_id(account_details.iter().map(|(_, a)| a.id));
+        account_details.push((
+            network.to_string(),
+            AccountDetail {
+                id,
+                address: profile.address.clone(),
+                alias: None,
+                display_name: profile.display_name.clone(),
+                avatar_url: profile.avatar_url.clone(),
+                website: profile.website.clone(),
+                social_links: profile.social_links.clone(),
+                description: profile.description.clone(),
+                domain: profile.domain.clone(),
+                domain_verified_by: None,
+                domain_verified_at: None,
+            },
+        ));
+
+        Ok(())
+    }
+
+    async fn update_account_profile(
+        &self,
+        id: i32,
+        profile: &NewAccountProfile,
+    ) -> Result<(), Error> {
+        let mut account_details = self.account_details.lock().unwrap();
+        if let Some((_, account)) = account_details.iter_mut().find(|(_, a)| a.id == id) {
+            if account.domain != profile.domain {
+                account.domain_verified_by = None;
+                account.domain_verified_at = None;
+            }
+            account.display_name = profile.display_name.clone();
+            account.avatar_url = profile.avatar_url.clone();
+            account.website = profile.website.clone();
+            account.social_links = profile.social_links.clone();
+            account.description = profile.description.clone();
+            account.domain = profile.domain.clone();
+        }
+
+        Ok(())
+    }
+
+    async fn set_domain_verified(
+        &self,
+        id: i32,
+        verified_by: &str,
+    ) -> Result<AccountDetail, Error> {
+        let mut account_details = self.account_details.lock().unwrap();
+        let (_, account) = account_details
+            .iter_mut()
+            .find(|(_, a)| a.id == id)
+            .ok_or(Error::SqlXError(sqlx::Error::RowNotFound))?;
+        account.domain_verified_by = Some(verified_by.to_string());
+        account.domain_verified_at = Some(Utc::now().naive_utc());
+
+        Ok(account.clone())
+    }
+}
+
+#[async_trait]
+impl OnChainModuleRepository for MemoryRepository {
+    async fn get_latest_modules(
+        &self,
+        addresses: &[String],
+        after: Option<(&str, &str)>,
+        limit: Option<i64>,
+    ) -> Result<Vec<MoveModule>, Error> {
+        let modules = self.modules.lock().unwrap();
+        let latest = latest_by_key(
+            modules
+                .iter()
+                .filter(|m| is_of(addresses, &m.address))
+                .cloned(),
+            module_key,
+            |m| m.transaction_version,
+        );
+
+        Ok(latest
+            .into_iter()
+            .filter(|m| after.is_none_or(|after| (m.name.as_str(), m.address.as_str()) > after))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect())
+    }
+
+    async fn count_modules(&self, addresses: &[String]) -> Result<i64, Error> {
+        let modules = self.modules.lock().unwrap();
+        let latest = latest_by_key(
+            modules.iter().filter(|m| is_of(addresses, &m.address)),
+            |m| module_key(m),
+            |m| m.transaction_version,
+        );
+
+        Ok(latest.len() as i64)
+    }
+
+    async fn count_modules_by_name(&self, module_name: &str) -> Result<i64, Error> {
+        let modules = self.modules.lock().unwrap();
+        Ok(modules.iter().filter(|m| m.name == module_name).count() as i64)
+    }
+
+    async fn get_latest_module(
+        &self,
+        address: &str,
+        module_name: &str,
+    ) -> Result<Option<MoveModule>, Error> {
+        let modules = self.modules.lock().unwrap();
+        Ok(modules
+            .iter()
+            .filter(|m| m.address == address && m.name == module_name)
+            .max_by_key(|m| m.transaction_version)
+            .cloned())
+    }
+
+    async fn get_module_versions(
+        &self,
+        addresses: &[String],
+        module_name: &str,
+    ) -> Result<Vec<ModuleVersion>, Error> {
+        let modules = self.modules.lock().unwrap();
+        let mut versions = modules
+            .iter()
+            .filter(|m| is_of(addresses, &m.address) && m.name == module_name)
+            .map(|m| ModuleVersion {
+                transaction_version: m.transaction_version,
+                transaction_block_height: m.transaction_block_height,
+                is_deleted: m.is_deleted,
+                inserted_at: m.inserted_at,
+            })
+            .collect::<Vec<_>>();
+        versions.sort_by_key(|v| v.transaction_version);
+
+        Ok(versions)
+    }
+
+    async fn get_module_at_version(
+        &self,
+        addresses: &[String],
+        module_name: &str,
+        transaction_version: Option<i64>,
+    ) -> Result<Option<MoveModule>, Error> {
+        let modules = self.modules.lock().unwrap();
+        Ok(modules
+            .iter()
+            .filter(|m| {
+                is_of(addresses, &m.address)
+                    && m.name == module_name
+                    && transaction_version.is_none_or(|v| m.transaction_version == v)
+            })
+            .max_by_key(|m| m.transaction_version)
+            .cloned())
+    }
+
+    async fn get_previous_module_version(
+        &self,
+        address: &str,
+        module_name: &str,
+        transaction_version: i64,
+    ) -> Result<Option<MoveModule>, Error> {
+        let modules = self.modules.lock().unwrap();
+        Ok(modules
+            .iter()
+            .filter(|m| {
+                m.address == address
+                    && m.name == module_name
+                    && m.transaction_version < transaction_version
+            })
+            .max_by_key(|m| m.transaction_version)
+            .cloned())
+    }
+}
+
+#[async_trait]
+impl ModuleDetailRepository for MemoryRepository {
+    async fn get_module_detail(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+    ) -> Result<Option<ModuleDetail>, Error> {
+        let module_details = self.module_details.lock().unwrap();
+        Ok(module_details
+            .iter()
+            .filter(|(n, a, m, _)| n == network && a == address && m == module_name)
+            .map(|(_, _, _, detail)| detail)
+            .max_by_key(|detail| detail.id)
+            .cloned())
+    }
+
+    async fn insert_module_detail(
+        &self,
+        network: &str,
+        module_detail: &NewModuleDetail,
+    ) -> Result<(), Error> {
+        let mut module_details = self.module_details.lock().unwrap();
+        let id = next_id(module_details.iter().map(|(_, _, _, detail)| detail.id));
+        module_details.push((
+            network.to_string(),
+            module_detail.address.clone(),
+            module_detail.module_name.clone(),
+            ModuleDetail {
+                id,
+                description: module_detail.description.clone(),
+                github_url: module_detail.github_url.clone(),
+                rev: module_detail.rev.clone(),
+                subdir: module_detail.subdir.clone(),
+            },
+        ));
+
+        Ok(())
+    }
+
+    async fn update_module_detail(
+        &self,
+        id: i32,
+        module_detail: &NewModuleDetail,
+    ) -> Result<(), Error> {
+        let mut module_details = self.module_details.lock().unwrap();
+        if let Some((_, _, _, detail)) = module_details.iter_mut().find(|(_, _, _, d)| d.id == id) {
+            detail.description = module_detail.description.clone();
+            detail.github_url = module_detail.github_url.clone();
+            detail.rev = module_detail.rev.clone();
+            detail.subdir = module_detail.subdir.clone();
+        }
+
+        Ok(())
+    }
+
+    async fn get_module_details_with_source(
+        &self,
+        network: &str,
+    ) -> Result<Vec<NewModuleDetail>, Error> {
+        let module_details = self.module_details.lock().unwrap();
+        let latest = latest_by_key(
+            module_details.iter().filter(|(n, _, _, _)| n == network),
+            |(_, address, module_name, _)| (address.clone(), module_name.clone()),
+            |(_, _, _, detail)| detail.id,
+        );
+
+        Ok(latest
+            .into_iter()
+            .filter(|(_, _, _, detail)| detail.github_url.is_some())
+            .map(|(_, address, module_name, detail)| {
+                NewModuleDetail::new(
+                    address,
+                    module_name,
+                    &detail.description,
+                    &detail.github_url,
+                    &detail.rev,
+                    &detail.subdir,
+                )
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl FunctionIndexRepository for MemoryRepository {
+    async fn count_functions(&self, entry_only: bool) -> Result<i64, Error> {
+        let functions = self.functions.lock().unwrap();
+        let latest = latest_by_key(
+            functions.iter().filter(|f| !entry_only || f.is_entry),
+            |f| function_key(f),
+            |f| f.id,
+        );
+
+        Ok(latest.into_iter().filter(|f| !f.is_deleted).count() as i64)
+    }
+
+    async fn count_latest_functions(&self, filter: &FunctionFilter) -> Result<i64, Error> {
+        let functions = self.functions.lock().unwrap();
+        let latest = latest_by_key(
+            functions.iter().filter(|f| matches_filter(f, filter)),
+            |f| function_key(f),
+            |f| f.id,
+        );
+
+        Ok(latest.into_iter().filter(|f| !f.is_deleted).count() as i64)
+    }
+
+    async fn get_latest_functions(
+        &self,
+        filter: &FunctionFilter,
+        after: Option<(&str, &str, &str)>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ModuleFunction>, Error> {
+        let functions = self.functions.lock().unwrap();
+        let latest = latest_by_key(
+            functions
+                .iter()
+                .filter(|f| matches_filter(f, filter))
+                .cloned(),
+            function_key,
+            |f| f.id,
+        );
+
+        Ok(latest
+            .into_iter()
+            .filter(|f| !f.is_deleted)
+            .filter(|f| {
+                after.is_none_or(|after| {
+                    (
+                        f.module_address.as_str(),
+                        f.module_name.as_str(),
+                        f.name.as_str(),
+                    ) > after
+                })
+            })
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn get_latest_module_functions(
+        &self,
+        address: &str,
+        module_name: &str,
+    ) -> Result<Vec<ModuleFunction>, Error> {
+        let functions = self.functions.lock().unwrap();
+        Ok(latest_by_key(
+            functions
+                .iter()
+                .filter(|f| f.module_address == address && f.module_name == module_name)
+                .cloned(),
+            function_key,
+            |f| f.id,
+        )
+        .into_iter()
+        .filter(|f| !f.is_deleted)
+        .collect())
+    }
+
+    async fn get_latest_function(
+        &self,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Option<ModuleFunction>, Error> {
+        let functions = self.functions.lock().unwrap();
+        Ok(functions
+            .iter()
+            .filter(|f| {
+                f.module_address == address
+                    && f.module_name == module_name
+                    && f.name == function_name
+            })
+            .max_by_key(|f| f.id)
+            .filter(|f| !f.is_deleted)
+            .cloned())
+    }
+
+    async fn count_latest_functions_by_struct_type(&self, struct_type: &str) -> Result<i64, Error> {
+        let functions = self.functions.lock().unwrap();
+        let latest = latest_by_key(functions.iter(), |f| function_key(f), |f| f.id);
+
+        Ok(latest
+            .into_iter()
+            .filter(|f| {
+                !f.is_deleted
+                    && (names_type(&f.params, struct_type)
+                        || names_type(&f.return_types, struct_type))
+            })
+            .count() as i64)
+    }
+
+    async fn get_latest_functions_by_struct_type(
+        &self,
+        struct_type: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ModuleFunction>, Error> {
+        let functions = self.functions.lock().unwrap();
+        let latest = latest_by_key(functions.iter().cloned(), function_key, |f| f.id);
+
+        Ok(latest
+            .into_iter()
+            .filter(|f| {
+                !f.is_deleted
+                    && (names_type(&f.params, struct_type)
+                        || names_type(&f.return_types, struct_type))
+            })
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl FunctionDetailRepository for MemoryRepository {
+    async fn get_function_detail(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Option<ModuleFunctionDetail>, Error> {
+        let function_details = self.function_details.lock().unwrap();
+        Ok(function_details
+            .iter()
+            .filter(|(n, _)| n == network)
+            .map(|(_, d)| d)
+            .filter(|d| {
+                d.address == address
+                    && d.module_name == module_name
+                    && d.function_name == function_name
+            })
+            .max_by_key(|d| d.id)
+            .cloned())
+    }
+
+    async fn insert_function_detail(
+        &self,
+        network: &str,
+        function_detail: &NewModuleFunctionDetail,
+    ) -> Result<(), Error> {
+        let mut function_details = self.function_details.lock().unwrap();
+        let id = next_id(function_details.iter().map(|(_, d)| d.id));
+        let detail = ModuleFunctionDetail {
+            id,
+            address: function_detail.address.clone(),
+            module_name: function_detail.module_name.clone(),
+            function_name: function_detail.function_name.clone(),
+            description: function_detail.description.clone(),
+            param_names: function_detail.param_names.clone(),
+            generic_type_params: function_detail.generic_type_params.clone(),
+        };
+        function_details.push((network.to_string(), detail));
+
+        Ok(())
+    }
+
+    async fn update_function_detail(
+        &self,
+        id: i32,
+        function_detail: &NewModuleFunctionDetail,
+    ) -> Result<(), Error> {
+        let mut function_details = self.function_details.lock().unwrap();
+        if let Some((_, detail)) = function_details.iter_mut().find(|(_, d)| d.id == id) {
+            detail.description = function_detail.description.clone();
+            detail.param_names = function_detail.param_names.clone();
+            detail.generic_type_params = function_detail.generic_type_params.clone();
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SessionRepository for MemoryRepository {
+    async fn insert_login_challenge(&self, challenge: &LoginChallenge) -> Result<(), Error> {
+        self.login_challenges
+            .lock()
+            .unwrap()
+            .push(challenge.clone());
+        Ok(())
+    }
+
+    async fn delete_expired_login_challenges(&self, now: NaiveDateTime) -> Result<u64, Error> {
+        let mut login_challenges = self.login_challenges.lock().unwrap();
+        let before = login_challenges.len();
+        login_challenges.retain(|c| c.expires_at >= now);
+
+        Ok((before - login_challenges.len()) as u64)
+    }
+
+    async fn take_login_challenge(
+        &self,
+        challenge: &str,
+        now: NaiveDateTime,
+    ) -> Result<Option<LoginChallenge>, Error> {
+        let mut login_challenges = self.login_challenges.lock().unwrap();
+        login_challenges.retain(|c| c.expires_at >= now);
+
+        let index = login_challenges
+            .iter()
+            .position(|c| c.challenge == challenge);
+        Ok(index.map(|index| login_challenges.remove(index)))
+    }
+
+    async fn insert_session(&self, new_session: &NewSession) -> Result<i32, Error> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let id = next_id(sessions.iter().map(|s| s.id));
+        sessions.push(Session {
+            id,
+            network: new_session.network.clone(),
+            address: new_session.address.clone(),
+            public_key: new_session.public_key.clone(),
+            token_hash: new_session.token_hash.clone(),
+            refresh_token_hash: new_session.refresh_token_hash.clone(),
+            expires_at: new_session.expires_at,
+            refresh_expires_at: new_session.refresh_expires_at,
+            revoked_at: None,
+            created_at: Utc::now().naive_utc(),
+        });
+
+        Ok(id)
+    }
+
+    async fn get_session_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, Error> {
+        let sessions = self.sessions.lock().unwrap();
+        Ok(sessions
+            .iter()
+            .find(|s| s.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn get_session_by_refresh_token_hash(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<Option<Session>, Error> {
+        let sessions = self.sessions.lock().unwrap();
+        Ok(sessions
+            .iter()
+            .find(|s| s.refresh_token_hash == refresh_token_hash)
+            .cloned())
+    }
+
+    async fn revoke_session(&self, id: i32, revoked_at: NaiveDateTime) -> Result<bool, Error> {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions
+            .iter_mut()
+            .find(|s| s.id == id && s.revoked_at.is_none())
+        {
+            Some(session) => {
+                session.revoked_at = Some(revoked_at);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn revoke_sessions_by_address(
+        &self,
+        network: &str,
+        address: &str,
+        revoked_at: NaiveDateTime,
+    ) -> Result<u64, Error> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let mut revoked = 0;
+        for session in sessions
+            .iter_mut()
+            .filter(|s| s.network == network && s.address == address && s.revoked_at.is_none())
+        {
+            session.revoked_at = Some(revoked_at);
+            revoked += 1;
+        }
+
+        Ok(revoked)
+    }
+}
+
+#[async_trait]
+impl StructDetailRepository for MemoryRepository {
+    async fn get_struct_detail(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        struct_name: &str,
+    ) -> Result<Option<ModuleStructDetail>, Error> {
+        let struct_details = self.struct_details.lock().unwrap();
+        Ok(struct_details
+            .iter()
+            .filter(|(n, _)| n == network)
+            .map(|(_, d)| d)
+            .filter(|d| {
+                d.address == address && d.module_name == module_name && d.struct_name == struct_name
+            })
+            .max_by_key(|d| d.id)
+            .cloned())
+    }
+
+    async fn insert_struct_detail(
+        &self,
+        network: &str,
+        struct_detail: &NewModuleStructDetail,
+    ) -> Result<(), Error> {
+        let mut struct_details = self.struct_details.lock().unwrap();
+        let id = next_id(struct_details.iter().map(|(_, d)| d.id));
+        let detail = ModuleStructDetail {
+            id,
+            address: struct_detail.address.clone(),
+            module_name: struct_detail.module_name.clone(),
+            struct_name: struct_detail.struct_name.clone(),
+            description: struct_detail.description.clone(),
+            field_descriptions: struct_detail.field_descriptions.clone(),
+        };
+        struct_details.push((network.to_string(), detail));
+
+        Ok(())
+    }
+
+    async fn update_struct_detail(
+        &self,
+        id: i32,
+        struct_detail: &NewModuleStructDetail,
+    ) -> Result<(), Error> {
+        let mut struct_details = self.struct_details.lock().unwrap();
+        if let Some((_, detail)) = struct_details.iter_mut().find(|(_, d)| d.id == id) {
+            detail.description = struct_detail.description.clone();
+            detail.field_descriptions = struct_detail.field_descriptions.clone();
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ResourceAccountRepository for MemoryRepository {
+    async fn save_registered_resource_account(
+        &self,
+        network: &str,
+        owner_address: &str,
+        resource_address: &str,
+        seed_hex: &str,
+    ) -> Result<ResourceAccount, Error> {
+        let mut resource_accounts = self.resource_accounts.lock().unwrap();
+        let id = next_id(resource_accounts.iter().map(|(_, r)| r.id));
+
+        let index = match resource_accounts
+            .iter()
+            .position(|(n, r)| n == network && r.resource_address == resource_address)
+        {
+            Some(index) => index,
+            None => {
+                resource_accounts.push((
+                    network.to_string(),
+                    ResourceAccount {
+                        id,
+                        owner_address: owner_address.to_string(),
+                        resource_address: resource_address.to_string(),
+                        seed: None,
+                        source: RESOURCE_ACCOUNT_SOURCE_REGISTERED.to_string(),
+                        created_at: Utc::now().naive_utc(),
+                    },
+                ));
+                resource_accounts.len() - 1
+            }
+        };
+
+        let (_, resource_account) = &mut resource_accounts[index];
+        resource_account.seed = Some(seed_hex.to_string());
+        resource_account.source = RESOURCE_ACCOUNT_SOURCE_REGISTERED.to_string();
+
+        Ok(resource_account.clone())
+    }
+
+    async fn get_resource_accounts_by_owner(
+        &self,
+        network: &str,
+        owner_address: &str,
+    ) -> Result<Vec<ResourceAccount>, Error> {
+        let mut resource_accounts = self
+            .resource_accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(n, r)| n == network && r.owner_address == owner_address)
+            .map(|(_, r)| r.clone())
+            .collect::<Vec<_>>();
+        resource_accounts.sort_by_key(|r| r.id);
+
+        Ok(resource_accounts)
+    }
+
+    async fn get_resource_account(
+        &self,
+        network: &str,
+        owner_address: &str,
+        resource_address: &str,
+    ) -> Result<Option<ResourceAccount>, Error> {
+        let resource_accounts = self.resource_accounts.lock().unwrap();
+        Ok(resource_accounts
+            .iter()
+            .find(|(n, r)| {
+                n == network
+                    && r.owner_address == owner_address
+                    && r.resource_address == resource_address
+            })
+            .map(|(_, r)| r.clone()))
+    }
+}
+
+#[async_trait]
+impl FunctionUsageRepository for MemoryRepository {
+    async fn get_function_usages(
+        &self,
+        network: &str,
+        filter: &UsageFilter,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<FunctionUsage>, Error> {
+        let mut usages = self
+            .function_usages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(n, u)| n == network && matches_usage_filter(u, filter))
+            .map(|(_, u)| u.clone())
+            .collect::<Vec<_>>();
+        usages.sort_by(|a, b| {
+            b.call_count.cmp(&a.call_count).then_with(|| {
+                (&a.module_address, &a.module_name, &a.function_name).cmp(&(
+                    &b.module_address,
+                    &b.module_name,
+                    &b.function_name,
+                ))
+            })
+        });
+
+        Ok(usages
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn count_function_usages(
+        &self,
+        network: &str,
+        filter: &UsageFilter,
+    ) -> Result<i64, Error> {
+        let usages = self.function_usages.lock().unwrap();
+        Ok(usages
+            .iter()
+            .filter(|(n, u)| n == network && matches_usage_filter(u, filter))
+            .count() as i64)
+    }
+
+    async fn get_function_usage(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Vec<FunctionUsage>, Error> {
+        let mut usages = self
+            .function_usages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(n, u)| {
+                n == network
+                    && u.module_address == address
+                    && u.module_name == module_name
+                    && u.function_name == function_name
+            })
+            .map(|(_, u)| u.clone())
+            .collect::<Vec<_>>();
+        usages.sort_by_key(|u| u.window_days);
+
+        Ok(usages)
+    }
+
+    async fn refresh_function_usage(&self, _network: &str, _windows: &[i32]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ModuleDependencyRepository for MemoryRepository {
+    async fn get_dependencies(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        kind: &str,
+        depth: i32,
+    ) -> Result<Vec<ModuleNode>, Error> {
+        let module_dependencies = self.module_dependencies.lock().unwrap();
+        Ok(walk_graph(
+            module_dependencies
+                .iter()
+                .filter(|(n, _)| n == network)
+                .map(|(_, d)| d),
+            (address, module_name),
+            kind,
+            depth,
+            false,
+        ))
+    }
+
+    async fn get_dependents(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        kind: &str,
+        depth: i32,
+    ) -> Result<Vec<ModuleNode>, Error> {
+        let module_dependencies = self.module_dependencies.lock().unwrap();
+        Ok(walk_graph(
+            module_dependencies
+                .iter()
+                .filter(|(n, _)| n == network)
+                .map(|(_, d)| d),
+            (address, module_name),
+            kind,
+            depth,
+            true,
+        ))
+    }
+
+    async fn get_function_dependents(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Vec<ModuleNode>, Error> {
+        let module_function_usages = self.module_function_usages.lock().unwrap();
+        let callers = module_function_usages
+            .iter()
+            .filter(|(n, u)| {
+                n == network
+                    && u.function_address == address
+                    && u.function_module_name == module_name
+                    && u.function_name == function_name
+            })
+            .map(|(_, u)| (u.address.clone(), u.module_name.clone()))
+            .collect::<BTreeSet<_>>();
+
+        Ok(callers
+            .into_iter()
+            .map(|(address, module_name)| ModuleNode {
+                address,
+                module_name,
+                depth: 1,
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl HealthRepository for MemoryRepository {
+    async fn ping(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Transactions are not recorded, the latest module stands in for them
+    async fn get_indexed_version(&self) -> Result<Option<i64>, Error> {
+        let modules = self.modules.lock().unwrap();
+        Ok(modules.iter().map(|m| m.transaction_version).max())
+    }
+}
@@ -0,0 +1,350 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+use crate::models::module_hub::core::module::{ModuleVersion, MoveModule};
+use crate::models::module_hub::detail::module::{ModuleDetail, NewModuleDetail};
+
+use async_trait::async_trait;
+use sqlx::{query, query_as};
+
+/// Modules published on chain, every upgrade is a row of its own
+#[async_trait]
+pub trait OnChainModuleRepository: Send + Sync {
+    /// The latest version per module ordered by name and address, after `after` when Some
+    async fn get_latest_modules(
+        &self,
+        addresses: &[String],
+        after: Option<(&str, &str)>,
+        limit: Option<i64>,
+    ) -> Result<Vec<MoveModule>, Error>;
+
+    async fn count_modules(&self, addresses: &[String]) -> Result<i64, Error>;
+
+    /// Rows of modules named `module_name` at any address
+    async fn count_modules_by_name(&self, module_name: &str) -> Result<i64, Error>;
+
+    async fn get_latest_module(
+        &self,
+        address: &str,
+        module_name: &str,
+    ) -> Result<Option<MoveModule>, Error>;
+
+    /// Oldest first
+    async fn get_module_versions(
+        &self,
+        addresses: &[String],
+        module_name: &str,
+    ) -> Result<Vec<ModuleVersion>, Error>;
+
+    /// The module as published at `transaction_version`, the latest one when None
+    async fn get_module_at_version(
+        &self,
+        addresses: &[String],
+        module_name: &str,
+        transaction_version: Option<i64>,
+    ) -> Result<Option<MoveModule>, Error>;
+
+    /// The latest version published before `transaction_version`
+    async fn get_previous_module_version(
+        &self,
+        address: &str,
+        module_name: &str,
+        transaction_version: i64,
+    ) -> Result<Option<MoveModule>, Error>;
+}
+
+#[async_trait]
+pub trait ModuleDetailRepository: Send + Sync {
+    /// The latest detail registered for the module on `network`
+    async fn get_module_detail(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+    ) -> Result<Option<ModuleDetail>, Error>;
+
+    async fn insert_module_detail(
+        &self,
+        network: &str,
+        module_detail: &NewModuleDetail,
+    ) -> Result<(), Error>;
+
+    async fn update_module_detail(
+        &self,
+        id: i32,
+        module_detail: &NewModuleDetail,
+    ) -> Result<(), Error>;
+
+    /// The latest detail of every module on `network` that points at a github source
+    async fn get_module_details_with_source(
+        &self,
+        network: &str,
+    ) -> Result<Vec<NewModuleDetail>, Error>;
+}
+
+#[async_trait]
+impl OnChainModuleRepository for PostgresPool {
+    async fn get_latest_modules(
+        &self,
+        addresses: &[String],
+        after: Option<(&str, &str)>,
+        limit: Option<i64>,
+    ) -> Result<Vec<MoveModule>, Error> {
+        Ok(query_as(
+            "
+                SELECT DISTINCT ON (name, address) *
+                FROM move_modules
+                WHERE
+                    address = ANY($1)
+                    AND
+                    (
+                        $2::TEXT IS NULL
+                        OR
+                        (name, address) > ($2, $3)
+                    )
+                ORDER BY name, address, transaction_version DESC
+                LIMIT $4
+            ",
+        )
+        .bind(addresses)
+        .bind(after.map(|(name, _)| name))
+        .bind(after.map(|(_, address)| address))
+        .bind(limit)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn count_modules(&self, addresses: &[String]) -> Result<i64, Error> {
+        let (count,): (i64,) = query_as(
+            "
+                SELECT COUNT(DISTINCT (name, address)) as count
+                FROM move_modules
+                WHERE address = ANY($1)
+            ",
+        )
+        .bind(addresses)
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn count_modules_by_name(&self, module_name: &str) -> Result<i64, Error> {
+        let (count,): (i64,) = query_as(
+            "
+                SELECT COUNT(name) as count
+                FROM move_modules
+                WHERE name = $1
+            ",
+        )
+        .bind(module_name)
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn get_latest_module(
+        &self,
+        address: &str,
+        module_name: &str,
+    ) -> Result<Option<MoveModule>, Error> {
+        Ok(query_as(
+            "
+                SELECT DISTINCT ON (name, address) *
+                FROM move_modules
+                WHERE
+                    address = $1
+                    AND
+                    name = $2
+                ORDER BY name, address, transaction_version DESC
+            ",
+        )
+        .bind(address)
+        .bind(module_name)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn get_module_versions(
+        &self,
+        addresses: &[String],
+        module_name: &str,
+    ) -> Result<Vec<ModuleVersion>, Error> {
+        Ok(query_as(
+            "
+                SELECT transaction_version, transaction_block_height, is_deleted, inserted_at
+                FROM move_modules
+                WHERE
+                    address = ANY($1)
+                    AND
+                    name = $2
+                ORDER BY transaction_version
+            ",
+        )
+        .bind(addresses)
+        .bind(module_name)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn get_module_at_version(
+        &self,
+        addresses: &[String],
+        module_name: &str,
+        transaction_version: Option<i64>,
+    ) -> Result<Option<MoveModule>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM move_modules
+                WHERE
+                    address = ANY($1)
+                    AND
+                    name = $2
+                    AND
+                    (
+                        $3::BIGINT IS NULL
+                        OR
+                        transaction_version = $3
+                    )
+                ORDER BY transaction_version DESC
+                LIMIT 1
+            ",
+        )
+        .bind(addresses)
+        .bind(module_name)
+        .bind(transaction_version)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn get_previous_module_version(
+        &self,
+        address: &str,
+        module_name: &str,
+        transaction_version: i64,
+    ) -> Result<Option<MoveModule>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM move_modules
+                WHERE
+                    address = $1
+                    AND
+                    name = $2
+                    AND
+                    transaction_version < $3
+                ORDER BY transaction_version DESC
+                LIMIT 1
+            ",
+        )
+        .bind(address)
+        .bind(module_name)
+        .bind(transaction_version)
+        .fetch_optional(self)
+        .await?)
+    }
+}
+
+#[async_trait]
+impl ModuleDetailRepository for PostgresPool {
+    async fn get_module_detail(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+    ) -> Result<Option<ModuleDetail>, Error> {
+        Ok(query_as(
+            "
+                SELECT DISTINCT ON (address, module_name) *
+                FROM module_detail
+                WHERE
+                    network = $1
+                    AND
+                    address = $2
+                    AND
+                    module_name = $3
+                ORDER BY address, module_name, id DESC
+            ",
+        )
+        .bind(network)
+        .bind(address)
+        .bind(module_name)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn insert_module_detail(
+        &self,
+        network: &str,
+        module_detail: &NewModuleDetail,
+    ) -> Result<(), Error> {
+        query(
+            "
+                INSERT INTO module_detail
+                    (network, address, module_name, description, github_url, rev, subdir)
+                    VALUES
+                    ($1, $2, $3, $4, $5, $6, $7)
+            ",
+        )
+        .bind(network)
+        .bind(&module_detail.address)
+        .bind(&module_detail.module_name)
+        .bind(&module_detail.description)
+        .bind(&module_detail.github_url)
+        .bind(&module_detail.rev)
+        .bind(&module_detail.subdir)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_module_detail(
+        &self,
+        id: i32,
+        module_detail: &NewModuleDetail,
+    ) -> Result<(), Error> {
+        query(
+            "
+                UPDATE module_detail SET
+                    description = $1,
+                    github_url = $2,
+                    rev = $3,
+                    subdir = $4
+                WHERE id = $5
+            ",
+        )
+        .bind(&module_detail.description)
+        .bind(&module_detail.github_url)
+        .bind(&module_detail.rev)
+        .bind(&module_detail.subdir)
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_module_details_with_source(
+        &self,
+        network: &str,
+    ) -> Result<Vec<NewModuleDetail>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM (
+                    SELECT DISTINCT ON (address, module_name)
+                        address, module_name, description, github_url, rev, subdir
+                    FROM module_detail
+                    WHERE network = $1
+                    ORDER BY address, module_name, id DESC
+                ) latest
+                WHERE github_url IS NOT NULL
+            ",
+        )
+        .bind(network)
+        .fetch_all(self)
+        .await?)
+    }
+}
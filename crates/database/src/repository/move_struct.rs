@@ -0,0 +1,110 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+use crate::models::module_hub::detail::move_struct::{ModuleStructDetail, NewModuleStructDetail};
+
+use async_trait::async_trait;
+use sqlx::{query, query_as};
+
+#[async_trait]
+pub trait StructDetailRepository: Send + Sync {
+    /// The latest detail registered for the struct on `network`
+    async fn get_struct_detail(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        struct_name: &str,
+    ) -> Result<Option<ModuleStructDetail>, Error>;
+
+    async fn insert_struct_detail(
+        &self,
+        network: &str,
+        struct_detail: &NewModuleStructDetail,
+    ) -> Result<(), Error>;
+
+    async fn update_struct_detail(
+        &self,
+        id: i32,
+        struct_detail: &NewModuleStructDetail,
+    ) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl StructDetailRepository for PostgresPool {
+    async fn get_struct_detail(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        struct_name: &str,
+    ) -> Result<Option<ModuleStructDetail>, Error> {
+        Ok(query_as(
+            "
+                SELECT DISTINCT ON (address, module_name, struct_name) *
+                FROM module_struct_detail
+                WHERE
+                    network = $1
+                    AND
+                    address = $2
+                    AND
+                    module_name = $3
+                    AND
+                    struct_name = $4
+                ORDER BY address, module_name, struct_name, id DESC
+            ",
+        )
+        .bind(network)
+        .bind(address)
+        .bind(module_name)
+        .bind(struct_name)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn insert_struct_detail(
+        &self,
+        network: &str,
+        struct_detail: &NewModuleStructDetail,
+    ) -> Result<(), Error> {
+        query(
+            "
+                INSERT INTO module_struct_detail
+                    (network, address, module_name, struct_name, description, field_descriptions)
+                    VALUES
+                    ($1, $2, $3, $4, $5, $6)
+            ",
+        )
+        .bind(network)
+        .bind(&struct_detail.address)
+        .bind(&struct_detail.module_name)
+        .bind(&struct_detail.struct_name)
+        .bind(&struct_detail.description)
+        .bind(&struct_detail.field_descriptions)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_struct_detail(
+        &self,
+        id: i32,
+        struct_detail: &NewModuleStructDetail,
+    ) -> Result<(), Error> {
+        query(
+            "
+                UPDATE module_struct_detail SET
+                    description = $1,
+                    field_descriptions = $2
+                WHERE id = $3
+            ",
+        )
+        .bind(&struct_detail.description)
+        .bind(&struct_detail.field_descriptions)
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+}
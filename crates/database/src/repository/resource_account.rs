@@ -0,0 +1,139 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+use crate::models::module_hub::detail::resource_account::{
+    ResourceAccount, RESOURCE_ACCOUNT_SOURCE_DISCOVERED, RESOURCE_ACCOUNT_SOURCE_REGISTERED,
+};
+
+use async_trait::async_trait;
+use sqlx::{query, query_as, Postgres, Transaction};
+
+/// Kept per network, addresses are stored standardized and callers standardize before querying
+#[async_trait]
+pub trait ResourceAccountRepository: Send + Sync {
+    /// Takes over an account discovered before, its seed is now known
+    async fn save_registered_resource_account(
+        &self,
+        network: &str,
+        owner_address: &str,
+        resource_address: &str,
+        seed_hex: &str,
+    ) -> Result<ResourceAccount, Error>;
+
+    /// Oldest first
+    async fn get_resource_accounts_by_owner(
+        &self,
+        network: &str,
+        owner_address: &str,
+    ) -> Result<Vec<ResourceAccount>, Error>;
+
+    async fn get_resource_account(
+        &self,
+        network: &str,
+        owner_address: &str,
+        resource_address: &str,
+    ) -> Result<Option<ResourceAccount>, Error>;
+}
+
+/// Part of the indexer batch transaction, keeps the seed of an account registered before
+pub async fn save_discovered_resource_account(
+    tx: &mut Transaction<'_, Postgres>,
+    network: &str,
+    owner_address: &str,
+    resource_address: &str,
+) -> Result<(), Error> {
+    query(
+        "
+            INSERT INTO resource_account
+                (network, owner_address, resource_address, source)
+                VALUES
+                ($1, $2, $3, $4)
+            ON CONFLICT (network, resource_address) DO NOTHING
+        ",
+    )
+    .bind(network)
+    .bind(owner_address)
+    .bind(resource_address)
+    .bind(RESOURCE_ACCOUNT_SOURCE_DISCOVERED)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl ResourceAccountRepository for PostgresPool {
+    async fn save_registered_resource_account(
+        &self,
+        network: &str,
+        owner_address: &str,
+        resource_address: &str,
+        seed_hex: &str,
+    ) -> Result<ResourceAccount, Error> {
+        Ok(query_as(
+            "
+                INSERT INTO resource_account
+                    (network, owner_address, resource_address, seed, source)
+                    VALUES
+                    ($1, $2, $3, $4, $5)
+                ON CONFLICT (network, resource_address) DO UPDATE SET
+                    seed = EXCLUDED.seed,
+                    source = EXCLUDED.source
+                RETURNING *
+            ",
+        )
+        .bind(network)
+        .bind(owner_address)
+        .bind(resource_address)
+        .bind(seed_hex)
+        .bind(RESOURCE_ACCOUNT_SOURCE_REGISTERED)
+        .fetch_one(self)
+        .await?)
+    }
+
+    async fn get_resource_accounts_by_owner(
+        &self,
+        network: &str,
+        owner_address: &str,
+    ) -> Result<Vec<ResourceAccount>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM resource_account
+                WHERE
+                    network = $1
+                    AND
+                    owner_address = $2
+                ORDER BY id
+            ",
+        )
+        .bind(network)
+        .bind(owner_address)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn get_resource_account(
+        &self,
+        network: &str,
+        owner_address: &str,
+        resource_address: &str,
+    ) -> Result<Option<ResourceAccount>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM resource_account
+                WHERE
+                    network = $1
+                    AND
+                    owner_address = $2
+                    AND
+                    resource_address = $3
+            ",
+        )
+        .bind(network)
+        .bind(owner_address)
+        .bind(resource_address)
+        .fetch_optional(self)
+        .await?)
+    }
+}
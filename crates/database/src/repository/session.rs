@@ -0,0 +1,190 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+use crate::models::session::{LoginChallenge, NewSession, Session};
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::{query, query_as};
+
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    async fn insert_login_challenge(&self, challenge: &LoginChallenge) -> Result<(), Error>;
+
+    /// Returns how many challenges expired before `now` were dropped
+    async fn delete_expired_login_challenges(&self, now: NaiveDateTime) -> Result<u64, Error>;
+
+    /// Removes the challenge so it signs in once, expired challenges are dropped along the way
+    async fn take_login_challenge(
+        &self,
+        challenge: &str,
+        now: NaiveDateTime,
+    ) -> Result<Option<LoginChallenge>, Error>;
+
+    /// Returns the id of the new session
+    async fn insert_session(&self, new_session: &NewSession) -> Result<i32, Error>;
+
+    /// Revoked and expired sessions included
+    async fn get_session_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, Error>;
+
+    /// Revoked and expired sessions included
+    async fn get_session_by_refresh_token_hash(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<Option<Session>, Error>;
+
+    /// Returns whether a live session was revoked
+    async fn revoke_session(&self, id: i32, revoked_at: NaiveDateTime) -> Result<bool, Error>;
+
+    /// Returns how many live sessions of the address on the network were revoked
+    async fn revoke_sessions_by_address(
+        &self,
+        network: &str,
+        address: &str,
+        revoked_at: NaiveDateTime,
+    ) -> Result<u64, Error>;
+}
+
+#[async_trait]
+impl SessionRepository for PostgresPool {
+    async fn insert_login_challenge(&self, challenge: &LoginChallenge) -> Result<(), Error> {
+        query(
+            "
+                INSERT INTO login_challenge
+                (challenge, address, purpose, expires_at) VALUES ($1, $2, $3, $4)
+            ",
+        )
+        .bind(&challenge.challenge)
+        .bind(&challenge.address)
+        .bind(&challenge.purpose)
+        .bind(challenge.expires_at)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_login_challenges(&self, now: NaiveDateTime) -> Result<u64, Error> {
+        let res = query("DELETE FROM login_challenge WHERE expires_at < $1")
+            .bind(now)
+            .execute(self)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn take_login_challenge(
+        &self,
+        challenge: &str,
+        now: NaiveDateTime,
+    ) -> Result<Option<LoginChallenge>, Error> {
+        self.delete_expired_login_challenges(now).await?;
+
+        Ok(query_as(
+            "
+                DELETE FROM login_challenge
+                WHERE challenge = $1
+                RETURNING *
+            ",
+        )
+        .bind(challenge)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn insert_session(&self, new_session: &NewSession) -> Result<i32, Error> {
+        let (id,): (i32,) = query_as(
+            "
+                INSERT INTO session
+                (network, address, public_key, token_hash, refresh_token_hash, expires_at, refresh_expires_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING id
+            ",
+        )
+        .bind(&new_session.network)
+        .bind(&new_session.address)
+        .bind(&new_session.public_key)
+        .bind(&new_session.token_hash)
+        .bind(&new_session.refresh_token_hash)
+        .bind(new_session.expires_at)
+        .bind(new_session.refresh_expires_at)
+        .fetch_one(self)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn get_session_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM session
+                WHERE token_hash = $1
+            ",
+        )
+        .bind(token_hash)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn get_session_by_refresh_token_hash(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<Option<Session>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM session
+                WHERE refresh_token_hash = $1
+            ",
+        )
+        .bind(refresh_token_hash)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn revoke_session(&self, id: i32, revoked_at: NaiveDateTime) -> Result<bool, Error> {
+        let res = query(
+            "
+                UPDATE session
+                    SET revoked_at = $1
+                WHERE
+                    id = $2
+                    AND
+                    revoked_at IS NULL
+            ",
+        )
+        .bind(revoked_at)
+        .bind(id)
+        .execute(self)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn revoke_sessions_by_address(
+        &self,
+        network: &str,
+        address: &str,
+        revoked_at: NaiveDateTime,
+    ) -> Result<u64, Error> {
+        let res = query(
+            "
+                UPDATE session
+                    SET revoked_at = $1
+                WHERE
+                    network = $2
+                    AND
+                    address = $3
+                    AND
+                    revoked_at IS NULL
+            ",
+        )
+        .bind(revoked_at)
+        .bind(network)
+        .bind(address)
+        .execute(self)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+}
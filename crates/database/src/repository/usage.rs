@@ -0,0 +1,237 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+use crate::models::module_hub::core::usage::{FunctionUsage, CALL_SOURCE_ENTRY_FUNCTION};
+
+use async_trait::async_trait;
+use sqlx::{query, query_as};
+
+/// Narrows the rollup of one window
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UsageFilter {
+    pub window_days: i32,
+    /// Matched against the module address, module name and function name
+    pub keyword: Option<String>,
+    /// The address `keyword` resolves to as an account alias
+    pub alias_address: Option<String>,
+    /// Only functions called directly by an entry function payload
+    pub entry_only: bool,
+}
+
+/// The `function_usage` rollup of `function_call_daily`, both kept per network
+#[async_trait]
+pub trait FunctionUsageRepository: Send + Sync {
+    /// Most called first
+    async fn get_function_usages(
+        &self,
+        network: &str,
+        filter: &UsageFilter,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<FunctionUsage>, Error>;
+
+    async fn count_function_usages(
+        &self,
+        network: &str,
+        filter: &UsageFilter,
+    ) -> Result<i64, Error>;
+
+    /// One row per window, smallest window first
+    async fn get_function_usage(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Vec<FunctionUsage>, Error>;
+
+    /// Rebuilds the rollup of every window and drops days no window covers anymore
+    async fn refresh_function_usage(&self, network: &str, windows: &[i32]) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl FunctionUsageRepository for PostgresPool {
+    async fn get_function_usages(
+        &self,
+        network: &str,
+        filter: &UsageFilter,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<FunctionUsage>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM function_usage
+                WHERE
+                    network = $7
+                    AND
+                    window_days = $1
+                    AND
+                    (
+                        $2::TEXT IS NULL
+                        OR
+                        module_address = $2
+                        OR
+                        module_address = $3
+                        OR
+                        module_name = $2
+                        OR
+                        function_name = $2
+                    )
+                    AND
+                    (
+                        NOT $4
+                        OR
+                        entry_call_count > 0
+                    )
+                ORDER BY call_count DESC, module_address, module_name, function_name
+                OFFSET $5
+                LIMIT $6
+            ",
+        )
+        .bind(filter.window_days)
+        .bind(&filter.keyword)
+        .bind(filter.alias_address.clone().unwrap_or_default())
+        .bind(filter.entry_only)
+        .bind(offset)
+        .bind(limit)
+        .bind(network)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn count_function_usages(
+        &self,
+        network: &str,
+        filter: &UsageFilter,
+    ) -> Result<i64, Error> {
+        let (count,): (i64,) = query_as(
+            "
+                SELECT COUNT(1) as count
+                FROM function_usage
+                WHERE
+                    network = $5
+                    AND
+                    window_days = $1
+                    AND
+                    (
+                        $2::TEXT IS NULL
+                        OR
+                        module_address = $2
+                        OR
+                        module_address = $3
+                        OR
+                        module_name = $2
+                        OR
+                        function_name = $2
+                    )
+                    AND
+                    (
+                        NOT $4
+                        OR
+                        entry_call_count > 0
+                    )
+            ",
+        )
+        .bind(filter.window_days)
+        .bind(&filter.keyword)
+        .bind(filter.alias_address.clone().unwrap_or_default())
+        .bind(filter.entry_only)
+        .bind(network)
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn get_function_usage(
+        &self,
+        network: &str,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Vec<FunctionUsage>, Error> {
+        Ok(query_as(
+            "
+                SELECT *
+                FROM function_usage
+                WHERE
+                    network = $1
+                    AND
+                    module_address = $2
+                    AND
+                    module_name = $3
+                    AND
+                    function_name = $4
+                ORDER BY window_days
+            ",
+        )
+        .bind(network)
+        .bind(address)
+        .bind(module_name)
+        .bind(function_name)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn refresh_function_usage(&self, network: &str, windows: &[i32]) -> Result<(), Error> {
+        let mut tx = self.begin().await?;
+
+        for window in windows {
+            query("DELETE FROM function_usage WHERE network = $1 AND window_days = $2")
+                .bind(network)
+                .bind(window)
+                .execute(&mut tx)
+                .await?;
+
+            query(
+                "
+                    INSERT INTO function_usage
+                        (network, module_address, module_name, function_name, window_days,
+                        call_count, entry_call_count, distinct_callers, last_called_at, updated_at)
+                    SELECT
+                        network,
+                        module_address,
+                        module_name,
+                        function_name,
+                        $1,
+                        SUM(call_count),
+                        COALESCE(SUM(call_count) FILTER (WHERE source = $2), 0),
+                        COUNT(DISTINCT sender),
+                        MAX(last_called_at),
+                        now()
+                    FROM function_call_daily
+                    WHERE
+                        network = $3
+                        AND
+                        day > (now() - make_interval(days => $1))::DATE
+                    GROUP BY network, module_address, module_name, function_name
+                ",
+            )
+            .bind(window)
+            .bind(CALL_SOURCE_ENTRY_FUNCTION)
+            .bind(network)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        if let Some(max_window) = windows.iter().max() {
+            query(
+                "
+                    DELETE FROM function_call_daily
+                    WHERE
+                        network = $1
+                        AND
+                        day <= (now() - make_interval(days => $2))::DATE
+                ",
+            )
+            .bind(network)
+            .bind(max_window)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use crate::db::PostgresPool;
+use crate::error::Error;
+
+use sqlx::migrate::Migrator;
+use sqlx::types::JsonValue;
+use sqlx::{query, query_as};
+
+/// `migrations` of this crate, embedded at build time and applied to the app database
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// A table maintained outside this repository and the columns read from it
+pub struct ExpectedTable {
+    pub name: &'static str,
+    pub columns: &'static [&'static str],
+}
+
+/// Written by the aptos full indexer
+pub const MOVE_MODULES: ExpectedTable = ExpectedTable {
+    name: "move_modules",
+    columns: &[
+        "transaction_version",
+        "write_set_change_index",
+        "transaction_block_height",
+        "name",
+        "address",
+        "bytecode",
+        "friends",
+        "exposed_functions",
+        "structs",
+        "is_deleted",
+        "inserted_at",
+    ],
+};
+
+/// Written by the function indexer
+pub const MODULE_FUNCTION: ExpectedTable = ExpectedTable {
+    name: "module_function",
+    columns: &[
+        "id",
+        "module_address",
+        "module_name",
+        "move_modules_transaction_version",
+        "move_modules_write_set_change_index",
+        "name",
+        "visibility",
+        "is_entry",
+        "generic_type_params",
+        "params",
+        "return_types",
+        "is_deleted",
+    ],
+};
+
+/// Tables filled by users rather than derived from the chain, exported and imported as is
+pub const DETAIL_TABLES: [&str; 4] = [
+    "account_detail",
+    "module_detail",
+    "module_function_detail",
+    "module_struct_detail",
+];
+
+pub async fn run_migrations(app_db: &PostgresPool) -> Result<(), Error> {
+    Ok(MIGRATOR.run(app_db).await?)
+}
+
+/// Tables and columns of `expected` absent from the current schema of the database
+pub async fn check_tables(
+    pool: &PostgresPool,
+    expected: &[&ExpectedTable],
+) -> Result<Vec<String>, Error> {
+    let names = expected.iter().map(|t| t.name).collect::<Vec<_>>();
+    let found: Vec<(String, String)> = query_as(
+        "
+            SELECT table_name::TEXT, column_name::TEXT
+            FROM information_schema.columns
+            WHERE
+                table_schema = current_schema()
+                AND
+                table_name = ANY($1)
+        ",
+    )
+    .bind(names)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(missing_columns(expected, &found))
+}
+
+fn check_detail_table(table: &str) -> Result<(), Error> {
+    match DETAIL_TABLES.contains(&table) {
+        true => Ok(()),
+        false => Err(Error::SqlXError(sqlx::Error::Protocol(format!(
+            "{} is not a detail table",
+            table
+        )))),
+    }
+}
+
+/// Every row of a detail table as a json array, ordered by id
+pub async fn export_detail_table(pool: &PostgresPool, table: &str) -> Result<JsonValue, Error> {
+    check_detail_table(table)?;
+
+    let (rows,): (Option<JsonValue>,) = query_as(&format!(
+        "
+            SELECT json_agg(row_to_json(t) ORDER BY t.id)
+            FROM {} t
+        ",
+        table
+    ))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(rows.unwrap_or_else(|| JsonValue::Array(vec![])))
+}
+
+/// Inserts rows exported by `export_detail_table`, keeping ids and skipping ids already present.
+/// Returns the number of rows inserted
+pub async fn import_detail_table(
+    pool: &PostgresPool,
+    table: &str,
+    rows: &JsonValue,
+) -> Result<u64, Error> {
+    check_detail_table(table)?;
+
+    let mut tx = pool.begin().await?;
+
+    let inserted = query(&format!(
+        "
+            INSERT INTO {0}
+            SELECT *
+            FROM json_populate_recordset(NULL::{0}, $1)
+            ON CONFLICT (id) DO NOTHING
+        ",
+        table
+    ))
+    .bind(rows)
+    .execute(&mut tx)
+    .await?
+    .rows_affected();
+
+    // Rows keep their ids, so new rows must be numbered after them
+    query(&format!(
+        "
+            SELECT setval(pg_get_serial_sequence('{0}', 'id'), COALESCE(MAX(id), 0) + 1, false)
+            FROM {0}
+        ",
+        table
+    ))
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(inserted)
+}
+
+fn missing_columns(expected: &[&ExpectedTable], found: &[(String, String)]) -> Vec<String> {
+    let mut missing = vec![];
+
+    for table in expected {
+        let columns = found
+            .iter()
+            .filter(|(name, _)| name == table.name)
+            .map(|(_, column)| column.as_str())
+            .collect::<Vec<_>>();
+
+        if columns.is_empty() {
+            missing.push(format!("table {} is missing", table.name));
+            continue;
+        }
+
+        for column in table.columns {
+            if !columns.contains(column) {
+                missing.push(format!("column {}.{} is missing", table.name, column));
+            }
+        }
+    }
+
+    missing
+}
+
+#[cfg(test)]
+mod expected_tables {
+    use crate::schema::{check_detail_table, missing_columns, MODULE_FUNCTION, MOVE_MODULES};
+
+    #[test]
+    fn test_missing_columns() {
+        let mut found = MODULE_FUNCTION
+            .columns
+            .iter()
+            .map(|column| ("module_function".to_string(), column.to_string()))
+            .collect::<Vec<_>>();
+        assert!(missing_columns(&[&MODULE_FUNCTION], &found).is_empty());
+
+        found.retain(|(_, column)| column != "is_entry");
+        assert_eq!(
+            missing_columns(&[&MODULE_FUNCTION, &MOVE_MODULES], &found),
+            [
+                "column module_function.is_entry is missing",
+                "table move_modules is missing",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_only_detail_tables_are_transferred() {
+        assert!(check_detail_table("module_detail").is_ok());
+        assert!(check_detail_table("block_stack").is_err());
+        assert!(check_detail_table("module_detail; DROP TABLE block_stack").is_err());
+    }
+}
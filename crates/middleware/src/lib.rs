@@ -1,11 +1,10 @@
 use actix_utils::future::{ready, Ready};
 use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
-use actix_web::http::StatusCode;
-use actix_web::Error;
-// use aptos_crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
-// use aptos_crypto::{Signature, ValidCryptoMaterialStringExt};
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
 use futures_util::future::LocalBoxFuture;
 use serde::{Deserialize, Serialize};
+use std::rc::Rc;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SignedBody {
@@ -26,16 +25,22 @@ pub struct SignedData {
     pub signature: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Auth {
+/// The signer a session token was issued to, added to the request extensions once accepted
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
     pub address: String,
-    pub timestamp: String,
+    pub public_key: String,
 }
 
-impl Auth {
-    pub fn new(address: String, timestamp: String) -> Self {
-        Auth { address, timestamp }
-    }
+/// Looks up bearer session tokens, registered as `Data<dyn SessionResolver>` app data
+pub trait SessionResolver: Send + Sync {
+    /// Some when the token is live and was issued to `address` on the network `req` is served from
+    fn resolve(
+        &self,
+        req: &HttpRequest,
+        token: String,
+        address: String,
+    ) -> LocalBoxFuture<'static, Result<Option<Session>, Error>>;
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -54,8 +59,28 @@ impl actix_web::ResponseError for MiddlewareError {
             MiddlewareError::BadRequest {} => StatusCode::BAD_REQUEST,
         }
     }
+
+    /// Same envelope as the controller errors
+    fn error_response(&self) -> HttpResponse {
+        let (code, message) = match self {
+            MiddlewareError::UnAuthorized {} => {
+                ("unauthorized", "missing or invalid authorization")
+            }
+            MiddlewareError::BadRequest {} => ("bad_request", "malformed authorization"),
+        };
+
+        HttpResponse::build(self.status_code()).json(serde_json::json!({
+            "error": {
+                "code": code,
+                "message": message,
+                "details": null,
+            }
+        }))
+    }
 }
 
+/// Block stack routes take a session token issued to the address of the path, the signature
+/// behind it was checked when the session was issued
 pub struct AptosAuth;
 
 impl AptosAuth {
@@ -66,7 +91,7 @@ impl AptosAuth {
 
 impl<S, B> Transform<S, ServiceRequest> for AptosAuth
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -77,17 +102,19 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(AptosAuthMiddleware { service }))
+        ready(Ok(AptosAuthMiddleware {
+            service: Rc::new(service),
+        }))
     }
 }
 
 pub struct AptosAuthMiddleware<S> {
-    service: S,
+    service: Rc<S>,
 }
 
 impl<S, B> Service<ServiceRequest> for AptosAuthMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -98,19 +125,6 @@ where
     actix_service::forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let signature = req.headers().get("aptos-auth");
-
-        let signature = signature
-            .map(|s| s.to_str().unwrap_or_default().to_string())
-            .unwrap_or_default();
-
-        // let public_key = public_key
-        //     .map(|p| p.to_str().unwrap_or_default().to_string())
-        //     .unwrap_or_default();
-        // let timestamp = timestamp
-        //     .map(|t| t.to_str().unwrap_or_default().to_string())
-        //     .unwrap_or_default();
-
         let address = req
             .path()
             .strip_prefix("/api/v1/block-stacks/")
@@ -119,22 +133,27 @@ where
             .take(1)
             .collect::<String>();
 
-        if signature != address {
-            return Box::pin(async move { Err(Error::from(MiddlewareError::UnAuthorized {})) });
-        }
-
-        let fut = self.service.call(req);
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string);
+        let resolving = match (token, req.app_data::<web::Data<dyn SessionResolver>>()) {
+            (Some(token), Some(resolver)) => Some(resolver.resolve(req.request(), token, address)),
+            _ => None,
+        };
+        let service = self.service.clone();
 
         Box::pin(async move {
-            let next_service = fut.await?;
-
-            // let signature = Ed25519Signature::from_encoded_string(&signature).map_err(|_| Error::from(MiddlewareError::UnAuthorized {}))?;
-            // let public_key = Ed25519PublicKey::from_encoded_string(&public_key).map_err(|_| Error::from(MiddlewareError::UnAuthorized {}))?;
-
-            // let auth = Auth::new(address, timestamp);
-            // let auth = serde_json::to_string(&auth).map_err(|_| Error::from(MiddlewareError::BadRequest {}))?;
-
-            Ok(next_service)
+            let session = match resolving {
+                Some(resolving) => resolving.await?,
+                None => None,
+            };
+            let session = session.ok_or_else(|| Error::from(MiddlewareError::UnAuthorized {}))?;
+
+            req.extensions_mut().insert(session);
+            service.call(req).await
         })
     }
 }
@@ -4,13 +4,14 @@ use crate::error::Error;
 
 use futures::future::join_all;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Output;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 
 pub async fn package_name_parser(
+    github_raw_base: &str,
     github_url: &str,
     rev: &str,
     subdir: &str,
@@ -22,9 +23,7 @@ pub async fn package_name_parser(
 
     let url = format!(
         "{}/{}/{}/{}/{}",
-        std::env::var("GITHUB_RAW_BASE").map_err(|_| Error::NotFound {
-            msg: "env GITHUB_RAW_BASE not found".to_string()
-        })?,
+        github_raw_base.trim_end_matches('/'),
         github_url,
         rev,
         subdir,
@@ -107,18 +106,18 @@ pub struct MoveScript {
     pub dependencies: Vec<Dependency>,
     pub functions: Vec<Function>,
     pub generated_main_function: String,
+    /// Raw file host the `Move.toml` of dependencies is fetched from
+    pub github_raw_base: String,
 }
 
 impl MoveScript {
     pub fn new() -> Self {
         Self::default()
     }
-    /// set temporal directory name
-    pub fn init(mut self) -> Self {
-        self.dir = PathBuf::new();
-        self.dir
-            .push(std::env::var("MOVE_SCRIPT_DEFAULT_PATH").unwrap_or_default());
-        self.dir.push(Uuid::new_v4().to_string());
+    /// set temporal directory name under `base_dir`
+    pub fn init(mut self, base_dir: &Path, github_raw_base: &str) -> Self {
+        self.dir = base_dir.join(Uuid::new_v4().to_string());
+        self.github_raw_base = github_raw_base.to_string();
         self
     }
 
@@ -171,7 +170,9 @@ impl MoveScript {
             .dependencies
             .iter()
             .map(|dep| async {
-                let deps_name = package_name_parser(&dep.git, &dep.rev, &dep.subdir).await;
+                let deps_name =
+                    package_name_parser(&self.github_raw_base, &dep.git, &dep.rev, &dep.subdir)
+                        .await;
                 match deps_name {
                     Ok(deps_name) => format_args!(
                         "{} = {{ git = '{}', rev = '{}', subdir = '{}' }}\n",
@@ -241,11 +242,16 @@ impl MoveScript {
     }
 
     pub async fn generate_script(&mut self) -> Result<CompileResult, Error> {
+        self.generate_package().await?;
+        self.compile()
+    }
+
+    /// Writes the package `compile` builds, without compiling it
+    pub async fn generate_package(&mut self) -> Result<(), Error> {
         self.create_base_dir().await?;
         self.generate_toml().await?;
         self.generate_main_function().await?;
-        self.wrap_to_script().await?;
-        self.compile()
+        self.wrap_to_script().await
     }
 
     pub async fn destroy_self(self) -> Result<(), Error> {
@@ -281,7 +287,11 @@ mod script {
     async fn test_script_generator() {
         dotenv().ok();
         let mut script = MoveScript::new()
-            .init()
+            .init(
+                &std::env::temp_dir(),
+                &std::env::var("GITHUB_RAW_BASE")
+                    .unwrap_or("https://raw.githubusercontent.com".to_string()),
+            )
             .add_dependency(Dependency::new(
                 "https://github.com/aptos-labs/aptos-core",
                 "main",